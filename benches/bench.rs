@@ -29,6 +29,14 @@ fn spawn_static(b: &mut Bencher) {
     });
 }
 
+fn spawn_with_id(b: &mut Bencher) {
+    let mut world = World::new();
+    let id = world.register_bundle::<(Position, Velocity)>();
+    b.iter(|| {
+        world.spawn_with_id(id, (Position(0.0), Velocity(0.0)));
+    });
+}
+
 fn spawn_batch(b: &mut Bencher) {
     #[derive(Bundle)]
     struct Bundle {
@@ -223,6 +231,41 @@ fn iterate_mut_cached_100_by_50(b: &mut Bencher) {
     })
 }
 
+fn spawn_256_archetypes(world: &mut World) {
+    fn add_if<const N: usize>(builder: &mut EntityBuilder, bits: u32) {
+        if bits & (1 << N) != 0 {
+            builder.add([(); N]);
+        }
+    }
+
+    let mut builder = EntityBuilder::new();
+    for bits in 0..256 {
+        builder.add(Position(0.0)).add(Velocity(1.0));
+        add_if::<0>(&mut builder, bits);
+        add_if::<1>(&mut builder, bits);
+        add_if::<2>(&mut builder, bits);
+        add_if::<3>(&mut builder, bits);
+        add_if::<4>(&mut builder, bits);
+        add_if::<5>(&mut builder, bits);
+        add_if::<6>(&mut builder, bits);
+        add_if::<7>(&mut builder, bits);
+        world.spawn(builder.build());
+    }
+}
+
+/// Dominated by matching the query against each archetype's component types
+fn match_256_archetypes(b: &mut Bencher) {
+    let mut world = World::new();
+    spawn_256_archetypes(&mut world);
+    b.iter(|| {
+        world
+            .query::<(&Position, &[(); 7])>()
+            .without::<&[(); 3]>()
+            .iter()
+            .count()
+    })
+}
+
 fn build(b: &mut Bencher) {
     let mut world = World::new();
     let mut builder = EntityBuilder::new();
@@ -270,6 +313,7 @@ benchmark_group!(
     benches,
     spawn_tuple,
     spawn_static,
+    spawn_with_id,
     spawn_batch,
     remove,
     insert,
@@ -282,6 +326,7 @@ benchmark_group!(
     iterate_cached_100_by_50,
     iterate_mut_uncached_100_by_50,
     iterate_mut_cached_100_by_50,
+    match_256_archetypes,
     build,
     build_cloneable,
     access_view,
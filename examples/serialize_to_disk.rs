@@ -143,12 +143,12 @@ fn main() {
     )
     .expect("Failed to serialize");
     let path = Path::new(save_file_name);
-    let mut file = match File::create(&path) {
+    let mut file = match File::create(path) {
         Err(why) => panic!("couldn't create {}: {}", path.display(), why),
         Ok(file) => file,
     };
-    file.write(&buffer)
-        .expect(&format!("Failed to write file: {}", save_file_name));
+    file.write_all(&buffer)
+        .unwrap_or_else(|e| panic!("Failed to write file {}: {}", save_file_name, e));
     println!("Saved world \'{}\' to disk.", path.display());
 
     // load our world from disk and deserialize it back as world:
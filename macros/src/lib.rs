@@ -3,6 +3,8 @@ extern crate proc_macro;
 mod bundle;
 mod bundle_clone;
 mod query;
+mod stable_hash;
+mod visit_entities;
 
 pub(crate) mod common;
 
@@ -65,6 +67,10 @@ pub fn derive_dynamic_bundle_clone(input: TokenStream) -> TokenStream {
 /// For enum queries, the result will always be the first variant that matches the entity.
 /// Unit variants and variants without any fields will always match an entity.
 ///
+/// A struct whose fields borrow the same component type directly, at least one of them uniquely,
+/// is rejected at compile time. Other aliasing, such as through nested queries, causes a panic when
+/// the query is used.
+///
 /// # Example
 /// ```
 /// # use hecs::*;
@@ -93,3 +99,68 @@ pub fn derive_query(input: TokenStream) -> TokenStream {
     }
     .into()
 }
+
+/// Implement `VisitEntities` for a struct, visiting each field marked `#[entities]`
+///
+/// Marked fields must implement `VisitEntities` themselves, as `Entity`, `Option<Entity>` and
+/// `Vec<Entity>` do.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// #[derive(VisitEntities)]
+/// struct Target {
+///     #[entities]
+///     entity: Entity,
+///     #[entities]
+///     fallback: Option<Entity>,
+///     range: f32,
+/// }
+///
+/// let mut registry = ComponentRegistry::new();
+/// registry.register_entities::<Target>("Target");
+/// ```
+#[proc_macro_derive(VisitEntities, attributes(entities))]
+pub fn derive_visit_entities(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match visit_entities::derive(input) {
+        Ok(ts) => ts,
+        Err(e) => e.to_compile_error(),
+    }
+    .into()
+}
+
+/// Implement `StableHash` for a struct or enum, hashing each field in declaration order
+///
+/// Enum variants are distinguished by their index, so reordering variants changes hashes. Every
+/// field must implement `StableHash`.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// #[derive(StableHash)]
+/// struct Body {
+///     position: [f32; 2],
+///     parent: Option<Entity>,
+/// }
+///
+/// #[derive(StableHash)]
+/// enum Shape {
+///     Circle(f32),
+///     Rect { width: f32, height: f32 },
+///     Point,
+/// }
+///
+/// let mut registry = ComponentRegistry::new();
+/// registry.register_stable_hash::<Body>("Body");
+/// registry.register_stable_hash::<Shape>("Shape");
+/// ```
+#[proc_macro_derive(StableHash)]
+pub fn derive_stable_hash(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match stable_hash::derive(input) {
+        Ok(ts) => ts,
+        Err(e) => e.to_compile_error(),
+    }
+    .into()
+}
@@ -78,6 +78,7 @@ fn derive_struct(
             .unzip(),
         syn::Fields::Unit => (Vec::new(), Vec::new()),
     };
+    check_aliasing(&data)?;
     let fetches = queries
         .iter()
         .map(|ty| quote! { <#ty as ::hecs::Query>::Fetch })
@@ -481,3 +482,42 @@ fn query_ty(lifetime: &Lifetime, ty: &Type) -> TokenStream2 {
     syn::visit_mut::visit_type_mut(&mut Visitor { replace: lifetime }, &mut ty);
     quote! { #ty }
 }
+
+/// Reject fields that borrow a component type also borrowed uniquely by another field
+///
+/// Only catches direct references to syntactically identical types; anything else, such as
+/// aliasing through a nested query or a type alias, is left to the runtime check.
+fn check_aliasing(data: &DataStruct) -> Result<()> {
+    let mut borrows = Vec::<(String, String, bool)>::new();
+    for (i, field) in data.fields.iter().enumerate() {
+        let Type::Reference(ref reference) = field.ty else {
+            continue;
+        };
+        let name = field
+            .ident
+            .as_ref()
+            .map_or_else(|| i.to_string(), |x| x.to_string());
+        let elem = &reference.elem;
+        let component = quote!(#elem).to_string();
+        let unique = reference.mutability.is_some();
+        if let Some((other, _, other_unique)) = borrows
+            .iter()
+            .find(|(_, ty, other_unique)| *ty == component && (unique || *other_unique))
+        {
+            let message = if *other_unique {
+                format!(
+                    "field `{}` borrows `{}`, which is already borrowed uniquely by field `{}`",
+                    name, component, other
+                )
+            } else {
+                format!(
+                    "field `{}` borrows `{}` uniquely, but it is already borrowed by field `{}`",
+                    name, component, other
+                )
+            };
+            return Err(Error::new_spanned(&field.ty, message));
+        }
+        borrows.push((name, component, unique));
+    }
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{DeriveInput, Error, Result};
+
+use crate::common::{member_as_idents, struct_fields};
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream2> {
+    let ident = input.ident;
+    let body = match input.data {
+        syn::Data::Struct(s) => {
+            let (_, members) = struct_fields(&s.fields);
+            quote! {
+                #(
+                    ::hecs::StableHash::stable_hash(&self.#members, hasher);
+                )*
+            }
+        }
+        syn::Data::Enum(e) => {
+            let arms = e.variants.iter().enumerate().map(|(index, variant)| {
+                let variant_ident = &variant.ident;
+                let index = index as u32;
+                let (_, members) = struct_fields(&variant.fields);
+                let bindings = member_as_idents(&members);
+                let fields = members
+                    .iter()
+                    .zip(&bindings)
+                    .map(|(member, binding)| match member {
+                        syn::Member::Named(_) => quote! { #member },
+                        syn::Member::Unnamed(_) => quote! { #member: #binding },
+                    });
+                quote! {
+                    Self::#variant_ident { #(#fields,)* } => {
+                        hasher.write_u32(#index);
+                        #(
+                            ::hecs::StableHash::stable_hash(#bindings, hasher);
+                        )*
+                    }
+                }
+            });
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        syn::Data::Union(_) => {
+            return Err(Error::new_spanned(
+                ident,
+                "derive(StableHash) does not support unions",
+            ))
+        }
+    };
+    let mut generics = input.generics;
+    for tp in generics.type_params_mut() {
+        tp.bounds.push(syn::parse_quote!(::hecs::StableHash));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::hecs::StableHash for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn stable_hash(&self, hasher: &mut ::hecs::StableHasher) {
+                #body
+            }
+        }
+    })
+}
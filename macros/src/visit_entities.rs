@@ -0,0 +1,48 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{DeriveInput, Error, Result};
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream2> {
+    let ident = input.ident;
+    let data = match input.data {
+        syn::Data::Struct(s) => s,
+        _ => {
+            return Err(Error::new_spanned(
+                ident,
+                "derive(VisitEntities) does not support enums or unions",
+            ))
+        }
+    };
+    let mut members = Vec::new();
+    for (i, field) in data.fields.iter().enumerate() {
+        let mut marked = false;
+        for attr in &field.attrs {
+            if !attr.path().is_ident("entities") {
+                continue;
+            }
+            attr.meta.require_path_only()?;
+            marked = true;
+        }
+        if !marked {
+            continue;
+        }
+        members.push(match field.ident {
+            Some(ref ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(syn::Index {
+                index: i as u32,
+                span: proc_macro2::Span::call_site(),
+            }),
+        });
+    }
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::hecs::VisitEntities for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn visit_entities(&mut self, f: &mut dyn ::core::ops::FnMut(&mut ::hecs::Entity)) {
+                #(
+                    ::hecs::VisitEntities::visit_entities(&mut self.#members, f);
+                )*
+            }
+        }
+    })
+}
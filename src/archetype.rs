@@ -1,17 +1,23 @@
 use crate::alloc::alloc::{alloc, dealloc, Layout};
 use crate::alloc::boxed::Box;
+use crate::alloc::sync::Arc;
 use crate::alloc::{vec, vec::Vec};
 use core::any::{type_name, TypeId};
+use core::cmp;
 use core::fmt;
 use core::hash::{BuildHasher, BuildHasherDefault, Hasher};
-use core::ops::{Deref, DerefMut};
+use core::mem;
+use core::ops::{Deref, DerefMut, Range};
 use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
 
 use hashbrown::HashMap;
 
 use crate::borrow::AtomicBorrow;
+use crate::entities::try_reserve_exact;
+use crate::inline_vec::InlineVec;
 use crate::query::Fetch;
-use crate::{Access, Component, ComponentRef, Query};
+use crate::{Access, AllocError, Component, ComponentRef, DynamicClone, Query};
 
 /// A collection of entities having the same component types
 ///
@@ -19,12 +25,33 @@ use crate::{Access, Component, ComponentRef, Query};
 /// [`World`](crate::World).
 pub struct Archetype {
     types: Vec<TypeInfo>,
-    type_ids: Box<[TypeId]>,
+    type_ids: InlineVec<TypeId, INLINE_TYPES>,
     index: OrderedTypeIdMap<usize>,
     len: u32,
     entities: Box<[u32]>,
     /// One allocation per type, in the same order as `types`
     data: Box<[Data]>,
+    /// Whether removal shifts later entities down rather than swapping the last entity in
+    stable_removal: bool,
+    /// The world's current tick, recorded in columns as they're modified
+    tick: u32,
+    /// Most recent tick during which an entity was added
+    entered: u32,
+    /// Component by which rows are kept sorted, if any
+    sort_key: Option<SortKey>,
+    /// Whether the sort key column may have been mutated in place since rows were last sorted
+    unsorted: AtomicBool,
+    /// Number of columns grown since last reported by `take_column_grows`
+    column_grows: u64,
+    /// Whether a query has accessed these entities since `clear_queried` was last called
+    queried: AtomicBool,
+    /// The world's current change tracking epoch, recorded in per-entity ticks
+    epoch: u32,
+    /// Whether any column has per-entity ticks
+    tracking: bool,
+    /// Total component bytes reserved by the archetypes of the world this belongs to, if any,
+    /// kept up to date as this archetype's capacity changes
+    reserved_bytes: Option<Arc<AtomicUsize>>,
 }
 
 impl Archetype {
@@ -60,8 +87,21 @@ impl Archetype {
                 .map(|_| Data {
                     state: AtomicBorrow::new(),
                     storage: NonNull::new(max_align as *mut u8).unwrap(),
+                    changed: AtomicU32::new(0),
+                    version: AtomicU32::new(0),
+                    ticks: None,
                 })
                 .collect(),
+            stable_removal: false,
+            tick: 0,
+            entered: 0,
+            sort_key: None,
+            unsorted: AtomicBool::new(false),
+            column_grows: 0,
+            queried: AtomicBool::new(false),
+            epoch: 0,
+            tracking: false,
+            reserved_bytes: None,
         }
     }
 
@@ -74,9 +114,87 @@ impl Archetype {
                 }
             }
         }
+        for data in self.data.iter_mut() {
+            if let Some(ticks) = &mut data.ticks {
+                ticks.clear();
+            }
+        }
         self.len = 0;
     }
 
+    /// Copy the archetype, including its change ticks, cloning the components of type
+    /// `types()[i]` with `clones[i]`
+    ///
+    /// `clones` must have one entry per type, each of which may only be `None` if the archetype is
+    /// empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any column is uniquely borrowed.
+    pub(crate) fn clone_with(&self, clones: &[Option<DynamicClone>]) -> Self {
+        let mut clone = Self::new(self.types.clone());
+        if self.len != 0 {
+            clone.set_capacity(self.len as usize);
+        }
+        for (state, (ty, data)) in self.types.iter().zip(&*self.data).enumerate() {
+            let target = &mut clone.data[state];
+            *target.changed.get_mut() = data.changed.load(Ordering::Relaxed);
+            *target.version.get_mut() = data.version.load(Ordering::Relaxed);
+            target.ticks = data.ticks.as_ref().map(|ticks| {
+                ticks
+                    .iter()
+                    .map(|x| RowTicks {
+                        added: AtomicU32::new(x.added.load(Ordering::Relaxed)),
+                        changed: AtomicU32::new(x.changed.load(Ordering::Relaxed)),
+                    })
+                    .collect()
+            });
+            if self.len == 0 {
+                continue;
+            }
+            let func = clones[state].unwrap().func;
+            let size = ty.layout.size();
+            // Released on drop, even if a `Clone` implementation panics
+            let _borrow = RawColumn::new(self, ty.id).unwrap();
+            for index in 0..self.len as usize {
+                unsafe {
+                    let src = data.storage.as_ptr().add(index * size);
+                    let dst = target.storage.as_ptr().add(index * size);
+                    func(src, &mut |ptr, _| ptr::copy_nonoverlapping(ptr, dst, size));
+                }
+            }
+        }
+        let len = self.len as usize;
+        clone.entities[..len].copy_from_slice(&self.entities[..len]);
+        clone.len = self.len;
+        clone.stable_removal = self.stable_removal;
+        clone.tick = self.tick;
+        clone.entered = self.entered;
+        clone.sort_key = self.sort_key;
+        *clone.unsorted.get_mut() = self.unsorted.load(Ordering::Relaxed);
+        *clone.queried.get_mut() = self.queried();
+        clone.epoch = self.epoch;
+        clone.tracking = self.tracking;
+        clone
+    }
+
+    /// Record that a query is accessing these entities
+    pub(crate) fn mark_queried(&self) {
+        // Avoid contending for the cache line when many threads query the same archetype
+        if !self.queried.load(Ordering::Relaxed) {
+            self.queried.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Whether a query has accessed these entities since `clear_queried` was last called
+    pub(crate) fn queried(&self) -> bool {
+        self.queried.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn clear_queried(&mut self) {
+        *self.queried.get_mut() = false;
+    }
+
     /// Whether this archetype contains `T` components
     pub fn has<T: Component>(&self) -> bool {
         self.has_dynamic(TypeId::of::<T>())
@@ -92,6 +210,11 @@ impl Archetype {
         self.index.get(&TypeId::of::<T>()).copied()
     }
 
+    /// Find the state index associated with the type identified by `id`, if present
+    pub(crate) fn get_state_dynamic(&self, id: TypeId) -> Option<usize> {
+        self.index.get(&id).copied()
+    }
+
     /// Get the address of the first `T` component using an index from `get_state::<T>`
     pub(crate) fn get_base<T: Component>(&self, state: usize) -> NonNull<T> {
         assert_eq!(self.types[state].id, TypeId::of::<T>());
@@ -103,9 +226,36 @@ impl Archetype {
 
     /// Borrow all components of a single type from these entities, if present
     ///
-    /// `T` must be a shared or unique reference to a component type.
+    /// `T` must be a shared or unique reference to a component type. The returned column
+    /// dereferences to a slice of every component, in the same order as [`ids`](Self::ids), and
+    /// holds a dynamic borrow of the column until dropped.
+    ///
+    /// Useful for efficient serialization, and for handing whole columns to SIMD kernels or GPU
+    /// uploads without per-entity query overhead.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// struct Position(f32);
+    /// struct Velocity(f32);
+    ///
+    /// fn integrate(positions: &mut [Position], velocities: &[Velocity]) {
+    ///     for (p, v) in positions.iter_mut().zip(velocities) {
+    ///         p.0 += v.0;
+    ///     }
+    /// }
     ///
-    /// Useful for efficient serialization.
+    /// let mut world = World::new();
+    /// let e = world.spawn((Position(0.0), Velocity(1.0)));
+    /// for archetype in world.archetypes() {
+    ///     if let (Some(mut p), Some(v)) = (
+    ///         archetype.get::<&mut Position>(),
+    ///         archetype.get::<&Velocity>(),
+    ///     ) {
+    ///         integrate(&mut p, &v);
+    ///     }
+    /// }
+    /// assert_eq!(world.get::<&Position>(e).unwrap().0, 1.0);
+    /// ```
     pub fn get<'a, T: ComponentRef<'a>>(&'a self) -> Option<T::Column> {
         T::get_column(self)
     }
@@ -130,6 +280,147 @@ impl Archetype {
         if !self.data[state].state.borrow_mut() {
             panic!("{} already borrowed", type_name::<T>());
         }
+        self.mark_mutated(state);
+    }
+
+    /// Record that the column at `state` was mutably accessed during the current tick
+    ///
+    /// Unlike `mark_changed`, accounts for rows no longer being sorted.
+    #[inline]
+    pub(crate) fn mark_mutated(&self, state: usize) {
+        self.mark_changed(state);
+        if self.sort_key.as_ref().map_or(false, |x| x.state == state) {
+            self.unsorted.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Record that the column at `state` was modified during the current tick
+    #[inline]
+    pub(crate) fn mark_changed(&self, state: usize) {
+        let data = &self.data[state];
+        data.changed.store(self.tick, Ordering::Relaxed);
+        data.version.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record that every column was modified during the current tick
+    pub(crate) fn mark_all_changed(&self) {
+        for data in &*self.data {
+            data.changed.store(self.tick, Ordering::Relaxed);
+            data.version.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Value that differs from that of any earlier call unless the column at `state` has been
+    /// modified a multiple of 2^32 times since
+    ///
+    /// Unlike `change_tick`, distinguishes modifications made during the same tick.
+    pub(crate) fn column_version(&self, state: usize) -> u32 {
+        self.data[state].version.load(Ordering::Relaxed)
+    }
+
+    /// Set the tick recorded by subsequent modifications
+    pub(crate) fn set_tick(&mut self, tick: u32) {
+        self.tick = tick;
+    }
+
+    /// The most recent [`World::tick`](crate::World::tick) during which components of the type
+    /// identified by `id` were added to or mutably accessed in this archetype, if present
+    ///
+    /// Tracked for the column as a whole rather than per entity.
+    pub fn change_tick(&self, id: TypeId) -> Option<u32> {
+        let state = *self.index.get(&id)?;
+        Some(self.data[state].changed.load(Ordering::Relaxed))
+    }
+
+    /// Set the epoch recorded by subsequent per-entity additions and modifications
+    pub(crate) fn set_epoch(&mut self, epoch: u32) {
+        self.epoch = epoch;
+    }
+
+    /// The epoch compared against per-entity ticks by [`Added`](crate::Added) and
+    /// [`Changed`](crate::Changed)
+    pub(crate) fn epoch(&self) -> u32 {
+        self.epoch
+    }
+
+    /// Start keeping per-entity ticks for the component type identified by `id`, if present,
+    /// recording `stamp` for the existing entities
+    pub(crate) fn track(&mut self, id: TypeId, stamp: u32) {
+        let Some(&state) = self.index.get(&id) else {
+            return;
+        };
+        let data = &mut self.data[state];
+        if data.ticks.is_none() {
+            data.ticks = Some((0..self.len).map(|_| RowTicks::new(stamp)).collect());
+            self.tracking = true;
+        }
+    }
+
+    /// Per-entity ticks of the column at `state`, if tracked
+    pub(crate) fn row_ticks(&self, state: usize) -> Option<NonNull<RowTicks>> {
+        let ticks = self.data[state].ticks.as_ref()?;
+        Some(NonNull::from(&ticks[..]).cast())
+    }
+
+    /// Record that the component at `state` of the entity at `index` was modified
+    #[inline]
+    pub(crate) fn mark_row_changed(&self, state: usize, index: u32) {
+        if let Some(ticks) = &self.data[state].ticks {
+            ticks[index as usize]
+                .changed
+                .store(self.epoch, Ordering::Relaxed);
+        }
+    }
+
+    /// Record that the component at `state` of every entity was modified
+    pub(crate) fn mark_rows_changed(&self, state: usize) {
+        if let Some(ticks) = &self.data[state].ticks {
+            for x in ticks {
+                x.changed.store(self.epoch, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Copy the per-entity ticks of the components identified by `ids` from the entity at `index`
+    /// to the entity at `target_index` of `target`
+    pub(crate) fn copy_ticks(
+        &self,
+        index: u32,
+        target: &Archetype,
+        target_index: u32,
+        ids: impl IntoIterator<Item = TypeId>,
+    ) {
+        if !self.tracking {
+            return;
+        }
+        for id in ids {
+            let (Some(&src), Some(&dst)) = (self.index.get(&id), target.index.get(&id)) else {
+                continue;
+            };
+            let (Some(src), Some(dst)) = (&self.data[src].ticks, &target.data[dst].ticks) else {
+                continue;
+            };
+            let (src, dst) = (&src[index as usize], &dst[target_index as usize]);
+            dst.added
+                .store(src.added.load(Ordering::Relaxed), Ordering::Relaxed);
+            dst.changed
+                .store(src.changed.load(Ordering::Relaxed), Ordering::Relaxed);
+        }
+    }
+
+    /// Record that entities were added during the current tick
+    pub(crate) fn mark_entered(&mut self) {
+        self.entered = self.tick;
+    }
+
+    /// Whether entities were added at or after `tick`
+    pub(crate) fn entered_since(&self, tick: u32) -> bool {
+        self.entered >= tick
+    }
+
+    /// Whether components of type `id` are present and were changed at or after `tick`
+    pub(crate) fn changed_since(&self, id: TypeId, tick: u32) -> bool {
+        self.change_tick(id).map_or(false, |x| x >= tick)
     }
 
     pub(crate) fn release<T: Component>(&self, state: usize) {
@@ -202,6 +493,94 @@ impl Archetype {
         self.types.iter().map(|typeinfo| typeinfo.id)
     }
 
+    /// Enumerate the [`TypeInfo`]s of the components of entities stored in this archetype
+    ///
+    /// Like [`component_types`](Self::component_types), but also exposes each component's
+    /// [`Layout`] and destructor, which is enough to copy or drop component data without knowing
+    /// its static type.
+    pub fn component_type_info(&self) -> impl ExactSizeIterator<Item = TypeInfo> + '_ {
+        self.types.iter().copied()
+    }
+
+    /// Layout of each element of the column of components with the type identified by `id`, if
+    /// present
+    ///
+    /// Columns are contiguous arrays whose base address is aligned to at least
+    /// [`Layout::align`], so every component is correctly aligned regardless of how large its
+    /// type's alignment is.
+    pub fn column_layout(&self, id: TypeId) -> Option<Layout> {
+        Some(self.types[*self.index.get(&id)?].layout)
+    }
+
+    /// Address of the first component in the column of the type identified by `id`, if present,
+    /// without borrowing it
+    ///
+    /// For FFI bridges and GPU uploads that synchronize access to component data themselves.
+    /// The column holds [`len`](Self::len) components, each [`column_layout`](Self::column_layout)
+    /// `.size()` bytes after the last, belonging to the entities with the corresponding
+    /// [`ids`](Self::ids).
+    ///
+    /// Obtaining the pointer is safe, but using it is only sound while:
+    /// - the archetype isn't modified, e.g. by spawning, despawning, inserting or removing
+    ///   components, or any other operation requiring `&mut World`, any of which may move the
+    ///   column, and
+    /// - no conflicting access occurs through hecs, i.e. no component in the column is read while
+    ///   written through the pointer or vice versa. Hold a borrow such as
+    ///   [`get`](Self::get)`::<&T>()` for the duration to have hecs enforce this, or ensure it by
+    ///   other means, e.g. by only using the pointer while no queries are running.
+    ///
+    /// Writes through the pointer aren't recorded by change detection; call
+    /// [`mark_column_changed`](Self::mark_column_changed) to record them.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// # use core::any::TypeId;
+    /// let mut world = World::new();
+    /// world.spawn_batch((0..4).map(|i| (i as f32,)));
+    /// let archetype = world.archetypes().find(|x| x.has::<f32>()).unwrap();
+    /// let ptr = archetype.column_ptr(TypeId::of::<f32>()).unwrap();
+    /// let stride = archetype.column_layout(TypeId::of::<f32>()).unwrap().size();
+    /// // No other access to the column can occur while `world` is borrowed immutably here
+    /// let sum = (0..archetype.len() as usize)
+    ///     .map(|i| unsafe { ptr.as_ptr().add(i * stride).cast::<f32>().read() })
+    ///     .sum::<f32>();
+    /// assert_eq!(sum, 6.0);
+    /// ```
+    pub fn column_ptr(&self, id: TypeId) -> Option<NonNull<u8>> {
+        let state = *self.index.get(&id)?;
+        Some(self.data[state].storage)
+    }
+
+    /// Like [`column_ptr`](Self::column_ptr), but typed
+    pub fn column_ptr_of<T: Component>(&self) -> Option<NonNull<T>> {
+        Some(self.get_base(self.get_state::<T>()?))
+    }
+
+    /// Address of the first of the [`ids`](Self::ids) of the entities in this archetype, without
+    /// borrowing them
+    ///
+    /// Subject to the same conditions as [`column_ptr`](Self::column_ptr), except that hecs never
+    /// writes to the IDs without `&mut World`.
+    pub fn ids_ptr(&self) -> NonNull<u32> {
+        self.entities()
+    }
+
+    /// Record that components of the type identified by `id` were modified during the current
+    /// tick, e.g. through [`column_ptr`](Self::column_ptr)
+    ///
+    /// Returns `false` if there is no such column.
+    pub fn mark_column_changed(&self, id: TypeId) -> bool {
+        match self.index.get(&id) {
+            Some(&state) => {
+                self.mark_mutated(state);
+                self.mark_rows_changed(state);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// `index` must be in-bounds or just past the end
     pub(crate) unsafe fn get_dynamic(
         &self,
@@ -228,12 +607,28 @@ impl Archetype {
 
         self.entities[self.len as usize] = id;
         self.len += 1;
+        self.entered = self.tick;
+        self.resize_ticks();
         self.len - 1
     }
 
     pub(crate) unsafe fn set_len(&mut self, len: u32) {
         debug_assert!(len <= self.capacity());
         self.len = len;
+        self.resize_ticks();
+    }
+
+    /// Match the number of per-entity ticks to `len`, recording new entities as added
+    fn resize_ticks(&mut self) {
+        if !self.tracking {
+            return;
+        }
+        let epoch = self.epoch;
+        for data in self.data.iter_mut() {
+            if let Some(ticks) = &mut data.ticks {
+                ticks.resize_with(self.len as usize, || RowTicks::new(epoch));
+            }
+        }
     }
 
     pub(crate) fn reserve(&mut self, additional: u32) {
@@ -243,10 +638,73 @@ impl Archetype {
         }
     }
 
-    pub(crate) fn capacity(&self) -> u32 {
+    /// Like `reserve`, but fails rather than aborting if memory can't be allocated
+    ///
+    /// Falls back to growing by exactly the shortfall if growing by the usual amount fails.
+    pub(crate) fn try_reserve(&mut self, additional: u32) -> Result<(), AllocError> {
+        let available = self.capacity() - self.len();
+        if additional <= available {
+            return Ok(());
+        }
+        let increment = additional - available;
+        let capacity = self.capacity();
+        let new_cap = |increment: u32| {
+            capacity
+                .checked_add(increment)
+                .map(|x| x as usize)
+                .ok_or(AllocError::CapacityOverflow)
+        };
+        let preferred = new_cap(Self::growth(capacity, self.len, additional));
+        preferred
+            .and_then(|x| self.try_set_capacity(x))
+            .or_else(|_| self.try_set_capacity(new_cap(increment)?))?;
+        self.column_grows += self.types.len() as u64;
+        Ok(())
+    }
+
+    /// Number of entities that can be stored without reallocating
+    pub fn capacity(&self) -> u32 {
         self.entities.len() as u32
     }
 
+    /// Number of entities `try_reserve(additional)` prefers to grow capacity by, given `capacity`
+    /// and `len`
+    fn growth(capacity: u32, len: u32, additional: u32) -> u32 {
+        let available = capacity - len;
+        if additional <= available {
+            return 0;
+        }
+        capacity.max((additional - available).max(64))
+    }
+
+    /// Bytes of component storage that `try_reserve(additional)` would allocate beyond the
+    /// current capacity
+    pub(crate) fn reserve_bytes(&self, additional: u32) -> usize {
+        Self::growth(self.capacity(), self.len, additional) as usize * self.row_size()
+    }
+
+    /// Bytes of component storage that reserving `additional` entities would allocate for a new
+    /// archetype whose components total `row_size` bytes per entity
+    pub(crate) fn new_reserve_bytes(row_size: usize, additional: u32) -> usize {
+        (Self::growth(0, 0, additional) as usize).saturating_mul(row_size)
+    }
+
+    /// Total size of one entity's components
+    fn row_size(&self) -> usize {
+        self.types.iter().map(|ty| ty.layout.size()).sum()
+    }
+
+    /// Count this archetype's reserved component bytes, now and as its capacity changes, in
+    /// `counter`
+    pub(crate) fn set_reserved_bytes(&mut self, counter: Arc<AtomicUsize>) {
+        let bytes = self.capacity() as usize * self.row_size();
+        if let Some(old) = self.reserved_bytes.take() {
+            old.fetch_sub(bytes, Ordering::Relaxed);
+        }
+        counter.fetch_add(bytes, Ordering::Relaxed);
+        self.reserved_bytes = Some(counter);
+    }
+
     /// Increase capacity by at least `min_increment`
     fn grow(&mut self, min_increment: u32) {
         // Double capacity or increase it by `min_increment`, whichever is larger.
@@ -255,42 +713,110 @@ impl Archetype {
 
     /// Increase capacity by exactly `increment`
     fn grow_exact(&mut self, increment: u32) {
+        self.column_grows += self.types.len() as u64;
+        self.set_capacity(self.entities.len() + increment as usize);
+    }
+
+    /// Number of columns grown since the previous call to `take_column_grows`
+    pub(crate) fn column_grows(&self) -> u64 {
+        self.column_grows
+    }
+
+    /// Reset the number of columns grown, returning its previous value
+    pub(crate) fn take_column_grows(&mut self) -> u64 {
+        mem::take(&mut self.column_grows)
+    }
+
+    /// Release any capacity beyond that needed for the current entities
+    pub(crate) fn shrink_to_fit(&mut self) {
+        if self.capacity() != self.len {
+            self.set_capacity(self.len as usize);
+        }
+    }
+
+    /// Reallocate storage to hold exactly `new_cap` entities, which must be at least `len`
+    fn set_capacity(&mut self, new_cap: usize) {
+        match self.try_set_capacity(new_cap) {
+            Ok(()) => {}
+            Err(AllocError::OutOfMemory(layout)) => alloc::alloc::handle_alloc_error(layout),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Like `set_capacity`, but leaves the archetype unchanged if memory can't be allocated
+    fn try_set_capacity(&mut self, new_cap: usize) -> Result<(), AllocError> {
+        self.try_set_capacity_for(new_cap).map_err(|(_, e)| e)
+    }
+
+    /// Like `try_set_capacity`, but also reports the component type whose column couldn't be
+    /// allocated, or `None` if the failure wasn't specific to one column
+    pub(crate) fn try_set_capacity_for(
+        &mut self,
+        new_cap: usize,
+    ) -> Result<(), (Option<TypeInfo>, AllocError)> {
+        debug_assert!(new_cap >= self.len as usize);
         let old_count = self.len as usize;
         let old_cap = self.entities.len();
-        let new_cap = self.entities.len() + increment as usize;
-        let mut new_entities = vec![!0; new_cap].into_boxed_slice();
-        new_entities[0..old_count].copy_from_slice(&self.entities[0..old_count]);
-        self.entities = new_entities;
-
-        let new_data = self
-            .types
-            .iter()
-            .zip(&*self.data)
-            .map(|(info, old)| {
-                let storage = if info.layout.size() == 0 {
-                    NonNull::new(info.layout.align() as *mut u8).unwrap()
-                } else {
-                    let layout =
-                        Layout::from_size_align(info.layout.size() * new_cap, info.layout.align())
-                            .unwrap();
-                    unsafe {
-                        let mem = alloc(layout);
-                        let mem = NonNull::new(mem)
-                            .unwrap_or_else(|| alloc::alloc::handle_alloc_error(layout));
-                        ptr::copy_nonoverlapping(
-                            old.storage.as_ptr(),
-                            mem.as_ptr(),
-                            info.layout.size() * old_count,
-                        );
-                        mem
+
+        // Allocate everything before modifying anything, so that failure leaves no trace
+        let mut new_entities = Vec::new();
+        try_reserve_exact(&mut new_entities, new_cap).map_err(|e| (None, e))?;
+        let mut new_data = Vec::<Data>::new();
+        try_reserve_exact(&mut new_data, self.types.len()).map_err(|e| (None, e))?;
+        for (info, old) in self.types.iter().zip(&*self.data) {
+            let storage = if info.layout.size() == 0 || new_cap == 0 {
+                Ok(NonNull::new(info.layout.align() as *mut u8).unwrap())
+            } else {
+                info.layout
+                    .size()
+                    .checked_mul(new_cap)
+                    .and_then(|size| Layout::from_size_align(size, info.layout.align()).ok())
+                    .ok_or(AllocError::CapacityOverflow)
+                    .and_then(|layout| {
+                        NonNull::new(unsafe { alloc(layout) })
+                            .ok_or(AllocError::OutOfMemory(layout))
+                    })
+            };
+            let storage = match storage {
+                Ok(storage) => storage,
+                Err(e) => {
+                    for (info, data) in self.types.iter().zip(&new_data) {
+                        if info.layout.size() != 0 && new_cap != 0 {
+                            unsafe {
+                                dealloc(
+                                    data.storage.as_ptr(),
+                                    Layout::from_size_align_unchecked(
+                                        info.layout.size() * new_cap,
+                                        info.layout.align(),
+                                    ),
+                                );
+                            }
+                        }
                     }
-                };
-                Data {
-                    state: AtomicBorrow::new(), // &mut self guarantees no outstanding borrows
-                    storage,
+                    return Err((Some(*info), e));
                 }
-            })
-            .collect::<Box<[_]>>();
+            };
+            new_data.push(Data {
+                state: AtomicBorrow::new(), // &mut self guarantees no outstanding borrows
+                storage,
+                changed: AtomicU32::new(old.changed.load(Ordering::Relaxed)),
+                version: AtomicU32::new(old.version.load(Ordering::Relaxed)),
+                ticks: None,
+            });
+        }
+
+        new_entities.extend_from_slice(&self.entities[0..old_count]);
+        new_entities.resize(new_cap, !0);
+        self.entities = new_entities.into_boxed_slice();
+        for ((info, old), new) in self.types.iter().zip(&*self.data).zip(&new_data) {
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    old.storage.as_ptr(),
+                    new.storage.as_ptr(),
+                    info.layout.size() * old_count,
+                );
+            }
+        }
 
         // Now that we've successfully constructed a replacement, we can
         // deallocate the old column data without risking `self.data` being left
@@ -310,55 +836,250 @@ impl Archetype {
             }
         }
 
-        self.data = new_data;
+        for (old, new) in self.data.iter_mut().zip(&mut new_data) {
+            new.ticks = old.ticks.take();
+        }
+        self.data = new_data.into_boxed_slice();
+        if let Some(ref counter) = self.reserved_bytes {
+            let row = self.row_size();
+            counter.fetch_add(new_cap * row, Ordering::Relaxed);
+            counter.fetch_sub(old_cap * row, Ordering::Relaxed);
+        }
+        Ok(())
     }
 
-    /// Returns the ID of the entity moved into `index`, if any
-    pub(crate) unsafe fn remove(&mut self, index: u32, drop: bool) -> Option<u32> {
-        let last = self.len - 1;
-        for (ty, data) in self.types.iter().zip(&*self.data) {
-            let removed = data.storage.as_ptr().add(index as usize * ty.layout.size());
+    /// Remove the entity at `index`, dropping its components if `drop` is set
+    ///
+    /// Returns the indices of the entities that were moved to fill the gap, whose locations must be
+    /// updated.
+    pub(crate) unsafe fn remove(&mut self, index: u32, drop: bool) -> Range<u32> {
+        self.remove_inner(index, |removed, ty| {
             if drop {
                 (ty.drop)(removed);
             }
-            if index != last {
-                let moved = data.storage.as_ptr().add(last as usize * ty.layout.size());
-                ptr::copy_nonoverlapping(moved, removed, ty.layout.size());
-            }
-        }
-        self.len = last;
-        if index != last {
-            self.entities[index as usize] = self.entities[last as usize];
-            Some(self.entities[last as usize])
-        } else {
-            None
-        }
+        })
     }
 
-    /// Returns the ID of the entity moved into `index`, if any
+    /// Pass each component of the entity at `index` to `f`, then remove it without dropping
+    ///
+    /// Returns the indices of the entities that were moved to fill the gap, whose locations must be
+    /// updated.
     pub(crate) unsafe fn move_to(
         &mut self,
         index: u32,
         mut f: impl FnMut(*mut u8, TypeId, usize),
-    ) -> Option<u32> {
+    ) -> Range<u32> {
+        self.remove_inner(index, |moved_out, ty| f(moved_out, ty.id, ty.layout.size()))
+    }
+
+    unsafe fn remove_inner(
+        &mut self,
+        index: u32,
+        mut f: impl FnMut(*mut u8, &TypeInfo),
+    ) -> Range<u32> {
         let last = self.len - 1;
+        let stable = self.stable_removal || self.sort_key.is_some();
         for (ty, data) in self.types.iter().zip(&*self.data) {
-            let moved_out = data.storage.as_ptr().add(index as usize * ty.layout.size());
-            f(moved_out, ty.id(), ty.layout().size());
-            if index != last {
-                let moved = data.storage.as_ptr().add(last as usize * ty.layout.size());
-                ptr::copy_nonoverlapping(moved, moved_out, ty.layout.size());
+            let size = ty.layout.size();
+            let moved_out = data.storage.as_ptr().add(index as usize * size);
+            f(moved_out, ty);
+            if index == last {
+                continue;
+            }
+            if stable {
+                // Shift every following entity down by one to preserve order
+                let next = data.storage.as_ptr().add((index as usize + 1) * size);
+                ptr::copy(next, moved_out, (last - index) as usize * size);
+            } else {
+                let moved = data.storage.as_ptr().add(last as usize * size);
+                ptr::copy_nonoverlapping(moved, moved_out, size);
+            }
+        }
+        self.len = last;
+        if self.tracking {
+            for data in self.data.iter_mut() {
+                if let Some(ticks) = &mut data.ticks {
+                    if stable {
+                        ticks.remove(index as usize);
+                    } else {
+                        ticks.swap_remove(index as usize);
+                    }
+                }
             }
         }
-        self.len -= 1;
-        if index != last {
+        if index == last {
+            index..index
+        } else if stable {
+            self.entities
+                .copy_within(index as usize + 1..=last as usize, index as usize);
+            index..last
+        } else {
             self.entities[index as usize] = self.entities[last as usize];
-            Some(self.entities[last as usize])
+            index..index + 1
+        }
+    }
+
+    /// Set whether removing an entity preserves the order of the entities that follow it
+    ///
+    /// Order is always preserved in archetypes with a sort key.
+    pub(crate) fn set_stable_removal(&mut self, stable: bool) {
+        self.stable_removal = stable;
+    }
+
+    /// Keep rows sorted by the component identified by `id` using `cmp`, if present
+    ///
+    /// Returns whether the key was applied. Rows aren't sorted until the next call to `sort`.
+    pub(crate) fn set_sort_key(&mut self, id: TypeId, cmp: KeyCmp) -> bool {
+        let Some(&state) = self.index.get(&id) else {
+            return false;
+        };
+        self.sort_key = Some(SortKey { state, cmp });
+        *self.unsorted.get_mut() = true;
+        true
+    }
+
+    /// The type of the component by which rows are kept sorted, if any
+    ///
+    /// See [`World::keep_sorted_by_key`](crate::World::keep_sorted_by_key).
+    pub fn sort_key(&self) -> Option<TypeId> {
+        Some(self.types[self.sort_key.as_ref()?.state].id)
+    }
+
+    /// Whether rows are currently sorted by `T`, allowing binary search
+    pub(crate) fn is_sorted_by<T: Component>(&self) -> bool {
+        self.sort_key.as_ref().map_or(false, |x| {
+            self.types[x.state].id == TypeId::of::<T>() && !self.unsorted.load(Ordering::Relaxed)
+        })
+    }
+
+    /// Compare the sort keys of the rows at `a` and `b`
+    unsafe fn cmp_rows(&self, key: &SortKey, a: u32, b: u32) -> cmp::Ordering {
+        let size = self.types[key.state].layout.size();
+        let base = self.data[key.state].storage.as_ptr();
+        (key.cmp)(base.add(a as usize * size), base.add(b as usize * size))
+    }
+
+    /// Move the row at `index` to its sorted position, assuming all other rows are sorted
+    ///
+    /// Returns the indices of the entities that were moved, whose locations must be updated.
+    pub(crate) fn reposition(&mut self, index: u32) -> Range<u32> {
+        let Some(key) = self.sort_key else {
+            return index..index;
+        };
+        if *self.unsorted.get_mut() {
+            return self.sort();
+        }
+        unsafe {
+            if index > 0 && self.cmp_rows(&key, index - 1, index) == cmp::Ordering::Greater {
+                // Find the first preceding row with a greater key and shift the rest up
+                let (mut lo, mut hi) = (0, index - 1);
+                while lo < hi {
+                    let mid = lo + (hi - lo) / 2;
+                    if self.cmp_rows(&key, mid, index) == cmp::Ordering::Greater {
+                        hi = mid;
+                    } else {
+                        lo = mid + 1;
+                    }
+                }
+                self.rotate_rows(lo..index + 1, true);
+                lo..index + 1
+            } else if index + 1 < self.len
+                && self.cmp_rows(&key, index + 1, index) == cmp::Ordering::Less
+            {
+                // Find the last following row with a lesser or equal key and shift the rest down
+                let (mut lo, mut hi) = (index + 1, self.len - 1);
+                while lo < hi {
+                    let mid = hi - (hi - lo) / 2;
+                    if self.cmp_rows(&key, mid, index) != cmp::Ordering::Greater {
+                        lo = mid;
+                    } else {
+                        hi = mid - 1;
+                    }
+                }
+                self.rotate_rows(index..lo + 1, false);
+                index..lo + 1
+            } else {
+                index..index
+            }
+        }
+    }
+
+    /// Rotate the rows in `range` by one, moving the last row to the front if `right` is set, or
+    /// the first row to the back otherwise
+    fn rotate_rows(&mut self, range: Range<u32>, right: bool) {
+        let (start, len) = (range.start as usize, range.len());
+        for (ty, data) in self.types.iter().zip(self.data.iter_mut()) {
+            let size = ty.layout.size();
+            let bytes = unsafe {
+                core::slice::from_raw_parts_mut(data.storage.as_ptr().add(start * size), len * size)
+            };
+            if right {
+                bytes.rotate_right(size);
+            } else {
+                bytes.rotate_left(size);
+            }
+            if let Some(ticks) = &mut data.ticks {
+                let ticks = &mut ticks[start..start + len];
+                if right {
+                    ticks.rotate_right(1);
+                } else {
+                    ticks.rotate_left(1);
+                }
+            }
+        }
+        let entities = &mut self.entities[range.start as usize..range.end as usize];
+        if right {
+            entities.rotate_right(1);
         } else {
-            None
+            entities.rotate_left(1);
         }
     }
 
+    /// Stably sort all rows by the sort key, if any
+    ///
+    /// Returns the indices of the entities that were moved, whose locations must be updated.
+    pub(crate) fn sort(&mut self) -> Range<u32> {
+        let Some(key) = self.sort_key else {
+            return 0..0;
+        };
+        *self.unsorted.get_mut() = false;
+        let sorted = (1..self.len)
+            .all(|i| unsafe { self.cmp_rows(&key, i - 1, i) } != cmp::Ordering::Greater);
+        if sorted {
+            return 0..0;
+        }
+
+        // Row `i` of the result is row `order[i]` of the input
+        let mut order = (0..self.len).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| unsafe { self.cmp_rows(&key, a, b) });
+
+        // Apply the permutation one cycle at a time
+        let mut done = vec![false; self.len as usize];
+        for start in 0..self.len as usize {
+            let mut i = start;
+            while !done[i] {
+                done[i] = true;
+                let next = order[i] as usize;
+                if next == start {
+                    break;
+                }
+                for (ty, data) in self.types.iter().zip(self.data.iter_mut()) {
+                    let size = ty.layout.size();
+                    unsafe {
+                        let base = data.storage.as_ptr();
+                        ptr::swap_nonoverlapping(base.add(i * size), base.add(next * size), size);
+                    }
+                    if let Some(ticks) = &mut data.ticks {
+                        ticks.swap(i, next);
+                    }
+                }
+                self.entities.swap(i, next);
+                i = next;
+            }
+        }
+        0..self.len
+    }
+
     pub(crate) unsafe fn put_dynamic(
         &mut self,
         component: *mut u8,
@@ -366,6 +1087,9 @@ impl Archetype {
         size: usize,
         index: u32,
     ) {
+        let state = *self.index.get(&ty).unwrap();
+        self.mark_changed(state);
+        self.mark_row_changed(state, index);
         let ptr = self
             .get_dynamic(ty, size, index)
             .unwrap()
@@ -402,6 +1126,9 @@ impl Archetype {
         }
         self.len += other.len;
         other.len = 0;
+        self.entered = self.tick;
+        self.mark_all_changed();
+        self.resize_ticks();
     }
 
     /// Raw IDs of the entities in this archetype
@@ -418,6 +1145,9 @@ impl Archetype {
 impl Drop for Archetype {
     fn drop(&mut self) {
         self.clear();
+        if let Some(ref counter) = self.reserved_bytes {
+            counter.fetch_sub(self.entities.len() * self.row_size(), Ordering::Relaxed);
+        }
         if self.entities.is_empty() {
             return;
         }
@@ -437,9 +1167,42 @@ impl Drop for Archetype {
     }
 }
 
+/// Compares two components of the same type
+pub(crate) type KeyCmp = unsafe fn(*const u8, *const u8) -> cmp::Ordering;
+
+#[derive(Copy, Clone)]
+struct SortKey {
+    /// Index of the key column
+    state: usize,
+    cmp: KeyCmp,
+}
+
 struct Data {
     state: AtomicBorrow,
     storage: NonNull<u8>,
+    /// Tick of the most recent modification
+    changed: AtomicU32,
+    /// Number of modifications, wrapping on overflow
+    version: AtomicU32,
+    /// One entry per entity if changes to this component are tracked per entity
+    ticks: Option<Vec<RowTicks>>,
+}
+
+/// Epochs at which a tracked component was added to and last modified in one entity
+///
+/// See [`World::track_changes`](crate::World::track_changes).
+pub(crate) struct RowTicks {
+    pub(crate) added: AtomicU32,
+    pub(crate) changed: AtomicU32,
+}
+
+impl RowTicks {
+    fn new(epoch: u32) -> Self {
+        Self {
+            added: AtomicU32::new(epoch),
+            changed: AtomicU32::new(epoch),
+        }
+    }
 }
 
 /// A hasher optimized for hashing a single TypeId.
@@ -486,11 +1249,14 @@ impl Hasher for TypeIdHasher {
 /// faster no-op hash.
 pub type TypeIdMap<V> = HashMap<TypeId, V, BuildHasherDefault<TypeIdHasher>>;
 
-struct OrderedTypeIdMap<V>(Box<[(TypeId, V)]>);
+/// Number of component types an archetype can have before its type lists spill to the heap
+const INLINE_TYPES: usize = 16;
+
+struct OrderedTypeIdMap<V: Copy>(InlineVec<(TypeId, V), INLINE_TYPES>);
 
-impl<V> OrderedTypeIdMap<V> {
+impl<V: Copy> OrderedTypeIdMap<V> {
     fn new(iter: impl Iterator<Item = (TypeId, V)>) -> Self {
-        let mut vals = iter.collect::<Box<[_]>>();
+        let mut vals = iter.collect::<InlineVec<_, INLINE_TYPES>>();
         vals.sort_unstable_by_key(|(id, _)| *id);
         Self(vals)
     }
@@ -604,6 +1370,46 @@ impl PartialEq for TypeInfo {
 
 impl Eq for TypeInfo {}
 
+/// Shared borrow of a column of dynamically typed component data, released on drop
+pub(crate) struct RawColumn<'a> {
+    archetype: &'a Archetype,
+    state: usize,
+}
+
+impl<'a> RawColumn<'a> {
+    pub(crate) fn new(archetype: &'a Archetype, id: TypeId) -> Option<Self> {
+        let state = archetype.get_state_dynamic(id)?;
+        unsafe {
+            archetype.borrow_raw(state);
+        }
+        Some(Self { archetype, state })
+    }
+
+    /// Address of the first component
+    pub(crate) fn base(&self) -> *const u8 {
+        self.archetype.data[self.state].storage.as_ptr()
+    }
+
+    /// The column's contents as raw bytes
+    ///
+    /// # Safety
+    ///
+    /// The component type must be plain old data, i.e. contain no padding or other uninitialized
+    /// bytes.
+    pub(crate) unsafe fn bytes(&self) -> &[u8] {
+        let len = self.archetype.types[self.state].layout.size() * self.archetype.len() as usize;
+        core::slice::from_raw_parts(self.base(), len)
+    }
+}
+
+impl Drop for RawColumn<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            self.archetype.release_raw(self.state);
+        }
+    }
+}
+
 /// Shared reference to a single column of component data in an [`Archetype`]
 pub struct ArchetypeColumn<'a, T: Component> {
     archetype: &'a Archetype,
@@ -664,6 +1470,7 @@ impl<'a, T: Component> ArchetypeColumnMut<'a, T> {
         let column =
             unsafe { core::slice::from_raw_parts_mut(ptr.as_ptr(), archetype.len() as usize) };
         archetype.borrow_mut::<T>(state);
+        archetype.mark_rows_changed(state);
         Some(Self { archetype, column })
     }
 }
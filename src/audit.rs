@@ -0,0 +1,152 @@
+use crate::alloc::collections::VecDeque;
+use crate::alloc::vec::Vec;
+use core::panic::Location;
+
+use crate::Entity;
+
+/// A record of an entity ID changing hands, kept when the `audit-log` feature is enabled
+///
+/// Retrieved with [`World::audit_history`](crate::World::audit_history) to find out which code
+/// spawned, reserved, or despawned an entity.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct AuditEvent {
+    sequence: u64,
+    entity: Entity,
+    op: AuditOp,
+    tag: Option<&'static str>,
+    caller: &'static Location<'static>,
+}
+
+impl AuditEvent {
+    /// Position of the event among all events recorded by the world, starting from 0
+    ///
+    /// Gaps indicate events that were discarded once the log reached capacity.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// The entity that was allocated or freed
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// What happened to the entity
+    pub fn op(&self) -> AuditOp {
+        self.op
+    }
+
+    /// The tag set by [`World::set_audit_tag`](crate::World::set_audit_tag) when the event
+    /// occurred
+    pub fn tag(&self) -> Option<&'static str> {
+        self.tag
+    }
+
+    /// The outermost `World` method call that caused the event
+    ///
+    /// Operations deferred by hecs, such as dropping the result of
+    /// [`World::take`](crate::World::take), report a location inside hecs; set a tag to identify
+    /// those.
+    pub fn caller(&self) -> &'static Location<'static> {
+        self.caller
+    }
+}
+
+/// Kinds of [`AuditEvent`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum AuditOp {
+    /// The entity was spawned with a fresh or recycled ID
+    Alloc,
+    /// The entity was spawned with a caller-chosen handle, e.g. by
+    /// [`World::spawn_at`](crate::World::spawn_at)
+    AllocAt,
+    /// The entity was reserved, e.g. by [`World::reserve_entity`](crate::World::reserve_entity)
+    Reserve,
+    /// The entity was despawned
+    Free,
+}
+
+/// Bounded record of the most recent [`AuditEvent`]s
+pub(crate) struct AuditLog {
+    events: VecDeque<AuditEvent>,
+    capacity: usize,
+    next_sequence: u64,
+    tag: Option<&'static str>,
+}
+
+impl AuditLog {
+    pub fn record(&mut self, op: AuditOp, entity: Entity, caller: &'static Location<'static>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(AuditEvent {
+            sequence: self.next_sequence,
+            entity,
+            op,
+            tag: self.tag,
+            caller,
+        });
+        self.next_sequence += 1;
+    }
+
+    /// Events concerning any generation of the entity ID `id`, oldest first
+    pub fn history(&self, id: u32) -> Vec<AuditEvent> {
+        self.events
+            .iter()
+            .filter(|event| event.entity.id() == id)
+            .copied()
+            .collect()
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        let excess = self.events.len().saturating_sub(capacity);
+        self.events.drain(..excess);
+    }
+
+    pub fn set_tag(&mut self, tag: Option<&'static str>) {
+        self.tag = tag;
+    }
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self {
+            events: VecDeque::new(),
+            capacity: DEFAULT_CAPACITY,
+            next_sequence: 0,
+            tag: None,
+        }
+    }
+}
+
+/// Number of events retained by a new world
+const DEFAULT_CAPACITY: usize = 4096;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded() {
+        let mut log = AuditLog::default();
+        log.set_capacity(3);
+        let caller = Location::caller();
+        for id in 0..5 {
+            log.record(
+                AuditOp::Alloc,
+                Entity::from_bits(1 << 32 | id).unwrap(),
+                caller,
+            );
+        }
+        assert!(log.history(1).is_empty());
+        let history = log.history(4);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].sequence(), 4);
+        log.set_capacity(1);
+        assert!(log.history(3).is_empty());
+        assert_eq!(log.history(4).len(), 1);
+    }
+}
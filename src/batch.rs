@@ -3,7 +3,7 @@ use core::{any::TypeId, fmt, mem::MaybeUninit, slice};
 
 use crate::{
     archetype::{TypeIdMap, TypeInfo},
-    Archetype, Component,
+    AllocError, Archetype, Component,
 };
 
 /// A collection of component types
@@ -32,15 +32,33 @@ impl ColumnBatchType {
 
     /// Construct a [`ColumnBatchBuilder`] for *exactly* `size` entities with these components
     pub fn into_batch(self, size: u32) -> ColumnBatchBuilder {
+        let mut builder = self.into_empty_batch(size);
+        builder.archetype.as_mut().unwrap().reserve(size);
+        builder
+    }
+
+    /// Like [`into_batch`](Self::into_batch), but fails rather than aborting if storage for the
+    /// components can't be allocated
+    pub fn try_into_batch(self, size: u32) -> Result<ColumnBatchBuilder, BatchAllocError> {
+        let mut builder = self.into_empty_batch(size);
+        builder
+            .archetype
+            .as_mut()
+            .unwrap()
+            .try_set_capacity_for(size as usize)
+            .map_err(|(ty, error)| BatchAllocError { ty, size, error })?;
+        Ok(builder)
+    }
+
+    /// Construct a builder for `size` entities without allocating storage for them
+    fn into_empty_batch(self, size: u32) -> ColumnBatchBuilder {
         let mut types = self.types.into_sorted_vec();
         types.dedup();
         let fill = TypeIdMap::with_capacity_and_hasher(types.len(), Default::default());
-        let mut arch = Archetype::new(types);
-        arch.reserve(size);
         ColumnBatchBuilder {
             fill,
             target_fill: size,
-            archetype: Some(arch),
+            archetype: Some(Archetype::new(types)),
         }
     }
 }
@@ -62,6 +80,12 @@ impl ColumnBatchBuilder {
         ty.into_batch(size)
     }
 
+    /// Like [`new`](Self::new), but fails rather than aborting if storage for the components can't
+    /// be allocated
+    pub fn try_new(ty: ColumnBatchType, size: u32) -> Result<Self, BatchAllocError> {
+        ty.try_into_batch(size)
+    }
+
     /// Get a handle for inserting `T` components if `T` was in the [`ColumnBatchType`]
     pub fn writer<T: Component>(&mut self) -> Option<BatchWriter<'_, T>> {
         let archetype = self.archetype.as_mut().unwrap();
@@ -76,6 +100,57 @@ impl ColumnBatchBuilder {
         })
     }
 
+    /// Move a component of type `ty` from `src` into the next free slot of its column
+    ///
+    /// `src` must point to a valid instance of `ty`, which must be one of the batch's types. The
+    /// pointee must not be used or dropped afterwards. Returns `false` if the column is full, in
+    /// which case the pointee is left untouched.
+    pub(crate) unsafe fn push_dynamic(&mut self, ty: TypeInfo, src: *mut u8) -> bool {
+        let archetype = self.archetype.as_mut().unwrap();
+        let fill = self.fill.entry(ty.id()).or_insert(0);
+        if *fill == self.target_fill {
+            return false;
+        }
+        let size = ty.layout().size();
+        let base = archetype
+            .get_dynamic(ty.id(), size, 0)
+            .expect("type not in batch");
+        core::ptr::copy_nonoverlapping(src, base.as_ptr().add(size * *fill as usize), size);
+        *fill += 1;
+        true
+    }
+
+    /// Copy `count` contiguous components of type `ty` from `src` into the next free slots of its
+    /// column
+    ///
+    /// `src` need not be aligned, but must otherwise point to `count` valid instances of `ty`,
+    /// which must be one of the batch's types. The pointees must not be used or dropped afterwards.
+    /// Returns `false` if the column has fewer than `count` free slots, in which case nothing is
+    /// copied.
+    pub(crate) unsafe fn extend_dynamic(
+        &mut self,
+        ty: TypeInfo,
+        src: *const u8,
+        count: u32,
+    ) -> bool {
+        let archetype = self.archetype.as_mut().unwrap();
+        let fill = self.fill.entry(ty.id()).or_insert(0);
+        if self.target_fill - *fill < count {
+            return false;
+        }
+        let size = ty.layout().size();
+        let base = archetype
+            .get_dynamic(ty.id(), size, 0)
+            .expect("type not in batch");
+        core::ptr::copy_nonoverlapping(
+            src,
+            base.as_ptr().add(size * *fill as usize),
+            size * count as usize,
+        );
+        *fill += count;
+        true
+    }
+
     /// Finish the batch, failing if any components are missing
     pub fn build(mut self) -> Result<ColumnBatch, BatchIncomplete> {
         let mut archetype = self.archetype.take().unwrap();
@@ -152,6 +227,59 @@ impl fmt::Display for BatchIncomplete {
     }
 }
 
+/// Error indicating that storage for a [`ColumnBatchBuilder`] couldn't be allocated
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BatchAllocError {
+    ty: Option<TypeInfo>,
+    size: u32,
+    error: AllocError,
+}
+
+impl BatchAllocError {
+    /// The component type whose column couldn't be allocated, or `None` if the failure was in
+    /// storage shared by every column
+    pub fn component_type(&self) -> Option<TypeInfo> {
+        self.ty
+    }
+
+    /// The number of entities the batch was to hold
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// The underlying allocation failure
+    pub fn error(&self) -> &AllocError {
+        &self.error
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BatchAllocError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl fmt::Display for BatchAllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ty {
+            Some(ty) => write!(
+                f,
+                "failed to allocate a column of {} components of {} bytes",
+                self.size,
+                ty.layout().size()
+            ),
+            None => write!(f, "failed to allocate a batch of {} entities", self.size),
+        }
+    }
+}
+
+impl From<BatchAllocError> for AllocError {
+    fn from(x: BatchAllocError) -> Self {
+        x.error
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -5,6 +5,14 @@ const UNIQUE_BIT: usize = !(usize::MAX >> 1);
 
 const COUNTER_MASK: usize = usize::MAX >> 1;
 
+/// Whether borrows are tracked at all
+///
+/// Release builds made with `RUSTFLAGS="--cfg hecs_unchecked_borrow"` skip dynamic borrow checking
+/// entirely, so conflicting borrows through safe APIs are undefined behavior. This is a rustflag
+/// rather than a Cargo feature so that only the final binary's builder can opt in; a dependency
+/// can't enable it for everyone else in the graph.
+const CHECKED: bool = !cfg!(all(hecs_unchecked_borrow, not(debug_assertions)));
+
 /// An atomic integer used to dynamicaly enforce borrowing rules
 ///
 /// The most significant bit is used to track mutable borrow, and the rest is a
@@ -23,6 +31,10 @@ impl AtomicBorrow {
     }
 
     pub fn borrow(&self) -> bool {
+        if !CHECKED {
+            return true;
+        }
+
         // Add one to the borrow counter
         let prev_value = self.0.fetch_add(1, Ordering::Acquire);
 
@@ -42,18 +54,27 @@ impl AtomicBorrow {
     }
 
     pub fn borrow_mut(&self) -> bool {
+        if !CHECKED {
+            return true;
+        }
         self.0
             .compare_exchange(0, UNIQUE_BIT, Ordering::Acquire, Ordering::Relaxed)
             .is_ok()
     }
 
     pub fn release(&self) {
+        if !CHECKED {
+            return;
+        }
         let value = self.0.fetch_sub(1, Ordering::Release);
         debug_assert!(value != 0, "unbalanced release");
         debug_assert!(value & UNIQUE_BIT == 0, "shared release of unique borrow");
     }
 
     pub fn release_mut(&self) {
+        if !CHECKED {
+            return;
+        }
         let value = self.0.fetch_and(!UNIQUE_BIT, Ordering::Release);
         debug_assert_ne!(value & UNIQUE_BIT, 0, "unique release of shared borrow");
     }
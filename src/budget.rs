@@ -0,0 +1,130 @@
+use crate::alloc::boxed::Box;
+use core::fmt;
+
+/// Limits on the resources used by a [`World`](crate::World)
+///
+/// Installed as hard caps with [`World::set_budget`](crate::World::set_budget), or as soft
+/// thresholds with [`World::set_soft_budget`](crate::World::set_soft_budget). `None` imposes no
+/// limit.
+///
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// world.set_budget(Budget {
+///     entities: Some(2),
+///     ..Budget::default()
+/// });
+/// world.try_spawn((1,)).unwrap();
+/// world.try_spawn((2,)).unwrap();
+/// assert_eq!(
+///     world.try_spawn((3,)),
+///     Err(TrySpawnError::Alloc(AllocError::BudgetExceeded(
+///         BudgetKind::Entities
+///     )))
+/// );
+/// ```
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Budget {
+    /// Maximum number of live entities
+    pub entities: Option<u32>,
+    /// Maximum total size, in bytes, of the component storage reserved by all archetypes,
+    /// including capacity not yet occupied by entities
+    pub component_bytes: Option<usize>,
+    /// Maximum number of archetypes, including the empty archetype every world has
+    pub archetypes: Option<usize>,
+}
+
+impl Budget {
+    /// The first resource of `usage` that exceeds its limit, if any
+    pub fn exceeded(&self, usage: &BudgetUsage) -> Option<BudgetKind> {
+        BudgetKind::ALL
+            .into_iter()
+            .find(|&kind| self.exceeds(kind, usage))
+    }
+
+    /// Whether `usage` of `kind` exceeds its limit
+    fn exceeds(&self, kind: BudgetKind, usage: &BudgetUsage) -> bool {
+        match kind {
+            BudgetKind::Entities => self.entities.map_or(false, |x| usage.entities > x),
+            BudgetKind::ComponentBytes => self
+                .component_bytes
+                .map_or(false, |x| usage.component_bytes > x),
+            BudgetKind::Archetypes => self.archetypes.map_or(false, |x| usage.archetypes > x),
+        }
+    }
+
+    pub(crate) fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Resources used by a [`World`](crate::World), as limited by a [`Budget`]
+///
+/// Obtained from [`World::budget_usage`](crate::World::budget_usage).
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct BudgetUsage {
+    /// Number of live entities
+    pub entities: u32,
+    /// Total size, in bytes, of the component storage reserved by all archetypes
+    pub component_bytes: usize,
+    /// Number of archetypes
+    pub archetypes: usize,
+}
+
+/// A resource limited by a [`Budget`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum BudgetKind {
+    /// See [`Budget::entities`]
+    Entities,
+    /// See [`Budget::component_bytes`]
+    ComponentBytes,
+    /// See [`Budget::archetypes`]
+    Archetypes,
+}
+
+impl BudgetKind {
+    const ALL: [Self; 3] = [Self::Entities, Self::ComponentBytes, Self::Archetypes];
+}
+
+impl fmt::Display for BudgetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match *self {
+            BudgetKind::Entities => "entity",
+            BudgetKind::ComponentBytes => "component memory",
+            BudgetKind::Archetypes => "archetype",
+        })
+    }
+}
+
+/// Callback registered with [`World::set_soft_budget`](crate::World::set_soft_budget)
+pub(crate) type BudgetCallback = Box<dyn FnMut(BudgetKind, &BudgetUsage) + Send + Sync>;
+
+/// Soft thresholds and the callback notified when they're crossed
+pub(crate) struct SoftBudget {
+    limits: Budget,
+    callback: BudgetCallback,
+    /// Whether each resource, indexed as in `BudgetKind::ALL`, was over its threshold when last
+    /// checked, so that the callback runs once per crossing
+    over: [bool; 3],
+}
+
+impl SoftBudget {
+    pub(crate) fn new(limits: Budget, callback: BudgetCallback) -> Self {
+        Self {
+            limits,
+            callback,
+            over: [false; 3],
+        }
+    }
+
+    /// Notify the callback of each resource in `usage` that newly exceeds its threshold
+    pub(crate) fn check(&mut self, usage: &BudgetUsage) {
+        for (kind, over) in BudgetKind::ALL.into_iter().zip(&mut self.over) {
+            let now = self.limits.exceeds(kind, usage);
+            if now && !*over {
+                (self.callback)(kind, usage);
+            }
+            *over = now;
+        }
+    }
+}
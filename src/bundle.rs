@@ -127,6 +127,11 @@ impl MissingComponent {
     pub fn new<T: Component>() -> Self {
         Self(type_name::<T>())
     }
+
+    /// Name of the missing component type, as given by [`core::any::type_name`]
+    pub fn type_name(&self) -> &'static str {
+        self.0
+    }
 }
 
 impl fmt::Display for MissingComponent {
@@ -8,12 +8,22 @@ use crate::alloc::vec::Vec;
 use crate::archetype::TypeInfo;
 use crate::{align, DynamicBundle};
 use crate::{Bundle, Entity};
-use crate::{Component, World};
+use crate::{Component, ComponentError, World};
 
 /// Records operations for future application to a [`World`]
 ///
 /// Useful when operations cannot be applied directly due to ordering concerns or borrow checking.
 ///
+/// # Ordering
+///
+/// When the buffer is [run](Self::run_on), entities reserved from the world are
+/// [flushed](World::flush) first, in the order they were reserved, and then every command is
+/// applied in the order it was recorded, regardless of its kind. Entities recorded with
+/// [`spawn`](Self::spawn) are allocated as their command is applied, so they reuse the IDs of
+/// entities despawned by earlier commands in the same buffer. Consequently, running identical
+/// buffers on identical worlds, e.g. on every peer of a lockstep simulation, always yields
+/// identical worlds, including the handles of every spawned entity.
+///
 /// ```
 /// # use hecs::*;
 /// let mut world = World::new();
@@ -30,6 +40,7 @@ pub struct CommandBuffer {
     cursor: usize,
     components: Vec<ComponentInfo>,
     ids: Vec<TypeId>,
+    policy: DeadEntityPolicy,
 }
 
 impl CommandBuffer {
@@ -70,12 +81,46 @@ impl CommandBuffer {
         self.cursor = end;
     }
 
+    /// Set how inserts and removals targeting entities that no longer exist when the buffer is
+    /// run are handled, unless overridden for an individual command
+    ///
+    /// Defaults to [`DeadEntityPolicy::Skip`].
+    pub fn set_dead_entity_policy(&mut self, policy: DeadEntityPolicy) -> &mut Self {
+        self.policy = policy;
+        self
+    }
+
     /// Add components from `bundle` to `entity`, if it exists
     ///
-    /// Pairs well with [`World::reserve_entity`] to spawn entities with a known handle.
+    /// Pairs well with [`World::reserve_entity`] to spawn entities with a known handle. Since
+    /// reserving only requires shared access to the world, a system that can't modify the world
+    /// can reserve entities, refer to them in any number of recorded commands and components, and
+    /// have them all materialize when the buffer is [run](Self::run_on).
     ///
     /// When inserting a single component, see [`insert_one`](Self::insert_one) for convenience.
     pub fn insert(&mut self, entity: Entity, components: impl DynamicBundle) {
+        self.insert_inner(entity, components, None);
+    }
+
+    /// Add components from `bundle` to `entity`, handling `entity` no longer existing according
+    /// to `policy`
+    ///
+    /// See [`insert`](Self::insert) and [`set_dead_entity_policy`](Self::set_dead_entity_policy).
+    pub fn insert_with_policy(
+        &mut self,
+        entity: Entity,
+        components: impl DynamicBundle,
+        policy: DeadEntityPolicy,
+    ) {
+        self.insert_inner(entity, components, Some(policy));
+    }
+
+    fn insert_inner(
+        &mut self,
+        entity: Entity,
+        components: impl DynamicBundle,
+        policy: Option<DeadEntityPolicy>,
+    ) {
         let first_component = self.components.len();
         unsafe {
             components.put(|ptr, ty| self.add_inner(ptr, ty));
@@ -84,6 +129,7 @@ impl CommandBuffer {
         self.cmds.push(Cmd::SpawnOrInsert(EntityIndex {
             entity: Some(entity),
             components: first_component..self.components.len(),
+            policy,
         }));
     }
 
@@ -98,12 +144,29 @@ impl CommandBuffer {
     ///
     /// When removing a single component, see [`remove_one`](Self::remove_one) for convenience.
     pub fn remove<T: Bundle + 'static>(&mut self, ent: Entity) {
-        fn remove_bundle_and_ignore_result<T: Bundle + 'static>(world: &mut World, ents: Entity) {
-            let _ = world.remove::<T>(ents);
+        self.remove_inner::<T>(ent, None);
+    }
+
+    /// Remove components from `entity` if they exist, handling `entity` no longer existing
+    /// according to `policy`
+    ///
+    /// See [`remove`](Self::remove) and [`set_dead_entity_policy`](Self::set_dead_entity_policy).
+    pub fn remove_with_policy<T: Bundle + 'static>(
+        &mut self,
+        ent: Entity,
+        policy: DeadEntityPolicy,
+    ) {
+        self.remove_inner::<T>(ent, Some(policy));
+    }
+
+    fn remove_inner<T: Bundle + 'static>(&mut self, ent: Entity, policy: Option<DeadEntityPolicy>) {
+        fn remove_bundle<T: Bundle + 'static>(world: &mut World, ents: Entity) -> bool {
+            !matches!(world.remove::<T>(ents), Err(ComponentError::NoSuchEntity))
         }
         self.cmds.push(Cmd::Remove(RemovedComps {
-            remove: remove_bundle_and_ignore_result::<T>,
+            remove: remove_bundle::<T>,
             entity: ent,
+            policy,
         }));
     }
 
@@ -132,19 +195,44 @@ impl CommandBuffer {
         self.cmds.push(Cmd::SpawnOrInsert(EntityIndex {
             entity: None,
             components: first_component..self.components.len(),
+            policy: None,
         }));
     }
 
     /// Run recorded commands on `world`, clearing the command buffer
-    pub fn run_on(&mut self, world: &mut World) {
+    ///
+    /// Entities reserved with [`World::reserve_entity`] or [`World::reserve_entities`] are
+    /// [flushed](World::flush) before any command runs, so every reserved entity exists while
+    /// commands targeting it are applied, regardless of the order they were recorded in.
+    ///
+    /// Inserts and removals targeting entities that no longer exist are handled according to their
+    /// [`DeadEntityPolicy`]. The returned report lists those whose policy is
+    /// [`Report`](DeadEntityPolicy::Report).
+    ///
+    /// # Panics
+    ///
+    /// Panics after running every command if any insert or removal whose policy is
+    /// [`Panic`](DeadEntityPolicy::Panic) targeted an entity that no longer exists.
+    pub fn run_on(&mut self, world: &mut World) -> CommandReport {
+        world.flush();
+        let mut report = CommandReport::default();
+        let mut fatal = None;
         for i in 0..self.cmds.len() {
             match mem::replace(&mut self.cmds[i], Cmd::Despawn(Entity::DANGLING)) {
-                Cmd::SpawnOrInsert(entity) => {
-                    let components = self.build(entity.components);
-                    match entity.entity {
+                Cmd::SpawnOrInsert(index) => {
+                    let components = self.build(index.components);
+                    match index.entity {
                         Some(entity) => {
-                            // If `entity` no longer exists, quietly drop the components.
-                            let _ = world.insert(entity, components);
+                            // If `entity` no longer exists, the components are dropped.
+                            if world.insert(entity, components).is_err() {
+                                match index.policy.unwrap_or(self.policy) {
+                                    DeadEntityPolicy::Skip => {}
+                                    DeadEntityPolicy::Report => report.dead_inserts.push(entity),
+                                    DeadEntityPolicy::Panic => {
+                                        fatal.get_or_insert(("insert into", entity));
+                                    }
+                                }
+                            }
                         }
                         None => {
                             world.spawn(components);
@@ -152,7 +240,15 @@ impl CommandBuffer {
                     }
                 }
                 Cmd::Remove(remove) => {
-                    (remove.remove)(world, remove.entity);
+                    if !(remove.remove)(world, remove.entity) {
+                        match remove.policy.unwrap_or(self.policy) {
+                            DeadEntityPolicy::Skip => {}
+                            DeadEntityPolicy::Report => report.dead_removes.push(remove.entity),
+                            DeadEntityPolicy::Panic => {
+                                fatal.get_or_insert(("remove from", remove.entity));
+                            }
+                        }
+                    }
                 }
                 Cmd::Despawn(entity) => {
                     let _ = world.despawn(entity);
@@ -163,6 +259,11 @@ impl CommandBuffer {
         self.components.clear();
 
         self.clear();
+
+        if let Some((op, entity)) = fatal {
+            panic!("command buffer tried to {op} nonexistent entity {entity:?}");
+        }
+        report
     }
 
     fn build(&mut self, components: Range<usize>) -> RecordedEntity<'_> {
@@ -215,10 +316,51 @@ impl Default for CommandBuffer {
             cursor: 0,
             components: Vec::new(),
             ids: Vec::new(),
+            policy: DeadEntityPolicy::Skip,
         }
     }
 }
 
+/// How a [`CommandBuffer`] handles an insert or removal targeting an entity that no longer exists
+/// when the buffer is run
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub enum DeadEntityPolicy {
+    /// Do nothing, dropping any components that would have been inserted
+    #[default]
+    Skip,
+    /// Do nothing, but list the entity in the [`CommandReport`] returned by
+    /// [`CommandBuffer::run_on`]
+    Report,
+    /// Panic once the remaining commands have been run
+    Panic,
+}
+
+/// Commands that targeted entities that no longer existed, returned by [`CommandBuffer::run_on`]
+///
+/// Only lists commands whose [`DeadEntityPolicy`] is [`Report`](DeadEntityPolicy::Report).
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct CommandReport {
+    dead_inserts: Vec<Entity>,
+    dead_removes: Vec<Entity>,
+}
+
+impl CommandReport {
+    /// Entities that components couldn't be inserted into, in the order the inserts were recorded
+    pub fn dead_inserts(&self) -> &[Entity] {
+        &self.dead_inserts
+    }
+
+    /// Entities that components couldn't be removed from, in the order the removals were recorded
+    pub fn dead_removes(&self) -> &[Entity] {
+        &self.dead_removes
+    }
+
+    /// Whether no commands were reported
+    pub fn is_empty(&self) -> bool {
+        self.dead_inserts.is_empty() && self.dead_removes.is_empty()
+    }
+}
+
 /// The output of an '[CommandBuffer]` suitable for passing to
 /// [`World::spawn_into`](crate::World::spawn_into)
 struct RecordedEntity<'a> {
@@ -275,12 +417,17 @@ struct EntityIndex {
     // We could store a single start point for the first initialized entity, rather than one for
     // each, but this would be more error prone for marginal space savings.
     components: Range<usize>,
+    /// Overrides `CommandBuffer::policy` if set
+    policy: Option<DeadEntityPolicy>,
 }
 
 /// Data required to remove components from 'entity'
 struct RemovedComps {
-    remove: fn(&mut World, Entity),
+    /// Returns whether the entity existed
+    remove: fn(&mut World, Entity) -> bool,
     entity: Entity,
+    /// Overrides `CommandBuffer::policy` if set
+    policy: Option<DeadEntityPolicy>,
 }
 
 /// A buffered command
@@ -360,4 +507,103 @@ mod tests {
         cmd.run_on(&mut world);
         assert_eq!(*world.get::<&i32>(a).unwrap(), 42);
     }
+
+    #[test]
+    fn dead_entity_policy() {
+        let mut world = World::new();
+        let a = world.spawn(());
+        let b = world.spawn(());
+        let mut cmd = CommandBuffer::new();
+        cmd.set_dead_entity_policy(DeadEntityPolicy::Report);
+        cmd.insert_one(a, 1i32);
+        cmd.insert_with_policy(b, (2i32,), DeadEntityPolicy::Skip);
+        cmd.remove_one::<i32>(b);
+        cmd.remove_one::<i32>(world.spawn(()));
+        world.despawn(a).unwrap();
+        world.despawn(b).unwrap();
+        let report = cmd.run_on(&mut world);
+        assert_eq!(report.dead_inserts(), [a]);
+        assert_eq!(report.dead_removes(), [b]);
+
+        assert!(cmd.run_on(&mut world).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "command buffer tried to remove from nonexistent entity")]
+    fn dead_entity_panic() {
+        let mut world = World::new();
+        let a = world.spawn(());
+        let mut cmd = CommandBuffer::new();
+        cmd.remove_with_policy::<(i32,)>(a, DeadEntityPolicy::Panic);
+        cmd.insert_one(a, 1i32);
+        world.despawn(a).unwrap();
+        cmd.run_on(&mut world);
+    }
+
+    #[test]
+    fn reserved_entities() {
+        struct Parent(Entity);
+
+        let mut world = World::new();
+        let mut cmd = CommandBuffer::new();
+        cmd.set_dead_entity_policy(DeadEntityPolicy::Panic);
+        let (parent, child, empty) = {
+            // A system with read-only access to the world
+            let world = &world;
+            let parent = world.reserve_entity();
+            let child = world.reserve_entity();
+            let empty = world.reserve_entity();
+            cmd.remove_one::<bool>(child);
+            cmd.insert(child, (Parent(parent), 1i32));
+            cmd.insert_one(parent, "parent");
+            (parent, child, empty)
+        };
+        assert!(world.get::<&i32>(child).is_err());
+        assert!(cmd.run_on(&mut world).is_empty());
+
+        assert_eq!(world.get::<&Parent>(child).unwrap().0, parent);
+        assert_eq!(*world.get::<&&str>(parent).unwrap(), "parent");
+        assert!(world.contains(empty));
+        assert_eq!(world.len(), 3);
+    }
+
+    #[test]
+    fn recording_order() {
+        fn record(world: &World, cmd: &mut CommandBuffer, victims: &[Entity]) -> Entity {
+            let reserved = world.reserve_entity();
+            cmd.insert_one(victims[0], 1i32);
+            cmd.despawn(victims[0]);
+            cmd.spawn((2i32,));
+            cmd.insert(reserved, (3i32, true));
+            cmd.despawn(victims[1]);
+            cmd.remove_one::<bool>(reserved);
+            cmd.spawn((4i32,));
+            cmd.spawn((5i32,));
+            reserved
+        }
+
+        let mut worlds = [World::new(), World::new()];
+        let mut results = Vec::new();
+        for world in &mut worlds {
+            let victims = [world.spawn(()), world.spawn(())];
+            let mut cmd = CommandBuffer::new();
+            let reserved = record(world, &mut cmd, &victims);
+            cmd.run_on(world);
+
+            assert!(!world.contains(victims[0]) && !world.contains(victims[1]));
+            assert!(!world.satisfies::<&bool>(reserved).unwrap());
+            let mut entities = world
+                .query::<&i32>()
+                .iter()
+                .map(|(e, &x)| (e, x))
+                .collect::<Vec<_>>();
+            entities.sort_unstable_by_key(|&(_, x)| x);
+            // Spawns reuse the IDs freed by preceding despawns
+            assert_eq!(entities[0].0.id(), victims[0].id());
+            assert_eq!(entities[1], (reserved, 3));
+            assert_eq!(entities[2].0.id(), victims[1].id());
+            results.push(entities);
+        }
+        assert_eq!(results[0], results[1]);
+    }
 }
@@ -0,0 +1,70 @@
+#[cfg(feature = "std")]
+use core::time::Duration;
+
+use crate::World;
+
+/// A resumable pass of the archetype maintenance performed by [`World::maintain`]
+///
+/// Shrinks oversized archetypes and finally removes empty ones, like `maintain`, but tracks its
+/// progress itself rather than in the world, and doesn't maintain plugins or despawn queued
+/// entities. A task can therefore be driven a little at a time from wherever the world is
+/// available at a safe point, e.g. by a worker thread between frames, without interfering with
+/// calls to `maintain`. Tasks may be used with any number of worlds, but are most effective when
+/// each is used with only one.
+///
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let a = world.spawn((1, true));
+/// world.remove_one::<bool>(a).unwrap();
+/// let mut task = CompactionTask::new();
+/// while !task.run(&mut world, 1) {}
+/// assert_eq!(world.archetypes().len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CompactionTask {
+    /// Index of the next archetype to visit
+    cursor: u32,
+}
+
+impl CompactionTask {
+    /// Units of work performed between checks of the clock by [`run_for`](Self::run_for)
+    #[cfg(feature = "std")]
+    const STEP: u32 = 64;
+
+    /// Create a task at the start of a pass
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Do at most about `budget` units of work on `world`, as defined by [`World::maintain`]
+    ///
+    /// Returns `true` when a full pass is complete, after which the next call begins a new pass.
+    pub fn run(&mut self, world: &mut World, budget: u32) -> bool {
+        world.compact(&mut self.cursor, budget)
+    }
+
+    /// Work on `world` until a full pass is complete or about `duration` has elapsed
+    ///
+    /// Returns `true` when a full pass is complete, after which the next call begins a new pass.
+    /// The clock is checked between small units of work, so removing a large number of empty
+    /// archetypes may overrun.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn run_for(&mut self, world: &mut World, duration: Duration) -> bool {
+        let start = std::time::Instant::now();
+        loop {
+            if self.run(world, Self::STEP) {
+                return true;
+            }
+            if start.elapsed() >= duration {
+                return false;
+            }
+        }
+    }
+
+    /// Whether the task is at the start of a pass
+    pub fn is_idle(&self) -> bool {
+        self.cursor == 0
+    }
+}
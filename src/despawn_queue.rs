@@ -0,0 +1,117 @@
+use crate::alloc::vec::Vec;
+use core::fmt;
+
+use spin::Mutex;
+
+use crate::Entity;
+
+/// Entities to be despawned once exclusive access to a [`World`](crate::World) is next available
+///
+/// Every world owns one, accessed with [`World::despawn_queue`](crate::World::despawn_queue), so
+/// that code holding only `&World`, such as concurrently running systems, can despawn entities.
+/// Queued entities are despawned by [`World::despawn_queued`](crate::World::despawn_queued) and at
+/// the start of [`World::maintain`](crate::World::maintain), in order of [`Entity`] rather than
+/// the order they were pushed in, so that entity IDs are recycled identically however threads
+/// were scheduled.
+///
+/// Entities remain alive until then, and entities that no longer exist at that point are ignored.
+///
+/// ```
+/// # use hecs::*;
+/// # #[cfg(not(hecs_single_threaded))] {
+/// let mut world = World::new();
+/// let a = world.spawn((1,));
+/// let b = world.spawn((2,));
+/// std::thread::scope(|s| {
+///     let world = &world;
+///     s.spawn(move || world.despawn_queue().push(b));
+///     s.spawn(move || world.despawn_queue().push(a));
+/// });
+/// assert!(world.contains(a));
+/// assert_eq!(world.despawn_queued(), 2);
+/// assert!(!world.contains(a) && !world.contains(b));
+/// # }
+/// ```
+pub struct DespawnQueue {
+    /// Entities are distributed by ID, so that threads despawning different entities rarely
+    /// contend
+    shards: [Mutex<Vec<Entity>>; SHARDS],
+}
+
+impl DespawnQueue {
+    pub(crate) fn new() -> Self {
+        Self {
+            shards: Default::default(),
+        }
+    }
+
+    /// Queue `entity` to be despawned
+    pub fn push(&self, entity: Entity) {
+        self.shards[entity.id() as usize % SHARDS]
+            .lock()
+            .push(entity);
+    }
+
+    /// Queue every entity in `entities` to be despawned
+    pub fn extend<I: IntoIterator<Item = Entity>>(&self, entities: I) {
+        for entity in entities {
+            self.push(entity);
+        }
+    }
+
+    /// Number of entities queued, counting duplicates
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|x| x.lock().len()).sum()
+    }
+
+    /// Whether no entities are queued
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|x| x.lock().is_empty())
+    }
+
+    /// Remove all queued entities, sorted and deduplicated
+    pub(crate) fn drain(&mut self) -> Vec<Entity> {
+        let mut entities = Vec::new();
+        for shard in &mut self.shards {
+            entities.append(shard.get_mut());
+        }
+        entities.sort_unstable();
+        entities.dedup();
+        entities
+    }
+
+    /// Forget all queued entities
+    pub(crate) fn clear(&mut self) {
+        for shard in &mut self.shards {
+            shard.get_mut().clear();
+        }
+    }
+}
+
+impl fmt::Debug for DespawnQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DespawnQueue")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+const SHARDS: usize = 8;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drain_is_canonical() {
+        let mut queue = DespawnQueue::new();
+        let entities = (0..20)
+            .map(|id| Entity::from_bits(1 << 32 | id).unwrap())
+            .collect::<Vec<_>>();
+        queue.extend(entities.iter().rev().copied());
+        queue.push(entities[3]);
+        assert_eq!(queue.len(), 21);
+        assert_eq!(queue.drain(), entities);
+        assert!(queue.is_empty());
+    }
+}
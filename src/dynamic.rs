@@ -0,0 +1,271 @@
+use core::alloc::Layout;
+use core::any::TypeId;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+use spin::Mutex;
+
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use crate::entities::EntityMeta;
+use crate::{Archetype, Entity, TypeInfo};
+
+/// Identifies a component type defined at runtime with [`World::register_dynamic`]
+///
+/// Each key carries the layout and destructor of its type, and is distinct from the keys of every
+/// other dynamic or static component type in the process, so it can be used with any world.
+/// [`id`](Self::id) plugs it into the APIs taking a [`TypeId`], such as
+/// [`QueryBuilder::read_dynamic`](crate::QueryBuilder::read_dynamic),
+/// [`World::remove_dynamic`](crate::World::remove_dynamic) and
+/// [`Archetype::column_ptr`](crate::Archetype::column_ptr).
+///
+/// [`World::register_dynamic`]: crate::World::register_dynamic
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentKey {
+    info: TypeInfo,
+}
+
+impl ComponentKey {
+    /// Allocate a fresh key, or `None` if all [`ComponentKey::MAX`] have been allocated
+    pub(crate) fn allocate(layout: Layout, drop: Option<unsafe fn(*mut u8)>) -> Option<Self> {
+        static NEXT: Mutex<u32> = Mutex::new(0);
+        let slot = {
+            let mut next = NEXT.lock();
+            if *next as usize == Self::MAX {
+                return None;
+            }
+            *next += 1;
+            *next - 1
+        };
+        unsafe fn noop(_: *mut u8) {}
+        Some(Self {
+            info: TypeInfo::from_parts(slot_id::<()>(slot), layout, drop.unwrap_or(noop)),
+        })
+    }
+
+    /// Number of dynamic component types that can be registered over the life of the process
+    pub const MAX: usize = 1 << SLOT_BITS;
+
+    /// The `TypeId` standing in for this component type
+    pub fn id(&self) -> TypeId {
+        self.info.id()
+    }
+
+    /// The layout of this component type
+    pub fn layout(&self) -> Layout {
+        self.info.layout()
+    }
+
+    /// The layout and destructor of this component type
+    pub fn type_info(&self) -> TypeInfo {
+        self.info
+    }
+}
+
+impl PartialEq for ComponentKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl Eq for ComponentKey {}
+
+impl Hash for ComponentKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
+
+/// The entities of one archetype matched by [`World::query_dynamic`], with their components
+///
+/// [`World::query_dynamic`]: crate::World::query_dynamic
+pub struct DynamicBatch<'a> {
+    archetype: &'a Archetype,
+    meta: &'a [EntityMeta],
+    keys: &'a [ComponentKey],
+}
+
+impl<'a> DynamicBatch<'a> {
+    pub(crate) fn new(
+        archetype: &'a Archetype,
+        meta: &'a [EntityMeta],
+        keys: &'a [ComponentKey],
+    ) -> Self {
+        Self {
+            archetype,
+            meta,
+            keys,
+        }
+    }
+
+    /// Number of entities in the batch
+    pub fn len(&self) -> usize {
+        self.archetype.len() as usize
+    }
+
+    /// Whether the batch has no entities, which is never the case for batches returned by
+    /// [`World::query_dynamic`](crate::World::query_dynamic)
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The entities in the batch, in the order of their components in each column
+    pub fn entities(&self) -> impl ExactSizeIterator<Item = Entity> + 'a {
+        let meta = self.meta;
+        self.archetype.ids().iter().map(move |&id| Entity {
+            id,
+            generation: meta[id as usize].generation,
+        })
+    }
+
+    /// The components of the type identified by the `index`th key passed to
+    /// [`World::query_dynamic`](crate::World::query_dynamic)
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn column(&self, index: usize) -> DynamicColumn<'a> {
+        let id = self.keys[index].id();
+        DynamicColumn {
+            base: self.archetype.column_ptr(id).unwrap(),
+            layout: self.keys[index].layout(),
+            len: self.len(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Record that the components in the `index`th column were modified, for change detection
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn mark_changed(&self, index: usize) {
+        self.archetype.mark_column_changed(self.keys[index].id());
+    }
+
+    /// The archetype the batch's entities are stored in
+    pub fn archetype(&self) -> &'a Archetype {
+        self.archetype
+    }
+}
+
+/// A type-erased array of components in a [`DynamicBatch`]
+///
+/// Components are stored contiguously, each [`layout`](Self::layout)`.size()` bytes after the
+/// last, and are valid to read and write for as long as the column's lifetime.
+#[derive(Debug, Copy, Clone)]
+pub struct DynamicColumn<'a> {
+    base: NonNull<u8>,
+    layout: Layout,
+    len: usize,
+    _marker: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> DynamicColumn<'a> {
+    /// Address of the first component
+    pub fn as_ptr(&self) -> NonNull<u8> {
+        self.base
+    }
+
+    /// Address of the `index`th component, if in bounds
+    pub fn get(&self, index: usize) -> Option<NonNull<u8>> {
+        if index >= self.len {
+            return None;
+        }
+        unsafe {
+            Some(NonNull::new_unchecked(
+                self.base.as_ptr().add(index * self.layout.size()),
+            ))
+        }
+    }
+
+    /// Number of components
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no components
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Layout of each component
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// The components as raw bytes
+    ///
+    /// # Safety
+    ///
+    /// The component type must be plain old data, i.e. contain no padding or other uninitialized
+    /// bytes, and the bytes must not be written through another pointer while the slice is live.
+    pub unsafe fn bytes(&self) -> &'a [u8] {
+        core::slice::from_raw_parts(self.base.as_ptr(), self.len * self.layout.size())
+    }
+}
+
+/// Errors that arise when registering a dynamic component type
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum RegisterDynamicError {
+    /// The world already has a dynamic component type of the same name
+    NameTaken,
+    /// [`ComponentKey::MAX`] dynamic component types were already registered in this process
+    Exhausted,
+}
+
+#[cfg(feature = "std")]
+impl Error for RegisterDynamicError {}
+
+impl fmt::Display for RegisterDynamicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use RegisterDynamicError::*;
+        match *self {
+            NameTaken => f.write_str("dynamic component name already registered"),
+            Exhausted => f.write_str("too many dynamic component types"),
+        }
+    }
+}
+
+const SLOT_BITS: u32 = 12;
+
+/// Marker types whose `TypeId`s stand in for dynamic component types
+///
+/// A distinct type is needed for every slot, so slots are numbered in binary by nesting one marker
+/// per bit, each level of `slot_id` consuming the lowest remaining bit.
+struct Zero<T>(PhantomData<T>);
+struct One<T>(PhantomData<T>);
+
+macro_rules! slot_levels {
+    ($name:ident) => {
+        fn $name<T: 'static>(_: u32) -> TypeId {
+            TypeId::of::<T>()
+        }
+    };
+    ($name:ident, $next:ident $(, $rest:ident)*) => {
+        fn $name<T: 'static>(slot: u32) -> TypeId {
+            if slot & 1 == 0 {
+                $next::<Zero<T>>(slot >> 1)
+            } else {
+                $next::<One<T>>(slot >> 1)
+            }
+        }
+        slot_levels!($next $(, $rest)*);
+    };
+}
+
+// One level per `SLOT_BITS`, plus the leaf
+slot_levels!(slot_id, l1, l2, l3, l4, l5, l6, l7, l8, l9, l10, l11, l12);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_distinct() {
+        let mut ids = (0..ComponentKey::MAX as u32)
+            .map(slot_id::<()>)
+            .collect::<crate::alloc::vec::Vec<_>>();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), ComponentKey::MAX);
+    }
+}
@@ -1,14 +1,23 @@
+use alloc::alloc::Layout;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
 use alloc::vec::Vec;
 use core::cmp;
 use core::convert::TryFrom;
 use core::iter::ExactSizeIterator;
 use core::num::{NonZeroU32, NonZeroU64};
 use core::ops::Range;
-use core::sync::atomic::{AtomicIsize, Ordering};
+#[cfg(not(hecs_single_threaded))]
+use core::sync::atomic::AtomicIsize;
+use core::sync::atomic::Ordering;
 use core::{fmt, mem};
 #[cfg(feature = "std")]
 use std::error::Error;
 
+#[cfg(feature = "audit-log")]
+use crate::audit::{AuditLog, AuditOp};
+use crate::{Archetype, BudgetKind, GenerationWrapStats};
+
 /// Lightweight unique ID, or handle, of an entity
 ///
 /// Obtained from `World::spawn`. Can be stored to refer to an entity in the future.
@@ -39,8 +48,8 @@ impl Entity {
     /// No particular structure is guaranteed for the returned bits.
     ///
     /// Useful for storing entity IDs externally, or in conjunction with `Entity::from_bits` and
-    /// `World::spawn_at` for easy serialization. Alternatively, consider `id` for more compact
-    /// representation.
+    /// `World::spawn_at` for easy serialization. Alternatively, consider `id` or
+    /// [`EntityBits::pack`] for more compact representation.
     pub const fn to_bits(self) -> NonZeroU64 {
         unsafe {
             NonZeroU64::new_unchecked(((self.generation.get() as u64) << 32) | (self.id as u64))
@@ -109,7 +118,126 @@ impl<'de> serde::Deserialize<'de> for Entity {
     }
 }
 
+/// How a packed entity handle is divided between ID and generation bits
+///
+/// [`Entity::to_bits`] always uses 32 bits for each. A [`World`](crate::World) configured with
+/// narrower fields through [`WorldBuilder::entity_bits`](crate::WorldBuilder::entity_bits) never
+/// allocates an ID or generation that doesn't fit, so its entities can be packed into fewer bits
+/// with [`pack`](Self::pack). Fewer generation bits make collisions between stale and live handles
+/// more likely; fewer ID bits limit the number of simultaneously live entities.
+///
+/// Fields can only be narrowed: entity IDs and generations are stored as `u32`s, so neither can
+/// have more than 32 bits, and splits such as 40 ID bits and 24 generation bits aren't supported.
+/// A world never holds more than 2<sup>32</sup> entities, and [`Entity::to_bits`] and
+/// [`Entity::from_bits`] are unaffected by the configured layout.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// // Fits losslessly in the mantissa of an `f64`
+/// let bits = EntityBits::new(24, 29);
+/// let mut world = World::builder().entity_bits(bits).build();
+/// let e = world.spawn(());
+/// let packed = bits.pack(e).unwrap();
+/// assert!(packed < 1 << 53);
+/// assert_eq!(bits.unpack(packed), Some(e));
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct EntityBits {
+    id_bits: u32,
+    generation_bits: u32,
+}
+
+impl EntityBits {
+    /// The layout used by [`Entity::to_bits`]
+    pub const DEFAULT: Self = Self {
+        id_bits: 32,
+        generation_bits: 32,
+    };
+
+    /// Use `id_bits` for the entity ID and `generation_bits` for its generation
+    ///
+    /// Panics unless both are in `1..=32`.
+    pub const fn new(id_bits: u32, generation_bits: u32) -> Self {
+        assert!(id_bits >= 1 && id_bits <= 32, "ID bits must be in 1..=32");
+        assert!(
+            generation_bits >= 1 && generation_bits <= 32,
+            "generation bits must be in 1..=32"
+        );
+        Self {
+            id_bits,
+            generation_bits,
+        }
+    }
+
+    /// Number of bits used for the entity ID
+    pub const fn id_bits(self) -> u32 {
+        self.id_bits
+    }
+
+    /// Number of bits used for the entity generation
+    pub const fn generation_bits(self) -> u32 {
+        self.generation_bits
+    }
+
+    /// Largest representable entity ID
+    pub const fn max_id(self) -> u32 {
+        u32::MAX >> (32 - self.id_bits)
+    }
+
+    /// Largest representable generation
+    pub const fn max_generation(self) -> u32 {
+        u32::MAX >> (32 - self.generation_bits)
+    }
+
+    /// Whether `entity`'s ID and generation are both representable
+    pub const fn contains(self, entity: Entity) -> bool {
+        entity.id <= self.max_id() && entity.generation.get() <= self.max_generation()
+    }
+
+    /// Pack `entity` into the low `id_bits + generation_bits` bits of a `u64`, if it fits
+    pub const fn pack(self, entity: Entity) -> Option<u64> {
+        if !self.contains(entity) {
+            return None;
+        }
+        Some(((entity.generation.get() as u64) << self.id_bits) | entity.id as u64)
+    }
+
+    /// Reconstruct an `Entity` previously packed with [`pack`](Self::pack) if the bitpattern is
+    /// valid, else `None`
+    pub const fn unpack(self, bits: u64) -> Option<Entity> {
+        let total_bits = self.id_bits + self.generation_bits;
+        if total_bits < 64 && bits >> total_bits != 0 {
+            return None;
+        }
+        let generation = match NonZeroU32::new((bits >> self.id_bits) as u32) {
+            Some(g) => g,
+            None => return None,
+        };
+        Some(Entity {
+            id: (bits & self.max_id() as u64) as u32,
+            generation,
+        })
+    }
+
+    /// Generation following `generation`, wrapping around to 1
+    fn next_generation(self, generation: NonZeroU32) -> NonZeroU32 {
+        if generation.get() >= self.max_generation() {
+            NonZeroU32::new(1).unwrap()
+        } else {
+            NonZeroU32::new(generation.get() + 1).unwrap()
+        }
+    }
+}
+
+impl Default for EntityBits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
 /// An iterator returning a sequence of Entity values from `Entities::reserve_entities`.
+#[derive(Clone)]
 pub struct ReserveEntitiesIterator<'a> {
     // Metas, so we can recover the current generation for anything in the freelist.
     meta: &'a [EntityMeta],
@@ -147,6 +275,54 @@ impl Iterator for ReserveEntitiesIterator<'_> {
 
 impl ExactSizeIterator for ReserveEntitiesIterator<'_> {}
 
+/// Storage for `Entities::free_cursor`
+#[cfg(not(hecs_single_threaded))]
+type FreeCursor = AtomicIsize;
+#[cfg(hecs_single_threaded)]
+type FreeCursor = CellIsize;
+
+/// Stand-in for `AtomicIsize` that makes `Entities` `!Sync`, used with `hecs_single_threaded` to
+/// avoid the cost of atomic read-modify-write operations
+#[cfg(hecs_single_threaded)]
+#[derive(Default)]
+struct CellIsize(core::cell::Cell<isize>);
+
+#[cfg(hecs_single_threaded)]
+impl CellIsize {
+    fn new(value: isize) -> Self {
+        Self(core::cell::Cell::new(value))
+    }
+
+    fn load(&self, _: Ordering) -> isize {
+        self.0.get()
+    }
+
+    fn fetch_sub(&self, value: isize, _: Ordering) -> isize {
+        let prev = self.0.get();
+        self.0.set(prev.wrapping_sub(value));
+        prev
+    }
+
+    fn compare_exchange_weak(
+        &self,
+        current: isize,
+        new: isize,
+        _: Ordering,
+        _: Ordering,
+    ) -> Result<isize, isize> {
+        let prev = self.0.get();
+        if prev != current {
+            return Err(prev);
+        }
+        self.0.set(new);
+        Ok(prev)
+    }
+
+    fn get_mut(&mut self) -> &mut isize {
+        self.0.get_mut()
+    }
+}
+
 #[derive(Default)]
 pub(crate) struct Entities {
     pub meta: Vec<EntityMeta>,
@@ -187,21 +363,79 @@ pub(crate) struct Entities {
     //
     // Once `flush()` is done, `free_cursor` will equal `pending.len()`.
     pending: Vec<u32>,
-    free_cursor: AtomicIsize,
+    free_cursor: FreeCursor,
     len: u32,
+    /// Limits on the IDs and generations of allocated entities
+    bits: EntityBits,
+    /// Whether the freelist is kept sorted so that the lowest free ID is always reused first
+    sorted_freelist: bool,
+    /// Number of reuse cycles a freed ID spends in `quarantine` before joining the freelist
+    reuse_delay: u32,
+    /// Number of times `advance_reuse_cycle` has been called
+    reuse_cycle: u64,
+    /// Freed IDs that may not yet be reused, with the cycle in which they were freed, oldest first
+    quarantine: VecDeque<(u64, u32)>,
+    /// Whether freed IDs are retired rather than reused
+    monotonic: bool,
+    /// Sequence number of the entity currently using each ID, in order of allocation, if tracked
+    spawn_seq: Option<Vec<u64>>,
+    /// Sequence number of the next entity to be allocated
+    next_seq: u64,
+    /// The world's current tick, recorded in place of the location of each freed entity
+    pub tick: u32,
+    /// Number of times the generation of each ID has wrapped around, for IDs that have wrapped
+    wraps: hashbrown::HashMap<u32, u32>,
+    /// Called with each freed entity whose generation wraps, and the new wrap count of its ID
+    pub wrap_hook: Option<Box<dyn FnMut(Entity, u32) + Send + Sync>>,
+    #[cfg(feature = "audit-log")]
+    pub audit: spin::Mutex<AuditLog>,
 }
 
 impl Entities {
     /// Reserve entity IDs concurrently
     ///
     /// Storage for entity generation and location is lazily allocated by calling `flush`.
-    pub fn reserve_entities(&self, count: u32) -> ReserveEntitiesIterator {
+    #[cfg_attr(feature = "audit-log", track_caller)]
+    pub fn reserve_entities(&self, count: u32) -> ReserveEntitiesIterator<'_> {
         // Use one atomic subtract to grab a range of new IDs. The range might be
         // entirely nonnegative, meaning all IDs come from the freelist, or entirely
         // negative, meaning they are all new IDs to allocate, or a mix of both.
         let range_end = self
             .free_cursor
             .fetch_sub(count as isize, Ordering::Relaxed);
+        self.reserved(range_end, count)
+    }
+
+    /// Like `reserve_entities`, but fails rather than panicking if the IDs would exceed `bits`
+    #[cfg_attr(feature = "audit-log", track_caller)]
+    pub fn try_reserve_entities(
+        &self,
+        count: u32,
+    ) -> Result<ReserveEntitiesIterator<'_>, AllocError> {
+        // Claim IDs only if they're all valid, so that no other reservation can be disturbed
+        let mut range_end = self.free_cursor.load(Ordering::Relaxed);
+        loop {
+            let range_start = range_end - count as isize;
+            if range_start < 0
+                && self.meta.len() as isize - range_start - 1 > self.bits.max_id() as isize
+            {
+                return Err(AllocError::TooManyEntities);
+            }
+            match self.free_cursor.compare_exchange_weak(
+                range_end,
+                range_start,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Ok(self.reserved(range_end, count)),
+                Err(actual) => range_end = actual,
+            }
+        }
+    }
+
+    /// The IDs claimed by moving `free_cursor` down from `range_end` by `count`
+    #[cfg_attr(feature = "audit-log", track_caller)]
+    fn reserved(&self, range_end: isize, count: u32) -> ReserveEntitiesIterator<'_> {
         let range_start = range_end - count as isize;
 
         let freelist_range = range_start.max(0) as usize..range_end.max(0) as usize;
@@ -222,6 +456,7 @@ impl Entities {
             let base = self.meta.len() as isize;
 
             let new_id_end = u32::try_from(base - range_start).expect("too many entities");
+            assert!(new_id_end - 1 <= self.bits.max_id(), "too many entities");
 
             // `new_id_end` is in range, so no need to check `start`.
             let new_id_start = (base - range_end.min(0)) as u32;
@@ -229,19 +464,25 @@ impl Entities {
             (new_id_start, new_id_end)
         };
 
-        ReserveEntitiesIterator {
+        let iter = ReserveEntitiesIterator {
             meta: &self.meta[..],
             id_iter: self.pending[freelist_range].iter(),
             id_range: new_id_start..new_id_end,
+        };
+        #[cfg(feature = "audit-log")]
+        for entity in iter.clone() {
+            self.audit(AuditOp::Reserve, entity);
         }
+        iter
     }
 
     /// Reserve one entity ID concurrently
     ///
     /// Equivalent to `self.reserve_entities(1).next().unwrap()`, but more efficient.
+    #[cfg_attr(feature = "audit-log", track_caller)]
     pub fn reserve_entity(&self) -> Entity {
         let n = self.free_cursor.fetch_sub(1, Ordering::Relaxed);
-        if n > 0 {
+        let entity = if n > 0 {
             // Allocate from the freelist.
             let id = self.pending[(n - 1) as usize];
             Entity {
@@ -254,11 +495,25 @@ impl Entities {
             //
             // As `self.free_cursor` goes more and more negative, we return IDs farther
             // and farther beyond `meta.len()`.
+            let id = u32::try_from(self.meta.len() as isize - n).expect("too many entities");
+            assert!(id <= self.bits.max_id(), "too many entities");
             Entity {
                 generation: NonZeroU32::new(1).unwrap(),
-                id: u32::try_from(self.meta.len() as isize - n).expect("too many entities"),
+                id,
             }
-        }
+        };
+        #[cfg(feature = "audit-log")]
+        self.audit(AuditOp::Reserve, entity);
+        entity
+    }
+
+    /// Record `op` happening to `entity` in the audit log
+    #[cfg(feature = "audit-log")]
+    #[track_caller]
+    fn audit(&self, op: AuditOp, entity: Entity) {
+        self.audit
+            .lock()
+            .record(op, entity, core::panic::Location::caller());
     }
 
     /// Check that we do not have pending work requiring `flush()` to be called.
@@ -272,11 +527,12 @@ impl Entities {
     /// Allocate an entity ID directly
     ///
     /// Location should be written immediately.
+    #[cfg_attr(feature = "audit-log", track_caller)]
     pub fn alloc(&mut self) -> Entity {
         self.verify_flushed();
 
         self.len += 1;
-        if let Some(id) = self.pending.pop() {
+        let entity = if let Some(id) = self.pending.pop() {
             let new_free_cursor = self.pending.len() as isize;
             *self.free_cursor.get_mut() = new_free_cursor;
             Entity {
@@ -285,17 +541,23 @@ impl Entities {
             }
         } else {
             let id = u32::try_from(self.meta.len()).expect("too many entities");
+            assert!(id <= self.bits.max_id(), "too many entities");
             self.meta.push(EntityMeta::EMPTY);
             Entity {
                 generation: NonZeroU32::new(1).unwrap(),
                 id,
             }
-        }
+        };
+        self.record_spawn(entity.id);
+        #[cfg(feature = "audit-log")]
+        self.audit(AuditOp::Alloc, entity);
+        entity
     }
 
     /// Allocate and set locations for many entity IDs laid out contiguously in an archetype
     ///
     /// `self.finish_alloc_many()` must be called after!
+    #[cfg_attr(feature = "audit-log", track_caller)]
     pub fn alloc_many(&mut self, n: u32, archetype: u32, mut first_index: u32) -> AllocManyState {
         self.verify_flushed();
 
@@ -304,6 +566,10 @@ impl Entities {
             (self.meta.len() + fresh as usize) < u32::MAX as usize,
             "too many entities"
         );
+        assert!(
+            self.meta.len() + fresh as usize <= self.bits.max_id() as usize + 1,
+            "too many entities"
+        );
         let pending_end = self.pending.len().saturating_sub(n as usize);
         for &id in &self.pending[pending_end..] {
             self.meta[id as usize].location = Location {
@@ -323,6 +589,25 @@ impl Entities {
 
         self.len += n;
 
+        if self.spawn_seq.is_some() {
+            for i in pending_end..self.pending.len() {
+                self.record_spawn(self.pending[i]);
+            }
+            for id in fresh_start..fresh_start + fresh {
+                self.record_spawn(id);
+            }
+        }
+
+        #[cfg(feature = "audit-log")]
+        for id in self.pending[pending_end..]
+            .iter()
+            .copied()
+            .chain(fresh_start..fresh_start + fresh)
+        {
+            let generation = self.meta[id as usize].generation;
+            self.audit(AuditOp::Alloc, Entity { id, generation });
+        }
+
         AllocManyState {
             fresh: fresh_start..(fresh_start + fresh),
             pending_end,
@@ -339,8 +624,14 @@ impl Entities {
     /// Allocate a specific entity ID, overwriting its generation
     ///
     /// Returns the location of the entity currently using the given ID, if any. Location should be written immediately.
+    #[cfg_attr(feature = "audit-log", track_caller)]
     pub fn alloc_at(&mut self, entity: Entity) -> Option<Location> {
         self.verify_flushed();
+        assert!(
+            self.bits.contains(entity),
+            "entity {:?} does not fit in the world's entity bit layout",
+            entity
+        );
 
         let loc = if entity.id as usize >= self.meta.len() {
             // ID has never been used in this world before
@@ -350,13 +641,26 @@ impl Entities {
             self.meta.resize(entity.id as usize + 1, EntityMeta::EMPTY);
             self.len += 1;
             None
+        } else if let Some(index) = self.quarantine.iter().position(|&(_, id)| id == entity.id) {
+            // ID was previously in use, and is awaiting reuse
+            self.quarantine.remove(index);
+            self.len += 1;
+            None
         } else if let Some(index) = self.pending.iter().position(|item| *item == entity.id) {
             // ID was previously in use, but is now free
-            self.pending.swap_remove(index);
+            if self.sorted_freelist {
+                self.pending.remove(index);
+            } else {
+                self.pending.swap_remove(index);
+            }
             let new_free_cursor = self.pending.len() as isize;
             *self.free_cursor.get_mut() = new_free_cursor;
             self.len += 1;
             None
+        } else if self.meta[entity.id as usize].location.index == u32::MAX {
+            // ID was previously in use, and has been retired
+            self.len += 1;
+            None
         } else {
             // ID is currently in use by a live entity
             Some(mem::replace(
@@ -366,6 +670,10 @@ impl Entities {
         };
 
         self.meta[entity.id as usize].generation = entity.generation;
+        self.record_spawn(entity.id);
+
+        #[cfg(feature = "audit-log")]
+        self.audit(AuditOp::AllocAt, entity);
 
         loc
     }
@@ -373,6 +681,7 @@ impl Entities {
     /// Destroy an entity, allowing it to be reused
     ///
     /// Must not be called while reserved entities are awaiting `flush()`.
+    #[cfg_attr(feature = "audit-log", track_caller)]
     pub fn free(&mut self, entity: Entity) -> Result<Location, NoSuchEntity> {
         self.verify_flushed();
 
@@ -381,20 +690,98 @@ impl Entities {
             return Err(NoSuchEntity);
         }
 
-        meta.generation = NonZeroU32::new(u32::from(meta.generation).wrapping_add(1))
-            .unwrap_or_else(|| NonZeroU32::new(1).unwrap());
+        meta.generation = self.bits.next_generation(meta.generation);
+        let wrapped = meta.generation.get() == 1;
 
-        let loc = mem::replace(&mut meta.location, EntityMeta::EMPTY.location);
+        let loc = mem::replace(
+            &mut meta.location,
+            Location {
+                archetype: self.tick,
+                index: u32::MAX,
+            },
+        );
 
-        self.pending.push(entity.id);
+        if wrapped {
+            let wraps = self.wraps.entry(entity.id).or_insert(0);
+            *wraps += 1;
+            if let Some(ref mut hook) = self.wrap_hook {
+                hook(entity, *wraps);
+            }
+        }
 
-        let new_free_cursor = self.pending.len() as isize;
-        *self.free_cursor.get_mut() = new_free_cursor;
+        if self.monotonic {
+            // Retired for good; `alloc_at` is the only way to bring it back
+        } else if self.reuse_delay > 0 {
+            self.quarantine.push_back((self.reuse_cycle, entity.id));
+        } else {
+            self.push_free(entity.id);
+        }
         self.len -= 1;
 
+        #[cfg(feature = "audit-log")]
+        self.audit(AuditOp::Free, entity);
+
         Ok(loc)
     }
 
+    /// Add `id` to the freelist
+    fn push_free(&mut self, id: u32) {
+        if self.sorted_freelist {
+            // Allocation pops from the back, so keep IDs in descending order
+            let index = self.pending.partition_point(|&x| x > id);
+            self.pending.insert(index, id);
+        } else {
+            self.pending.push(id);
+        }
+
+        let new_free_cursor = self.pending.len() as isize;
+        *self.free_cursor.get_mut() = new_free_cursor;
+    }
+
+    /// Hold freed IDs back from reuse until `delay` further calls to `advance_reuse_cycle`
+    pub fn set_reuse_delay(&mut self, delay: u32) {
+        self.reuse_delay = delay;
+    }
+
+    /// Never reuse freed IDs
+    pub fn set_monotonic(&mut self, monotonic: bool) {
+        self.monotonic = monotonic;
+    }
+
+    /// Begin a new reuse cycle, moving IDs whose quarantine has expired to the freelist
+    pub fn advance_reuse_cycle(&mut self) {
+        self.verify_flushed();
+        self.reuse_cycle += 1;
+        while let Some(&(freed, id)) = self.quarantine.front() {
+            if self.reuse_cycle - freed < u64::from(self.reuse_delay) {
+                break;
+            }
+            self.quarantine.pop_front();
+            self.push_free(id);
+        }
+    }
+
+    /// Number of freed IDs that are not yet eligible for reuse
+    pub fn quarantined(&self) -> usize {
+        self.quarantine.len()
+    }
+
+    /// Ensure at least `additional` allocations can succeed without reallocating or exceeding
+    /// `bits`, or fail without allocating anything
+    pub fn try_reserve(&mut self, additional: u32) -> Result<(), AllocError> {
+        self.verify_flushed();
+
+        let freelist_size = *self.free_cursor.get_mut();
+        let shortfall = additional as isize - freelist_size;
+        if shortfall > 0 {
+            if self.meta.len() as isize + shortfall - 1 > self.bits.max_id() as isize {
+                return Err(AllocError::TooManyEntities);
+            }
+            try_reserve_exact(&mut self.meta, shortfall as usize)?;
+        }
+        Ok(())
+    }
+
     /// Ensure at least `n` allocations can succeed without reallocating
     pub fn reserve(&mut self, additional: u32) {
         self.verify_flushed();
@@ -406,6 +793,34 @@ impl Entities {
         }
     }
 
+    pub fn bits(&self) -> EntityBits {
+        self.bits
+    }
+
+    /// Must only be called before any entities are allocated
+    pub fn set_bits(&mut self, bits: EntityBits) {
+        debug_assert!(self.meta.is_empty());
+        self.bits = bits;
+    }
+
+    /// Reuse the lowest free ID first, rather than the most recently freed one
+    pub fn set_sorted_freelist(&mut self, sorted: bool) {
+        self.verify_flushed();
+        self.sorted_freelist = sorted;
+        if sorted {
+            self.pending.sort_unstable_by(|a, b| b.cmp(a));
+        }
+    }
+
+    /// Update the locations of the entities at `indices` in `archetype` after they've been moved
+    pub fn relocate(&mut self, archetype: &Archetype, indices: Range<u32>) {
+        for index in indices {
+            self.meta[archetype.entity_id(index) as usize]
+                .location
+                .index = index;
+        }
+    }
+
     pub fn contains(&self, entity: Entity) -> bool {
         match self.meta.get(entity.id as usize) {
             Some(meta) => {
@@ -427,6 +842,11 @@ impl Entities {
     pub fn clear(&mut self) {
         self.meta.clear();
         self.pending.clear();
+        self.quarantine.clear();
+        self.wraps.clear();
+        if let Some(seq) = &mut self.spawn_seq {
+            seq.clear();
+        }
         *self.free_cursor.get_mut() = 0;
         self.len = 0;
     }
@@ -517,34 +937,92 @@ impl Entities {
             for (id, meta) in self.meta.iter_mut().enumerate().skip(old_meta_len) {
                 init(id as u32, &mut meta.location);
             }
+            if self.spawn_seq.is_some() {
+                for id in old_meta_len..new_meta_len {
+                    self.record_spawn(id as u32);
+                }
+            }
 
             *self.free_cursor.get_mut() = 0;
             0
         };
 
         self.len += (self.pending.len() - new_free_cursor) as u32;
+        if self.spawn_seq.is_some() {
+            for i in new_free_cursor..self.pending.len() {
+                self.record_spawn(self.pending[i]);
+            }
+        }
         for id in self.pending.drain(new_free_cursor..) {
             init(id, &mut self.meta[id as usize].location);
         }
     }
 
+    /// Whether to track the order in which entities are allocated, for `spawn_seq`
+    ///
+    /// Entities that are already live are ordered by ID.
+    pub fn set_track_spawn_order(&mut self, enabled: bool) {
+        if !enabled {
+            self.spawn_seq = None;
+        } else if self.spawn_seq.is_none() {
+            self.spawn_seq = Some((0..self.meta.len() as u64).collect());
+            self.next_seq = self.meta.len() as u64;
+        }
+    }
+
+    pub fn tracks_spawn_order(&self) -> bool {
+        self.spawn_seq.is_some()
+    }
+
+    /// Position of the live entity with ID `id` in allocation order
+    ///
+    /// Must only be called while spawn order is tracked.
+    #[inline]
+    pub fn spawn_seq(&self, id: u32) -> u64 {
+        self.spawn_seq.as_ref().unwrap()[id as usize]
+    }
+
+    fn record_spawn(&mut self, id: u32) {
+        let Some(seq) = &mut self.spawn_seq else {
+            return;
+        };
+        if seq.len() <= id as usize {
+            seq.resize(id as usize + 1, 0);
+        }
+        seq[id as usize] = self.next_seq;
+        self.next_seq += 1;
+    }
+
     #[inline]
     pub fn len(&self) -> u32 {
         self.len
     }
 
+    /// Quarantined IDs, newest first, followed by the freelist proper
+    ///
+    /// Allocation pops from the back, so restoring this with `set_freelist` on another world
+    /// reuses quarantined IDs only once every other free ID is exhausted.
     pub fn freelist(&self) -> impl ExactSizeIterator<Item = Entity> + '_ {
         let free = self.free_cursor.load(Ordering::Relaxed);
         let ids = match usize::try_from(free) {
             Err(_) => &[],
             Ok(free) => &self.pending[0..free],
         };
-        ids.iter().map(|&id| Entity {
-            id,
-            generation: self.meta[id as usize].generation,
-        })
+        self.quarantine
+            .iter()
+            .rev()
+            .map(|&(_, id)| id)
+            .chain(ids.iter().copied())
+            .map(|id| Entity {
+                id,
+                generation: self.meta[id as usize].generation,
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
     }
 
+    /// Replace the freelist with `freelist`, leaving any of its IDs that are already quarantined
+    /// in quarantine
     pub fn set_freelist(&mut self, freelist: &[Entity]) {
         #[cfg(debug_assertions)]
         {
@@ -564,15 +1042,262 @@ impl Entities {
                 self.meta.resize(max as usize + 1, EntityMeta::EMPTY);
             }
         }
+        let mut ids = freelist.iter().map(|e| e.id).collect::<Vec<_>>();
+        ids.sort_unstable();
+        self.quarantine
+            .retain(|&(_, id)| ids.binary_search(&id).is_ok());
+        let mut quarantined = self
+            .quarantine
+            .iter()
+            .map(|&(_, id)| id)
+            .collect::<Vec<_>>();
+        quarantined.sort_unstable();
         self.pending.clear();
         for entity in freelist {
-            self.pending.push(entity.id);
             self.meta[entity.id as usize].generation = entity.generation;
+            if quarantined.binary_search(&entity.id).is_err() {
+                self.pending.push(entity.id);
+            }
         }
-        self.free_cursor = AtomicIsize::new(freelist.len() as isize);
+        if self.sorted_freelist {
+            self.pending.sort_unstable_by(|a, b| b.cmp(a));
+        }
+        self.free_cursor = FreeCursor::new(self.pending.len() as isize);
+    }
+
+    /// Number of times the generation of `id` has wrapped around
+    pub fn generation_wraps(&self, id: u32) -> u32 {
+        self.wraps.get(&id).copied().unwrap_or(0)
+    }
+
+    pub fn generation_wrap_stats(&self) -> GenerationWrapStats {
+        let mut stats = GenerationWrapStats {
+            wrapped_ids: self.wraps.len() as u32,
+            ..GenerationWrapStats::default()
+        };
+        for &wraps in self.wraps.values() {
+            stats.total_wraps += u64::from(wraps);
+            stats.max_wraps = stats.max_wraps.max(wraps);
+        }
+        stats
+    }
+
+    /// IDs of dead entities despawned at or after `tick`, in ascending order
+    pub fn despawned_since(&self, tick: u32) -> impl Iterator<Item = u32> + '_ {
+        self.meta.iter().enumerate().filter_map(move |(id, meta)| {
+            let dead = meta.location.index == u32::MAX
+                && !self.contains(Entity {
+                    id: id as u32,
+                    generation: meta.generation,
+                });
+            (dead && meta.location.archetype >= tick).then_some(id as u32)
+        })
+    }
+
+    /// The live entity with ID `id`, if any
+    pub fn resolve(&self, id: u32) -> Option<Entity> {
+        let meta = self.meta.get(id as usize)?;
+        let entity = Entity {
+            id,
+            generation: meta.generation,
+        };
+        (meta.location.index != u32::MAX).then_some(entity)
+    }
+
+    /// Discard the metadata of the dead IDs above the highest live ID, returning how many
+    ///
+    /// Does nothing when IDs are monotonic, since discarded IDs would be allocated again.
+    pub fn compact_meta(&mut self) -> u32 {
+        self.verify_flushed();
+        if self.monotonic {
+            return 0;
+        }
+        let len = self
+            .meta
+            .iter()
+            .rposition(|meta| meta.location.index != u32::MAX)
+            .map_or(0, |i| i + 1);
+        let removed = self.meta.len() - len;
+        if removed == 0 {
+            return 0;
+        }
+        self.meta.truncate(len);
+        self.meta.shrink_to_fit();
+        self.pending.retain(|&id| (id as usize) < len);
+        self.quarantine.retain(|&(_, id)| (id as usize) < len);
+        self.wraps.retain(|&id, _| (id as usize) < len);
+        *self.free_cursor.get_mut() = self.pending.len() as isize;
+        removed as u32
+    }
+
+    /// Copy the allocator, including the locations of live entities, without the wrap hook or
+    /// audit log
+    pub fn clone_state(&self) -> Self {
+        Self {
+            meta: self.meta.clone(),
+            pending: self.pending.clone(),
+            free_cursor: FreeCursor::new(self.free_cursor.load(Ordering::Relaxed)),
+            len: self.len,
+            bits: self.bits,
+            sorted_freelist: self.sorted_freelist,
+            reuse_delay: self.reuse_delay,
+            reuse_cycle: self.reuse_cycle,
+            quarantine: self.quarantine.clone(),
+            monotonic: self.monotonic,
+            spawn_seq: self.spawn_seq.clone(),
+            next_seq: self.next_seq,
+            tick: self.tick,
+            wraps: self.wraps.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Capture everything that determines which IDs and generations future allocations yield
+    pub fn save(&self) -> AllocatorState {
+        AllocatorState {
+            generations: self.meta.iter().map(|x| x.generation).collect(),
+            pending: self.pending.clone(),
+            free_cursor: self.free_cursor.load(Ordering::Relaxed),
+            len: self.len,
+            reuse_cycle: self.reuse_cycle,
+            quarantine: self.quarantine.clone(),
+        }
+    }
+
+    /// Whether `state` can be restored, i.e. agrees with the generations of the live entities and
+    /// doesn't mark any of them free
+    ///
+    /// Must only be called when there are no reserved entities to flush.
+    pub fn can_restore(&self, state: &AllocatorState) -> bool {
+        if state.len != self.len || state.free_cursor > state.pending.len() as isize {
+            return false;
+        }
+        // IDs reserved from beyond the end of `meta` must be representable
+        let reserved = state.free_cursor.min(0).unsigned_abs();
+        if state.generations.len().saturating_add(reserved) > u32::MAX as usize {
+            return false;
+        }
+        let mut free = alloc::vec![false; state.generations.len()];
+        let free_ids = state
+            .pending
+            .iter()
+            .chain(state.quarantine.iter().map(|(_, id)| id));
+        for &id in free_ids {
+            match free.get_mut(id as usize) {
+                Some(x) if !*x => *x = true,
+                _ => return false,
+            }
+        }
+        self.meta
+            .iter()
+            .enumerate()
+            .filter(|(_, meta)| meta.location.index != u32::MAX)
+            .all(|(id, meta)| state.generations.get(id) == Some(&meta.generation) && !free[id])
+    }
+
+    /// Restore state captured by `save`
+    ///
+    /// Must only be called when the set of live entities exactly matches those that were live
+    /// when `state` was captured.
+    pub fn restore(&mut self, state: &AllocatorState) {
+        debug_assert_eq!(self.len, state.len, "live entities differ");
+        self.meta.resize(state.generations.len(), EntityMeta::EMPTY);
+        for (meta, &generation) in self.meta.iter_mut().zip(&state.generations) {
+            meta.generation = generation;
+        }
+        self.pending.clone_from(&state.pending);
+        self.free_cursor = FreeCursor::new(state.free_cursor);
+        self.len = state.len;
+        self.reuse_cycle = state.reuse_cycle;
+        self.quarantine.clone_from(&state.quarantine);
+    }
+}
+
+/// The state of a [`World`](crate::World)'s entity allocator, which determines the handles of
+/// future spawns
+///
+/// Captured by [`World::allocator_state`](crate::World::allocator_state) and restored by
+/// [`World::set_allocator_state`](crate::World::set_allocator_state). Unlike the
+/// [freelist](crate::World::freelist), this includes the generations of despawned entities and IDs
+/// held back from reuse, so that a restored world allocates exactly the same handles as the
+/// original.
+///
+/// Enable the `serde` feature on the crate to make this `Serialize`able, e.g. to save and load
+/// worlds without breaking the determinism of subsequent spawns.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AllocatorState {
+    /// Generation of every ID, live or dead
+    generations: Vec<NonZeroU32>,
+    pending: Vec<u32>,
+    free_cursor: isize,
+    len: u32,
+    reuse_cycle: u64,
+    quarantine: VecDeque<(u64, u32)>,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for AllocatorState {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        (
+            &self.generations,
+            &self.pending,
+            self.free_cursor as i64,
+            self.len,
+            self.reuse_cycle,
+            &self.quarantine,
+        )
+            .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for AllocatorState {
+    fn deserialize<D>(deserializer: D) -> Result<AllocatorState, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let (generations, pending, free_cursor, len, reuse_cycle, quarantine) =
+            <(
+                Vec<NonZeroU32>,
+                Vec<u32>,
+                i64,
+                u32,
+                u64,
+                VecDeque<(u64, u32)>,
+            )>::deserialize(deserializer)?;
+        let free_cursor = isize::try_from(free_cursor).map_err(|_| {
+            serde::de::Error::invalid_value(
+                serde::de::Unexpected::Signed(free_cursor),
+                &"a free cursor representable as `isize`",
+            )
+        })?;
+        Ok(AllocatorState {
+            generations,
+            pending,
+            free_cursor,
+            len,
+            reuse_cycle,
+            quarantine,
+        })
+    }
+}
+
+/// Error indicating that an [`AllocatorState`] doesn't match a world's live entities
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct AllocatorStateMismatch;
+
+impl fmt::Display for AllocatorStateMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("allocator state does not match live entities")
     }
 }
 
+#[cfg(feature = "std")]
+impl Error for AllocatorStateMismatch {}
+
 #[derive(Copy, Clone)]
 pub(crate) struct EntityMeta {
     pub generation: NonZeroU32,
@@ -611,6 +1336,49 @@ impl fmt::Display for NoSuchEntity {
 #[cfg(feature = "std")]
 impl Error for NoSuchEntity {}
 
+/// Error indicating that entities or their storage couldn't be allocated
+///
+/// Returned by fallible methods such as
+/// [`World::try_reserve_entities`](crate::World::try_reserve_entities), whose infallible
+/// counterparts panic or abort instead.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum AllocError {
+    /// Every entity ID permitted by the world's [`EntityBits`] is in use
+    TooManyEntities,
+    /// The requested amount of storage exceeds the limits of the address space
+    CapacityOverflow,
+    /// The memory allocator failed to provide memory with this layout
+    OutOfMemory(Layout),
+    /// The operation would exceed a cap installed by
+    /// [`World::set_budget`](crate::World::set_budget)
+    BudgetExceeded(BudgetKind),
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use AllocError::*;
+        match *self {
+            TooManyEntities => f.write_str("too many entities"),
+            CapacityOverflow => f.write_str("capacity overflow"),
+            OutOfMemory(layout) => write!(f, "failed to allocate {} bytes", layout.size()),
+            BudgetExceeded(kind) => write!(f, "{} budget exceeded", kind),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for AllocError {}
+
+/// Like `Vec::try_reserve_exact`, but reports the layout that couldn't be allocated
+pub(crate) fn try_reserve_exact<T>(vec: &mut Vec<T>, additional: usize) -> Result<(), AllocError> {
+    vec.try_reserve_exact(additional).map_err(|_| {
+        vec.len()
+            .checked_add(additional)
+            .and_then(|len| Layout::array::<T>(len).ok())
+            .map_or(AllocError::CapacityOverflow, AllocError::OutOfMemory)
+    })
+}
+
 #[derive(Clone)]
 pub(crate) struct AllocManyState {
     pub pending_end: usize,
@@ -648,6 +1416,52 @@ mod tests {
         assert_eq!(Entity::from_bits(e.to_bits().into()).unwrap(), e);
     }
 
+    #[test]
+    fn entity_bits_pack() {
+        let bits = EntityBits::new(20, 12);
+        let e = Entity {
+            generation: NonZeroU32::new(0xABC).unwrap(),
+            id: 0xDEF01,
+        };
+        let packed = bits.pack(e).unwrap();
+        assert_eq!(packed, 0xABC_DEF01);
+        assert_eq!(bits.unpack(packed), Some(e));
+        assert_eq!(bits.unpack(1 << 32), None);
+        assert_eq!(bits.unpack(0xDEF01), None);
+        assert_eq!(
+            bits.pack(Entity {
+                generation: NonZeroU32::new(1).unwrap(),
+                id: 1 << 20,
+            }),
+            None
+        );
+        assert_eq!(EntityBits::DEFAULT.pack(e), Some(e.to_bits().get()),);
+    }
+
+    #[test]
+    fn limited_generation_wraps() {
+        let mut e = Entities::default();
+        e.set_bits(EntityBits::new(32, 2));
+        let mut generations = Vec::new();
+        for _ in 0..5 {
+            let x = e.alloc();
+            e.meta[x.id as usize].location.index = 0;
+            generations.push(x.generation.get());
+            e.free(x).unwrap();
+        }
+        assert_eq!(generations, [1, 2, 3, 1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "too many entities")]
+    fn limited_ids() {
+        let mut e = Entities::default();
+        e.set_bits(EntityBits::new(2, 32));
+        for _ in 0..5 {
+            e.alloc();
+        }
+    }
+
     #[test]
     fn alloc_and_free() {
         let mut rng = StdRng::seed_from_u64(0xFEEDFACEDEADF00D);
@@ -7,7 +7,10 @@ use core::ptr::{self, NonNull};
 use hashbrown::hash_map::Entry;
 
 use crate::archetype::{TypeIdMap, TypeInfo};
-use crate::{align, Component, ComponentRef, ComponentRefShared, DynamicBundle};
+use crate::{
+    align, Component, ComponentKey, ComponentRef, ComponentRefShared, ComponentRegistry,
+    DynamicBundle, MissingDefault,
+};
 
 /// Helper for incrementally constructing a bundle of components with dynamic component types
 ///
@@ -53,6 +56,65 @@ impl EntityBuilder {
         self
     }
 
+    /// Add a default-constructed component of the type identified by `id` to the entity.
+    ///
+    /// The type's [`Default`] implementation must have been registered in `registry` with
+    /// [`ComponentRegistry::register_default`]. Replaces any existing component of the same type.
+    pub fn add_default_dynamic(
+        &mut self,
+        registry: &ComponentRegistry,
+        id: TypeId,
+    ) -> Result<&mut Self, MissingDefault> {
+        let component = registry.default_bundle(id)?;
+        Ok(self.add_bundle(component))
+    }
+
+    /// Add a component of the runtime-defined type identified by `key` to the entity, copied from
+    /// its raw bytes
+    ///
+    /// `bytes` need not be aligned. Replaces any existing component of the same type.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// # use core::alloc::Layout;
+    /// let mut world = World::new();
+    /// let key = world
+    ///     .register_dynamic(Layout::new::<u32>(), None, "Score")
+    ///     .unwrap();
+    /// let mut builder = EntityBuilder::new();
+    /// unsafe {
+    ///     builder.add_dynamic(key, &7u32.to_ne_bytes());
+    /// }
+    /// let e = world.spawn(builder.build());
+    /// let ptr = world.entity(e).unwrap().component_ptr(key.id()).unwrap();
+    /// assert_eq!(unsafe { ptr.cast::<u32>().as_ptr().read() }, 7);
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// `bytes` must be a valid instance of the type, i.e. one that its destructor, if any, can be
+    /// called on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` isn't exactly the size of the type.
+    pub unsafe fn add_dynamic(&mut self, key: ComponentKey, bytes: &[u8]) -> &mut Self {
+        let ty = key.type_info();
+        assert_eq!(
+            bytes.len(),
+            ty.layout().size(),
+            "bytes are not the size of the component type"
+        );
+        self.inner.add(bytes.as_ptr().cast_mut(), ty, ());
+        self
+    }
+
+    /// Add the component of type `ty` at `ptr`, which is moved into the builder
+    #[cfg(feature = "row-serialize")]
+    pub(crate) unsafe fn add_raw(&mut self, ptr: *mut u8, ty: TypeInfo) {
+        self.inner.add(ptr, ty, ());
+    }
+
     /// Construct a `Bundle` suitable for spawning
     pub fn build(&mut self) -> BuiltEntity<'_> {
         self.inner.info.sort_unstable_by_key(|x| x.0);
@@ -187,6 +249,13 @@ impl EntityBuilderClone {
         self
     }
 
+    /// Add a clone of the component at `src`, replacing any existing component of the same type
+    ///
+    /// `src` must point to a valid instance of the type cloned by `clone`.
+    pub(crate) unsafe fn add_cloned(&mut self, src: *const u8, clone: DynamicClone) {
+        (clone.func)(src, &mut |ptr, ty| self.inner.add(ptr, ty, clone));
+    }
+
     /// Convert into a value whose shared references are [`DynamicBundle`]s suitable for repeated
     /// spawning
     pub fn build(self) -> BuiltEntityClone {
@@ -233,6 +302,64 @@ impl EntityBuilderClone {
 #[derive(Clone)]
 pub struct BuiltEntityClone(Common<DynamicClone>);
 
+impl BuiltEntityClone {
+    /// Types of the components, sorted as in a [`DynamicBundle`]
+    pub(crate) fn ids(&self) -> &[TypeId] {
+        &self.0.ids
+    }
+
+    /// Pointer to the component of type `id`
+    pub(crate) fn get_dynamic(&self, id: TypeId) -> Option<*const u8> {
+        let &(_, offset, _) = self.0.info.iter().find(|x| x.0.id() == id)?;
+        unsafe { Some(self.0.storage.as_ptr().add(offset)) }
+    }
+
+    /// A bundle cloning only the components whose types are in `ids`
+    pub(crate) fn subset(&self, ids: &[TypeId]) -> BuiltEntityCloneSubset<'_> {
+        BuiltEntityCloneSubset {
+            ids: self
+                .0
+                .ids
+                .iter()
+                .copied()
+                .filter(|id| ids.contains(id))
+                .collect(),
+            source: self,
+        }
+    }
+}
+
+/// Some of the components of a [`BuiltEntityClone`], cloned on use
+pub(crate) struct BuiltEntityCloneSubset<'a> {
+    source: &'a BuiltEntityClone,
+    ids: Vec<TypeId>,
+}
+
+unsafe impl DynamicBundle for BuiltEntityCloneSubset<'_> {
+    fn with_ids<T>(&self, f: impl FnOnce(&[TypeId]) -> T) -> T {
+        f(&self.ids)
+    }
+
+    fn type_info(&self) -> Vec<TypeInfo> {
+        self.source
+            .0
+            .info
+            .iter()
+            .map(|x| x.0)
+            .filter(|x| self.ids.contains(&x.id()))
+            .collect()
+    }
+
+    unsafe fn put(self, mut f: impl FnMut(*mut u8, TypeInfo)) {
+        for &(ty, offset, clone) in &self.source.0.info {
+            if self.ids.contains(&ty.id()) {
+                let ptr = self.source.0.storage.as_ptr().add(offset);
+                (clone.func)(ptr, &mut f);
+            }
+        }
+    }
+}
+
 unsafe impl DynamicBundle for &'_ BuiltEntityClone {
     fn has<T: Component>(&self) -> bool {
         self.0.has::<T>()
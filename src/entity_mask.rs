@@ -0,0 +1,270 @@
+use crate::alloc::vec::Vec;
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Sub, SubAssign};
+
+use crate::Entity;
+
+/// A set of entities, stored as a bitset indexed by entity ID
+///
+/// Convenient for representing the output of broad-phase collision detection, region-of-interest
+/// selection, and similar spatial or external filters. Masks can be combined with set operations
+/// and then passed to [`QueryBorrow::iter_masked`](crate::QueryBorrow::iter_masked) to visit only
+/// the entities they contain.
+///
+/// Only the ID of each entity is stored, so a mask will also contain any entity that is later
+/// spawned with the ID of a despawned member.
+///
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let a = world.spawn((1,));
+/// let b = world.spawn((2,));
+/// let c = world.spawn((3,));
+/// let near: EntityMask = [a, b].into_iter().collect();
+/// let visible: EntityMask = [b, c].into_iter().collect();
+/// let mask = &near & &visible;
+/// assert!(mask.contains(b));
+/// assert_eq!(mask.len(), 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EntityMask {
+    words: Vec<u64>,
+}
+
+impl EntityMask {
+    /// Create an empty mask
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty mask that can hold entities with IDs below `ids` without reallocating
+    pub fn with_capacity(ids: u32) -> Self {
+        Self {
+            words: Vec::with_capacity(word_count(ids)),
+        }
+    }
+
+    /// Add `entity`, returning whether it was newly added
+    pub fn insert(&mut self, entity: Entity) -> bool {
+        self.insert_id(entity.id())
+    }
+
+    /// Remove `entity`, returning whether it was present
+    pub fn remove(&mut self, entity: Entity) -> bool {
+        let (word, bit) = split(entity.id());
+        let Some(x) = self.words.get_mut(word) else {
+            return false;
+        };
+        let present = *x & bit != 0;
+        *x &= !bit;
+        present
+    }
+
+    /// Whether `entity` is present
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.contains_id(entity.id())
+    }
+
+    /// Add the entity with ID `id`, returning whether it was newly added
+    ///
+    /// Useful with the IDs from [`Archetype::ids`](crate::Archetype::ids).
+    pub fn insert_id(&mut self, id: u32) -> bool {
+        let (word, bit) = split(id);
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let x = &mut self.words[word];
+        let added = *x & bit == 0;
+        *x |= bit;
+        added
+    }
+
+    /// Whether the entity with ID `id` is present
+    #[inline]
+    pub fn contains_id(&self, id: u32) -> bool {
+        let (word, bit) = split(id);
+        self.words.get(word).map_or(false, |x| x & bit != 0)
+    }
+
+    /// Remove all entities
+    pub fn clear(&mut self) {
+        self.words.clear();
+    }
+
+    /// Number of entities present
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|x| x.count_ones() as usize).sum()
+    }
+
+    /// Whether no entities are present
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&x| x == 0)
+    }
+
+    /// Iterate over the IDs of the entities present, in ascending order
+    pub fn ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &word)| {
+            let base = i as u32 * 64;
+            let mut rest = word;
+            core::iter::from_fn(move || {
+                if rest == 0 {
+                    return None;
+                }
+                let bit = rest.trailing_zeros();
+                rest &= rest - 1;
+                Some(base + bit)
+            })
+        })
+    }
+
+    /// Add every entity present in `other`
+    pub fn union_with(&mut self, other: &EntityMask) {
+        if self.words.len() < other.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (x, &y) in self.words.iter_mut().zip(&other.words) {
+            *x |= y;
+        }
+    }
+
+    /// Remove every entity not present in `other`
+    pub fn intersect_with(&mut self, other: &EntityMask) {
+        self.words.truncate(other.words.len());
+        for (x, &y) in self.words.iter_mut().zip(&other.words) {
+            *x &= y;
+        }
+    }
+
+    /// Remove every entity present in `other`
+    pub fn difference_with(&mut self, other: &EntityMask) {
+        for (x, &y) in self.words.iter_mut().zip(&other.words) {
+            *x &= !y;
+        }
+    }
+}
+
+/// Index of the word containing `id`, and the bit representing it within that word
+#[inline]
+fn split(id: u32) -> (usize, u64) {
+    ((id / 64) as usize, 1 << (id % 64))
+}
+
+/// Number of words required to represent IDs below `ids`
+fn word_count(ids: u32) -> usize {
+    ((ids as usize) + 63) / 64
+}
+
+impl PartialEq for EntityMask {
+    fn eq(&self, other: &Self) -> bool {
+        let (short, long) = if self.words.len() <= other.words.len() {
+            (&self.words, &other.words)
+        } else {
+            (&other.words, &self.words)
+        };
+        short[..] == long[..short.len()] && long[short.len()..].iter().all(|&x| x == 0)
+    }
+}
+
+impl Eq for EntityMask {}
+
+impl FromIterator<Entity> for EntityMask {
+    fn from_iter<I: IntoIterator<Item = Entity>>(iter: I) -> Self {
+        let mut mask = Self::new();
+        mask.extend(iter);
+        mask
+    }
+}
+
+impl Extend<Entity> for EntityMask {
+    fn extend<I: IntoIterator<Item = Entity>>(&mut self, iter: I) {
+        for entity in iter {
+            self.insert(entity);
+        }
+    }
+}
+
+impl BitOrAssign<&EntityMask> for EntityMask {
+    fn bitor_assign(&mut self, rhs: &EntityMask) {
+        self.union_with(rhs);
+    }
+}
+
+impl BitAndAssign<&EntityMask> for EntityMask {
+    fn bitand_assign(&mut self, rhs: &EntityMask) {
+        self.intersect_with(rhs);
+    }
+}
+
+impl SubAssign<&EntityMask> for EntityMask {
+    fn sub_assign(&mut self, rhs: &EntityMask) {
+        self.difference_with(rhs);
+    }
+}
+
+impl BitOr for &EntityMask {
+    type Output = EntityMask;
+    fn bitor(self, rhs: &EntityMask) -> EntityMask {
+        let mut x = self.clone();
+        x.union_with(rhs);
+        x
+    }
+}
+
+impl BitAnd for &EntityMask {
+    type Output = EntityMask;
+    fn bitand(self, rhs: &EntityMask) -> EntityMask {
+        let mut x = self.clone();
+        x.intersect_with(rhs);
+        x
+    }
+}
+
+impl Sub for &EntityMask {
+    type Output = EntityMask;
+    fn sub(self, rhs: &EntityMask) -> EntityMask {
+        let mut x = self.clone();
+        x.difference_with(rhs);
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(ids: &[u32]) -> EntityMask {
+        let mut x = EntityMask::new();
+        for &id in ids {
+            x.insert_id(id);
+        }
+        x
+    }
+
+    #[test]
+    fn insert_remove() {
+        let mut x = EntityMask::new();
+        assert!(x.is_empty());
+        assert!(x.insert_id(3));
+        assert!(!x.insert_id(3));
+        assert!(x.insert_id(200));
+        assert_eq!(x.len(), 2);
+        assert!(x.contains_id(200));
+        assert!(!x.contains_id(199));
+        assert!(!x.contains_id(100_000));
+        assert_eq!(x.ids().collect::<Vec<_>>(), [3, 200]);
+        assert!(x.remove(Entity::from_bits(1 << 32 | 200).unwrap()));
+        assert!(!x.remove(Entity::from_bits(1 << 32 | 200).unwrap()));
+        assert_eq!(x, mask(&[3]));
+    }
+
+    #[test]
+    fn set_ops() {
+        let a = mask(&[1, 2, 64, 130]);
+        let b = mask(&[2, 64, 65]);
+        assert_eq!(&a | &b, mask(&[1, 2, 64, 65, 130]));
+        assert_eq!(&a & &b, mask(&[2, 64]));
+        assert_eq!(&a - &b, mask(&[1, 130]));
+        assert_eq!(&b - &a, mask(&[65]));
+        assert_eq!(&b & &a, mask(&[2, 64]));
+        assert_eq!(&mask(&[500]) & &mask(&[1]), EntityMask::new());
+    }
+}
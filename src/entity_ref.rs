@@ -5,9 +5,10 @@ use core::ops::{Deref, DerefMut, FnOnce};
 use core::ptr::NonNull;
 
 use crate::archetype::Archetype;
+use crate::reflect::ComponentInfo;
 use crate::{
-    ArchetypeColumn, ArchetypeColumnMut, Component, Entity, Fetch, MissingComponent, Query,
-    QueryOne,
+    ArchetypeColumn, ArchetypeColumnMut, Component, ComponentRegistry, Entity, Fetch,
+    MissingComponent, Query, QueryDescription, QueryOne, TypeInfo,
 };
 
 /// Handle to an entity with any component types
@@ -27,6 +28,11 @@ impl<'a> EntityRef<'a> {
         }
     }
 
+    /// The archetype storing this entity and its index within it
+    pub(crate) fn raw(&self) -> (&'a Archetype, u32) {
+        (self.archetype, self.index)
+    }
+
     /// Get the [`Entity`] handle associated with this entity
     #[inline]
     pub fn entity(&self) -> Entity {
@@ -38,6 +44,12 @@ impl<'a> EntityRef<'a> {
         Q::Fetch::access(self.archetype).is_some()
     }
 
+    /// Determine whether this entity would satisfy a query assembled at runtime, without
+    /// borrowing any components
+    pub fn satisfies_dynamic(&self, query: &QueryDescription) -> bool {
+        query.matches(self.archetype)
+    }
+
     /// Determine whether this entity has a `T` component without borrowing it
     ///
     /// Equivalent to [`satisfies::<&T>`](Self::satisfies)
@@ -65,6 +77,23 @@ impl<'a> EntityRef<'a> {
         T::get_component(*self)
     }
 
+    /// Address of this entity's component of the type identified by `id`, if present, without
+    /// borrowing it
+    ///
+    /// Allows reading and writing components whose types are only known at runtime, such as those
+    /// registered with [`World::register_dynamic`](crate::World::register_dynamic). Using the
+    /// pointer is subject to the same conditions as
+    /// [`Archetype::column_ptr`](crate::Archetype::column_ptr).
+    pub fn component_ptr(&self, id: TypeId) -> Option<NonNull<u8>> {
+        let base = self.archetype.column_ptr(id)?;
+        let size = self.archetype.column_layout(id)?.size();
+        unsafe {
+            Some(NonNull::new_unchecked(
+                base.as_ptr().add(size * self.index as usize),
+            ))
+        }
+    }
+
     /// Run a query against this entity
     ///
     /// Equivalent to invoking [`World::query_one`](crate::World::query_one) on the entity. May
@@ -95,6 +124,35 @@ impl<'a> EntityRef<'a> {
         self.archetype.types().iter().map(|ty| ty.id())
     }
 
+    /// Enumerate the [`TypeInfo`]s of the entity's components
+    ///
+    /// Like [`component_types`](Self::component_types), but also exposes each component's layout
+    /// and destructor, allowing generic code such as entity cloners or inspectors to handle
+    /// component data without probing for every known type individually.
+    pub fn component_type_info(&self) -> impl ExactSizeIterator<Item = TypeInfo> + 'a {
+        self.archetype.component_type_info()
+    }
+
+    /// Describe each of the entity's components, using the names and fields registered in
+    /// `registry`
+    ///
+    /// Components of types missing from `registry` are described by their layout alone. See the
+    /// [`reflect`](crate::reflect) module.
+    pub fn component_info<'r>(
+        &self,
+        registry: &'r ComponentRegistry,
+    ) -> impl ExactSizeIterator<Item = ComponentInfo> + 'r
+    where
+        'a: 'r,
+    {
+        self.archetype
+            .component_type_info()
+            .map(move |ty| match registry.get(ty.id()) {
+                Some(reg) => reg.component_info(),
+                None => ComponentInfo::new(ty.id(), None, ty.layout(), None),
+            })
+    }
+
     /// Number of components in this entity
     pub fn len(&self) -> usize {
         self.archetype.types().len()
@@ -165,6 +223,49 @@ impl<'a, T: ?Sized> Ref<'a, T> {
             _phantom: PhantomData,
         }
     }
+
+    /// Like [`map`](Self::map), but for projections that may fail, e.g. to an enum variant's
+    /// field
+    ///
+    /// Returns the original `Ref<'_, T>` if `f` returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// enum Shape {
+    ///     Circle { radius: f32 },
+    ///     Point,
+    /// }
+    ///
+    /// fn radius(entity: EntityRef<'_>) -> Option<Ref<'_, f32>> {
+    ///     let shape = entity.get::<&Shape>()?;
+    ///     Ref::try_map(shape, |shape| match shape {
+    ///         Shape::Circle { radius } => Some(radius),
+    ///         Shape::Point => None,
+    ///     })
+    ///     .ok()
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// let a = world.spawn((Shape::Circle { radius: 2.0 },));
+    /// let b = world.spawn((Shape::Point,));
+    /// assert_eq!(radius(world.entity(a).unwrap()).as_deref(), Some(&2.0));
+    /// assert!(radius(world.entity(b).unwrap()).is_none());
+    /// ```
+    pub fn try_map<U: ?Sized, F>(orig: Ref<'a, T>, f: F) -> Result<Ref<'a, U>, Ref<'a, T>>
+    where
+        F: FnOnce(&T) -> Option<&U>,
+    {
+        match f(&*orig).map(NonNull::from) {
+            Some(target) => Ok(Ref {
+                borrow: orig.borrow,
+                target,
+                _phantom: PhantomData,
+            }),
+            None => Err(orig),
+        }
+    }
 }
 
 impl<T: ?Sized> Deref for Ref<'_, T> {
@@ -253,6 +354,48 @@ impl<'a, T: ?Sized> RefMut<'a, T> {
             _phantom: PhantomData,
         }
     }
+
+    /// Like [`map`](Self::map), but for projections that may fail, e.g. to an enum variant's
+    /// field
+    ///
+    /// Returns the original `RefMut<'_, T>` if `f` returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// enum Shape {
+    ///     Circle { radius: f32 },
+    ///     Point,
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// let a = world.spawn((Shape::Circle { radius: 2.0 },));
+    /// let shape = world.get::<&mut Shape>(a).unwrap();
+    /// let mut radius = RefMut::try_map(shape, |shape| match shape {
+    ///     Shape::Circle { radius } => Some(radius),
+    ///     Shape::Point => None,
+    /// })
+    /// .ok()
+    /// .unwrap();
+    /// *radius = 3.0;
+    /// ```
+    pub fn try_map<U: ?Sized, F>(
+        mut orig: RefMut<'a, T>,
+        f: F,
+    ) -> Result<RefMut<'a, U>, RefMut<'a, T>>
+    where
+        F: FnOnce(&mut T) -> Option<&mut U>,
+    {
+        match f(&mut *orig).map(NonNull::from) {
+            Some(target) => Ok(RefMut {
+                borrow: orig.borrow,
+                target,
+                _phantom: PhantomData,
+            }),
+            None => Err(orig),
+        }
+    }
 }
 
 impl<T: ?Sized> Deref for RefMut<'_, T> {
@@ -431,6 +574,7 @@ impl<'a> ComponentBorrowMut<'a> {
             NonNull::new_unchecked(archetype.get_base::<T>(state).as_ptr().add(index as usize));
 
         archetype.borrow_mut::<T>(state);
+        archetype.mark_row_changed(state, index);
 
         Ok((target, Self { archetype, state }))
     }
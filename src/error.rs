@@ -0,0 +1,137 @@
+use core::fmt;
+
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use crate::{ComponentError, Entity, MissingComponent, NoSuchEntity};
+
+/// An error from an operation on a specific entity, with context for diagnostics
+///
+/// Methods such as [`World::get`](crate::World::get) and [`World::despawn`](crate::World::despawn)
+/// return compact errors that don't identify the entity involved. Attach that context with
+/// [`ErrorContext::context`] where an error is propagated, so that logs say what failed and to
+/// which entity. Converts back into [`ComponentError`] for code that expects the compact form.
+///
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let e = world.spawn((1,));
+/// let err = world.get::<&bool>(e).context(e, "read flag").unwrap_err();
+/// assert_eq!(err.entity(), e);
+/// assert_eq!(err.to_string(), "failed to read flag 0v1: missing bool component");
+///
+/// world.despawn(e).unwrap();
+/// let err = world.despawn(e).context(e, "despawn").unwrap_err();
+/// assert_eq!(err.kind(), &EntityErrorKind::NoSuchEntity);
+/// assert_eq!(err.to_string(), "failed to despawn 0v1: no such entity");
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct EntityError {
+    entity: Entity,
+    operation: &'static str,
+    kind: EntityErrorKind,
+}
+
+impl EntityError {
+    /// Construct an error describing a failure to perform `operation` on `entity`
+    pub fn new(entity: Entity, operation: &'static str, kind: impl Into<EntityErrorKind>) -> Self {
+        Self {
+            entity,
+            operation,
+            kind: kind.into(),
+        }
+    }
+
+    /// The entity the operation was performed on, including its generation
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Short description of the operation that failed
+    pub fn operation(&self) -> &'static str {
+        self.operation
+    }
+
+    /// Why the operation failed
+    pub fn kind(&self) -> &EntityErrorKind {
+        &self.kind
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for EntityError {}
+
+impl fmt::Display for EntityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to {} {:?}: {}",
+            self.operation, self.entity, self.kind
+        )
+    }
+}
+
+/// Why an operation described by an [`EntityError`] failed
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum EntityErrorKind {
+    /// The entity was already despawned
+    NoSuchEntity,
+    /// The entity did not have a required component
+    MissingComponent(MissingComponent),
+}
+
+impl fmt::Display for EntityErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use EntityErrorKind::*;
+        match *self {
+            NoSuchEntity => f.write_str("no such entity"),
+            MissingComponent(ref x) => x.fmt(f),
+        }
+    }
+}
+
+impl From<NoSuchEntity> for EntityErrorKind {
+    fn from(NoSuchEntity: NoSuchEntity) -> Self {
+        EntityErrorKind::NoSuchEntity
+    }
+}
+
+impl From<MissingComponent> for EntityErrorKind {
+    fn from(x: MissingComponent) -> Self {
+        EntityErrorKind::MissingComponent(x)
+    }
+}
+
+impl From<ComponentError> for EntityErrorKind {
+    fn from(x: ComponentError) -> Self {
+        match x {
+            ComponentError::NoSuchEntity => EntityErrorKind::NoSuchEntity,
+            ComponentError::MissingComponent(x) => EntityErrorKind::MissingComponent(x),
+        }
+    }
+}
+
+impl From<EntityError> for ComponentError {
+    /// Discard the context
+    fn from(x: EntityError) -> Self {
+        match x.kind {
+            EntityErrorKind::NoSuchEntity => ComponentError::NoSuchEntity,
+            EntityErrorKind::MissingComponent(x) => ComponentError::MissingComponent(x),
+        }
+    }
+}
+
+/// Attaches context to the errors returned by operations on entities
+///
+/// Implemented for results of operations that fail with [`NoSuchEntity`], [`MissingComponent`],
+/// or [`ComponentError`].
+pub trait ErrorContext<T> {
+    /// Describe an error as a failure to perform `operation` on `entity`
+    fn context(self, entity: Entity, operation: &'static str) -> Result<T, EntityError>;
+}
+
+impl<T, E: Into<EntityErrorKind>> ErrorContext<T> for Result<T, E> {
+    fn context(self, entity: Entity, operation: &'static str) -> Result<T, EntityError> {
+        self.map_err(|e| EntityError::new(entity, operation, e))
+    }
+}
@@ -0,0 +1,194 @@
+use crate::alloc::{string::String, vec::Vec};
+use core::alloc::Layout;
+use core::any::TypeId;
+use core::{fmt, mem, ptr};
+
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use crate::archetype::RawColumn;
+use crate::{Component, ComponentRegistry, World};
+
+/// Component columns copied out of a [`World`] in structure-of-arrays form, e.g. for offline
+/// analytics
+///
+/// Every entity having all of the selected components contributes one row, in the order of
+/// [`World::archetypes`] and then of [`Archetype::ids`](crate::Archetype::ids). Each column is a
+/// contiguous buffer holding the raw bytes of one plain-old-data component type per row, as
+/// registered with [`ComponentRegistry::register_pod`]. For fixed-width types such as integers,
+/// floats, and arrays thereof, this is the layout of Arrow's fixed-size primitive and fixed-size
+/// list buffers on little-endian targets, so columns can be wrapped in Arrow arrays without
+/// further conversion. The entities themselves are exported as a column of
+/// [`Entity::to_bits`](crate::Entity::to_bits).
+///
+/// ```
+/// # use hecs::*;
+/// #[derive(Copy, Clone, Debug, PartialEq)]
+/// #[repr(C)]
+/// struct Position([f32; 2]);
+///
+/// let mut registry = ComponentRegistry::new();
+/// unsafe {
+///     registry.register_pod::<Position>("Position");
+///     registry.register_pod::<u32>("Health");
+/// }
+/// let mut world = World::new();
+/// let a = world.spawn((Position([1.0, 2.0]), 100u32));
+/// world.spawn((Position([3.0, 4.0]),));
+///
+/// let export = ColumnExport::new(&world, &registry, &["Position", "Health"]).unwrap();
+/// assert_eq!(export.entities(), [a.to_bits().get()]);
+/// let position = export.column("Position").unwrap();
+/// assert_eq!(position.data().len(), 8);
+/// assert_eq!(position.to_vec::<Position>().unwrap(), [Position([1.0, 2.0])]);
+/// ```
+pub struct ColumnExport {
+    entities: Vec<u64>,
+    columns: Vec<ExportedColumn>,
+}
+
+impl ColumnExport {
+    /// Copy the components registered in `registry` under `names` from every entity in `world`
+    /// that has all of them
+    ///
+    /// # Panics
+    ///
+    /// Panics if any selected component is uniquely borrowed.
+    pub fn new(
+        world: &World,
+        registry: &ComponentRegistry,
+        names: &[&str],
+    ) -> Result<Self, ExportError> {
+        let mut columns = names
+            .iter()
+            .map(|&name| {
+                let reg = registry
+                    .get_by_name(name)
+                    .ok_or_else(|| ExportError::UnknownComponent(name.into()))?;
+                if !reg.is_pod() {
+                    return Err(ExportError::NotPod(reg.name()));
+                }
+                Ok(ExportedColumn {
+                    name: reg.name(),
+                    id: reg.type_info().id(),
+                    layout: reg.type_info().layout(),
+                    data: Vec::new(),
+                    rows: 0,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut entities = Vec::new();
+        for archetype in world.archetypes() {
+            if archetype.is_empty() || !columns.iter().all(|x| archetype.has_dynamic(x.id)) {
+                continue;
+            }
+            entities.extend(
+                archetype
+                    .ids()
+                    .iter()
+                    .map(|&id| unsafe { world.find_entity_from_id(id) }.to_bits().get()),
+            );
+            for column in &mut columns {
+                let source = RawColumn::new(archetype, column.id).unwrap();
+                column.data.extend_from_slice(unsafe { source.bytes() });
+                column.rows += archetype.len() as usize;
+            }
+        }
+        Ok(Self { entities, columns })
+    }
+
+    /// Number of rows
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Whether there are no rows
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// [`Entity::to_bits`](crate::Entity::to_bits) of the entity in each row
+    pub fn entities(&self) -> &[u64] {
+        &self.entities
+    }
+
+    /// The exported columns, in the order their names were given
+    pub fn columns(&self) -> &[ExportedColumn] {
+        &self.columns
+    }
+
+    /// The column of the component registered under `name`, if exported
+    pub fn column(&self, name: &str) -> Option<&ExportedColumn> {
+        self.columns.iter().find(|x| x.name == name)
+    }
+}
+
+/// The components of a single type exported by a [`ColumnExport`]
+pub struct ExportedColumn {
+    name: &'static str,
+    id: TypeId,
+    layout: Layout,
+    data: Vec<u8>,
+    rows: usize,
+}
+
+impl ExportedColumn {
+    /// Name under which the component type is registered
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Layout of a single component
+    ///
+    /// Rows are `layout().size()` bytes apart.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// The raw bytes of each component in turn
+    ///
+    /// Not necessarily aligned to [`layout`](Self::layout).
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Take ownership of the raw bytes
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Copy the components out, if they're of type `T`
+    pub fn to_vec<T: Component + Copy>(&self) -> Option<Vec<T>> {
+        if self.id != TypeId::of::<T>() {
+            return None;
+        }
+        let size = mem::size_of::<T>();
+        Some(
+            (0..self.rows)
+                .map(|i| unsafe { ptr::read_unaligned(self.data.as_ptr().add(i * size).cast()) })
+                .collect(),
+        )
+    }
+}
+
+/// Error produced by [`ColumnExport::new`]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ExportError {
+    /// No component type is registered under the given name
+    UnknownComponent(String),
+    /// The named component type wasn't registered as plain old data
+    NotPod(&'static str),
+}
+
+#[cfg(feature = "std")]
+impl Error for ExportError {}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::UnknownComponent(name) => write!(f, "unknown component {name:?}"),
+            ExportError::NotPod(name) => write!(f, "component {name:?} is not plain old data"),
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::alloc::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use crate::alloc::vec::Vec;
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
+use core::{fmt, slice};
+
+use spin::Mutex;
+
+/// A bump allocator for temporary data that lives until the end of the current tick
+///
+/// Every [`World`](crate::World) owns one, accessed with
+/// [`World::frame_alloc`](crate::World::frame_alloc) and reset by
+/// [`World::advance_tick`](crate::World::advance_tick). Helpers such as
+/// [`QueryBorrow::collect_entities_in`](crate::QueryBorrow::collect_entities_in) use it to avoid
+/// allocating a fresh `Vec` on every call. Once memory for a tick's peak usage has been
+/// allocated, it is reused on every later tick, so steady-state use allocates nothing.
+///
+/// Only [`Copy`] types can be allocated, since values are never dropped.
+///
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let a = world.spawn((3,));
+/// let b = world.spawn((1,));
+/// let entities = world.query::<&i32>().sorted_entities_in(world.frame_alloc(), |&x| x);
+/// assert_eq!(entities, [b, a]);
+/// world.advance_tick(); // Releases `entities` for reuse
+/// ```
+pub struct FrameAlloc {
+    inner: Mutex<Chunks>,
+}
+
+impl FrameAlloc {
+    /// Create an allocator that holds no memory until first used
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(Chunks {
+                chunks: Vec::new(),
+                current: 0,
+                offset: 0,
+            }),
+        }
+    }
+
+    /// Copy `values` into the allocator
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_copy<T: Copy>(&self, values: &[T]) -> &mut [T] {
+        self.alloc_slice_fill_iter(values.iter().copied())
+    }
+
+    /// Move the values yielded by `iter` into the allocator
+    ///
+    /// Space is reserved for the number of values `iter` claims it will yield, and any values
+    /// beyond that are discarded.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_fill_iter<T: Copy, I>(&self, iter: I) -> &mut [T]
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let iter = iter.into_iter();
+        let storage = self.alloc_uninit::<T>(iter.len());
+        let mut len = 0;
+        for (slot, value) in storage.iter_mut().zip(iter) {
+            slot.write(value);
+            len += 1;
+        }
+        unsafe { slice::from_raw_parts_mut(storage.as_mut_ptr().cast::<T>(), len) }
+    }
+
+    /// Reserve uninitialized space for `len` values of type `T`
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_uninit<T>(&self, len: usize) -> &mut [MaybeUninit<T>] {
+        let layout = Layout::array::<T>(len).expect("allocation too large");
+        if layout.size() == 0 {
+            return unsafe {
+                slice::from_raw_parts_mut(NonNull::<MaybeUninit<T>>::dangling().as_ptr(), len)
+            };
+        }
+        let ptr = self.inner.lock().alloc(layout);
+        // Each call is given a distinct region that stays valid until `reset`, which requires
+        // exclusive access
+        unsafe { slice::from_raw_parts_mut(ptr.as_ptr().cast(), len) }
+    }
+
+    /// Bytes of memory held by the allocator, whether in use or not
+    pub fn capacity(&self) -> usize {
+        let inner = self.inner.lock();
+        inner.chunks.iter().map(|x| x.layout.size()).sum()
+    }
+
+    /// Bytes allocated since the last reset
+    pub fn used(&self) -> usize {
+        let inner = self.inner.lock();
+        inner.chunks[..inner.current]
+            .iter()
+            .map(|x| x.layout.size())
+            .sum::<usize>()
+            + inner.offset
+    }
+
+    /// Make all memory available for reuse
+    ///
+    /// If the previous allocations spanned several chunks, they are merged into one large enough
+    /// to hold them all, so that repeating the same allocations won't need more memory.
+    pub fn reset(&mut self) {
+        let inner = self.inner.get_mut();
+        if inner.current > 0 {
+            let total = inner.chunks.iter().map(|x| x.layout.size()).sum();
+            inner.chunks.clear();
+            inner.chunks.push(Chunk::new(total));
+        }
+        inner.current = 0;
+        inner.offset = 0;
+    }
+}
+
+impl Default for FrameAlloc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for FrameAlloc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameAlloc")
+            .field("used", &self.used())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+struct Chunks {
+    chunks: Vec<Chunk>,
+    /// Index of the chunk being allocated from
+    current: usize,
+    /// Offset of the first free byte in the current chunk
+    offset: usize,
+}
+
+impl Chunks {
+    fn alloc(&mut self, layout: Layout) -> NonNull<u8> {
+        loop {
+            if let Some(chunk) = self.chunks.get(self.current) {
+                let base = chunk.ptr.as_ptr() as usize;
+                let start =
+                    ((base + self.offset + layout.align() - 1) & !(layout.align() - 1)) - base;
+                if start + layout.size() <= chunk.layout.size() {
+                    self.offset = start + layout.size();
+                    return unsafe { NonNull::new_unchecked(chunk.ptr.as_ptr().add(start)) };
+                }
+                if self.current + 1 < self.chunks.len() {
+                    self.current += 1;
+                    self.offset = 0;
+                    continue;
+                }
+            }
+            let last = self.chunks.last().map_or(0, |x| x.layout.size());
+            let size = (layout.size() + layout.align())
+                .max(last * 2)
+                .max(MIN_CHUNK_SIZE);
+            self.chunks.push(Chunk::new(size));
+            self.current = self.chunks.len() - 1;
+            self.offset = 0;
+        }
+    }
+}
+
+const MIN_CHUNK_SIZE: usize = 4096;
+
+/// Alignment of every chunk, sufficient for all common types
+const CHUNK_ALIGN: usize = 16;
+
+struct Chunk {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+impl Chunk {
+    fn new(size: usize) -> Self {
+        let layout = Layout::from_size_align(size, CHUNK_ALIGN).unwrap();
+        let ptr =
+            NonNull::new(unsafe { alloc(layout) }).unwrap_or_else(|| handle_alloc_error(layout));
+        Self { ptr, layout }
+    }
+}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        unsafe {
+            dealloc(self.ptr.as_ptr(), self.layout);
+        }
+    }
+}
+
+// Chunks hold only untyped memory, and all access to them is synchronized by the mutex
+unsafe impl Send for Chunk {}
+unsafe impl Sync for Chunk {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuse_after_reset() {
+        let mut frame = FrameAlloc::new();
+        assert_eq!(frame.capacity(), 0);
+        let a = frame.alloc_slice_copy(&[1u8, 2, 3]);
+        let b = frame.alloc_slice_fill_iter((0..2000).map(|x| x as u64));
+        assert_eq!(a, [1, 2, 3]);
+        assert_eq!(b.len(), 2000);
+        assert_eq!(b[1999], 1999);
+        assert_eq!(b.as_ptr() as usize % core::mem::align_of::<u64>(), 0);
+        assert!(frame.used() >= 16003);
+        let capacity = frame.capacity();
+
+        frame.reset();
+        assert_eq!(frame.used(), 0);
+        assert_eq!(frame.capacity(), capacity);
+        frame.alloc_slice_copy(&[1u8, 2, 3]);
+        frame.alloc_slice_fill_iter((0..2000).map(|x| x as u64));
+        assert_eq!(frame.capacity(), capacity);
+    }
+
+    #[test]
+    fn over_aligned() {
+        #[derive(Copy, Clone)]
+        #[repr(align(64))]
+        struct Aligned(u8);
+
+        let frame = FrameAlloc::new();
+        frame.alloc_slice_copy(&[0u8]);
+        let x = frame.alloc_slice_copy(&[Aligned(7); 3]);
+        assert_eq!(x.as_ptr() as usize % 64, 0);
+        assert_eq!(x[2].0, 7);
+        assert_eq!(frame.alloc_slice_copy::<()>(&[(); 10]).len(), 10);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::alloc::vec::Vec;
+use core::any::TypeId;
+use core::mem;
+
+use crate::{Component, Entity, World};
+
+/// Callbacks through which an external index, such as a spatial grid or BVH, is kept in sync with
+/// the `T` components of a [`World`]
+///
+/// Invoked by [`IndexSync`].
+pub trait IndexHooks<T> {
+    /// `entity` gained a `T` component, or was spawned with one
+    fn on_add(&mut self, entity: Entity, new: &T);
+
+    /// `entity`'s `T` component changed according to [`PartialEq`]
+    fn on_change(&mut self, entity: Entity, old: &T, new: &T);
+
+    /// `entity` lost its `T` component, or was despawned
+    fn on_remove(&mut self, entity: Entity, old: &T);
+
+    /// Discard all contents and rebuild from scratch
+    ///
+    /// Useful for indices that are cheaper to build in bulk than incrementally.
+    fn rebuild(&mut self, components: &mut dyn Iterator<Item = (Entity, &T)>);
+}
+
+/// Helper to keep an external index in sync with the `T` components of a [`World`]
+///
+/// Each call to [`sync`](Self::sync) reports every addition, change, and removal of a `T`
+/// component since the previous call to an [`IndexHooks`] implementation. A copy of each
+/// component is stored to detect changes and to report the values of removed components, including
+/// those of despawned entities.
+///
+/// Hooks are called in a deterministic order: first `on_remove` in ascending order of entity ID,
+/// then `on_add` and `on_change` in query iteration order. Archetypes whose `T` components haven't
+/// changed since the [`World::tick`] of the previous call are skipped entirely, so advancing the
+/// tick between calls makes syncing largely unchanged worlds cheap.
+///
+/// Always use the same `IndexSync` with the same [`World`].
+///
+/// ```
+/// # use hecs::*;
+/// # use std::collections::BTreeMap;
+/// #[derive(Clone, PartialEq)]
+/// struct Position(i32);
+///
+/// #[derive(Default)]
+/// struct Grid(BTreeMap<i32, Vec<Entity>>);
+///
+/// impl IndexHooks<Position> for Grid {
+///     fn on_add(&mut self, entity: Entity, new: &Position) {
+///         self.0.entry(new.0).or_default().push(entity);
+///     }
+///     fn on_change(&mut self, entity: Entity, old: &Position, new: &Position) {
+///         self.on_remove(entity, old);
+///         self.on_add(entity, new);
+///     }
+///     fn on_remove(&mut self, entity: Entity, old: &Position) {
+///         self.0.get_mut(&old.0).unwrap().retain(|&e| e != entity);
+///     }
+///     fn rebuild(&mut self, components: &mut dyn Iterator<Item = (Entity, &Position)>) {
+///         self.0.clear();
+///         for (entity, new) in components {
+///             self.on_add(entity, new);
+///         }
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// let mut grid = Grid::default();
+/// let mut sync = IndexSync::<Position>::new();
+/// let a = world.spawn((Position(3),));
+/// sync.sync(&world, &mut grid);
+/// assert_eq!(grid.0[&3], [a]);
+///
+/// world.advance_tick();
+/// world.get::<&mut Position>(a).unwrap().0 = 4;
+/// sync.sync(&world, &mut grid);
+/// assert!(grid.0[&3].is_empty());
+/// assert_eq!(grid.0[&4], [a]);
+/// ```
+pub struct IndexSync<T> {
+    /// Copy of each entity's component as of the previous sync, indexed by entity ID
+    slots: Vec<Option<(Entity, T)>>,
+    /// Tick at which the previous sync occurred
+    synced: Option<u32>,
+}
+
+impl<T: Component + Clone + PartialEq> IndexSync<T> {
+    /// Create a helper which has yet to observe any components
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            synced: None,
+        }
+    }
+
+    /// Report changes in `world`'s `T` components since the previous call to `hooks`
+    pub fn sync(&mut self, world: &World, hooks: &mut impl IndexHooks<T>) {
+        for slot in &mut self.slots {
+            let Some((entity, old)) = slot else {
+                continue;
+            };
+            if world.satisfies::<&T>(*entity).unwrap_or(false) {
+                continue;
+            }
+            hooks.on_remove(*entity, old);
+            *slot = None;
+        }
+
+        for archetype in world.archetypes() {
+            let Some(column) = archetype.get::<&T>() else {
+                continue;
+            };
+            let unchanged = self.synced.map_or(false, |tick| {
+                archetype.change_tick(TypeId::of::<T>()).unwrap() < tick
+            });
+            if unchanged {
+                continue;
+            }
+            for (&id, new) in archetype.ids().iter().zip(column.iter()) {
+                if self.slots.len() <= id as usize {
+                    self.slots.resize_with(id as usize + 1, || None);
+                }
+                let entity = unsafe { world.find_entity_from_id(id) };
+                match &mut self.slots[id as usize] {
+                    Some((_, old)) => {
+                        if old != new {
+                            let old = mem::replace(old, new.clone());
+                            hooks.on_change(entity, &old, new);
+                        }
+                    }
+                    slot @ None => {
+                        *slot = Some((entity, new.clone()));
+                        hooks.on_add(entity, new);
+                    }
+                }
+            }
+        }
+        self.synced = Some(world.tick());
+    }
+
+    /// Discard all previously observed components and pass the current contents of `world` to
+    /// [`IndexHooks::rebuild`]
+    pub fn rebuild(&mut self, world: &World, hooks: &mut impl IndexHooks<T>) {
+        self.slots.clear();
+        for archetype in world.archetypes() {
+            let Some(column) = archetype.get::<&T>() else {
+                continue;
+            };
+            for (&id, new) in archetype.ids().iter().zip(column.iter()) {
+                if self.slots.len() <= id as usize {
+                    self.slots.resize_with(id as usize + 1, || None);
+                }
+                let entity = unsafe { world.find_entity_from_id(id) };
+                self.slots[id as usize] = Some((entity, new.clone()));
+            }
+        }
+        let mut components = self
+            .slots
+            .iter()
+            .filter_map(|x| x.as_ref().map(|(entity, x)| (*entity, x)));
+        hooks.rebuild(&mut components);
+        self.synced = Some(world.tick());
+    }
+}
+
+impl<T: Component + Clone + PartialEq> Default for IndexSync<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Log(Vec<(&'static str, Entity, i32)>);
+
+    impl IndexHooks<i32> for Log {
+        fn on_add(&mut self, entity: Entity, new: &i32) {
+            self.0.push(("add", entity, *new));
+        }
+        fn on_change(&mut self, entity: Entity, _: &i32, new: &i32) {
+            self.0.push(("change", entity, *new));
+        }
+        fn on_remove(&mut self, entity: Entity, old: &i32) {
+            self.0.push(("remove", entity, *old));
+        }
+        fn rebuild(&mut self, components: &mut dyn Iterator<Item = (Entity, &i32)>) {
+            self.0.clear();
+            self.0
+                .extend(components.map(|(entity, x)| ("rebuild", entity, *x)));
+        }
+    }
+
+    #[test]
+    fn smoke() {
+        let mut world = World::new();
+        let mut log = Log::default();
+        let mut sync = IndexSync::<i32>::new();
+
+        let a = world.spawn((1,));
+        let b = world.spawn((2, true));
+        let c = world.spawn((true,));
+        sync.sync(&world, &mut log);
+        assert_eq!(log.0, [("add", a, 1), ("add", b, 2)]);
+        log.0.clear();
+
+        world.advance_tick();
+        sync.sync(&world, &mut log);
+        assert!(log.0.is_empty());
+
+        world.advance_tick();
+        *world.get::<&mut i32>(b).unwrap() = 5;
+        world.despawn(a).unwrap();
+        world.insert_one(c, 3).unwrap();
+        let d = world.spawn((4,));
+        assert_eq!(d.id(), a.id());
+        sync.sync(&world, &mut log);
+        assert_eq!(
+            log.0,
+            [
+                ("remove", a, 1),
+                ("add", d, 4),
+                ("change", b, 5),
+                ("add", c, 3),
+            ]
+        );
+        log.0.clear();
+
+        world.advance_tick();
+        world.remove_one::<i32>(b).unwrap();
+        sync.sync(&world, &mut log);
+        assert_eq!(log.0, [("remove", b, 5)]);
+
+        sync.rebuild(&world, &mut log);
+        assert_eq!(log.0, [("rebuild", d, 4), ("rebuild", c, 3)]);
+    }
+}
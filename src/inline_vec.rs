@@ -0,0 +1,86 @@
+use crate::alloc::boxed::Box;
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut};
+use core::{fmt, slice};
+
+/// A fixed-length list of `Copy` values, stored inline if there are at most `N`
+///
+/// Keeps the short lists that describe most archetypes in the same allocation as the archetype
+/// itself, so that matching queries against archetypes doesn't need to chase pointers.
+#[derive(Clone)]
+pub(crate) enum InlineVec<T: Copy, const N: usize> {
+    Inline {
+        len: usize,
+        data: [MaybeUninit<T>; N],
+    },
+    Heap(Box<[T]>),
+}
+
+impl<T: Copy, const N: usize> FromIterator<T> for InlineVec<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut iter = iter.into_iter();
+        let mut data = [MaybeUninit::uninit(); N];
+        let mut len = 0;
+        for slot in &mut data {
+            match iter.next() {
+                Some(x) => {
+                    slot.write(x);
+                    len += 1;
+                }
+                None => return InlineVec::Inline { len, data },
+            }
+        }
+        match iter.next() {
+            None => InlineVec::Inline { len, data },
+            Some(x) => {
+                let inline = data.iter().map(|x| unsafe { x.assume_init() });
+                InlineVec::Heap(inline.chain(Some(x)).chain(iter).collect())
+            }
+        }
+    }
+}
+
+impl<T: Copy, const N: usize> Deref for InlineVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match *self {
+            InlineVec::Inline { len, ref data } => unsafe {
+                slice::from_raw_parts(data.as_ptr().cast(), len)
+            },
+            InlineVec::Heap(ref x) => x,
+        }
+    }
+}
+
+impl<T: Copy, const N: usize> DerefMut for InlineVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        match *self {
+            InlineVec::Inline { len, ref mut data } => unsafe {
+                slice::from_raw_parts_mut(data.as_mut_ptr().cast(), len)
+            },
+            InlineVec::Heap(ref mut x) => x,
+        }
+    }
+}
+
+impl<T: Copy + fmt::Debug, const N: usize> fmt::Debug for InlineVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_and_heap() {
+        for len in 0..10 {
+            let mut x = (0..len).rev().collect::<InlineVec<u32, 4>>();
+            assert_eq!(matches!(x, InlineVec::Inline { .. }), len <= 4);
+            x.sort_unstable();
+            assert!(x.iter().copied().eq(0..len));
+        }
+    }
+}
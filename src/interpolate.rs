@@ -0,0 +1,160 @@
+use crate::alloc::vec::Vec;
+
+use crate::archetype::RawColumn;
+use crate::{Component, ComponentRegistry, Entity, World};
+
+/// Linear interpolation between two values of a type, e.g. for rendering a fixed-timestep
+/// simulation between steps
+///
+/// Registered with [`ComponentRegistry::register_lerp`] for use by [`interpolate_into`].
+pub trait Lerp {
+    /// Compute the value `alpha` of the way from `self` to `other`
+    ///
+    /// `alpha` is usually between 0, which should yield `self`, and 1, which should yield `other`.
+    fn lerp(&self, other: &Self, alpha: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &Self, alpha: f32) -> Self {
+        self + (other - self) * alpha
+    }
+}
+
+impl Lerp for f64 {
+    fn lerp(&self, other: &Self, alpha: f32) -> Self {
+        self + (other - self) * f64::from(alpha)
+    }
+}
+
+impl<T: Lerp, const N: usize> Lerp for [T; N] {
+    fn lerp(&self, other: &Self, alpha: f32) -> Self {
+        core::array::from_fn(|i| self[i].lerp(&other[i], alpha))
+    }
+}
+
+/// Visit the `T` component of every entity in `current`, interpolated `alpha` of the way from
+/// its value in `previous`
+///
+/// Intended for extracting render state from the previous and current steps of a fixed-timestep
+/// simulation. Entities whose `T` component is absent from `previous`, e.g. because they were just
+/// spawned, are visited with their current value interpolated towards itself.
+///
+/// ```
+/// # use hecs::*;
+/// #[derive(Debug, PartialEq)]
+/// struct Position([f32; 2]);
+///
+/// impl Lerp for Position {
+///     fn lerp(&self, other: &Self, alpha: f32) -> Self {
+///         Position(self.0.lerp(&other.0, alpha))
+///     }
+/// }
+///
+/// let mut previous = World::new();
+/// let e = previous.spawn((Position([0.0, 0.0]),));
+/// let mut current = World::new();
+/// current.spawn_at(e, (Position([2.0, 4.0]),));
+///
+/// let mut extracted = Vec::new();
+/// interpolate::<Position>(&previous, &current, 0.25, |entity, x| extracted.push((entity, x)));
+/// assert_eq!(extracted, [(e, Position([0.5, 1.0]))]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if any `T` component in either world is uniquely borrowed.
+pub fn interpolate<T: Component + Lerp>(
+    previous: &World,
+    current: &World,
+    alpha: f32,
+    mut f: impl FnMut(Entity, T),
+) {
+    for (entity, new) in current.query::<&T>().iter() {
+        let old = previous.get::<&T>(entity).ok();
+        f(entity, old.as_deref().unwrap_or(new).lerp(new, alpha));
+    }
+}
+
+/// Overwrite components in `target` with values interpolated `alpha` of the way from `previous`
+/// to `current`
+///
+/// Every component in `current` whose type was registered in `registry` with
+/// [`ComponentRegistry::register_lerp`] is interpolated and written to the entity with the same
+/// handle in `target`, if that entity has a component of the same type. Components absent from
+/// `previous` are interpolated from their current value towards itself. Other components of
+/// `target` are left unchanged, so `target` is typically a render world kept structurally in sync
+/// with `current`, e.g. by [`World::restore_exact`].
+///
+/// ```
+/// # use hecs::*;
+/// let mut registry = ComponentRegistry::new();
+/// registry.register_lerp::<f32>("f32").register_clone::<f32>("f32");
+///
+/// let mut previous = World::new();
+/// let e = previous.spawn((1.0f32, 'a'));
+/// let mut current = World::new();
+/// current.spawn_at(e, (3.0f32, 'b'));
+///
+/// let mut render = World::new();
+/// render.restore_exact(&current.snapshot(&registry));
+/// interpolate_into(&previous, &current, 0.5, &registry, &mut render);
+/// assert_eq!(*render.get::<&f32>(e).unwrap(), 2.0);
+/// ```
+///
+/// # Panics
+///
+/// Panics if any interpolated component in `previous` or `current` is uniquely borrowed.
+pub fn interpolate_into(
+    previous: &World,
+    current: &World,
+    alpha: f32,
+    registry: &ComponentRegistry,
+    target: &mut World,
+) {
+    for archetype in current.archetypes().filter(|x| !x.is_empty()) {
+        let columns = archetype
+            .types()
+            .iter()
+            .filter_map(|&ty| {
+                let lerp = registry.get(ty.id())?.lerp_fn()?;
+                Some((ty, lerp, RawColumn::new(archetype, ty.id()).unwrap()))
+            })
+            .collect::<Vec<_>>();
+        if columns.is_empty() {
+            continue;
+        }
+        for (index, &id) in archetype.ids().iter().enumerate() {
+            let entity = unsafe { current.find_entity_from_id(id) };
+            let Ok(dest) = target.entity(entity) else {
+                continue;
+            };
+            let old = previous.entity(entity).ok();
+            let (dest_archetype, dest_index) = dest.raw();
+            for (ty, lerp, column) in &columns {
+                let Some(state) = dest_archetype.get_state_dynamic(ty.id()) else {
+                    continue;
+                };
+                let size = ty.layout().size();
+                unsafe {
+                    let new = column.base().add(index * size);
+                    let old = old.map(|x| x.raw()).filter(|(x, _)| x.has_dynamic(ty.id()));
+                    let _old_borrow = old.map(|(x, _)| RawColumn::new(x, ty.id()).unwrap());
+                    let old = match old {
+                        Some((x, i)) => x
+                            .get_dynamic(ty.id(), size, i)
+                            .unwrap()
+                            .as_ptr()
+                            .cast_const(),
+                        None => new,
+                    };
+                    dest_archetype.mark_mutated(state);
+                    dest_archetype.mark_row_changed(state, dest_index);
+                    let out = dest_archetype
+                        .get_dynamic(ty.id(), size, dest_index)
+                        .unwrap();
+                    lerp(old, new, alpha, out.as_ptr());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::alloc::collections::VecDeque;
+use crate::alloc::vec::Vec;
+
+use crate::{Entity, TypeIdMap, TypeInfo, World};
+
+/// Looks for signs that a long-running [`World`] is leaking entities
+///
+/// Intended for soak tests: call [`sample`](Self::sample) periodically, e.g. once per tick, and
+/// inspect the [`report`](Self::report) at the end. Reports three kinds of suspect:
+///
+/// - Entities with no components, which are invisible to every query and so are rarely intended
+///   to live long.
+/// - Entities that no query has accessed since
+///   [`World::reset_query_tracking`](World::reset_query_tracking) was last called. Tracking is at
+///   the granularity of archetypes, so an entity is only reported if no query has accessed
+///   anything sharing its exact set of component types.
+/// - Component types whose number of instances increased at every one of the most recent samples.
+///
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let mut detector = LeakDetector::new(3);
+/// let orphan = world.spawn(());
+/// for i in 0..3 {
+///     world.spawn((i, true));
+///     world.query::<&i32>().iter().for_each(|_| {});
+///     detector.sample(&world);
+/// }
+/// let report = detector.report(&world);
+/// assert_eq!(report.empty_entities(), [orphan]);
+/// assert!(report.unqueried_entities().is_empty());
+/// assert_eq!(report.growing_components().len(), 2); // i32 and bool
+/// ```
+pub struct LeakDetector {
+    window: usize,
+    /// Instance counts of each component type at the most recent samples, oldest first
+    counts: TypeIdMap<(TypeInfo, VecDeque<u32>)>,
+}
+
+impl LeakDetector {
+    /// Flag component types whose instance counts increase over `window` consecutive samples
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is less than 2.
+    pub fn new(window: usize) -> Self {
+        assert!(window >= 2, "growth is measured over at least two samples");
+        Self {
+            window,
+            counts: TypeIdMap::default(),
+        }
+    }
+
+    /// Record the number of instances of each component type in `world`
+    pub fn sample(&mut self, world: &World) {
+        let mut current = TypeIdMap::<(TypeInfo, u32)>::default();
+        for archetype in world.archetypes() {
+            for ty in archetype.types() {
+                current.entry(ty.id()).or_insert((*ty, 0)).1 += archetype.len();
+            }
+        }
+        for (id, &(ty, _)) in &current {
+            self.counts
+                .entry(*id)
+                .or_insert_with(|| (ty, VecDeque::new()));
+        }
+        let window = self.window;
+        self.counts.retain(|id, (_, history)| {
+            history.push_back(current.get(id).map_or(0, |&(_, count)| count));
+            if history.len() > window {
+                history.pop_front();
+            }
+            // Forget types that have been absent for a whole window
+            history.iter().any(|&count| count != 0)
+        });
+    }
+
+    /// Find suspected leaks in `world`, using the samples taken so far
+    pub fn report(&self, world: &World) -> LeakReport {
+        let mut report = LeakReport::default();
+        for archetype in world.archetypes() {
+            let list = if archetype.types().is_empty() {
+                &mut report.empty_entities
+            } else if !archetype.queried() {
+                &mut report.unqueried_entities
+            } else {
+                continue;
+            };
+            // Archetypes only hold live entities
+            list.extend(
+                archetype
+                    .ids()
+                    .iter()
+                    .map(|&id| unsafe { world.find_entity_from_id(id) }),
+            );
+        }
+        report.growing_components = self
+            .counts
+            .values()
+            .filter(|(_, history)| {
+                history.len() == self.window
+                    && history
+                        .iter()
+                        .zip(history.iter().skip(1))
+                        .all(|(a, b)| a < b)
+            })
+            .map(|(ty, history)| ComponentGrowth {
+                ty: *ty,
+                first: history[0],
+                last: history[history.len() - 1],
+            })
+            .collect();
+        report.growing_components.sort_unstable_by_key(|x| x.ty);
+        report
+    }
+}
+
+/// Suspected leaks found by [`LeakDetector::report`]
+#[derive(Debug, Clone, Default)]
+pub struct LeakReport {
+    empty_entities: Vec<Entity>,
+    unqueried_entities: Vec<Entity>,
+    growing_components: Vec<ComponentGrowth>,
+}
+
+impl LeakReport {
+    /// Entities having no components
+    pub fn empty_entities(&self) -> &[Entity] {
+        &self.empty_entities
+    }
+
+    /// Entities having components, but never accessed by a query since tracking was last reset
+    pub fn unqueried_entities(&self) -> &[Entity] {
+        &self.unqueried_entities
+    }
+
+    /// Component types whose instance counts increased at every sample in the window
+    pub fn growing_components(&self) -> &[ComponentGrowth] {
+        &self.growing_components
+    }
+
+    /// Whether nothing suspicious was found
+    pub fn is_empty(&self) -> bool {
+        self.empty_entities.is_empty()
+            && self.unqueried_entities.is_empty()
+            && self.growing_components.is_empty()
+    }
+}
+
+/// A component type whose number of instances grew steadily, reported by [`LeakReport`]
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentGrowth {
+    /// The component type
+    pub ty: TypeInfo,
+    /// Number of instances at the first sample in the window
+    pub first: u32,
+    /// Number of instances at the most recent sample
+    pub last: u32,
+}
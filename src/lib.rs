@@ -34,6 +34,9 @@ extern crate std;
 
 #[doc(hidden)]
 pub extern crate alloc;
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub extern crate serde;
 #[doc(hidden)]
 pub extern crate spin;
 
@@ -62,43 +65,119 @@ macro_rules! smaller_tuples_too {
 }
 
 mod archetype;
+#[cfg(feature = "audit-log")]
+mod audit;
 mod batch;
 mod borrow;
+mod budget;
 mod bundle;
 mod change_tracker;
 mod command_buffer;
+mod compaction;
+mod despawn_queue;
+mod dynamic;
 mod entities;
 mod entity_builder;
+mod entity_mask;
 mod entity_ref;
+mod error;
+mod export;
+mod frame_alloc;
+mod index_sync;
+mod inline_vec;
+mod interpolate;
+mod leak;
+mod metrics;
+mod op_log;
+mod order;
+mod parallel;
+mod patch;
+mod pinned;
+mod plugin;
+mod prefab;
 mod query;
+mod query_builder;
 mod query_one;
-#[cfg(any(feature = "row-serialize", feature = "column-serialize"))]
+pub mod reflect;
+mod registry;
+mod remap;
+mod scope;
+mod script;
+#[cfg(any(
+    feature = "row-serialize",
+    feature = "column-serialize",
+    feature = "rkyv"
+))]
 pub mod serialize;
+mod snapshot;
+mod stable_hash;
 mod take;
+pub mod testing;
+mod validate;
+mod value_index;
 mod world;
+mod world_diff;
 
 pub use archetype::{Archetype, ArchetypeColumn, ArchetypeColumnMut, TypeIdMap, TypeInfo};
-pub use batch::{BatchIncomplete, BatchWriter, ColumnBatch, ColumnBatchBuilder, ColumnBatchType};
+#[cfg(feature = "audit-log")]
+pub use audit::{AuditEvent, AuditOp};
+pub use batch::{
+    BatchAllocError, BatchIncomplete, BatchWriter, ColumnBatch, ColumnBatchBuilder, ColumnBatchType,
+};
+pub use budget::{Budget, BudgetKind, BudgetUsage};
 pub use bundle::{
     bundle_satisfies_query, dynamic_bundle_satisfies_query, Bundle, DynamicBundle,
     DynamicBundleClone, MissingComponent,
 };
 pub use change_tracker::{ChangeTracker, Changes};
-pub use command_buffer::CommandBuffer;
-pub use entities::{Entity, NoSuchEntity};
+pub use command_buffer::{CommandBuffer, CommandReport, DeadEntityPolicy};
+pub use compaction::CompactionTask;
+pub use despawn_queue::DespawnQueue;
+pub use dynamic::{ComponentKey, DynamicBatch, DynamicColumn, RegisterDynamicError};
+pub use entities::{
+    AllocError, AllocatorState, AllocatorStateMismatch, Entity, EntityBits, NoSuchEntity,
+};
 pub use entity_builder::{BuiltEntity, BuiltEntityClone, EntityBuilder, EntityBuilderClone};
+pub use entity_mask::EntityMask;
 pub use entity_ref::{ComponentRef, ComponentRefShared, EntityRef, Ref, RefMut};
+pub use error::{EntityError, EntityErrorKind, ErrorContext};
+pub use export::{ColumnExport, ExportError, ExportedColumn};
+pub use frame_alloc::FrameAlloc;
+pub use index_sync::{IndexHooks, IndexSync};
+pub use interpolate::{interpolate, interpolate_into, Lerp};
+pub use leak::{ComponentGrowth, LeakDetector, LeakReport};
+pub use metrics::{GenerationWrapStats, MetricsSink, OperationCounts};
+pub use op_log::{LoggedOp, Op, OpLog};
+pub use order::OrderToken;
+#[cfg(feature = "std")]
+pub use parallel::ThreadExecutor;
+pub use parallel::{Executor, SerialExecutor, Task};
+pub use patch::WorldPatch;
+pub use pinned::Pinned;
+pub use plugin::Plugin;
+pub use prefab::{Prefab, PrefabDiff, PrefabId, PrefabInstance};
 pub use query::{
-    Access, Batch, BatchedIter, Or, PreparedQuery, PreparedQueryBorrow, PreparedQueryIter,
-    PreparedView, Query, QueryBorrow, QueryIter, QueryMut, QueryShared, Satisfies, View,
-    ViewBorrow, With, Without,
+    Access, Added, Batch, BatchedIter, Changed, EntityLocation, FrozenIter, LocatedIter,
+    MaskedIter, Or, PreparedQuery, PreparedQueryBorrow, PreparedQueryIter, PreparedView, Query,
+    QueryBorrow, QueryIter, QueryMut, QueryShared, Satisfies, View, ViewBorrow, With, Without,
 };
+pub use query_builder::{QueryBuilder, QueryDescription};
 pub use query_one::QueryOne;
+pub use registry::{ComponentRegistration, ComponentRegistry, MissingClone, MissingDefault};
+pub use remap::{EntityRemap, VisitEntities};
+pub use scope::Scope;
+pub use script::{ScriptError, ScriptValue, ScriptWorld};
+pub use snapshot::WorldSnapshot;
+pub use stable_hash::{StableHash, StableHasher};
 pub use take::TakenEntity;
+pub use validate::{Transition, ValidationError, ValidationMode};
+pub use value_index::IndexKind;
 pub use world::{
-    ArchetypesGeneration, Component, ComponentError, Iter, QueryOneError, SpawnBatchIter,
-    SpawnColumnBatchIter, World,
+    ArchetypesGeneration, BundleId, Component, ComponentError, ExclusionPolicy, InsertDefaultError,
+    InsertDynamicError, Iter, IterationOrder, QueryOneError, SingletonPolicy, SpawnBatchIter,
+    SpawnColumnBatchIter, TryInsertError, TrySpawnError, World, WorldBuilder,
 };
+pub use world_diff::{ChangeKind, ComponentChange, DiffReport, WorldDiff};
 
 // Unstable implementation details needed by the macros
 #[doc(hidden)]
@@ -107,7 +186,7 @@ pub use bundle::DynamicClone;
 pub use query::Fetch;
 
 #[cfg(feature = "macros")]
-pub use hecs_macros::{Bundle, DynamicBundleClone, Query};
+pub use hecs_macros::{Bundle, DynamicBundleClone, Query, StableHash, VisitEntities};
 
 fn align(x: usize, alignment: usize) -> usize {
     debug_assert!(alignment.is_power_of_two());
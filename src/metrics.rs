@@ -0,0 +1,56 @@
+/// Numbers of structural operations performed on a [`World`](crate::World) during a tick
+///
+/// See [`World::operation_counts`](crate::World::operation_counts).
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct OperationCounts {
+    /// Entities spawned, including reserved entities made real by
+    /// [`World::flush`](crate::World::flush)
+    pub spawns: u64,
+    /// Entities despawned, taken, or replaced by spawning another entity with the same ID
+    pub despawns: u64,
+    /// Entities moved to a different archetype by adding or removing components
+    pub archetype_moves: u64,
+    /// Component columns reallocated to increase their capacity
+    pub column_grows: u64,
+    /// Calls to [`World::flush`](crate::World::flush), including implicit ones, that made any
+    /// reserved entities real
+    pub flushes: u64,
+}
+
+/// How often the generations of a [`World`](crate::World)'s entity IDs have wrapped around
+///
+/// Each time an entity is despawned, the generation of its ID is incremented so that stale
+/// handles to it can be detected. Once the generation reaches the limit set by
+/// [`EntityBits`](crate::EntityBits), it starts over from 1, so a handle held since the previous
+/// wraparound could mistake a later entity with the same ID for its own. Frequent wraps suggest
+/// that the world should use more generation bits, or reuse IDs less eagerly.
+///
+/// See [`World::generation_wrap_stats`](crate::World::generation_wrap_stats).
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct GenerationWrapStats {
+    /// Number of IDs whose generation has wrapped at least once
+    pub wrapped_ids: u32,
+    /// Total number of wraps across all IDs
+    pub total_wraps: u64,
+    /// Greatest number of times any one ID's generation has wrapped
+    pub max_wraps: u32,
+}
+
+/// Receives the [`OperationCounts`] of each tick of a [`World`](crate::World)
+///
+/// Installed with [`World::set_metrics_sink`](crate::World::set_metrics_sink). Counting is always
+/// enabled and costs only an integer addition per operation, so a sink can forward counts to
+/// telemetry without sampling.
+///
+/// Implemented for closures taking the same arguments as [`record`](Self::record).
+pub trait MetricsSink: Send + Sync {
+    /// Called by [`World::advance_tick`](crate::World::advance_tick) with the operations performed
+    /// during `tick`, the tick that just ended
+    fn record(&mut self, tick: u32, counts: &OperationCounts);
+}
+
+impl<F: FnMut(u32, &OperationCounts) + Send + Sync> MetricsSink for F {
+    fn record(&mut self, tick: u32, counts: &OperationCounts) {
+        self(tick, counts)
+    }
+}
@@ -0,0 +1,297 @@
+use crate::alloc::vec::Vec;
+use core::any::TypeId;
+use core::fmt;
+
+use crate::alloc::boxed::Box;
+use crate::{
+    Archetype, BuiltEntityClone, ComponentRegistry, Entity, EntityBuilderClone, NoSuchEntity, World,
+};
+
+/// A recording of the structural operations performed on a [`World`]
+///
+/// Started with [`World::start_op_log`] and retrieved with [`World::take_op_log`]. Replaying the
+/// log onto a fresh world with [`replay`](Self::replay) reproduces the recorded world's entities
+/// and components, which makes rare desyncs between simulations reproducible.
+///
+/// Component values are cloned from the world as they're added, using the clone functions of the
+/// [`ComponentRegistry`] passed to `start_op_log`. Components of other types can't be recorded;
+/// they're listed by [`unrecorded`](Self::unrecorded). Modifications made to components in place,
+/// e.g. through queries, aren't structural operations and aren't recorded either, nor are bulk
+/// restores such as [`World::clear`].
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut registry = ComponentRegistry::new();
+/// registry.register_clone::<i32>("i32");
+/// registry.register_clone::<bool>("bool");
+///
+/// let mut world = World::new();
+/// world.start_op_log(&registry);
+/// let a = world.spawn((1, true));
+/// let b = world.spawn((2,));
+/// world.advance_tick();
+/// world.remove_one::<bool>(a).unwrap();
+/// world.insert_one(b, false).unwrap();
+/// world.despawn(a).unwrap();
+/// let log = world.take_op_log().unwrap();
+///
+/// let mut replayed = World::new();
+/// log.replay(&mut replayed).unwrap();
+/// assert!(!replayed.contains(a));
+/// assert_eq!(*replayed.get::<&i32>(b).unwrap(), 2);
+/// assert!(!*replayed.get::<&bool>(b).unwrap());
+/// assert_eq!(replayed.tick(), 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OpLog {
+    ops: Vec<LoggedOp>,
+    unrecorded: Vec<TypeId>,
+}
+
+impl OpLog {
+    /// The recorded operations, oldest first
+    pub fn ops(&self) -> &[LoggedOp] {
+        &self.ops
+    }
+
+    /// Number of recorded operations
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Whether no operations were recorded
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Component types that were added to entities but left out of the log because they weren't
+    /// registered with [`ComponentRegistry::register_clone`], sorted
+    ///
+    /// If this is non-empty, [`replay`](Self::replay) won't reproduce the recorded world exactly.
+    pub fn unrecorded(&self) -> &[TypeId] {
+        &self.unrecorded
+    }
+
+    /// Perform the recorded operations on `world`, setting its tick to that of each operation
+    ///
+    /// `world` should be in the state the recorded world was in when recording started, typically
+    /// empty. Fails if an operation refers to an entity that doesn't exist, which indicates that
+    /// it wasn't.
+    pub fn replay(&self, world: &mut World) -> Result<(), NoSuchEntity> {
+        for logged in &self.ops {
+            if world.tick() != logged.tick {
+                world.set_tick(logged.tick);
+            }
+            match logged.op {
+                Op::Spawn {
+                    entity,
+                    ref components,
+                } => world.spawn_at(entity, components),
+                Op::Despawn { entity } => world.despawn(entity)?,
+                Op::Insert {
+                    entity,
+                    ref components,
+                } => world.insert(entity, components)?,
+                Op::Remove { entity, ref types } => {
+                    for &id in types.iter() {
+                        world.remove_dynamic(entity, id)?;
+                    }
+                }
+                Op::Flush { ref entities } => {
+                    for &entity in entities.iter() {
+                        world.spawn_at(entity, ());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, tick: u32, op: Op) {
+        self.ops.push(LoggedOp { tick, op });
+    }
+}
+
+/// An [`Op`] and the tick it was performed during
+#[derive(Debug, Clone)]
+pub struct LoggedOp {
+    tick: u32,
+    op: Op,
+}
+
+impl LoggedOp {
+    /// The value of [`World::tick`] when the operation was performed
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// The operation
+    pub fn op(&self) -> &Op {
+        &self.op
+    }
+}
+
+/// A structural operation recorded in an [`OpLog`]
+#[derive(Clone)]
+pub enum Op {
+    /// `entity` was spawned with `components`
+    Spawn {
+        /// The new entity
+        entity: Entity,
+        /// Clones of the entity's components
+        components: BuiltEntityClone,
+    },
+    /// `entity` was despawned
+    Despawn {
+        /// The former entity
+        entity: Entity,
+    },
+    /// `components` were added to `entity`, replacing any of the same types
+    Insert {
+        /// The modified entity
+        entity: Entity,
+        /// Clones of the added components
+        components: BuiltEntityClone,
+    },
+    /// The components of `types` were removed from `entity`
+    Remove {
+        /// The modified entity
+        entity: Entity,
+        /// The types of the removed components
+        types: Box<[TypeId]>,
+    },
+    /// Reserved `entities` became empty entities
+    Flush {
+        /// The new entities
+        entities: Box<[Entity]>,
+    },
+}
+
+impl fmt::Debug for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Op::Spawn {
+                entity,
+                ref components,
+            } => f
+                .debug_struct("Spawn")
+                .field("entity", &entity)
+                .field("components", &components.ids())
+                .finish(),
+            Op::Despawn { entity } => f.debug_struct("Despawn").field("entity", &entity).finish(),
+            Op::Insert {
+                entity,
+                ref components,
+            } => f
+                .debug_struct("Insert")
+                .field("entity", &entity)
+                .field("components", &components.ids())
+                .finish(),
+            Op::Remove { entity, ref types } => f
+                .debug_struct("Remove")
+                .field("entity", &entity)
+                .field("types", types)
+                .finish(),
+            Op::Flush { ref entities } => {
+                f.debug_struct("Flush").field("entities", entities).finish()
+            }
+        }
+    }
+}
+
+/// State of a world whose operations are being recorded
+pub(crate) struct OpRecorder {
+    registry: ComponentRegistry,
+    log: OpLog,
+}
+
+impl OpRecorder {
+    pub(crate) fn new(registry: &ComponentRegistry) -> Self {
+        Self {
+            registry: registry.clone(),
+            log: OpLog::default(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> OpLog {
+        self.log
+    }
+
+    pub(crate) fn push(&mut self, tick: u32, op: Op) {
+        self.log.push(tick, op);
+    }
+
+    /// Record that the entity at `index` in `archetype` was spawned
+    pub(crate) fn spawned(&mut self, tick: u32, entity: Entity, archetype: &Archetype, index: u32) {
+        let components = self.clone_row(archetype, index, None);
+        self.push(tick, Op::Spawn { entity, components });
+    }
+
+    /// Record that the components of `types` were added to the entity at `index` in `archetype`
+    pub(crate) fn inserted(
+        &mut self,
+        tick: u32,
+        entity: Entity,
+        archetype: &Archetype,
+        index: u32,
+        types: &[TypeId],
+    ) {
+        let components = self.clone_row(archetype, index, Some(types));
+        self.push(tick, Op::Insert { entity, components });
+    }
+
+    /// Record that `entity` moved from `source` to `target`, or was despawned if `target` is `None`
+    pub(crate) fn removed(
+        &mut self,
+        tick: u32,
+        entity: Entity,
+        source: &Archetype,
+        target: Option<&Archetype>,
+    ) {
+        let op = match target {
+            None => Op::Despawn { entity },
+            Some(target) => Op::Remove {
+                entity,
+                types: source
+                    .type_ids()
+                    .iter()
+                    .copied()
+                    .filter(|&id| !target.has_dynamic(id))
+                    .collect(),
+            },
+        };
+        self.push(tick, op);
+    }
+
+    /// Clone the components of `types`, or all components, of the entity at `index` in `archetype`
+    fn clone_row(
+        &mut self,
+        archetype: &Archetype,
+        index: u32,
+        types: Option<&[TypeId]>,
+    ) -> BuiltEntityClone {
+        let mut builder = EntityBuilderClone::new();
+        for ty in archetype.types() {
+            if types.map_or(false, |x| !x.contains(&ty.id())) {
+                continue;
+            }
+            let clone = match self.registry.get(ty.id()).and_then(|x| x.dynamic_clone()) {
+                Some(x) => x,
+                None => {
+                    if let Err(i) = self.log.unrecorded.binary_search(&ty.id()) {
+                        self.log.unrecorded.insert(i, ty.id());
+                    }
+                    continue;
+                }
+            };
+            unsafe {
+                let src = archetype
+                    .get_dynamic(ty.id(), ty.layout().size(), index)
+                    .unwrap();
+                builder.add_cloned(src.as_ptr(), clone);
+            }
+        }
+        builder.build()
+    }
+}
@@ -0,0 +1,50 @@
+use crate::alloc::vec::Vec;
+
+use crate::Entity;
+
+/// A canonical order in which to visit entities, captured by [`World::freeze_order`] or
+/// [`QueryBorrow::freeze_order`] and followed by [`QueryBorrow::iter_frozen`]
+///
+/// Queries normally visit entities in storage order, which changes whenever an entity is removed
+/// from the middle of an archetype. Capturing a token at the start of a tick and iterating with it
+/// throughout lets every system in the tick observe entities in the same order, however
+/// structural changes were interleaved with them.
+///
+/// [`World::freeze_order`]: crate::World::freeze_order
+/// [`QueryBorrow::freeze_order`]: crate::QueryBorrow::freeze_order
+/// [`QueryBorrow::iter_frozen`]: crate::QueryBorrow::iter_frozen
+#[derive(Debug, Clone, Default)]
+pub struct OrderToken {
+    /// Entities in the order to visit them
+    order: Vec<Entity>,
+    /// `order`, sorted
+    sorted: Vec<Entity>,
+}
+
+impl OrderToken {
+    pub(crate) fn new(order: Vec<Entity>) -> Self {
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        Self { order, sorted }
+    }
+
+    /// The entities captured, in order
+    pub fn entities(&self) -> &[Entity] {
+        &self.order
+    }
+
+    /// Whether `entity` was captured
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.sorted.binary_search(&entity).is_ok()
+    }
+
+    /// Number of entities captured
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Whether no entities were captured
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
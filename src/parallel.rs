@@ -0,0 +1,87 @@
+use crate::alloc::boxed::Box;
+use crate::alloc::vec::Vec;
+
+/// A unit of work submitted to an [`Executor`]
+pub type Task<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+/// Runs independent tasks, possibly in parallel
+///
+/// Used by parallel query operations such as
+/// [`QueryBorrow::par_fold_ordered`](crate::QueryBorrow::par_fold_ordered), which arrange for their
+/// results not to depend on the order in which tasks run or which threads run them. Implement this
+/// for your thread pool of choice; for example, with rayon:
+///
+/// ```ignore
+/// struct Rayon;
+///
+/// impl hecs::Executor for Rayon {
+///     fn execute<'a>(&self, tasks: Vec<hecs::Task<'a>>) {
+///         rayon::scope(|s| {
+///             for task in tasks {
+///                 s.spawn(move |_| task());
+///             }
+///         });
+///     }
+/// }
+/// ```
+pub trait Executor {
+    /// Run every task in `tasks` exactly once, returning only once all have completed
+    fn execute<'a>(&self, tasks: Vec<Task<'a>>);
+}
+
+/// An [`Executor`] that runs every task on the calling thread, in order
+///
+/// Useful for testing that the results of a parallel operation are independent of scheduling.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SerialExecutor;
+
+impl Executor for SerialExecutor {
+    fn execute<'a>(&self, tasks: Vec<Task<'a>>) {
+        for task in tasks {
+            task();
+        }
+    }
+}
+
+/// An [`Executor`] that spreads tasks over a fixed number of scoped threads
+///
+/// Spawns threads on every call, so is best suited to coarse work; prefer a thread pool where
+/// one is available.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+#[derive(Debug, Copy, Clone)]
+pub struct ThreadExecutor {
+    threads: usize,
+}
+
+#[cfg(feature = "std")]
+impl ThreadExecutor {
+    /// Create an executor that uses up to `threads` threads, including the calling thread
+    pub fn new(threads: usize) -> Self {
+        Self {
+            threads: threads.max(1),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Executor for ThreadExecutor {
+    fn execute<'a>(&self, tasks: Vec<Task<'a>>) {
+        let chunk = (tasks.len() + self.threads - 1) / self.threads;
+        if chunk == 0 {
+            return;
+        }
+        let mut tasks = tasks.into_iter();
+        std::thread::scope(|scope| {
+            let local = tasks.by_ref().take(chunk).collect::<Vec<_>>();
+            loop {
+                let remote = tasks.by_ref().take(chunk).collect::<Vec<_>>();
+                if remote.is_empty() {
+                    break;
+                }
+                scope.spawn(move || remote.into_iter().for_each(|task| task()));
+            }
+            local.into_iter().for_each(|task| task());
+        });
+    }
+}
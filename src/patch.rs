@@ -0,0 +1,72 @@
+use crate::alloc::vec::Vec;
+use core::any::TypeId;
+
+use crate::snapshot::ArchetypeSnapshot;
+
+/// Everything about a [`World`](crate::World) modified since a given tick, captured by
+/// [`World::serialize_changes_since`](crate::World::serialize_changes_since) and applied to
+/// another world with [`World::apply_patch`](crate::World::apply_patch)
+///
+/// Built from per-column change ticks, so granularity is that of an archetype's column: when any
+/// `T` component in an archetype is modified, every `T` component of that archetype is included,
+/// and when any entity enters an archetype, all of that archetype's components are. Only
+/// components whose types were registered with
+/// [`ComponentRegistry::register_clone`](crate::ComponentRegistry::register_clone) are captured.
+///
+/// Whereas [`WorldDiff`](crate::WorldDiff) compares two complete worlds, a patch is computed from
+/// one world alone, making it suitable for replicating a live world to mirrors that have applied
+/// every earlier patch.
+#[derive(Clone)]
+pub struct WorldPatch {
+    pub(crate) since: u32,
+    pub(crate) tick: u32,
+    /// IDs of entities despawned without their IDs being reused
+    pub(crate) despawned: Vec<u32>,
+    /// Types of all components the patch may describe, sorted
+    pub(crate) scope: Vec<TypeId>,
+    pub(crate) archetypes: Vec<PatchArchetype>,
+}
+
+impl WorldPatch {
+    /// The tick changes were captured since
+    pub fn since(&self) -> u32 {
+        self.since
+    }
+
+    /// The world's tick when the patch was captured
+    ///
+    /// Pass `tick() + 1` to the next `serialize_changes_since` call, after
+    /// [`World::advance_tick`](crate::World::advance_tick), to capture each change once.
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// IDs of the entities despawned since [`since`](Self::since), in ascending order
+    pub fn despawned(&self) -> &[u32] {
+        &self.despawned
+    }
+
+    /// Number of spawned or modified entities whose components are included
+    pub fn len(&self) -> usize {
+        self.archetypes
+            .iter()
+            .map(|x| x.snapshot.entities.len())
+            .sum()
+    }
+
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.archetypes.is_empty() && self.despawned.is_empty()
+    }
+}
+
+/// Changed components of the entities of one archetype
+#[derive(Clone)]
+pub(crate) struct PatchArchetype {
+    /// Whether entities entered the archetype, so that their components outside `types` must be
+    /// removed
+    pub(crate) entered: bool,
+    /// Types of the archetype's components within the patch's scope, sorted
+    pub(crate) types: Vec<TypeId>,
+    pub(crate) snapshot: ArchetypeSnapshot,
+}
@@ -0,0 +1,79 @@
+use crate::alloc::boxed::Box;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+/// A component whose value stays at the same address for as long as an entity holds it
+///
+/// Components are ordinarily stored inline in their archetype's columns, so their addresses change
+/// whenever an entity moves to another archetype, a column grows, or another entity is despawned.
+/// Wrapping a value in `Pinned` stores it on the heap instead, so that only the pointer moves, and
+/// a raw pointer obtained from [`as_ptr`](Self::as_ptr) remains valid until the `Pinned` is
+/// dropped, e.g. by despawning the entity or removing the component. This suits external systems
+/// that cache pointers to component data, such as physics engines referencing rigid bodies.
+///
+/// Query for `Pinned<T>` rather than `T`. Access through the pointer must not conflict with access
+/// through hecs, as for [`Archetype::column_ptr`](crate::Archetype::column_ptr).
+///
+/// ```
+/// # use hecs::*;
+/// struct Body {
+///     mass: f32,
+/// }
+///
+/// let mut world = World::new();
+/// let e = world.spawn((Pinned::new(Body { mass: 1.0 }),));
+/// let body = world.get::<&Pinned<Body>>(e).unwrap().as_ptr();
+/// world.insert_one(e, true).unwrap(); // Moves `e` to another archetype
+/// assert_eq!(world.get::<&Pinned<Body>>(e).unwrap().as_ptr(), body);
+/// assert_eq!(unsafe { (*body).mass }, 1.0);
+/// ```
+#[derive(Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Pinned<T>(Box<T>);
+
+impl<T> Pinned<T> {
+    /// Move `value` to the heap
+    pub fn new(value: T) -> Self {
+        Self(Box::new(value))
+    }
+
+    /// Address of the value, which is stable for the lifetime of the `Pinned`
+    pub fn as_ptr(&self) -> *const T {
+        &*self.0
+    }
+
+    /// Like [`as_ptr`](Self::as_ptr), but for mutation
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        &mut *self.0
+    }
+
+    /// Move the value out
+    pub fn into_inner(self) -> T {
+        *self.0
+    }
+}
+
+impl<T> From<T> for Pinned<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> Deref for Pinned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Pinned<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Pinned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
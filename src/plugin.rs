@@ -0,0 +1,116 @@
+use crate::alloc::boxed::Box;
+use crate::alloc::sync::Arc;
+use core::any::{Any, TypeId};
+
+use crate::World;
+
+/// An optional subsystem that integrates with a [`World`] through a single
+/// [`World::add_plugin`] call
+///
+/// Hierarchies, event queues, indexes and replication typically need several of the world's
+/// extension points at once, such as [validators](World::add_validator),
+/// [requirements](World::set_requirements) and [indexes](World::register_index), along with state
+/// of their own and periodic upkeep. A plugin bundles these: [`build`](Self::build) installs its
+/// hooks, the plugin value itself holds its state, reachable through [`World::plugin`], and
+/// [`maintain`](Self::maintain) runs from [`World::maintain`]. Plugins that implement
+/// [`snapshot`](Self::snapshot) and [`restore`](Self::restore) have their state captured by
+/// [`World::snapshot`] and reinstated by [`World::restore_exact`] alongside the entities.
+///
+/// ```
+/// # use hecs::*;
+/// #[derive(Clone, Default)]
+/// struct Spawned(u32);
+///
+/// impl Plugin for Spawned {
+///     fn build(&mut self, world: &mut World) {
+///         self.0 = world.len();
+///     }
+///
+///     fn maintain(&mut self, world: &mut World) {
+///         self.0 = world.len();
+///     }
+///
+///     fn snapshot(&self) -> Option<Self> {
+///         Some(self.clone())
+///     }
+///
+///     fn restore(&mut self, snapshot: &Self) {
+///         *self = snapshot.clone();
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.spawn(());
+/// world.add_plugin(Spawned::default());
+/// assert_eq!(world.plugin::<Spawned>().unwrap().0, 1);
+/// world.spawn(());
+/// world.maintain(0);
+/// assert_eq!(world.plugin::<Spawned>().unwrap().0, 2);
+/// ```
+pub trait Plugin: Send + Sync + Sized + 'static {
+    /// Install the plugin's hooks into `world`, which it's being added to
+    fn build(&mut self, world: &mut World);
+
+    /// Perform periodic upkeep, called by [`World::maintain`] before any other maintenance
+    ///
+    /// Plugins are detached from `world` while they're maintained, so aren't visible to
+    /// [`World::plugin`].
+    fn maintain(&mut self, world: &mut World) {
+        let _ = world;
+    }
+
+    /// A copy of the state to be captured by [`World::snapshot`], if any
+    fn snapshot(&self) -> Option<Self> {
+        None
+    }
+
+    /// Reinstate state captured by [`snapshot`](Self::snapshot), called by
+    /// [`World::restore_exact`] after the snapshot's entities have been restored
+    fn restore(&mut self, snapshot: &Self) {
+        let _ = snapshot;
+    }
+}
+
+/// Type-erased plugin state
+pub(crate) type AnyPlugin = dyn Any + Send + Sync;
+
+/// A plugin added to a world, along with its type-erased trait methods
+pub(crate) struct PluginEntry {
+    pub(crate) id: TypeId,
+    pub(crate) plugin: Box<AnyPlugin>,
+    maintain: fn(&mut AnyPlugin, &mut World),
+    snapshot: fn(&AnyPlugin) -> Option<Arc<AnyPlugin>>,
+    restore: fn(&mut AnyPlugin, &AnyPlugin),
+}
+
+impl PluginEntry {
+    pub(crate) fn new<P: Plugin>(plugin: P) -> Self {
+        Self {
+            id: TypeId::of::<P>(),
+            plugin: Box::new(plugin),
+            maintain: |plugin, world| plugin.downcast_mut::<P>().unwrap().maintain(world),
+            snapshot: |plugin| {
+                let state = plugin.downcast_ref::<P>().unwrap().snapshot()?;
+                Some(Arc::new(state))
+            },
+            restore: |plugin, state| {
+                plugin
+                    .downcast_mut::<P>()
+                    .unwrap()
+                    .restore(state.downcast_ref::<P>().unwrap())
+            },
+        }
+    }
+
+    pub(crate) fn maintain(&mut self, world: &mut World) {
+        (self.maintain)(&mut *self.plugin, world)
+    }
+
+    pub(crate) fn snapshot(&self) -> Option<Arc<AnyPlugin>> {
+        (self.snapshot)(&*self.plugin)
+    }
+
+    pub(crate) fn restore(&mut self, state: &AnyPlugin) {
+        (self.restore)(&mut *self.plugin, state)
+    }
+}
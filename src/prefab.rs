@@ -0,0 +1,199 @@
+use crate::alloc::vec::Vec;
+use core::any::TypeId;
+
+use crate::{BuiltEntityClone, ComponentRegistry, EntityBuilderClone, EntityRef};
+
+/// A template from which any number of similar entities can be spawned
+///
+/// Instantiated with [`World::spawn_prefab`](crate::World::spawn_prefab) or
+/// [`World::spawn_prefab_with`](crate::World::spawn_prefab_with), each of which clones the
+/// prefab's components into a new entity, applies per-instance overrides, and attaches a
+/// [`PrefabInstance`] recording where the entity came from.
+///
+/// ```
+/// # use hecs::*;
+/// let mut builder = EntityBuilderClone::new();
+/// builder.add(100u32).add("goblin");
+/// let prefab = Prefab::new(PrefabId(1), builder);
+///
+/// let mut world = World::new();
+/// let a = world.spawn_prefab(&prefab, ());
+/// let b = world.spawn_prefab(&prefab, (50u32,));
+/// assert_eq!(*world.get::<&u32>(a).unwrap(), 100);
+/// assert_eq!(*world.get::<&u32>(b).unwrap(), 50);
+/// assert_eq!(world.get::<&PrefabInstance>(b).unwrap().prefab(), PrefabId(1));
+/// ```
+#[derive(Clone)]
+pub struct Prefab {
+    id: PrefabId,
+    components: BuiltEntityClone,
+}
+
+impl Prefab {
+    /// Create a prefab identified by `id` with the components in `components`
+    pub fn new(id: PrefabId, components: EntityBuilderClone) -> Self {
+        Self {
+            id,
+            components: components.build(),
+        }
+    }
+
+    /// Create a prefab identified by `id` from the components of an existing entity
+    ///
+    /// Only components whose type was registered in `registry` with
+    /// [`ComponentRegistry::register_clone`] are included. Useful for building prefabs from
+    /// entities loaded with the serialization module.
+    pub fn from_entity(id: PrefabId, entity: EntityRef<'_>, registry: &ComponentRegistry) -> Self {
+        let (archetype, index) = entity.raw();
+        let mut components = EntityBuilderClone::new();
+        for ty in archetype.types() {
+            let Some(clone) = registry.get(ty.id()).and_then(|x| x.dynamic_clone()) else {
+                continue;
+            };
+            unsafe {
+                let src = archetype
+                    .get_dynamic(ty.id(), ty.layout().size(), index)
+                    .unwrap();
+                components.add_cloned(src.as_ptr(), clone);
+            }
+        }
+        Self::new(id, components)
+    }
+
+    /// The identifier recorded in each instance's [`PrefabInstance`]
+    pub fn id(&self) -> PrefabId {
+        self.id
+    }
+
+    /// The components cloned into each instance
+    pub fn components(&self) -> &BuiltEntityClone {
+        &self.components
+    }
+
+    /// Enumerate the types of the prefab's components
+    pub fn component_types(&self) -> impl ExactSizeIterator<Item = TypeId> + '_ {
+        self.components.ids().iter().copied()
+    }
+
+    /// Compute the component-level differences from `self` to a modified version, `new`
+    ///
+    /// Components present in both are compared using the [`PartialEq`] implementations registered
+    /// in `registry` with [`ComponentRegistry::register_eq`]. Components whose type has no
+    /// registered implementation are conservatively reported as changed.
+    pub fn diff(&self, new: &Prefab, registry: &ComponentRegistry) -> PrefabDiff {
+        let mut diff = PrefabDiff {
+            old: self.id,
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        };
+        for &id in new.components.ids() {
+            let Some(old_ptr) = self.components.get_dynamic(id) else {
+                diff.added.push(id);
+                continue;
+            };
+            let new_ptr = new.components.get_dynamic(id).unwrap();
+            let equal = registry
+                .get(id)
+                .and_then(|x| unsafe { x.eq(old_ptr, new_ptr) })
+                .unwrap_or(false);
+            if !equal {
+                diff.changed.push(id);
+            }
+        }
+        diff.removed.extend(
+            self.components
+                .ids()
+                .iter()
+                .copied()
+                .filter(|&id| new.components.get_dynamic(id).is_none()),
+        );
+        diff.added.sort_unstable();
+        diff.removed.sort_unstable();
+        diff.changed.sort_unstable();
+        diff
+    }
+}
+
+/// Component-level differences between two versions of a [`Prefab`]
+///
+/// Computed by [`Prefab::diff`] and applied to existing instances by
+/// [`World::apply_prefab_diff`](crate::World::apply_prefab_diff).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PrefabDiff {
+    old: PrefabId,
+    added: Vec<TypeId>,
+    removed: Vec<TypeId>,
+    changed: Vec<TypeId>,
+}
+
+impl PrefabDiff {
+    /// The identifier of the old version, whose instances the diff applies to
+    pub fn old(&self) -> PrefabId {
+        self.old
+    }
+
+    /// Types of components present only in the new version
+    pub fn added(&self) -> &[TypeId] {
+        &self.added
+    }
+
+    /// Types of components present only in the old version
+    pub fn removed(&self) -> &[TypeId] {
+        &self.removed
+    }
+
+    /// Types of components present in both versions with different values
+    pub fn changed(&self) -> &[TypeId] {
+        &self.changed
+    }
+
+    /// Whether the versions are identical
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Identifies a [`Prefab`]
+///
+/// Chosen by the application, e.g. an asset ID, so that it remains meaningful across runs and in
+/// saved data.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct PrefabId(pub u64);
+
+/// Component recording the [`Prefab`] an entity was spawned from
+///
+/// Attached automatically by [`World::spawn_prefab`](crate::World::spawn_prefab) and
+/// [`World::spawn_prefab_with`](crate::World::spawn_prefab_with).
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct PrefabInstance {
+    prefab: PrefabId,
+    overrides: Vec<TypeId>,
+}
+
+impl PrefabInstance {
+    pub(crate) fn new(prefab: PrefabId, mut overrides: Vec<TypeId>) -> Self {
+        overrides.sort_unstable();
+        overrides.dedup();
+        Self { prefab, overrides }
+    }
+
+    pub(crate) fn set_prefab(&mut self, prefab: PrefabId) {
+        self.prefab = prefab;
+    }
+
+    /// The prefab the entity was spawned from
+    pub fn prefab(&self) -> PrefabId {
+        self.prefab
+    }
+
+    /// Types of the components that were supplied for this instance rather than by the prefab
+    pub fn overrides(&self) -> &[TypeId] {
+        &self.overrides
+    }
+
+    /// Whether the component of type `id` was supplied for this instance rather than by the prefab
+    pub fn is_overridden(&self, id: TypeId) -> bool {
+        self.overrides.binary_search(&id).is_ok()
+    }
+}
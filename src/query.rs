@@ -1,12 +1,15 @@
 use core::any::TypeId;
 use core::marker::PhantomData;
+use core::mem;
 use core::ptr::NonNull;
 use core::slice::Iter as SliceIter;
 
 use crate::alloc::{boxed::Box, vec::Vec};
-use crate::archetype::Archetype;
+use crate::archetype::{Archetype, RowTicks};
 use crate::entities::EntityMeta;
-use crate::{Component, Entity, World};
+use crate::{
+    Component, Entity, EntityMask, Executor, FrameAlloc, IterationOrder, OrderToken, Task, World,
+};
 
 /// A collection of component types to fetch from a [`World`](crate::World)
 ///
@@ -133,18 +136,32 @@ impl<T: Component> Query for &'_ mut T {
     type Fetch = FetchWrite<T>;
 
     unsafe fn get<'q>(fetch: &FetchWrite<T>, n: usize) -> &'q mut T {
-        &mut *fetch.0.as_ptr().add(n)
+        if let Some(ticks) = fetch.ticks {
+            (*ticks.as_ptr().add(n))
+                .changed
+                .store(fetch.epoch, core::sync::atomic::Ordering::Relaxed);
+        }
+        &mut *fetch.base.as_ptr().add(n)
     }
 }
 
 #[doc(hidden)]
-pub struct FetchWrite<T>(NonNull<T>);
+pub struct FetchWrite<T> {
+    base: NonNull<T>,
+    /// Per-entity ticks to mark as changed, if tracked
+    ticks: Option<NonNull<RowTicks>>,
+    epoch: u32,
+}
 
 unsafe impl<T: Component> Fetch for FetchWrite<T> {
     type State = usize;
 
     fn dangling() -> Self {
-        Self(NonNull::dangling())
+        Self {
+            base: NonNull::dangling(),
+            ticks: None,
+            epoch: 0,
+        }
     }
 
     fn access(archetype: &Archetype) -> Option<Access> {
@@ -163,7 +180,12 @@ unsafe impl<T: Component> Fetch for FetchWrite<T> {
         Some(archetype.get_state::<T>()?)
     }
     fn execute(archetype: &Archetype, state: Self::State) -> Self {
-        Self(archetype.get_base::<T>(state))
+        archetype.mark_mutated(state);
+        Self {
+            base: archetype.get_base::<T>(state),
+            ticks: archetype.row_ticks(state),
+            epoch: archetype.epoch(),
+        }
     }
     fn release(archetype: &Archetype, state: Self::State) {
         archetype.release_mut::<T>(state);
@@ -177,7 +199,11 @@ unsafe impl<T: Component> Fetch for FetchWrite<T> {
 impl<T> Clone for FetchWrite<T> {
     #[inline]
     fn clone(&self) -> Self {
-        Self(self.0)
+        Self {
+            base: self.base,
+            ticks: self.ticks,
+            epoch: self.epoch,
+        }
     }
 }
 
@@ -592,12 +618,154 @@ impl<T> Clone for FetchSatisfies<T> {
     }
 }
 
+/// A query that matches entities with a `T` component, yielding `bool`s indicating whether it was
+/// added since the world's change trackers were last cleared
+///
+/// Always `false` unless [`World::track_changes`](crate::World::track_changes) was called for `T`.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// world.track_changes::<i32>();
+/// let a = world.spawn((123,));
+/// world.clear_trackers();
+/// let b = world.spawn((456,));
+/// let added = world.query_mut::<Added<i32>>()
+///     .into_iter()
+///     .filter_map(|(e, added)| added.then_some(e))
+///     .collect::<Vec<_>>();
+/// assert_eq!(added, [b]);
+/// ```
+pub struct Added<T>(PhantomData<T>);
+
+impl<T: Component> Query for Added<T> {
+    type Item<'q> = bool;
+
+    type Fetch = FetchTicks<T>;
+
+    unsafe fn get<'q>(fetch: &Self::Fetch, n: usize) -> Self::Item<'q> {
+        fetch.get(n).map_or(false, |x| {
+            x.added.load(core::sync::atomic::Ordering::Relaxed) == fetch.epoch
+        })
+    }
+}
+
+unsafe impl<T> QueryShared for Added<T> {}
+
+/// A query that matches entities with a `T` component, yielding `bool`s indicating whether it was
+/// added or mutably accessed since the world's change trackers were last cleared
+///
+/// Like [`Archetype::change_tick`], mutable access counts as a change whether or not the
+/// component was actually modified. Always `false` unless
+/// [`World::track_changes`](crate::World::track_changes) was called for `T`.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// world.track_changes::<i32>();
+/// let a = world.spawn((123,));
+/// let b = world.spawn((456,));
+/// world.clear_trackers();
+/// *world.get::<&mut i32>(b).unwrap() += 1;
+/// let changed = world.query_mut::<Changed<i32>>()
+///     .into_iter()
+///     .filter_map(|(e, changed)| changed.then_some(e))
+///     .collect::<Vec<_>>();
+/// assert_eq!(changed, [b]);
+/// ```
+pub struct Changed<T>(PhantomData<T>);
+
+impl<T: Component> Query for Changed<T> {
+    type Item<'q> = bool;
+
+    type Fetch = FetchTicks<T>;
+
+    unsafe fn get<'q>(fetch: &Self::Fetch, n: usize) -> Self::Item<'q> {
+        fetch.get(n).map_or(false, |x| {
+            x.changed.load(core::sync::atomic::Ordering::Relaxed) == fetch.epoch
+        })
+    }
+}
+
+unsafe impl<T> QueryShared for Changed<T> {}
+
+#[doc(hidden)]
+pub struct FetchTicks<T> {
+    ticks: Option<NonNull<RowTicks>>,
+    epoch: u32,
+    _marker: PhantomData<T>,
+}
+
+impl<T> FetchTicks<T> {
+    /// Per-entity ticks of the `n`th entity, if tracked
+    unsafe fn get<'q>(&self, n: usize) -> Option<&'q RowTicks> {
+        Some(&*self.ticks?.as_ptr().add(n))
+    }
+}
+
+unsafe impl<T: Component> Fetch for FetchTicks<T> {
+    type State = usize;
+
+    fn dangling() -> Self {
+        Self {
+            ticks: None,
+            epoch: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    fn access(archetype: &Archetype) -> Option<Access> {
+        if archetype.has::<T>() {
+            Some(Access::Read)
+        } else {
+            None
+        }
+    }
+
+    fn borrow(archetype: &Archetype, state: Self::State) {
+        archetype.borrow::<T>(state);
+    }
+    fn prepare(archetype: &Archetype) -> Option<Self::State> {
+        archetype.get_state::<T>()
+    }
+    fn execute(archetype: &Archetype, state: Self::State) -> Self {
+        Self {
+            ticks: archetype.row_ticks(state),
+            epoch: archetype.epoch(),
+            _marker: PhantomData,
+        }
+    }
+    fn release(archetype: &Archetype, state: Self::State) {
+        archetype.release::<T>(state);
+    }
+
+    fn for_each_borrow(mut f: impl FnMut(TypeId, bool)) {
+        f(TypeId::of::<T>(), false);
+    }
+}
+
+impl<T> Clone for FetchTicks<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            ticks: self.ticks,
+            epoch: self.epoch,
+            _marker: PhantomData,
+        }
+    }
+}
+
 /// A borrow of a [`World`](crate::World) sufficient to execute the query `Q`
 ///
 /// Note that borrows are not released until this object is dropped.
 pub struct QueryBorrow<'w, Q: Query> {
     world: &'w World,
+    /// Indices of the archetypes the query is restricted to
+    archetypes: core::ops::Range<usize>,
     borrowed: bool,
+    changed: Vec<(TypeId, u32)>,
     _marker: PhantomData<Q>,
 }
 
@@ -605,7 +773,9 @@ impl<'w, Q: Query> QueryBorrow<'w, Q> {
     pub(crate) fn new(world: &'w World) -> Self {
         Self {
             world,
+            archetypes: 0..world.archetypes().len(),
             borrowed: false,
+            changed: Vec::new(),
             _marker: PhantomData,
         }
     }
@@ -614,13 +784,203 @@ impl<'w, Q: Query> QueryBorrow<'w, Q> {
     // The lifetime narrowing here is required for soundness.
     pub fn iter(&mut self) -> QueryIter<'_, Q> {
         self.borrow();
-        unsafe { QueryIter::new(self.world) }
+        unsafe {
+            QueryIter::new(self.world)
+                .with_archetypes(self.archetypes.clone())
+                .with_changed(self.changed.clone())
+                .in_spawn_order()
+        }
+    }
+
+    /// Like `iter`, but only visits entities present in `mask`
+    ///
+    /// Entities are visited in the same order as by `iter`, and the components of entities not in
+    /// `mask` are never fetched. Useful for restricting a query to the output of a broad-phase or
+    /// region-of-interest pass.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((1,));
+    /// let b = world.spawn((2,));
+    /// let mut mask = EntityMask::new();
+    /// mask.insert(b);
+    /// let entities = world.query::<&i32>()
+    ///     .iter_masked(&mask)
+    ///     .map(|(e, &i)| (e, i))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(entities, &[(b, 2)]);
+    /// ```
+    // The lifetime narrowing here is required for soundness.
+    pub fn iter_masked<'q>(&'q mut self, mask: &'q EntityMask) -> MaskedIter<'q, Q> {
+        MaskedIter {
+            inner: self.iter(),
+            mask,
+        }
+    }
+
+    /// Like `iter`, but also yields each entity's current [`EntityLocation`]
+    ///
+    /// Locations can later be passed to [`World::entity_at`] to access the entity without looking
+    /// up where it's stored, letting caches keyed by location skip repeated lookups until a
+    /// structural change moves the entity.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((1,));
+    /// let located = world.query::<&i32>()
+    ///     .iter_located()
+    ///     .map(|(e, loc, _)| (e, loc))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(located[0].0, a);
+    /// assert_eq!(world.entity_at(located[0].1).unwrap().entity(), a);
+    /// ```
+    // The lifetime narrowing here is required for soundness.
+    pub fn iter_located(&mut self) -> LocatedIter<'_, Q> {
+        LocatedIter {
+            inner: self.iter(),
+            archetype: 0,
+        }
+    }
+
+    /// Capture the order in which [`iter`](Self::iter) would currently visit the matching entities
+    ///
+    /// See [`OrderToken`] and [`World::freeze_order`].
+    pub fn freeze_order(&mut self) -> OrderToken {
+        OrderToken::new(self.iter().map(|(e, _)| e).collect())
+    }
+
+    /// Like `iter`, but visits entities in the order captured by `token`
+    ///
+    /// Entities in `token` that no longer exist or no longer match are skipped. Matching entities
+    /// not in `token`, such as those spawned since it was captured, are visited afterwards in
+    /// storage order.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((1,));
+    /// let b = world.spawn((2,));
+    /// let c = world.spawn((3,));
+    /// let token = world.freeze_order();
+    /// // Moves `c` into `a`'s storage
+    /// world.despawn(a).unwrap();
+    /// let d = world.spawn((4,));
+    /// let order = world.query::<&i32>()
+    ///     .iter_frozen(&token)
+    ///     .map(|(e, _)| e)
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(order, [b, c, d]);
+    /// ```
+    // The lifetime narrowing here is required for soundness.
+    pub fn iter_frozen<'q>(&'q mut self, token: &'q OrderToken) -> FrozenIter<'q, Q> {
+        self.borrow();
+        let archetypes = self.world.archetypes_inner();
+        let fetch = archetypes
+            .iter()
+            .enumerate()
+            .map(|(i, archetype)| {
+                if !self.archetypes.contains(&i) || !changed_since(archetype, &self.changed) {
+                    return None;
+                }
+                let state = Q::Fetch::prepare(archetype)?;
+                archetype.mark_queried();
+                Some(Q::Fetch::execute(archetype, state))
+            })
+            .collect();
+        FrozenIter {
+            meta: self.world.entities_meta(),
+            archetypes,
+            fetch,
+            token,
+            order: token.entities().iter(),
+            archetype: 0,
+            iter: ChunkIter::empty(),
+        }
+    }
+
+    /// Add every entity matching the query to `mask`
+    ///
+    /// Matching is decided a whole archetype at a time without fetching any components, so masks
+    /// from several queries can be cheaply built and combined with set operations before a final
+    /// [`iter_masked`](Self::iter_masked).
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((1, true));
+    /// let b = world.spawn((2,));
+    /// let c = world.spawn((true,));
+    /// let mut numbers = EntityMask::new();
+    /// world.query::<&i32>().collect_mask(&mut numbers);
+    /// let mut flags = EntityMask::new();
+    /// world.query::<&bool>().collect_mask(&mut flags);
+    /// let both = &numbers & &flags;
+    /// assert_eq!(both.ids().collect::<Vec<_>>(), &[a.id()]);
+    /// ```
+    pub fn collect_mask(&self, mask: &mut EntityMask) {
+        for archetype in self.archetypes() {
+            if Q::Fetch::access(archetype).is_none() || !changed_since(archetype, &self.changed) {
+                continue;
+            }
+            for &id in archetype.ids() {
+                mask.insert_id(id);
+            }
+        }
+    }
+
+    /// Collect the entities matching the query into `frame`, in iteration order
+    ///
+    /// Typically used with [`World::frame_alloc`](crate::World::frame_alloc) to avoid allocating
+    /// a new `Vec` each time. Doesn't borrow any components.
+    pub fn collect_entities_in<'a>(&self, frame: &'a FrameAlloc) -> &'a mut [Entity] {
+        let archetypes = self
+            .archetypes()
+            .iter()
+            .filter(|x| Q::Fetch::access(x).is_some() && changed_since(x, &self.changed));
+        let len = archetypes.clone().map(|x| x.len() as usize).sum();
+        let ids = archetypes.flat_map(|x| x.ids().iter());
+        let meta = self.world.entities_meta();
+        frame.alloc_slice_fill_iter(ExactLen(
+            ids.map(|&id| Entity {
+                id,
+                generation: meta[id as usize].generation,
+            }),
+            len,
+        ))
+    }
+
+    /// Collect the entities matching the query into `frame`, stably sorted by the key that `key`
+    /// computes from each entity's query results
+    ///
+    /// Typically used with [`World::frame_alloc`](crate::World::frame_alloc) to iterate in a
+    /// deterministic, application-defined order without allocating a new `Vec` each time.
+    pub fn sorted_entities_in<'a, K>(
+        &mut self,
+        frame: &'a FrameAlloc,
+        mut key: impl FnMut(Q::Item<'_>) -> K,
+    ) -> &'a mut [Entity]
+    where
+        K: Ord + Copy,
+    {
+        let iter = self.iter();
+        let keyed = frame.alloc_slice_fill_iter(iter.map(|(e, item)| (key(item), e)));
+        keyed.sort_by_key(|x| x.0);
+        frame.alloc_slice_fill_iter(keyed.iter().map(|&(_, e)| e))
     }
 
     /// Provide random access to the query results
     pub fn view(&mut self) -> View<'_, Q> {
         self.borrow();
-        unsafe { View::new(self.world.entities_meta(), self.world.archetypes_inner()) }
+        unsafe {
+            View::new(self.world.entities_meta(), self.world.archetypes_inner())
+                .with_archetypes(self.archetypes.clone())
+        }
     }
 
     /// Like `iter`, but returns child iterators of at most `batch_size` elements
@@ -632,9 +992,211 @@ impl<'w, Q: Query> QueryBorrow<'w, Q> {
         unsafe {
             BatchedIter::new(
                 self.world.entities_meta(),
-                self.world.archetypes_inner().iter(),
+                self.archetypes().iter(),
                 batch_size,
             )
+            .with_changed(self.changed.clone())
+        }
+    }
+
+    /// Fold over every entity matching the query in parallel, producing a result that doesn't
+    /// depend on how the work was scheduled
+    ///
+    /// Entities are divided into batches of at most `batch_size`, as by
+    /// [`iter_batched`](Self::iter_batched). Each batch is folded by a task run on `executor`,
+    /// starting from a fresh `identity()`, and the partial results are then combined with `reduce`
+    /// on the calling thread in the order that `iter_batched` yields the batches. Since that order
+    /// and the batch boundaries depend only on the world's contents and history, the result is
+    /// identical on every run even when `fold` and `reduce` aren't associative, as with sums of
+    /// floating-point numbers. Returns `identity()` if no entities match.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// world.spawn_batch((0..1000).map(|i| (i as f32 * 0.1,)));
+    /// let sum = |executor: &dyn Executor| {
+    ///     world.query::<&f32>().par_fold_ordered(
+    ///         64,
+    ///         executor,
+    ///         || 0.0,
+    ///         |acc, _, &x| acc + x,
+    ///         |a, b| a + b,
+    ///     )
+    /// };
+    /// let expected = sum(&SerialExecutor);
+    /// # #[cfg(feature = "std")]
+    /// assert_eq!(sum(&ThreadExecutor::new(4)).to_bits(), expected.to_bits());
+    /// ```
+    pub fn par_fold_ordered<T, E>(
+        &mut self,
+        batch_size: u32,
+        executor: &E,
+        identity: impl Fn() -> T + Sync,
+        fold: impl Fn(T, Entity, Q::Item<'_>) -> T + Sync,
+        reduce: impl FnMut(T, T) -> T,
+    ) -> T
+    where
+        T: Send,
+        E: Executor + ?Sized,
+        for<'a> Q::Item<'a>: Send,
+    {
+        let mut partials = (0..self.batch_count(batch_size))
+            .map(|_| None)
+            .collect::<Vec<Option<T>>>();
+        self.par_for_each_batch(batch_size, executor, &mut partials, |_, batch, out| {
+            *out = Some(batch.fold(identity(), |acc, (e, item)| fold(acc, e, item)));
+        });
+        partials
+            .into_iter()
+            .map(|x| x.expect("executor must run every task"))
+            .reduce(reduce)
+            .unwrap_or_else(identity)
+    }
+
+    /// Number of batches of at most `batch_size` entities that
+    /// [`iter_batched`](Self::iter_batched) would yield
+    ///
+    /// Doesn't borrow any components.
+    pub fn batch_count(&self, batch_size: u32) -> usize {
+        self.archetypes()
+            .iter()
+            .filter(|x| Q::Fetch::access(x).is_some() && changed_since(x, &self.changed))
+            .map(|x| ((x.len() + batch_size - 1) / batch_size) as usize)
+            .sum()
+    }
+
+    /// Process batches of at most `batch_size` entities in parallel, each writing its results to
+    /// its own element of `outputs`
+    ///
+    /// Batches are those yielded by [`iter_batched`](Self::iter_batched), and each is identified
+    /// by its position in that sequence, which depends only on the world's contents and history.
+    /// `f` is called with each batch's index, the batch, and `outputs[index]` by a task run on
+    /// `executor`. Since every batch writes to a slot determined in advance, results gathered
+    /// across threads, such as per-batch lists of render instances, can be concatenated in the
+    /// same order on every run regardless of which batches finished first.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// world.spawn_batch((0..100).map(|i| (i,)));
+    /// let mut query = world.query::<&i32>();
+    /// let mut outputs = vec![Vec::new(); query.batch_count(16)];
+    /// query.par_for_each_batch(16, &SerialExecutor, &mut outputs, |_, batch, out| {
+    ///     out.extend(batch.filter(|(_, &x)| x % 3 == 0).map(|(e, _)| e));
+    /// });
+    /// let gathered = outputs.concat();
+    /// assert_eq!(gathered.len(), 34);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `outputs` differs from [`batch_count(batch_size)`](Self::batch_count).
+    pub fn par_for_each_batch<O, E>(
+        &mut self,
+        batch_size: u32,
+        executor: &E,
+        outputs: &mut [O],
+        f: impl Fn(usize, Batch<'_, Q>, &mut O) + Sync,
+    ) where
+        O: Send,
+        E: Executor + ?Sized,
+        for<'a> Q::Item<'a>: Send,
+    {
+        assert_eq!(
+            outputs.len(),
+            self.batch_count(batch_size),
+            "one output is required per batch"
+        );
+        let f = &f;
+        let tasks = self
+            .iter_batched(batch_size)
+            .zip(outputs.iter_mut())
+            .enumerate()
+            .map(|(index, (batch, out))| Box::new(move || f(index, batch, out)) as Task<'_>)
+            .collect();
+        executor.execute(tasks);
+    }
+
+    /// Collect the batches of at most `batch_size` entities yielded by
+    /// [`iter_batched`](Self::iter_batched)
+    ///
+    /// Each batch lies within a single archetype, and batches are `Send` whenever the query's items
+    /// are, so the result can be processed by a data-parallel library such as rayon, e.g. with
+    /// `into_par_iter`. A batch's position depends only on the world's contents and history, so
+    /// per-batch results collected by position, as by rayon's `collect`, are combined in the same
+    /// order on every run.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// world.spawn_batch((0..100).map(|i| (i,)));
+    /// let mut query = world.query::<&i32>();
+    /// let batches = query.par_iter_batched(16);
+    /// assert_eq!(batches.len(), 7);
+    /// // With rayon: `batches.into_par_iter().map(|batch| ...).collect::<Vec<_>>()`
+    /// let sums = batches
+    ///     .into_iter()
+    ///     .map(|batch| batch.map(|(_, &x)| x).sum::<i32>())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(sums.iter().sum::<i32>(), 4950);
+    /// ```
+    pub fn par_iter_batched(&mut self, batch_size: u32) -> Vec<Batch<'_, Q>> {
+        let mut batches = Vec::with_capacity(self.batch_count(batch_size));
+        batches.extend(self.iter_batched(batch_size));
+        batches
+    }
+
+    /// Compute `f` for every entity matching the query in parallel, then pass each result to
+    /// `combine` in iteration order
+    ///
+    /// Entities are processed in batches of at most `batch_size`, as by
+    /// [`par_for_each_batch`](Self::par_for_each_batch). Results are buffered, then `combine` is
+    /// called on the calling thread with each entity and its result in the order that
+    /// [`iter_batched`](Self::iter_batched) visits them, so reductions performed by `combine`
+    /// produce the same result on every run and platform regardless of scheduling.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// world.spawn_batch((0..100).map(|i| (i as f32,)));
+    /// let mut total = 0.0;
+    /// let mut largest = None;
+    /// world.query::<&f32>().par_for_each_ordered(
+    ///     16,
+    ///     &SerialExecutor,
+    ///     |_, &x| x.sqrt(),
+    ///     |e, root| {
+    ///         total += root;
+    ///         if root > 9.9 {
+    ///             largest = Some(e);
+    ///         }
+    ///     },
+    /// );
+    /// assert!(largest.is_some());
+    /// ```
+    pub fn par_for_each_ordered<T, E>(
+        &mut self,
+        batch_size: u32,
+        executor: &E,
+        f: impl Fn(Entity, Q::Item<'_>) -> T + Sync,
+        mut combine: impl FnMut(Entity, T),
+    ) where
+        T: Send,
+        E: Executor + ?Sized,
+        for<'a> Q::Item<'a>: Send,
+    {
+        let mut outputs = (0..self.batch_count(batch_size))
+            .map(|_| Vec::new())
+            .collect::<Vec<Vec<(Entity, T)>>>();
+        self.par_for_each_batch(batch_size, executor, &mut outputs, |_, batch, out| {
+            out.extend(batch.map(|(e, item)| (e, f(e, item))));
+        });
+        for (e, x) in outputs.into_iter().flatten() {
+            combine(e, x);
         }
     }
 
@@ -642,10 +1204,54 @@ impl<'w, Q: Query> QueryBorrow<'w, Q> {
         if self.borrowed {
             return;
         }
-        start_borrow::<Q>(self.world.archetypes_inner());
+        start_borrow::<Q>(self.archetypes());
         self.borrowed = true;
     }
 
+    /// The archetypes the query is restricted to
+    fn archetypes(&self) -> &'w [Archetype] {
+        &self.world.archetypes_inner()[self.archetypes.clone()]
+    }
+
+    /// Restrict the query to the archetypes whose positions in
+    /// [`World::archetypes`](crate::World::archetypes) are within `range`
+    ///
+    /// Components are borrowed separately in each archetype, so queries restricted to disjoint
+    /// ranges never conflict, even if they access the same component types mutably. This allows
+    /// entities to be partitioned by archetype among threads, e.g. one per physics island. The
+    /// same holds for queries that can't match the same archetypes for other reasons, such as
+    /// requiring different marker components.
+    ///
+    /// Any borrow already held by the query is released. Indices beyond the end of the world's
+    /// archetypes are ignored.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// world.spawn_batch((0..10).map(|i| (i,)));
+    /// world.spawn_batch((0..10).map(|i| (i, true)));
+    /// let split = world.archetypes().len() - 1;
+    /// let mut left = world.query::<&mut i32>().in_archetypes(0..split);
+    /// let mut right = world.query::<&mut i32>().in_archetypes(split..usize::MAX);
+    /// std::thread::scope(|s| {
+    ///     s.spawn(|| left.iter().for_each(|(_, x)| *x += 1));
+    ///     s.spawn(|| right.iter().for_each(|(_, x)| *x -= 1));
+    /// });
+    /// # drop((left, right));
+    /// assert_eq!(world.query::<&i32>().iter().map(|(_, &x)| x).sum::<i32>(), 90);
+    /// ```
+    pub fn in_archetypes(mut self, range: core::ops::Range<usize>) -> Self {
+        if self.borrowed {
+            release_borrow::<Q>(self.archetypes());
+            self.borrowed = false;
+        }
+        let len = self.world.archetypes().len();
+        let end = range.end.min(len);
+        self.archetypes = range.start.min(end)..end;
+        self
+    }
+
     /// Transform the query into one that requires another query be satisfied
     ///
     /// Convenient when the values of the components in the other query are not of interest.
@@ -694,11 +1300,44 @@ impl<'w, Q: Query> QueryBorrow<'w, Q> {
         self.transform()
     }
 
+    /// Skip entities whose `T` components haven't changed at or after `tick`
+    ///
+    /// Components are changed when they're added to an entity or mutably accessed, and each
+    /// change is recorded with the [`World::tick`](crate::World::tick) during which it occurred.
+    /// Changes are tracked per archetype rather than per entity, so unmodified entities sharing
+    /// an archetype with modified ones are also yielded. Entities lacking `T` are always skipped.
+    /// Applies to [`iter`](Self::iter) and [`iter_batched`](Self::iter_batched), but not to
+    /// [`view`](Self::view).
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((1, true));
+    /// let b = world.spawn((2,));
+    /// let tick = world.advance_tick();
+    /// for (_, x) in world.query::<&mut i32>().with::<&bool>().iter() {
+    ///     *x += 1;
+    /// }
+    /// let changed = world.query::<&i32>()
+    ///     .changed_since::<i32>(tick)
+    ///     .iter()
+    ///     .map(|(e, _)| e)
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(changed, &[a]);
+    /// ```
+    pub fn changed_since<T: Component>(mut self, tick: u32) -> Self {
+        self.changed.push((TypeId::of::<T>(), tick));
+        self
+    }
+
     /// Helper to change the type of the query
     fn transform<R: Query>(mut self) -> QueryBorrow<'w, R> {
         let x = QueryBorrow {
             world: self.world,
+            archetypes: self.archetypes.clone(),
             borrowed: self.borrowed,
+            changed: mem::take(&mut self.changed),
             _marker: PhantomData,
         };
         // Ensure `Drop` won't fire redundantly
@@ -707,107 +1346,602 @@ impl<'w, Q: Query> QueryBorrow<'w, Q> {
     }
 }
 
-unsafe impl<Q: Query> Send for QueryBorrow<'_, Q> where for<'a> Q::Item<'a>: Send {}
-unsafe impl<Q: Query> Sync for QueryBorrow<'_, Q> where for<'a> Q::Item<'a>: Send {}
-
-impl<Q: Query> Drop for QueryBorrow<'_, Q> {
-    fn drop(&mut self) {
-        if self.borrowed {
-            release_borrow::<Q>(self.world.archetypes_inner());
+unsafe impl<Q: Query> Send for QueryBorrow<'_, Q> where for<'a> Q::Item<'a>: Send {}
+unsafe impl<Q: Query> Sync for QueryBorrow<'_, Q> where for<'a> Q::Item<'a>: Send {}
+
+impl<Q: Query> Drop for QueryBorrow<'_, Q> {
+    fn drop(&mut self) {
+        if self.borrowed {
+            release_borrow::<Q>(self.archetypes());
+        }
+    }
+}
+
+impl<'q, Q: Query> IntoIterator for &'q mut QueryBorrow<'_, Q> {
+    type Item = (Entity, Q::Item<'q>);
+    type IntoIter = QueryIter<'q, Q>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the set of entities with the components in `Q`
+///
+/// Visits archetypes in order of creation, and the entities within each archetype in storage
+/// order. Iterating in reverse visits exactly the reverse sequence.
+pub struct QueryIter<'q, Q: Query> {
+    world: &'q World,
+    archetypes: core::ops::Range<usize>,
+    iter: ChunkIter<Q>,
+    /// Remainder of the archetype most recently entered from the back
+    back: ChunkIter<Q>,
+    changed: Vec<(TypeId, u32)>,
+    /// All remaining results, if the world iterates in spawn order, leaving the other fields empty
+    ordered: Option<SpawnOrdered<Q>>,
+}
+
+impl<'q, Q: Query> QueryIter<'q, Q> {
+    /// # Safety
+    ///
+    /// `'q` must be sufficient to guarantee that `Q` cannot violate borrow safety, either with
+    /// dynamic borrow checks or by representing exclusive access to the `World`.
+    unsafe fn new(world: &'q World) -> Self {
+        let n = world.archetypes().len();
+        Self {
+            world,
+            archetypes: 0..n,
+            iter: ChunkIter::empty(),
+            back: ChunkIter::empty(),
+            changed: Vec::new(),
+            ordered: None,
+        }
+    }
+
+    /// Visit only the archetypes with indices in `archetypes`
+    fn with_archetypes(mut self, archetypes: core::ops::Range<usize>) -> Self {
+        self.archetypes = archetypes;
+        self
+    }
+
+    /// Skip archetypes that don't satisfy the `changed_since` filters in `changed`
+    fn with_changed(mut self, changed: Vec<(TypeId, u32)>) -> Self {
+        self.changed = changed;
+        self
+    }
+
+    /// Collect the results up front and sort them into spawn order, if the world iterates in
+    /// spawn order
+    ///
+    /// Must be called before iteration begins.
+    fn in_spawn_order(mut self) -> Self {
+        if self.world.iteration_order() != IterationOrder::SpawnOrder {
+            return self;
+        }
+        let archetypes = self.world.archetypes_inner();
+        let mut fetch = Vec::new();
+        let mut rows = Vec::new();
+        for index in self.archetypes.clone() {
+            let archetype = &archetypes[index];
+            let state = if archetype.is_empty() || !changed_since(archetype, &self.changed) {
+                None
+            } else {
+                Q::Fetch::prepare(archetype)
+            };
+            let Some(state) = state else {
+                fetch.push(None);
+                continue;
+            };
+            archetype.mark_queried();
+            fetch.push(Some(Q::Fetch::execute(archetype, state)));
+            rows.extend(archetype.ids().iter().enumerate().map(|(row, &id)| {
+                let seq = self.world.spawn_seq(id);
+                (seq, index as u32, row as u32)
+            }));
+        }
+        rows.sort_unstable_by_key(|x| x.0);
+        self.ordered = Some(SpawnOrdered {
+            first: self.archetypes.start,
+            fetch,
+            range: 0..rows.len(),
+            rows: rows.into_iter().map(|(_, a, r)| (a, r)).collect(),
+        });
+        self.archetypes.start = self.archetypes.end;
+        self
+    }
+
+    /// Take the first or, if `back`, last remaining result whose entity ID satisfies `filter`,
+    /// when iterating in spawn order, along with its location
+    ///
+    /// Components of entities that don't satisfy `filter` aren't fetched.
+    #[inline]
+    unsafe fn next_ordered(
+        ordered: &mut SpawnOrdered<Q>,
+        world: &'q World,
+        back: bool,
+        filter: impl Fn(u32) -> bool,
+    ) -> Option<(u32, u32, u32, Q::Item<'q>)> {
+        loop {
+            let (archetype, row) = if back {
+                ordered.rows[ordered.range.next_back()?]
+            } else {
+                ordered.rows[ordered.range.next()?]
+            };
+            let id = world.archetypes_inner()[archetype as usize].ids()[row as usize];
+            if filter(id) {
+                let fetch = ordered.fetch[archetype as usize - ordered.first]
+                    .as_ref()
+                    .unwrap();
+                return Some((archetype, row, id, Q::get(fetch, row as usize)));
+            }
+        }
+    }
+
+    /// Divide the remaining results into the first `index` and the rest
+    ///
+    /// The two iterators visit disjoint entities and may be sent to different threads, allowing
+    /// custom schedulers to divide query work, e.g. by repeatedly splitting off halves for other
+    /// workers to steal. Chaining the first with the second yields exactly the results this
+    /// iterator would have. Takes time proportional to the number of archetypes, not entities.
+    ///
+    /// If `index` exceeds the number of remaining results, the second iterator is empty.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// world.spawn_batch((0..10).map(|i| (i,)));
+    /// world.spawn_batch((10..20).map(|i| (i, true)));
+    /// let mut query = world.query::<&i32>();
+    /// let (left, right) = query.iter().split_at(15);
+    /// assert_eq!(left.len(), 15);
+    /// assert_eq!(right.map(|(_, &i)| i).collect::<Vec<_>>(), (15..20).collect::<Vec<_>>());
+    /// ```
+    pub fn split_at(mut self, index: usize) -> (Self, Self) {
+        let mut right = Self {
+            world: self.world,
+            archetypes: self.archetypes.end..self.archetypes.end,
+            iter: ChunkIter::empty(),
+            back: ChunkIter::empty(),
+            changed: self.changed.clone(),
+            ordered: None,
+        };
+        if let Some(ordered) = &mut self.ordered {
+            let mid = ordered.range.start + index.min(ordered.range.len());
+            right.ordered = Some(SpawnOrdered {
+                first: ordered.first,
+                fetch: ordered.fetch.clone(),
+                rows: ordered.rows[mid..ordered.range.end].to_vec(),
+                range: 0..ordered.range.end - mid,
+            });
+            ordered.range.end = mid;
+            return (self, right);
+        }
+        let mut n = index;
+        if n <= self.iter.remaining() {
+            let (front, rest) = self.iter.split_at(n);
+            self.iter = front;
+            right.iter = rest;
+            right.archetypes = self.archetypes.clone();
+            self.archetypes.end = self.archetypes.start;
+            right.back = mem::replace(&mut self.back, ChunkIter::empty());
+            return (self, right);
+        }
+        n -= self.iter.remaining();
+        for archetype in self.archetypes.clone() {
+            let chunk = self.chunk(archetype);
+            if n <= chunk.remaining() {
+                let (front, rest) = chunk.split_at(n);
+                right.back = mem::replace(&mut self.back, front);
+                right.iter = rest;
+                right.archetypes = archetype + 1..self.archetypes.end;
+                self.archetypes.end = archetype;
+                return (self, right);
+            }
+            n -= chunk.remaining();
+        }
+        let n = n.min(self.back.remaining());
+        let back = mem::replace(&mut self.back, ChunkIter::empty());
+        let (front, rest) = back.split_at(n);
+        self.back = front;
+        right.back = rest;
+        (self, right)
+    }
+
+    /// Divide the remaining results into two iterators of nearly equal length
+    ///
+    /// Equivalent to `split_at(self.len() / 2)`.
+    pub fn split_half(self) -> (Self, Self) {
+        let index = self.len() / 2;
+        self.split_at(index)
+    }
+
+    /// Advance query to the next archetype
+    ///
+    /// Outlined from `Iterator::next` for improved iteration performance.
+    fn next_archetype(&mut self) -> Option<()> {
+        let archetype = self.archetypes.next()?;
+        self.iter = self.chunk(archetype);
+        Some(())
+    }
+
+    /// Like `next_archetype`, but takes the last archetype and replaces `back`
+    fn next_back_archetype(&mut self) -> Option<()> {
+        let archetype = self.archetypes.next_back()?;
+        self.back = self.chunk(archetype);
+        Some(())
+    }
+
+    fn chunk(&self, archetype: usize) -> ChunkIter<Q> {
+        let archetype = unsafe { self.world.archetypes_inner().get_unchecked(archetype) };
+        if !changed_since(archetype, &self.changed) {
+            return ChunkIter::empty();
+        }
+        let Some(state) = Q::Fetch::prepare(archetype) else {
+            return ChunkIter::empty();
+        };
+        archetype.mark_queried();
+        ChunkIter::new(archetype, Q::Fetch::execute(archetype, state))
+    }
+
+    #[inline]
+    fn entity(&self, id: u32) -> Entity {
+        Entity {
+            id,
+            generation: unsafe {
+                self.world
+                    .entities_meta()
+                    .get_unchecked(id as usize)
+                    .generation
+            },
+        }
+    }
+}
+
+unsafe impl<Q: Query> Send for QueryIter<'_, Q> where for<'a> Q::Item<'a>: Send {}
+unsafe impl<Q: Query> Sync for QueryIter<'_, Q> where for<'a> Q::Item<'a>: Send {}
+
+impl<'q, Q: Query> Iterator for QueryIter<'q, Q> {
+    type Item = (Entity, Q::Item<'q>);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(ordered) = &mut self.ordered {
+            let (_, _, id, components) =
+                unsafe { Self::next_ordered(ordered, self.world, false, |_| true)? };
+            return Some((self.entity(id), components));
+        }
+        loop {
+            match unsafe { self.iter.next() } {
+                None => {
+                    if self.next_archetype().is_none() {
+                        let (id, components) = unsafe { self.back.next()? };
+                        return Some((self.entity(id), components));
+                    }
+                    continue;
+                }
+                Some((id, components)) => {
+                    return Some((self.entity(id), components));
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.len();
+        (n, Some(n))
+    }
+
+    /// Skips whole archetypes at a time, so takes time proportional to the number of archetypes
+    /// passed over rather than the number of entities
+    fn nth(&mut self, mut n: usize) -> Option<Self::Item> {
+        if let Some(ordered) = &mut self.ordered {
+            ordered.range.start += n.min(ordered.range.len());
+            return self.next();
+        }
+        n = self.iter.skip(n);
+        while n > 0 {
+            if self.next_archetype().is_none() {
+                self.back.skip(n);
+                break;
+            }
+            n = self.iter.skip(n);
+        }
+        self.next()
+    }
+}
+
+impl<Q: Query> DoubleEndedIterator for QueryIter<'_, Q> {
+    fn nth_back(&mut self, mut n: usize) -> Option<Self::Item> {
+        if let Some(ordered) = &mut self.ordered {
+            ordered.range.end -= n.min(ordered.range.len());
+            return self.next_back();
+        }
+        n = self.back.skip_back(n);
+        while n > 0 {
+            if self.next_back_archetype().is_none() {
+                self.iter.skip_back(n);
+                break;
+            }
+            n = self.back.skip_back(n);
+        }
+        self.next_back()
+    }
+
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if let Some(ordered) = &mut self.ordered {
+            let (_, _, id, components) =
+                unsafe { Self::next_ordered(ordered, self.world, true, |_| true)? };
+            return Some((self.entity(id), components));
+        }
+        loop {
+            match unsafe { self.back.next_back() } {
+                None => {
+                    if self.next_back_archetype().is_none() {
+                        let (id, components) = unsafe { self.iter.next_back()? };
+                        return Some((self.entity(id), components));
+                    }
+                    continue;
+                }
+                Some((id, components)) => {
+                    return Some((self.entity(id), components));
+                }
+            }
+        }
+    }
+}
+
+impl<Q: Query> ExactSizeIterator for QueryIter<'_, Q> {
+    fn len(&self) -> usize {
+        self.archetypes
+            .clone()
+            .map(|x| unsafe { self.world.archetypes_inner().get_unchecked(x) })
+            .filter(|&x| Q::Fetch::access(x).is_some() && changed_since(x, &self.changed))
+            .map(|x| x.len() as usize)
+            .sum::<usize>()
+            + self.iter.remaining()
+            + self.back.remaining()
+            + self.ordered.as_ref().map_or(0, |x| x.range.len())
+    }
+}
+
+/// The remaining results of a [`QueryIter`] over a world that iterates in spawn order
+struct SpawnOrdered<Q: Query> {
+    /// Index of the archetype corresponding to the first element of `fetch`
+    first: usize,
+    /// Fetch for each archetype visited by the query
+    fetch: Vec<Option<Q::Fetch>>,
+    /// Archetype index and row of each result, in spawn order
+    rows: Vec<(u32, u32)>,
+    /// Indices of the remaining `rows`
+    range: core::ops::Range<usize>,
+}
+
+/// Iterator over the entities in an [`EntityMask`] with the components in `Q`
+///
+/// Obtained from [`QueryBorrow::iter_masked`].
+pub struct MaskedIter<'q, Q: Query> {
+    inner: QueryIter<'q, Q>,
+    mask: &'q EntityMask,
+}
+
+unsafe impl<Q: Query> Send for MaskedIter<'_, Q> where for<'a> Q::Item<'a>: Send {}
+unsafe impl<Q: Query> Sync for MaskedIter<'_, Q> where for<'a> Q::Item<'a>: Send {}
+
+impl<'q, Q: Query> Iterator for MaskedIter<'q, Q> {
+    type Item = (Entity, Q::Item<'q>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(ordered) = &mut self.inner.ordered {
+            let mask = self.mask;
+            let (_, _, id, components) = unsafe {
+                QueryIter::next_ordered(ordered, self.inner.world, false, |id| {
+                    mask.contains_id(id)
+                })?
+            };
+            return Some((self.inner.entity(id), components));
+        }
+        loop {
+            match unsafe { self.inner.iter.next_masked(self.mask) } {
+                None => {
+                    if self.inner.next_archetype().is_none() {
+                        let (id, components) = unsafe { self.inner.back.next_masked(self.mask)? };
+                        return Some((self.inner.entity(id), components));
+                    }
+                    continue;
+                }
+                Some((id, components)) => {
+                    return Some((self.inner.entity(id), components));
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.inner.len()))
+    }
+}
+
+impl<Q: Query> DoubleEndedIterator for MaskedIter<'_, Q> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if let Some(ordered) = &mut self.inner.ordered {
+            let mask = self.mask;
+            let (_, _, id, components) = unsafe {
+                QueryIter::next_ordered(ordered, self.inner.world, true, |id| mask.contains_id(id))?
+            };
+            return Some((self.inner.entity(id), components));
+        }
+        loop {
+            match unsafe { self.inner.back.next_back_masked(self.mask) } {
+                None => {
+                    if self.inner.next_back_archetype().is_none() {
+                        let (id, components) =
+                            unsafe { self.inner.iter.next_back_masked(self.mask)? };
+                        return Some((self.inner.entity(id), components));
+                    }
+                    continue;
+                }
+                Some((id, components)) => {
+                    return Some((self.inner.entity(id), components));
+                }
+            }
         }
     }
 }
 
-impl<'q, Q: Query> IntoIterator for &'q mut QueryBorrow<'_, Q> {
-    type Item = (Entity, Q::Item<'q>);
-    type IntoIter = QueryIter<'q, Q>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter()
-    }
+/// Iterator over the set of entities with the components in `Q`, along with their locations
+///
+/// Obtained from [`QueryBorrow::iter_located`].
+pub struct LocatedIter<'q, Q: Query> {
+    inner: QueryIter<'q, Q>,
+    /// Index of the archetype `inner.iter` is visiting
+    archetype: u32,
 }
 
-/// Iterator over the set of entities with the components in `Q`
-pub struct QueryIter<'q, Q: Query> {
-    world: &'q World,
-    archetypes: core::ops::Range<usize>,
-    iter: ChunkIter<Q>,
-}
+unsafe impl<Q: Query> Send for LocatedIter<'_, Q> where for<'a> Q::Item<'a>: Send {}
+unsafe impl<Q: Query> Sync for LocatedIter<'_, Q> where for<'a> Q::Item<'a>: Send {}
 
-impl<'q, Q: Query> QueryIter<'q, Q> {
-    /// # Safety
-    ///
-    /// `'q` must be sufficient to guarantee that `Q` cannot violate borrow safety, either with
-    /// dynamic borrow checks or by representing exclusive access to the `World`.
-    unsafe fn new(world: &'q World) -> Self {
-        let n = world.archetypes().len();
-        Self {
-            world,
-            archetypes: 0..n,
-            iter: ChunkIter::empty(),
+impl<'q, Q: Query> Iterator for LocatedIter<'q, Q> {
+    type Item = (Entity, EntityLocation, Q::Item<'q>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(ordered) = &mut self.inner.ordered {
+            let (archetype, row, id, components) =
+                unsafe { QueryIter::next_ordered(ordered, self.inner.world, false, |_| true)? };
+            let entity = self.inner.entity(id);
+            let location = EntityLocation {
+                entity,
+                archetype,
+                row,
+            };
+            return Some((entity, location, components));
+        }
+        loop {
+            let row = self.inner.iter.position as u32;
+            match unsafe { self.inner.iter.next() } {
+                None => {
+                    let archetype = self.inner.archetypes.start as u32;
+                    self.inner.next_archetype()?;
+                    self.archetype = archetype;
+                }
+                Some((id, components)) => {
+                    let entity = self.inner.entity(id);
+                    let location = EntityLocation {
+                        entity,
+                        archetype: self.archetype,
+                        row,
+                    };
+                    return Some((entity, location, components));
+                }
+            }
         }
     }
 
-    /// Advance query to the next archetype
-    ///
-    /// Outlined from `Iterator::next` for improved iteration performance.
-    fn next_archetype(&mut self) -> Option<()> {
-        let archetype = self.archetypes.next()?;
-        let archetype = unsafe { self.world.archetypes_inner().get_unchecked(archetype) };
-        let state = Q::Fetch::prepare(archetype);
-        let fetch = state.map(|state| Q::Fetch::execute(archetype, state));
-        self.iter = fetch.map_or(ChunkIter::empty(), |fetch| ChunkIter::new(archetype, fetch));
-        Some(())
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.inner.len();
+        (n, Some(n))
     }
 }
 
-unsafe impl<Q: Query> Send for QueryIter<'_, Q> where for<'a> Q::Item<'a>: Send {}
-unsafe impl<Q: Query> Sync for QueryIter<'_, Q> where for<'a> Q::Item<'a>: Send {}
+impl<Q: Query> ExactSizeIterator for LocatedIter<'_, Q> {}
 
-impl<'q, Q: Query> Iterator for QueryIter<'q, Q> {
+/// Iterator over the set of entities with the components in `Q`, in the order captured by an
+/// [`OrderToken`]
+///
+/// Obtained from [`QueryBorrow::iter_frozen`].
+pub struct FrozenIter<'q, Q: Query> {
+    meta: &'q [EntityMeta],
+    archetypes: &'q [Archetype],
+    /// Fetch for each archetype visited by the query
+    fetch: Vec<Option<Q::Fetch>>,
+    token: &'q OrderToken,
+    order: SliceIter<'q, Entity>,
+    /// Index of the archetype following the one `iter` is visiting, once `order` is exhausted
+    archetype: usize,
+    iter: ChunkIter<Q>,
+}
+
+unsafe impl<Q: Query> Send for FrozenIter<'_, Q> where for<'a> Q::Item<'a>: Send {}
+unsafe impl<Q: Query> Sync for FrozenIter<'_, Q> where for<'a> Q::Item<'a>: Send {}
+
+impl<'q, Q: Query> Iterator for FrozenIter<'q, Q> {
     type Item = (Entity, Q::Item<'q>);
 
-    #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
+        // Entities are unique within a token, and those visited afterwards aren't in it, so no
+        // entity's components are yielded twice
+        for &entity in &mut self.order {
+            let Some(meta) = self.meta.get(entity.id as usize) else {
+                continue;
+            };
+            if meta.generation != entity.generation {
+                continue;
+            }
+            let Some(fetch) = &self.fetch[meta.location.archetype as usize] else {
+                continue;
+            };
+            return Some((entity, unsafe {
+                Q::get(fetch, meta.location.index as usize)
+            }));
+        }
         loop {
             match unsafe { self.iter.next() } {
                 None => {
-                    self.next_archetype()?;
-                    continue;
+                    let archetype = self.archetypes.get(self.archetype)?;
+                    if let Some(fetch) = &self.fetch[self.archetype] {
+                        self.iter = ChunkIter::new(archetype, fetch.clone());
+                    }
+                    self.archetype += 1;
                 }
                 Some((id, components)) => {
-                    return Some((
-                        Entity {
-                            id,
-                            generation: unsafe {
-                                self.world
-                                    .entities_meta()
-                                    .get_unchecked(id as usize)
-                                    .generation
-                            },
-                        },
-                        components,
-                    ));
+                    let entity = Entity {
+                        id,
+                        generation: unsafe { self.meta.get_unchecked(id as usize).generation },
+                    };
+                    if !self.token.contains(entity) {
+                        return Some((entity, components));
+                    }
                 }
             }
         }
     }
+}
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        let n = self.len();
-        (n, Some(n))
+/// Where an entity was stored when it was visited by [`QueryBorrow::iter_located`]
+///
+/// Valid until the entity is moved by a structural change, such as inserting or removing its
+/// components or despawning another entity of the same archetype. [`World::entity_at`] detects
+/// stale locations.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct EntityLocation {
+    entity: Entity,
+    archetype: u32,
+    row: u32,
+}
+
+impl EntityLocation {
+    pub(crate) fn new(entity: Entity, archetype: u32, row: u32) -> Self {
+        Self {
+            entity,
+            archetype,
+            row,
+        }
     }
-}
 
-impl<Q: Query> ExactSizeIterator for QueryIter<'_, Q> {
-    fn len(&self) -> usize {
-        self.archetypes
-            .clone()
-            .map(|x| unsafe { self.world.archetypes_inner().get_unchecked(x) })
-            .filter(|&x| Q::Fetch::access(x).is_some())
-            .map(|x| x.len() as usize)
-            .sum::<usize>()
-            + self.iter.remaining()
+    /// The entity found at this location
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Index of the archetype the entity was stored in, as in [`World::archetypes`]
+    pub fn archetype(&self) -> u32 {
+        self.archetype
+    }
+
+    /// Position of the entity within its archetype
+    pub fn row(&self) -> u32 {
+        self.row
     }
 }
 
@@ -849,10 +1983,18 @@ impl<'q, Q: Query> QueryMut<'q, Q> {
         self.transform()
     }
 
+    /// Skip entities whose `T` components haven't changed at or after `tick`
+    ///
+    /// See `QueryBorrow::changed_since`
+    pub fn changed_since<T: Component>(mut self, tick: u32) -> Self {
+        self.iter.changed.push((TypeId::of::<T>(), tick));
+        self
+    }
+
     /// Helper to change the type of the query
     fn transform<R: Query>(self) -> QueryMut<'q, R> {
         QueryMut {
-            iter: unsafe { QueryIter::new(self.iter.world) },
+            iter: unsafe { QueryIter::new(self.iter.world).with_changed(self.iter.changed) },
         }
     }
 
@@ -866,6 +2008,7 @@ impl<'q, Q: Query> QueryMut<'q, Q> {
                 self.iter.world.archetypes_inner().iter(),
                 batch_size,
             )
+            .with_changed(self.iter.changed)
         }
     }
 }
@@ -876,11 +2019,14 @@ impl<'q, Q: Query> IntoIterator for QueryMut<'q, Q> {
 
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
-        self.iter
+        self.iter.in_spawn_order()
     }
 }
 
 /// Check that Q doesn't alias a `&mut T` on its own. Currently over-conservative for `Or` queries.
+///
+/// Queries declared with `#[derive(Query)]` are also checked at compile time where the aliasing is
+/// syntactically evident.
 pub(crate) fn assert_borrow<Q: Query>() {
     // This looks like an ugly O(n^2) loop, but everything's constant after inlining, so in
     // practice LLVM optimizes it out entirely.
@@ -890,7 +2036,11 @@ pub(crate) fn assert_borrow<Q: Query>() {
             let mut j = 0;
             Q::Fetch::for_each_borrow(|b, _| {
                 if i != j {
-                    core::assert!(a != b, "query violates a unique borrow");
+                    core::assert!(
+                        a != b,
+                        "query violates a unique borrow: `{}`",
+                        core::any::type_name::<Q>()
+                    );
                 }
                 j += 1;
             })
@@ -936,18 +2086,93 @@ impl<Q: Query> ChunkIter<Q> {
         Some((*entity, item))
     }
 
+    /// Like `next`, but skips entities not in `mask`
+    #[inline]
+    unsafe fn next_masked<'a>(&mut self, mask: &EntityMask) -> Option<(u32, Q::Item<'a>)> {
+        while self.position < self.len {
+            let position = self.position;
+            self.position += 1;
+            let entity = *self.entities.as_ptr().add(position);
+            if mask.contains_id(entity) {
+                return Some((entity, Q::get(&self.fetch, position)));
+            }
+        }
+        None
+    }
+
+    /// Like `next`, but takes the last remaining entity
+    #[inline]
+    unsafe fn next_back<'a>(&mut self) -> Option<(u32, Q::Item<'a>)> {
+        if self.position == self.len {
+            return None;
+        }
+        self.len -= 1;
+        let entity = self.entities.as_ptr().add(self.len);
+        Some((*entity, Q::get(&self.fetch, self.len)))
+    }
+
+    /// Like `next_back`, but skips entities not in `mask`
+    #[inline]
+    unsafe fn next_back_masked<'a>(&mut self, mask: &EntityMask) -> Option<(u32, Q::Item<'a>)> {
+        while self.position < self.len {
+            self.len -= 1;
+            let entity = *self.entities.as_ptr().add(self.len);
+            if mask.contains_id(entity) {
+                return Some((entity, Q::get(&self.fetch, self.len)));
+            }
+        }
+        None
+    }
+
+    /// Divide into the first `n` remaining entities and the rest
+    fn split_at(self, n: usize) -> (Self, Self) {
+        debug_assert!(n <= self.remaining());
+        let front = Self {
+            entities: self.entities,
+            fetch: self.fetch.clone(),
+            position: self.position,
+            len: self.position + n,
+        };
+        let rest = Self {
+            position: self.position + n,
+            ..self
+        };
+        (front, rest)
+    }
+
+    /// Advance past up to `n` entities, returning how many fewer than `n` remained
+    fn skip(&mut self, n: usize) -> usize {
+        let skipped = n.min(self.remaining());
+        self.position += skipped;
+        n - skipped
+    }
+
+    /// Like `skip`, but from the back
+    fn skip_back(&mut self, n: usize) -> usize {
+        let skipped = n.min(self.remaining());
+        self.len -= skipped;
+        n - skipped
+    }
+
     fn remaining(&self) -> usize {
         self.len - self.position
     }
 }
 
 /// Batched version of [`QueryIter`]
+///
+/// Batches are yielded in the same order as [`QueryIter`] visits their entities, and iterating in
+/// reverse yields exactly the reverse sequence.
 pub struct BatchedIter<'q, Q: Query> {
     _marker: PhantomData<&'q Q>,
     meta: &'q [EntityMeta],
     archetypes: SliceIter<'q, Archetype>,
     batch_size: u32,
+    /// Index of the next batch to yield from the first archetype
     batch: u32,
+    /// Index after the last batch to yield from the last archetype, if any were taken from the back
+    batch_end: Option<u32>,
+    changed: Vec<(TypeId, u32)>,
 }
 
 impl<'q, Q: Query> BatchedIter<'q, Q> {
@@ -966,7 +2191,43 @@ impl<'q, Q: Query> BatchedIter<'q, Q> {
             archetypes,
             batch_size,
             batch: 0,
+            batch_end: None,
+            changed: Vec::new(),
+        }
+    }
+
+    /// Skip archetypes that don't satisfy the `changed_since` filters in `changed`
+    fn with_changed(mut self, changed: Vec<(TypeId, u32)>) -> Self {
+        self.changed = changed;
+        self
+    }
+
+    /// Range of batches of `archetype` not yet yielded, given whether it's the first and/or last
+    /// remaining archetype
+    fn remaining_batches(&self, archetype: &Archetype, first: bool, last: bool) -> (u32, u32) {
+        let start = if first { self.batch } else { 0 };
+        let end = match self.batch_end {
+            Some(end) if last => end,
+            _ => (archetype.len() + self.batch_size - 1) / self.batch_size,
+        };
+        (start, end)
+    }
+
+    fn batch(&self, archetype: &'q Archetype, index: u32) -> Option<Batch<'q, Q>> {
+        if !changed_since(archetype, &self.changed) {
+            return None;
         }
+        let state = Q::Fetch::prepare(archetype)?;
+        archetype.mark_queried();
+        let fetch = Q::Fetch::execute(archetype, state);
+        let offset = self.batch_size * index;
+        let mut state = ChunkIter::new(archetype, fetch);
+        state.position = offset as usize;
+        state.len = (offset + self.batch_size.min(archetype.len() - offset)) as usize;
+        Some(Batch {
+            meta: self.meta,
+            state,
+        })
     }
 }
 
@@ -978,33 +2239,33 @@ impl<'q, Q: Query> Iterator for BatchedIter<'q, Q> {
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            let mut archetypes = self.archetypes.clone();
-            let archetype = archetypes.next()?;
-            let offset = self.batch_size * self.batch;
-            if offset >= archetype.len() {
-                self.archetypes = archetypes;
-                self.batch = 0;
-                continue;
+            let archetype = self.archetypes.as_slice().first()?;
+            let (start, end) = self.remaining_batches(archetype, true, self.archetypes.len() == 1);
+            if start < end {
+                if let Some(batch) = self.batch(archetype, start) {
+                    self.batch += 1;
+                    return Some(batch);
+                }
             }
-            let state = Q::Fetch::prepare(archetype);
-            let fetch = state.map(|state| Q::Fetch::execute(archetype, state));
-            if let Some(fetch) = fetch {
-                self.batch += 1;
-                let mut state = ChunkIter::new(archetype, fetch);
-                state.position = offset as usize;
-                state.len = (offset + self.batch_size.min(archetype.len() - offset)) as usize;
-                return Some(Batch {
-                    meta: self.meta,
-                    state,
-                });
-            } else {
-                self.archetypes = archetypes;
-                debug_assert_eq!(
-                    self.batch, 0,
-                    "query fetch should always reject at the first batch or not at all"
-                );
-                continue;
+            self.archetypes.next();
+            self.batch = 0;
+        }
+    }
+}
+
+impl<'q, Q: Query> DoubleEndedIterator for BatchedIter<'q, Q> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            let archetype = self.archetypes.as_slice().last()?;
+            let (start, end) = self.remaining_batches(archetype, self.archetypes.len() == 1, true);
+            if start < end {
+                if let Some(batch) = self.batch(archetype, end - 1) {
+                    self.batch_end = Some(end - 1);
+                    return Some(batch);
+                }
             }
+            self.archetypes.next_back();
+            self.batch_end = None;
         }
     }
 }
@@ -1030,6 +2291,19 @@ impl<'q, Q: Query> Iterator for Batch<'q, Q> {
     }
 }
 
+impl<'q, Q: Query> DoubleEndedIterator for Batch<'q, Q> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (id, components) = unsafe { self.state.next_back()? };
+        Some((
+            Entity {
+                id,
+                generation: self.meta[id as usize].generation,
+            },
+            components,
+        ))
+    }
+}
+
 unsafe impl<Q: Query> Send for Batch<'_, Q> where for<'a> Q::Item<'a>: Send {}
 unsafe impl<Q: Query> Sync for Batch<'_, Q> where for<'a> Q::Item<'a>: Send {}
 
@@ -1244,6 +2518,7 @@ pub struct PreparedQueryIter<'q, Q: Query> {
     archetypes: &'q [Archetype],
     state: SliceIter<'q, (usize, <Q::Fetch as Fetch>::State)>,
     iter: ChunkIter<Q>,
+    back: ChunkIter<Q>,
 }
 
 impl<'q, Q: Query> PreparedQueryIter<'q, Q> {
@@ -1261,6 +2536,21 @@ impl<'q, Q: Query> PreparedQueryIter<'q, Q> {
             archetypes,
             state,
             iter: ChunkIter::empty(),
+            back: ChunkIter::empty(),
+        }
+    }
+
+    fn chunk(&self, &(idx, state): &(usize, <Q::Fetch as Fetch>::State)) -> ChunkIter<Q> {
+        let archetype = &self.archetypes[idx];
+        archetype.mark_queried();
+        ChunkIter::new(archetype, Q::Fetch::execute(archetype, state))
+    }
+
+    #[inline]
+    fn entity(&self, id: u32) -> Entity {
+        Entity {
+            id,
+            generation: unsafe { self.meta.get_unchecked(id as usize).generation },
         }
     }
 }
@@ -1275,20 +2565,18 @@ impl<'q, Q: Query> Iterator for PreparedQueryIter<'q, Q> {
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             match unsafe { self.iter.next() } {
-                None => {
-                    let (idx, state) = self.state.next()?;
-                    let archetype = &self.archetypes[*idx];
-                    self.iter = ChunkIter::new(archetype, Q::Fetch::execute(archetype, *state));
-                    continue;
-                }
+                None => match self.state.next() {
+                    Some(state) => {
+                        self.iter = self.chunk(state);
+                        continue;
+                    }
+                    None => {
+                        let (id, components) = unsafe { self.back.next()? };
+                        return Some((self.entity(id), components));
+                    }
+                },
                 Some((id, components)) => {
-                    return Some((
-                        Entity {
-                            id,
-                            generation: unsafe { self.meta.get_unchecked(id as usize).generation },
-                        },
-                        components,
-                    ));
+                    return Some((self.entity(id), components));
                 }
             }
         }
@@ -1298,6 +2586,64 @@ impl<'q, Q: Query> Iterator for PreparedQueryIter<'q, Q> {
         let n = self.len();
         (n, Some(n))
     }
+
+    /// Skips whole archetypes at a time, so takes time proportional to the number of archetypes
+    /// passed over rather than the number of entities
+    fn nth(&mut self, mut n: usize) -> Option<Self::Item> {
+        n = self.iter.skip(n);
+        while n > 0 {
+            match self.state.next() {
+                Some(state) => {
+                    self.iter = self.chunk(state);
+                    n = self.iter.skip(n);
+                }
+                None => {
+                    self.back.skip(n);
+                    break;
+                }
+            }
+        }
+        self.next()
+    }
+}
+
+impl<Q: Query> DoubleEndedIterator for PreparedQueryIter<'_, Q> {
+    fn nth_back(&mut self, mut n: usize) -> Option<Self::Item> {
+        n = self.back.skip_back(n);
+        while n > 0 {
+            match self.state.next_back() {
+                Some(state) => {
+                    self.back = self.chunk(state);
+                    n = self.back.skip_back(n);
+                }
+                None => {
+                    self.iter.skip_back(n);
+                    break;
+                }
+            }
+        }
+        self.next_back()
+    }
+
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            match unsafe { self.back.next_back() } {
+                None => match self.state.next_back() {
+                    Some(state) => {
+                        self.back = self.chunk(state);
+                        continue;
+                    }
+                    None => {
+                        let (id, components) = unsafe { self.iter.next_back()? };
+                        return Some((self.entity(id), components));
+                    }
+                },
+                Some((id, components)) => {
+                    return Some((self.entity(id), components));
+                }
+            }
+        }
+    }
 }
 
 impl<Q: Query> ExactSizeIterator for PreparedQueryIter<'_, Q> {
@@ -1307,6 +2653,7 @@ impl<Q: Query> ExactSizeIterator for PreparedQueryIter<'_, Q> {
             .map(|(idx, _)| self.archetypes[*idx].len() as usize)
             .sum::<usize>()
             + self.iter.remaining()
+            + self.back.remaining()
     }
 }
 
@@ -1333,7 +2680,9 @@ impl<'q, Q: Query> View<'q, Q> {
         let fetch = archetypes
             .iter()
             .map(|archetype| {
-                Q::Fetch::prepare(archetype).map(|state| Q::Fetch::execute(archetype, state))
+                let state = Q::Fetch::prepare(archetype)?;
+                archetype.mark_queried();
+                Some(Q::Fetch::execute(archetype, state))
             })
             .collect();
 
@@ -1344,6 +2693,16 @@ impl<'q, Q: Query> View<'q, Q> {
         }
     }
 
+    /// Exclude archetypes with indices outside `range`, which may not have been borrowed
+    fn with_archetypes(mut self, range: core::ops::Range<usize>) -> Self {
+        for (i, fetch) in self.fetch.iter_mut().enumerate() {
+            if !range.contains(&i) {
+                *fetch = None;
+            }
+        }
+        self
+    }
+
     /// Retrieve the query results corresponding to `entity`
     ///
     /// Will yield `None` if the entity does not exist or does not match the query.
@@ -1370,6 +2729,27 @@ impl<'q, Q: Query> View<'q, Q> {
         unsafe { self.get_unchecked(entity) }
     }
 
+    /// Retrieve the `index`th result of the query, in iteration order
+    ///
+    /// Takes time proportional to the number of archetypes, not entities, making it suitable for
+    /// jumping into the middle of a large result set. Will yield `None` if `index` is out of
+    /// bounds.
+    ///
+    /// Does not require exclusive access to the map, but is defined only for queries yielding only shared references.
+    pub fn at(&self, index: usize) -> Option<(Entity, Q::Item<'_>)>
+    where
+        Q: QueryShared,
+    {
+        unsafe { fetch_at::<Q>(self.meta, self.archetypes, &self.fetch, index) }
+    }
+
+    /// Retrieve the `index`th result of the query, in iteration order
+    ///
+    /// Will yield `None` if `index` is out of bounds.
+    pub fn at_mut(&mut self, index: usize) -> Option<(Entity, Q::Item<'_>)> {
+        unsafe { fetch_at::<Q>(self.meta, self.archetypes, &self.fetch, index) }
+    }
+
     /// Equivalent to `get(entity).is_some()`, but does not require `Q: QueryShared`
     pub fn contains(&self, entity: Entity) -> bool {
         let Some(meta) = self.meta.get(entity.id as usize) else {
@@ -1501,6 +2881,36 @@ impl<'a, Q: Query> Iterator for ViewIter<'a, Q> {
     }
 }
 
+/// Locate the `index`th result of a view over `archetypes`
+///
+/// # Safety
+///
+/// Must not be invoked while any unique borrow of the fetched components of the result is live.
+unsafe fn fetch_at<'a, Q: Query>(
+    meta: &[EntityMeta],
+    archetypes: &[Archetype],
+    fetch: &'a [Option<Q::Fetch>],
+    mut index: usize,
+) -> Option<(Entity, Q::Item<'a>)> {
+    for (archetype, fetch) in archetypes.iter().zip(fetch) {
+        let Some(fetch) = fetch else {
+            continue;
+        };
+        let len = archetype.len() as usize;
+        if index >= len {
+            index -= len;
+            continue;
+        }
+        let id = archetype.entity_id(index as u32);
+        let entity = Entity {
+            id,
+            generation: meta.get_unchecked(id as usize).generation,
+        };
+        return Some((entity, Q::get(fetch, index)));
+    }
+    None
+}
+
 /// Provides random access to the results of a prepared query
 pub struct PreparedView<'q, Q: Query> {
     meta: &'q [EntityMeta],
@@ -1526,6 +2936,7 @@ impl<'q, Q: Query> PreparedView<'q, Q> {
 
         for (idx, state) in state {
             let archetype = &archetypes[*idx];
+            archetype.mark_queried();
             fetch[*idx] = Some(Q::Fetch::execute(archetype, *state));
         }
 
@@ -1562,6 +2973,27 @@ impl<'q, Q: Query> PreparedView<'q, Q> {
         unsafe { self.get_unchecked(entity) }
     }
 
+    /// Retrieve the `index`th result of the query, in iteration order
+    ///
+    /// Takes time proportional to the number of archetypes, not entities, making it suitable for
+    /// jumping into the middle of a large result set. Will yield `None` if `index` is out of
+    /// bounds.
+    ///
+    /// Does not require exclusive access to the map, but is defined only for queries yielding only shared references.
+    pub fn at(&self, index: usize) -> Option<(Entity, Q::Item<'_>)>
+    where
+        Q: QueryShared,
+    {
+        unsafe { fetch_at::<Q>(self.meta, self.archetypes, self.fetch, index) }
+    }
+
+    /// Retrieve the `index`th result of the query, in iteration order
+    ///
+    /// Will yield `None` if `index` is out of bounds.
+    pub fn at_mut(&mut self, index: usize) -> Option<(Entity, Q::Item<'_>)> {
+        unsafe { fetch_at::<Q>(self.meta, self.archetypes, self.fetch, index) }
+    }
+
     /// Equivalent to `get(entity).is_some()`, but does not require `Q: QueryShared`
     pub fn contains(&self, entity: Entity) -> bool {
         let Some(meta) = self.meta.get(entity.id as usize) else {
@@ -1678,6 +3110,23 @@ impl<'w, Q: Query> ViewBorrow<'w, Q> {
         self.view.get_mut(entity)
     }
 
+    /// Retrieve the `index`th result of the query, in iteration order
+    ///
+    /// See [`View::at`].
+    pub fn at(&self, index: usize) -> Option<(Entity, Q::Item<'_>)>
+    where
+        Q: QueryShared,
+    {
+        self.view.at(index)
+    }
+
+    /// Retrieve the `index`th result of the query, in iteration order
+    ///
+    /// See [`View::at_mut`].
+    pub fn at_mut(&mut self, index: usize) -> Option<(Entity, Q::Item<'_>)> {
+        self.view.at_mut(index)
+    }
+
     /// Equivalent to `get(entity).is_some()`, but does not require `Q: QueryShared`
     ///
     /// See [`View::contains``].
@@ -1769,6 +3218,32 @@ pub(crate) fn assert_distinct<const N: usize>(entities: &[Entity; N]) {
     }
 }
 
+/// Adapts an iterator known to yield exactly `.1` items into an [`ExactSizeIterator`]
+struct ExactLen<I>(I, usize);
+
+impl<I: Iterator> Iterator for ExactLen<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let x = self.0.next()?;
+        self.1 -= 1;
+        Some(x)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.1, Some(self.1))
+    }
+}
+
+impl<I: Iterator> ExactSizeIterator for ExactLen<I> {}
+
+/// Whether `archetype` satisfies every `changed_since` filter in `changed`
+fn changed_since(archetype: &Archetype, changed: &[(TypeId, u32)]) -> bool {
+    changed
+        .iter()
+        .all(|&(id, tick)| archetype.changed_since(id, tick))
+}
+
 /// Start the borrow
 fn start_borrow<Q: Query>(archetypes: &[Archetype]) {
     for x in archetypes {
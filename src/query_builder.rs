@@ -0,0 +1,137 @@
+use crate::alloc::vec::Vec;
+use core::any::TypeId;
+
+use crate::{Archetype, Component};
+
+/// Assembles a [`QueryDescription`] at runtime, e.g. from data-driven rules
+///
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let a = world.spawn((1, true));
+/// let b = world.spawn((2, 'x'));
+///
+/// let query = QueryBuilder::new()
+///     .read::<i32>()
+///     .without::<char>()
+///     .build();
+/// assert!(world.entity(a).unwrap().satisfies_dynamic(&query));
+/// assert!(!world.entity(b).unwrap().satisfies_dynamic(&query));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QueryBuilder {
+    desc: QueryDescription,
+}
+
+impl QueryBuilder {
+    /// Create a builder for a query that matches every entity
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require and borrow `T` components immutably, like `&T`
+    pub fn read<T: Component>(&mut self) -> &mut Self {
+        self.read_dynamic(TypeId::of::<T>())
+    }
+
+    /// Require and borrow components of the type identified by `id` immutably
+    pub fn read_dynamic(&mut self, id: TypeId) -> &mut Self {
+        self.desc.read.push(id);
+        self
+    }
+
+    /// Require and borrow `T` components mutably, like `&mut T`
+    pub fn write<T: Component>(&mut self) -> &mut Self {
+        self.write_dynamic(TypeId::of::<T>())
+    }
+
+    /// Require and borrow components of the type identified by `id` mutably
+    pub fn write_dynamic(&mut self, id: TypeId) -> &mut Self {
+        self.desc.write.push(id);
+        self
+    }
+
+    /// Require `T` components without borrowing them, like [`With`](crate::With)
+    pub fn with<T: Component>(&mut self) -> &mut Self {
+        self.with_dynamic(TypeId::of::<T>())
+    }
+
+    /// Require components of the type identified by `id` without borrowing them
+    pub fn with_dynamic(&mut self, id: TypeId) -> &mut Self {
+        self.desc.with.push(id);
+        self
+    }
+
+    /// Exclude entities having `T` components, like [`Without`](crate::Without)
+    pub fn without<T: Component>(&mut self) -> &mut Self {
+        self.without_dynamic(TypeId::of::<T>())
+    }
+
+    /// Exclude entities having components of the type identified by `id`
+    pub fn without_dynamic(&mut self, id: TypeId) -> &mut Self {
+        self.desc.without.push(id);
+        self
+    }
+
+    /// Require at least one of the component types identified by `ids`, like [`Or`](crate::Or)
+    ///
+    /// An empty `ids` matches no entities.
+    pub fn any_of(&mut self, ids: &[TypeId]) -> &mut Self {
+        let mut ids = ids.to_vec();
+        ids.sort_unstable();
+        ids.dedup();
+        self.desc.any_of.push(ids);
+        self
+    }
+
+    /// Produce the description
+    pub fn build(&self) -> QueryDescription {
+        let mut desc = self.desc.clone();
+        for ids in [
+            &mut desc.read,
+            &mut desc.write,
+            &mut desc.with,
+            &mut desc.without,
+        ] {
+            ids.sort_unstable();
+            ids.dedup();
+        }
+        desc
+    }
+}
+
+/// The component access and filters of a query assembled at runtime by a [`QueryBuilder`]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct QueryDescription {
+    read: Vec<TypeId>,
+    write: Vec<TypeId>,
+    with: Vec<TypeId>,
+    without: Vec<TypeId>,
+    any_of: Vec<Vec<TypeId>>,
+}
+
+impl QueryDescription {
+    /// Whether entities in `archetype` match the query
+    pub fn matches(&self, archetype: &Archetype) -> bool {
+        self.read
+            .iter()
+            .chain(&self.write)
+            .chain(&self.with)
+            .all(|&id| archetype.has_dynamic(id))
+            && !self.without.iter().any(|&id| archetype.has_dynamic(id))
+            && self
+                .any_of
+                .iter()
+                .all(|ids| ids.iter().any(|&id| archetype.has_dynamic(id)))
+    }
+
+    /// Component types borrowed immutably
+    pub fn reads(&self) -> &[TypeId] {
+        &self.read
+    }
+
+    /// Component types borrowed mutably
+    pub fn writes(&self) -> &[TypeId] {
+        &self.write
+    }
+}
@@ -41,6 +41,7 @@ impl<'a, Q: Query> QueryOne<'a, Q> {
         }
         let state = Q::Fetch::prepare(self.archetype)?;
         Q::Fetch::borrow(self.archetype, state);
+        self.archetype.mark_queried();
         let fetch = Q::Fetch::execute(self.archetype, state);
         self.borrowed = true;
         unsafe { Some(Q::get(&fetch, self.index as usize)) }
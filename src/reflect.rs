@@ -0,0 +1,160 @@
+//! Runtime descriptions of component types, for inspectors and editors
+//!
+//! A [`ComponentInfo`] describes a component type's name, size and alignment, and, if the type
+//! implements [`Reflect`] and was registered with
+//! [`ComponentRegistry::register_reflect`], its fields. Describe an entity's components with
+//! [`EntityRef::component_info`](crate::EntityRef::component_info).
+//!
+//! ```
+//! # use hecs::*;
+//! use hecs::reflect::{FieldInfo, Reflect};
+//!
+//! #[repr(C)]
+//! struct Position {
+//!     x: f32,
+//!     y: f32,
+//! }
+//!
+//! impl Reflect for Position {
+//!     const FIELDS: &'static [FieldInfo] = &[
+//!         FieldInfo::new("x", "f32", 0, 4),
+//!         FieldInfo::new("y", "f32", 4, 4),
+//!     ];
+//! }
+//!
+//! let mut registry = ComponentRegistry::new();
+//! registry.register_reflect::<Position>("Position");
+//!
+//! let mut world = World::new();
+//! let e = world.spawn((Position { x: 0.0, y: 1.0 }, true));
+//! let entity = world.entity(e).unwrap();
+//! let mut names = entity
+//!     .component_info(&registry)
+//!     .map(|info| info.name())
+//!     .collect::<Vec<_>>();
+//! names.sort();
+//! assert_eq!(names, [None, Some("Position")]);
+//!
+//! let info = registry.get_by_name("Position").unwrap().component_info();
+//! assert_eq!(info.size(), 8);
+//! let fields = info.fields().unwrap();
+//! assert_eq!(fields[1].name(), "y");
+//! assert_eq!(fields[1].type_name(), "f32");
+//! ```
+
+use core::alloc::Layout;
+use core::any::TypeId;
+
+#[cfg(doc)]
+use crate::ComponentRegistry;
+
+/// A component type whose fields can be described at runtime
+pub trait Reflect {
+    /// The type's fields, in declaration order
+    const FIELDS: &'static [FieldInfo];
+}
+
+/// Describes a field of a component type
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct FieldInfo {
+    name: &'static str,
+    type_name: &'static str,
+    offset: usize,
+    size: usize,
+}
+
+impl FieldInfo {
+    /// Describe a field named `name` of the type named `type_name`, occupying `size` bytes
+    /// starting `offset` bytes into the component
+    pub const fn new(
+        name: &'static str,
+        type_name: &'static str,
+        offset: usize,
+        size: usize,
+    ) -> Self {
+        Self {
+            name,
+            type_name,
+            offset,
+            size,
+        }
+    }
+
+    /// Name of the field
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Name of the field's type
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Offset of the field from the start of the component, in bytes
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Size of the field, in bytes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// Describes a component type
+///
+/// Types that weren't registered in the [`ComponentRegistry`] used to obtain the description
+/// have no name or fields.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ComponentInfo {
+    id: TypeId,
+    name: Option<&'static str>,
+    layout: Layout,
+    fields: Option<&'static [FieldInfo]>,
+}
+
+impl ComponentInfo {
+    pub(crate) fn new(
+        id: TypeId,
+        name: Option<&'static str>,
+        layout: Layout,
+        fields: Option<&'static [FieldInfo]>,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            layout,
+            fields,
+        }
+    }
+
+    /// The [`TypeId`] of the component type
+    pub fn id(&self) -> TypeId {
+        self.id
+    }
+
+    /// The name the type was registered under, if any
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    /// Size of the component type, in bytes
+    pub fn size(&self) -> usize {
+        self.layout.size()
+    }
+
+    /// Alignment of the component type, in bytes
+    pub fn align(&self) -> usize {
+        self.layout.align()
+    }
+
+    /// Layout of the component type
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// The type's fields, if it was registered with [`ComponentRegistry::register_reflect`]
+    pub fn fields(&self) -> Option<&'static [FieldInfo]> {
+        self.fields
+    }
+}
@@ -0,0 +1,634 @@
+use crate::alloc::boxed::Box;
+use crate::alloc::sync::Arc;
+use crate::alloc::vec::Vec;
+use core::any::{Any, TypeId};
+use core::fmt;
+
+use hashbrown::HashMap;
+
+use crate::archetype::{TypeIdMap, TypeInfo};
+use crate::reflect::{ComponentInfo, FieldInfo, Reflect};
+use crate::{
+    Component, DynamicBundle, DynamicClone, Entity, StableHash, StableHasher, VisitEntities,
+};
+
+/// Runtime metadata about a set of component types
+///
+/// Allows code that only knows a component by its [`TypeId`] or name, such as editors and
+/// data-driven spawners, to operate on it. Types are listed in registration order.
+///
+/// ```
+/// # use hecs::*;
+/// #[derive(Default, Debug, PartialEq)]
+/// struct Health(u32);
+///
+/// let mut registry = ComponentRegistry::new();
+/// registry.register_default::<Health>("Health");
+///
+/// let mut world = World::new();
+/// let e = world.spawn(());
+/// let id = registry.id_of("Health").unwrap();
+/// world.insert_default_dynamic(e, &registry, id).unwrap();
+/// assert_eq!(*world.get::<&Health>(e).unwrap(), Health(0));
+/// ```
+#[derive(Default, Clone)]
+pub struct ComponentRegistry {
+    entries: Vec<ComponentRegistration>,
+    by_id: TypeIdMap<usize>,
+    by_name: HashMap<&'static str, usize>,
+    requirements: Vec<Requirement>,
+}
+
+impl ComponentRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `T` under `name`
+    ///
+    /// Registering a type that is already present replaces its name, but preserves any other
+    /// metadata.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is already used by a different type.
+    pub fn register<T: Component>(&mut self, name: &'static str) -> &mut Self {
+        self.entry::<T>(name);
+        self
+    }
+
+    /// Register `T` under `name`, along with its [`Default`] implementation
+    ///
+    /// Enables [`World::insert_default_dynamic`](crate::World::insert_default_dynamic) and
+    /// [`EntityBuilder::add_default_dynamic`](crate::EntityBuilder::add_default_dynamic).
+    pub fn register_default<T: Component + Default>(&mut self, name: &'static str) -> &mut Self {
+        self.entry::<T>(name).default = Some(|f| {
+            let mut tmp = T::default();
+            f((&mut tmp as *mut T).cast(), TypeInfo::of::<T>());
+            core::mem::forget(tmp);
+        });
+        self
+    }
+
+    /// Register `T` under `name`, along with its [`Clone`] implementation
+    ///
+    /// Enables [`World::spawn_clones`](crate::World::spawn_clones) and reading components through
+    /// a [`ScriptWorld`](crate::ScriptWorld).
+    pub fn register_clone<T: Component + Clone>(&mut self, name: &'static str) -> &mut Self {
+        let entry = self.entry::<T>(name);
+        entry.clone = Some(DynamicClone::new::<T>());
+        entry.clone_boxed = Some(|x| unsafe { Box::new((*x.cast::<T>()).clone()) });
+        self
+    }
+
+    /// Register `T` under `name`, along with its [`PartialEq`] implementation
+    ///
+    /// Allows [`Prefab::diff`](crate::Prefab::diff) and [`WorldDiff`](crate::WorldDiff) to detect
+    /// unchanged components.
+    pub fn register_eq<T: Component + PartialEq>(&mut self, name: &'static str) -> &mut Self {
+        self.entry::<T>(name).eq = Some(|a, b| unsafe { *a.cast::<T>() == *b.cast::<T>() });
+        self
+    }
+
+    /// Register `T` under `name`, along with its [`Debug`](fmt::Debug) implementation
+    ///
+    /// Allows [`WorldDiff::report`](crate::WorldDiff::report) to show component values.
+    pub fn register_debug<T: Component + fmt::Debug>(&mut self, name: &'static str) -> &mut Self {
+        self.entry::<T>(name).debug = Some(|x, f| unsafe { fmt::Debug::fmt(&*x.cast::<T>(), f) });
+        self
+    }
+
+    /// Register `T` under `name`, along with its [`Generate`](crate::testing::Generate)
+    /// implementation
+    ///
+    /// Allows [`testing::generate`](crate::testing::generate) to produce `T` components.
+    pub fn register_generate<T: Component + crate::testing::Generate>(
+        &mut self,
+        name: &'static str,
+    ) -> &mut Self {
+        self.entry::<T>(name).generate = Some(|rng, f| {
+            let mut tmp = T::generate(rng);
+            f((&mut tmp as *mut T).cast(), TypeInfo::of::<T>());
+            core::mem::forget(tmp);
+        });
+        self
+    }
+
+    /// Register `T` under `name`, along with its [`Lerp`](crate::Lerp) implementation
+    ///
+    /// Allows [`interpolate_into`](crate::interpolate_into) to interpolate `T` components.
+    pub fn register_lerp<T: Component + crate::Lerp>(&mut self, name: &'static str) -> &mut Self {
+        self.entry::<T>(name).lerp = Some(|a, b, alpha, out| unsafe {
+            *out.cast::<T>() = T::lerp(&*a.cast::<T>(), &*b.cast::<T>(), alpha);
+        });
+        self
+    }
+
+    /// Register `T` under `name`, along with its [`VisitEntities`] implementation
+    ///
+    /// Allows [`World::remap_entities`](crate::World::remap_entities) to rewrite the entity handles
+    /// within `T` components.
+    pub fn register_entities<T: Component + VisitEntities>(
+        &mut self,
+        name: &'static str,
+    ) -> &mut Self {
+        self.entry::<T>(name).visit = Some(|x, f| unsafe { (*x.cast::<T>()).visit_entities(f) });
+        self
+    }
+
+    /// Register `T` under `name`, along with its [`StableHash`] implementation
+    ///
+    /// Includes `T` components in [`World::stable_hash`](crate::World::stable_hash).
+    pub fn register_stable_hash<T: Component + StableHash>(
+        &mut self,
+        name: &'static str,
+    ) -> &mut Self {
+        self.entry::<T>(name).stable_hash = Some(|x, hasher| unsafe {
+            (*x.cast::<T>()).stable_hash(hasher);
+        });
+        self
+    }
+
+    /// Register `T` under `name`, along with its [`Reflect`] implementation
+    ///
+    /// Exposes `T`'s fields through [`ComponentInfo::fields`], e.g. for in-game inspectors.
+    pub fn register_reflect<T: Component + Reflect>(&mut self, name: &'static str) -> &mut Self {
+        self.entry::<T>(name).fields = Some(T::FIELDS);
+        self
+    }
+
+    /// Register `T` under `name`, marking it as plain old data
+    ///
+    /// Plain old data can be copied as raw bytes, e.g. by
+    /// [`serialize::rkyv`](crate::serialize::rkyv) snapshots.
+    ///
+    /// # Safety
+    ///
+    /// `T` must contain no padding, pointers, or references, and every bit pattern must be a valid
+    /// `T`.
+    pub unsafe fn register_pod<T: Component + Copy>(&mut self, name: &'static str) -> &mut Self {
+        self.entry::<T>(name).pod = true;
+        self
+    }
+
+    /// Register `T` under `name`, along with its [`rkyv`](::rkyv) implementations
+    ///
+    /// Allows components that aren't plain old data to be included in
+    /// [`serialize::rkyv`](crate::serialize::rkyv) snapshots.
+    #[cfg(feature = "rkyv")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rkyv")))]
+    pub fn register_archive<T>(&mut self, name: &'static str) -> &mut Self
+    where
+        T: Component
+            + ::rkyv::Archive
+            + ::rkyv::Serialize<::rkyv::ser::serializers::AllocSerializer<256>>,
+        T::Archived: for<'a> ::rkyv::CheckBytes<::rkyv::validation::validators::DefaultValidator<'a>>
+            + ::rkyv::Deserialize<T, ::rkyv::de::deserializers::SharedDeserializeMap>,
+    {
+        self.entry::<T>(name).archive = Some(crate::serialize::rkyv::ArchiveFns::new::<T>());
+        self
+    }
+
+    /// Register `T` under `name`, along with its [`serde`](::serde) implementations, encoded by `C`
+    ///
+    /// Allows components of type `T` to be saved and loaded with
+    /// [`serialize::row::RegistryContext`](crate::serialize::row::RegistryContext).
+    #[cfg(feature = "row-serialize")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "row-serialize")))]
+    pub fn register_serde<T, C>(&mut self, name: &'static str) -> &mut Self
+    where
+        T: Component + ::serde::Serialize + ::serde::de::DeserializeOwned,
+        C: crate::serialize::row::ComponentCodec,
+    {
+        self.entry::<T>(name).serde = Some(crate::serialize::row::SerdeFns::new::<T, C>());
+        self
+    }
+
+    /// Declare that entities having an `A` component must also have a `B` component
+    ///
+    /// Once passed to [`World::set_requirements`](crate::World::set_requirements), a
+    /// default-constructed `B` is added to any entity that gains an `A` without already having a
+    /// `B`. Neither type needs to be registered by name.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// #[derive(Default)]
+    /// struct Transform([f32; 2]);
+    /// struct Sprite(&'static str);
+    ///
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.require::<Sprite, Transform>();
+    /// let mut world = World::new();
+    /// world.set_requirements(&registry);
+    /// let e = world.spawn((Sprite("goblin.png"),));
+    /// assert!(world.satisfies::<&Transform>(e).unwrap());
+    /// ```
+    pub fn require<A: Component, B: Component + Default>(&mut self) -> &mut Self {
+        self.require_with::<A, B>(B::default)
+    }
+
+    /// Like [`require`](Self::require), but constructs missing `B` components with `factory`
+    pub fn require_with<A: Component, B: Component>(
+        &mut self,
+        factory: impl Fn() -> B + Send + Sync + 'static,
+    ) -> &mut Self {
+        let requirement = Requirement {
+            dependent: TypeId::of::<A>(),
+            required: TypeInfo::of::<B>(),
+            ctor: Arc::new(move |f| {
+                let mut tmp = factory();
+                f((&mut tmp as *mut B).cast(), TypeInfo::of::<B>());
+                core::mem::forget(tmp);
+            }),
+        };
+        // Redeclaring a requirement replaces its factory
+        match self.requirements.iter_mut().find(|x| {
+            x.dependent == requirement.dependent && x.required.id() == requirement.required.id()
+        }) {
+            Some(existing) => *existing = requirement,
+            None => self.requirements.push(requirement),
+        }
+        self
+    }
+
+    /// Requirements declared with [`require`](Self::require), in declaration order
+    pub(crate) fn requirements(&self) -> &[Requirement] {
+        &self.requirements
+    }
+
+    fn entry<T: Component>(&mut self, name: &'static str) -> &mut ComponentRegistration {
+        let id = TypeId::of::<T>();
+        if let Some(&other) = self.by_name.get(name) {
+            assert!(
+                self.entries[other].info.id() == id,
+                "component name {name:?} is already registered to a different type"
+            );
+        }
+        let index = match self.by_id.get(&id) {
+            Some(&index) => {
+                let old = self.entries[index].name;
+                self.by_name.remove(old);
+                self.entries[index].name = name;
+                index
+            }
+            None => {
+                self.entries.push(ComponentRegistration {
+                    name,
+                    info: TypeInfo::of::<T>(),
+                    default: None,
+                    clone: None,
+                    clone_boxed: None,
+                    eq: None,
+                    debug: None,
+                    generate: None,
+                    lerp: None,
+                    visit: None,
+                    stable_hash: None,
+                    fields: None,
+                    pod: false,
+                    #[cfg(feature = "rkyv")]
+                    archive: None,
+                    #[cfg(feature = "row-serialize")]
+                    serde: None,
+                });
+                self.by_id.insert(id, self.entries.len() - 1);
+                self.entries.len() - 1
+            }
+        };
+        self.by_name.insert(name, index);
+        &mut self.entries[index]
+    }
+
+    /// Look up the metadata for the component type identified by `id`
+    pub fn get(&self, id: TypeId) -> Option<&ComponentRegistration> {
+        Some(&self.entries[*self.by_id.get(&id)?])
+    }
+
+    /// Look up the metadata for the component type registered as `name`
+    pub fn get_by_name(&self, name: &str) -> Option<&ComponentRegistration> {
+        Some(&self.entries[*self.by_name.get(name)?])
+    }
+
+    /// Find the [`TypeId`] of the component type registered as `name`
+    pub fn id_of(&self, name: &str) -> Option<TypeId> {
+        self.get_by_name(name).map(|x| x.info.id())
+    }
+
+    /// Whether the component type identified by `id` has been registered
+    pub fn contains(&self, id: TypeId) -> bool {
+        self.by_id.contains_key(&id)
+    }
+
+    /// Iterate over all registered component types, in registration order
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &ComponentRegistration> + '_ {
+        self.entries.iter()
+    }
+
+    /// Number of registered component types
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no component types have been registered
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// A single default-constructed component of the type identified by `id`
+    pub(crate) fn default_bundle(&self, id: TypeId) -> Result<DefaultComponent, MissingDefault> {
+        let reg = self.get(id).ok_or(MissingDefault(id))?;
+        Ok(DefaultComponent {
+            info: reg.info,
+            ctor: reg.default.ok_or(MissingDefault(id))?,
+        })
+    }
+}
+
+impl fmt::Debug for ComponentRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.entries.iter()).finish()
+    }
+}
+
+/// Moves a default-constructed component into the callback
+type DefaultFn = fn(&mut dyn FnMut(*mut u8, TypeInfo));
+
+/// Moves a newly constructed component into the callback
+type FactoryFn = dyn Fn(&mut dyn FnMut(*mut u8, TypeInfo)) + Send + Sync;
+
+/// Clones a component into a type-erased box
+type CloneBoxedFn = unsafe fn(*const u8) -> Box<dyn Any + Send + Sync>;
+
+/// Compares two components of the same type
+type EqFn = unsafe fn(*const u8, *const u8) -> bool;
+
+/// Formats a component
+type DebugFn = unsafe fn(*const u8, &mut fmt::Formatter<'_>) -> fmt::Result;
+
+/// Moves a pseudo-randomly generated component into the callback
+pub(crate) type GenerateFn = fn(&mut crate::testing::SeededRng, &mut dyn FnMut(*mut u8, TypeInfo));
+
+/// Writes the interpolation between two components over a third
+type LerpFn = unsafe fn(*const u8, *const u8, f32, *mut u8);
+
+/// Calls a function on each entity handle within a component
+pub(crate) type VisitFn = unsafe fn(*mut u8, &mut dyn FnMut(&mut Entity));
+
+/// Feeds a component into a hasher
+pub(crate) type StableHashFn = unsafe fn(*const u8, &mut StableHasher);
+
+/// Metadata about a single registered component type
+#[derive(Clone)]
+pub struct ComponentRegistration {
+    name: &'static str,
+    info: TypeInfo,
+    default: Option<DefaultFn>,
+    clone: Option<DynamicClone>,
+    clone_boxed: Option<CloneBoxedFn>,
+    eq: Option<EqFn>,
+    debug: Option<DebugFn>,
+    generate: Option<GenerateFn>,
+    lerp: Option<LerpFn>,
+    visit: Option<VisitFn>,
+    stable_hash: Option<StableHashFn>,
+    fields: Option<&'static [FieldInfo]>,
+    pod: bool,
+    #[cfg(feature = "rkyv")]
+    archive: Option<crate::serialize::rkyv::ArchiveFns>,
+    #[cfg(feature = "row-serialize")]
+    serde: Option<crate::serialize::row::SerdeFns>,
+}
+
+impl ComponentRegistration {
+    /// The name the type was registered under
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The [`TypeId`] of the component type
+    pub fn id(&self) -> TypeId {
+        self.info.id()
+    }
+
+    /// Layout and drop metadata of the component type
+    pub fn type_info(&self) -> TypeInfo {
+        self.info
+    }
+
+    /// Description of the type, including its fields if a [`Reflect`] implementation was
+    /// registered
+    pub fn component_info(&self) -> ComponentInfo {
+        ComponentInfo::new(self.id(), Some(self.name), self.info.layout(), self.fields)
+    }
+
+    /// Whether a [`Default`] implementation was registered for the type
+    pub fn has_default(&self) -> bool {
+        self.default.is_some()
+    }
+
+    /// Whether a [`Clone`] implementation was registered for the type
+    pub fn has_clone(&self) -> bool {
+        self.clone.is_some()
+    }
+
+    /// Whether a [`PartialEq`] implementation was registered for the type
+    pub fn has_eq(&self) -> bool {
+        self.eq.is_some()
+    }
+
+    /// Whether a [`Debug`](fmt::Debug) implementation was registered for the type
+    pub fn has_debug(&self) -> bool {
+        self.debug.is_some()
+    }
+
+    /// Whether a [`Generate`](crate::testing::Generate) implementation was registered for the type
+    pub fn has_generate(&self) -> bool {
+        self.generate.is_some()
+    }
+
+    /// Whether a [`Lerp`](crate::Lerp) implementation was registered for the type
+    pub fn has_lerp(&self) -> bool {
+        self.lerp.is_some()
+    }
+
+    /// Whether a [`VisitEntities`] implementation was registered for the type
+    pub fn has_entities(&self) -> bool {
+        self.visit.is_some()
+    }
+
+    /// Whether a [`StableHash`] implementation was registered for the type
+    pub fn has_stable_hash(&self) -> bool {
+        self.stable_hash.is_some()
+    }
+
+    /// Whether the type was registered as plain old data
+    pub fn is_pod(&self) -> bool {
+        self.pod
+    }
+
+    /// Whether [`rkyv`](::rkyv) implementations were registered for the type
+    #[cfg(feature = "rkyv")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rkyv")))]
+    pub fn has_archive(&self) -> bool {
+        self.archive.is_some()
+    }
+
+    #[cfg(feature = "rkyv")]
+    pub(crate) fn archive(&self) -> Option<crate::serialize::rkyv::ArchiveFns> {
+        self.archive
+    }
+
+    /// Whether [`serde`](::serde) implementations were registered for the type
+    #[cfg(feature = "row-serialize")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "row-serialize")))]
+    pub fn has_serde(&self) -> bool {
+        self.serde.is_some()
+    }
+
+    #[cfg(feature = "row-serialize")]
+    pub(crate) fn serde(&self) -> Option<crate::serialize::row::SerdeFns> {
+        self.serde
+    }
+
+    pub(crate) fn generate_fn(&self) -> Option<GenerateFn> {
+        self.generate
+    }
+
+    pub(crate) fn lerp_fn(&self) -> Option<LerpFn> {
+        self.lerp
+    }
+
+    pub(crate) fn visit_fn(&self) -> Option<VisitFn> {
+        self.visit
+    }
+
+    pub(crate) fn stable_hash_fn(&self) -> Option<StableHashFn> {
+        self.stable_hash
+    }
+
+    pub(crate) fn dynamic_clone(&self) -> Option<DynamicClone> {
+        self.clone
+    }
+
+    /// Clone an instance of the type into a box, if a [`Clone`] implementation was registered
+    ///
+    /// `x` must refer to a valid instance of the type.
+    pub(crate) unsafe fn clone_boxed(&self, x: *const u8) -> Option<Box<dyn Any + Send + Sync>> {
+        Some((self.clone_boxed?)(x))
+    }
+
+    /// Compare two instances of the type, if a [`PartialEq`] implementation was registered
+    ///
+    /// Both pointers must refer to valid instances of the type.
+    pub(crate) unsafe fn eq(&self, a: *const u8, b: *const u8) -> Option<bool> {
+        Some((self.eq?)(a, b))
+    }
+
+    /// Format an instance of the type, if a [`Debug`](fmt::Debug) implementation was registered
+    ///
+    /// `x` must refer to a valid instance of the type.
+    pub(crate) unsafe fn fmt_debug(
+        &self,
+        x: *const u8,
+        f: &mut fmt::Formatter<'_>,
+    ) -> Option<fmt::Result> {
+        Some((self.debug?)(x, f))
+    }
+}
+
+impl fmt::Debug for ComponentRegistration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComponentRegistration")
+            .field("name", &self.name)
+            .field("has_default", &self.has_default())
+            .field("has_clone", &self.has_clone())
+            .field("has_eq", &self.has_eq())
+            .field("has_debug", &self.has_debug())
+            .field("has_generate", &self.has_generate())
+            .field("has_lerp", &self.has_lerp())
+            .field("has_stable_hash", &self.has_stable_hash())
+            .field("has_fields", &self.fields.is_some())
+            .field("is_pod", &self.is_pod())
+            .finish()
+    }
+}
+
+/// A single component produced by a registered [`Default`] implementation
+pub(crate) struct DefaultComponent {
+    info: TypeInfo,
+    ctor: DefaultFn,
+}
+
+unsafe impl DynamicBundle for DefaultComponent {
+    fn with_ids<T>(&self, f: impl FnOnce(&[TypeId]) -> T) -> T {
+        f(&[self.info.id()])
+    }
+
+    fn type_info(&self) -> Vec<TypeInfo> {
+        crate::alloc::vec![self.info]
+    }
+
+    unsafe fn put(self, mut f: impl FnMut(*mut u8, TypeInfo)) {
+        (self.ctor)(&mut f);
+    }
+}
+
+/// A dependency declared with [`ComponentRegistry::require`]
+#[derive(Clone)]
+pub(crate) struct Requirement {
+    /// Type whose presence demands `required`
+    pub(crate) dependent: TypeId,
+    pub(crate) required: TypeInfo,
+    ctor: Arc<FactoryFn>,
+}
+
+impl Requirement {
+    /// A newly constructed component of the required type
+    pub(crate) fn component(&self) -> RequiredComponent<'_> {
+        RequiredComponent(self)
+    }
+}
+
+/// A single component produced by a [`Requirement`]'s factory
+pub(crate) struct RequiredComponent<'a>(&'a Requirement);
+
+unsafe impl DynamicBundle for RequiredComponent<'_> {
+    fn with_ids<T>(&self, f: impl FnOnce(&[TypeId]) -> T) -> T {
+        f(&[self.0.required.id()])
+    }
+
+    fn type_info(&self) -> Vec<TypeInfo> {
+        crate::alloc::vec![self.0.required]
+    }
+
+    unsafe fn put(self, mut f: impl FnMut(*mut u8, TypeInfo)) {
+        (self.0.ctor)(&mut f);
+    }
+}
+
+/// Error indicating that a component type has no registered [`Default`] implementation
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct MissingDefault(pub TypeId);
+
+impl fmt::Display for MissingDefault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no default registered for component type {:?}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MissingDefault {}
+
+/// Error indicating that a component type has no registered [`Clone`] implementation
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct MissingClone(pub TypeId);
+
+impl fmt::Display for MissingClone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no clone registered for component type {:?}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MissingClone {}
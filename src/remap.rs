@@ -0,0 +1,134 @@
+use crate::alloc::boxed::Box;
+use crate::alloc::vec::Vec;
+
+use hashbrown::HashMap;
+
+use crate::Entity;
+
+/// Types containing [`Entity`] handles that can be rewritten by
+/// [`World::remap_entities`](crate::World::remap_entities)
+///
+/// Components implementing this are registered with
+/// [`ComponentRegistry::register_entities`](crate::ComponentRegistry::register_entities). With the
+/// `macros` feature, it can be derived for structs, visiting the fields marked `#[entities]`.
+///
+/// ```
+/// # use hecs::*;
+/// struct Target {
+///     entity: Entity,
+///     fallback: Option<Entity>,
+///     range: f32,
+/// }
+///
+/// impl VisitEntities for Target {
+///     fn visit_entities(&mut self, f: &mut dyn FnMut(&mut Entity)) {
+///         self.entity.visit_entities(f);
+///         self.fallback.visit_entities(f);
+///     }
+/// }
+/// ```
+pub trait VisitEntities {
+    /// Call `f` on every `Entity` handle in `self`
+    fn visit_entities(&mut self, f: &mut dyn FnMut(&mut Entity));
+}
+
+impl VisitEntities for Entity {
+    fn visit_entities(&mut self, f: &mut dyn FnMut(&mut Entity)) {
+        f(self)
+    }
+}
+
+impl<T: VisitEntities> VisitEntities for Option<T> {
+    fn visit_entities(&mut self, f: &mut dyn FnMut(&mut Entity)) {
+        if let Some(x) = self {
+            x.visit_entities(f);
+        }
+    }
+}
+
+impl<T: VisitEntities + ?Sized> VisitEntities for Box<T> {
+    fn visit_entities(&mut self, f: &mut dyn FnMut(&mut Entity)) {
+        (**self).visit_entities(f)
+    }
+}
+
+impl<T: VisitEntities> VisitEntities for [T] {
+    fn visit_entities(&mut self, f: &mut dyn FnMut(&mut Entity)) {
+        for x in self {
+            x.visit_entities(f);
+        }
+    }
+}
+
+impl<T: VisitEntities, const N: usize> VisitEntities for [T; N] {
+    fn visit_entities(&mut self, f: &mut dyn FnMut(&mut Entity)) {
+        self[..].visit_entities(f)
+    }
+}
+
+impl<T: VisitEntities> VisitEntities for Vec<T> {
+    fn visit_entities(&mut self, f: &mut dyn FnMut(&mut Entity)) {
+        self[..].visit_entities(f)
+    }
+}
+
+/// A mapping from old to new [`Entity`] handles, applied by
+/// [`World::remap_entities`](crate::World::remap_entities)
+///
+/// Handles absent from the mapping are left unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct EntityRemap {
+    map: HashMap<Entity, Entity>,
+}
+
+impl EntityRemap {
+    /// Create an empty mapping
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map `from` to `to`, returning the handle `from` was previously mapped to, if any
+    pub fn insert(&mut self, from: Entity, to: Entity) -> Option<Entity> {
+        self.map.insert(from, to)
+    }
+
+    /// The handle `from` is mapped to, if any
+    pub fn get(&self, from: Entity) -> Option<Entity> {
+        self.map.get(&from).copied()
+    }
+
+    /// Replace `entity` with the handle it's mapped to, if any, returning whether it was
+    pub fn apply(&self, entity: &mut Entity) -> bool {
+        match self.map.get(entity) {
+            Some(&to) => {
+                *entity = to;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of handles mapped
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether no handles are mapped
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl FromIterator<(Entity, Entity)> for EntityRemap {
+    fn from_iter<I: IntoIterator<Item = (Entity, Entity)>>(iter: I) -> Self {
+        Self {
+            map: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<(Entity, Entity)> for EntityRemap {
+    fn extend<I: IntoIterator<Item = (Entity, Entity)>>(&mut self, iter: I) {
+        self.map.extend(iter)
+    }
+}
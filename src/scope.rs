@@ -0,0 +1,108 @@
+use core::cell::RefCell;
+
+use crate::{
+    Bundle, CommandBuffer, Component, ComponentError, ComponentRef, DynamicBundle, Entity,
+    EntityRef, NoSuchEntity, Query, QueryBorrow, World,
+};
+
+/// Shared access to a [`World`] that defers structural changes
+///
+/// Obtained from [`World::iter_scope`]. Queries and random access go directly to the world, while
+/// spawning, despawning, and adding or removing components are recorded and applied in order
+/// once the scope ends. This allows entities to be freely modified while iterating over a query.
+pub struct Scope<'w> {
+    world: &'w World,
+    cmd: RefCell<CommandBuffer>,
+}
+
+impl<'w> Scope<'w> {
+    pub(crate) fn new(world: &'w World) -> Self {
+        Self {
+            world,
+            cmd: RefCell::new(CommandBuffer::new()),
+        }
+    }
+
+    pub(crate) fn into_commands(self) -> CommandBuffer {
+        self.cmd.into_inner()
+    }
+
+    /// The world this scope provides access to
+    pub fn world(&self) -> &'w World {
+        self.world
+    }
+
+    /// Iterate over entities with certain components
+    ///
+    /// See [`World::query`].
+    pub fn query<Q: Query>(&self) -> QueryBorrow<'w, Q> {
+        self.world.query()
+    }
+
+    /// Access an entity regardless of its component types
+    ///
+    /// See [`World::entity`].
+    pub fn entity(&self, entity: Entity) -> Result<EntityRef<'w>, NoSuchEntity> {
+        self.world.entity(entity)
+    }
+
+    /// Borrow a single component of `entity`
+    ///
+    /// See [`World::get`].
+    pub fn get<T: ComponentRef<'w>>(&self, entity: Entity) -> Result<T::Ref, ComponentError> {
+        self.world.get::<T>(entity)
+    }
+
+    /// Whether `entity` exists
+    ///
+    /// Entities spawned in this scope exist, while entities despawned in this scope still exist
+    /// until it ends.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.world.contains(entity)
+    }
+
+    /// Spawn an entity with `components` when the scope ends
+    ///
+    /// The returned handle is reserved immediately, but the entity has no components until the
+    /// scope ends.
+    pub fn spawn(&self, components: impl DynamicBundle) -> Entity {
+        let entity = self.world.reserve_entity();
+        self.cmd.borrow_mut().insert(entity, components);
+        entity
+    }
+
+    /// Add `components` to `entity` when the scope ends
+    ///
+    /// See [`World::insert`].
+    pub fn insert(&self, entity: Entity, components: impl DynamicBundle) {
+        self.cmd.borrow_mut().insert(entity, components);
+    }
+
+    /// Add `component` to `entity` when the scope ends
+    ///
+    /// See [`World::insert_one`].
+    pub fn insert_one(&self, entity: Entity, component: impl Component) {
+        self.cmd.borrow_mut().insert_one(entity, component);
+    }
+
+    /// Remove `T` components from `entity` when the scope ends
+    ///
+    /// See [`World::remove`].
+    pub fn remove<T: Bundle + 'static>(&self, entity: Entity) {
+        self.cmd.borrow_mut().remove::<T>(entity);
+    }
+
+    /// Remove the `T` component from `entity` when the scope ends
+    ///
+    /// See [`World::remove_one`].
+    pub fn remove_one<T: Component>(&self, entity: Entity) {
+        self.cmd.borrow_mut().remove_one::<T>(entity);
+    }
+
+    /// Despawn `entity` when the scope ends
+    ///
+    /// See [`World::despawn`].
+    pub fn despawn(&self, entity: Entity) {
+        self.cmd.borrow_mut().despawn(entity);
+    }
+}
@@ -0,0 +1,243 @@
+use crate::alloc::alloc::dealloc;
+use crate::alloc::boxed::Box;
+use crate::alloc::string::{String, ToString};
+use crate::alloc::vec::Vec;
+use core::any::Any;
+use core::fmt;
+
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use crate::archetype::RawColumn;
+use crate::{
+    ComponentRegistration, ComponentRegistry, Entity, EntityRef, InsertDynamicError, NoSuchEntity,
+    QueryBuilder, World,
+};
+
+/// A component value of a type known only at runtime, as exchanged with a [`ScriptWorld`]
+pub type ScriptValue = Box<dyn Any + Send + Sync>;
+
+/// Access to a [`World`]'s components by the names they were registered under
+///
+/// Intended as the glue between a world and a scripting or modding layer, which identifies
+/// component types by strings rather than Rust types. Values are exchanged as [`ScriptValue`]s,
+/// which the binding layer downcasts to the concrete types it knows how to expose.
+///
+/// Reading a component requires that its type was registered with
+/// [`ComponentRegistry::register_clone`]; values are cloned out of the world, so scripts never
+/// hold borrows into it. Writing only requires that the type was registered.
+///
+/// ```
+/// # use hecs::*;
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Health(u32);
+///
+/// let mut registry = ComponentRegistry::new();
+/// registry
+///     .register_clone::<Health>("Health")
+///     .register_clone::<[f32; 2]>("Position");
+///
+/// let mut world = World::new();
+/// let e = world.spawn(([0.0f32, 1.0],));
+///
+/// let mut script = ScriptWorld::new(&mut world, &registry);
+/// script.set(e, "Health", Box::new(Health(10))).unwrap();
+/// let health = script.get(e, "Health").unwrap();
+/// assert_eq!(health.downcast_ref::<Health>(), Some(&Health(10)));
+///
+/// for (entity, values) in script.query(&["Position", "Health"]).unwrap() {
+///     assert_eq!(entity, e);
+///     assert_eq!(values[0].downcast_ref::<[f32; 2]>(), Some(&[0.0, 1.0]));
+/// }
+/// assert_eq!(
+///     script.set(e, "Health", Box::new(7u32)),
+///     Err(ScriptError::TypeMismatch("Health"))
+/// );
+/// ```
+pub struct ScriptWorld<'a> {
+    world: &'a mut World,
+    registry: &'a ComponentRegistry,
+}
+
+impl<'a> ScriptWorld<'a> {
+    /// Expose the components of `world` whose types are registered in `registry`
+    pub fn new(world: &'a mut World, registry: &'a ComponentRegistry) -> Self {
+        Self { world, registry }
+    }
+
+    /// The underlying world
+    pub fn world(&self) -> &World {
+        self.world
+    }
+
+    /// The underlying world, for operations not expressible by name
+    pub fn world_mut(&mut self) -> &mut World {
+        self.world
+    }
+
+    /// The registry used to resolve names
+    pub fn registry(&self) -> &'a ComponentRegistry {
+        self.registry
+    }
+
+    /// Whether `entity` has a component of the type registered as `name`
+    pub fn has(&self, entity: Entity, name: &str) -> Result<bool, ScriptError> {
+        let reg = self.registration(name)?;
+        Ok(self.world.entity(entity)?.raw().0.has_dynamic(reg.id()))
+    }
+
+    /// Clone the component of the type registered as `name` out of `entity`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the component is uniquely borrowed.
+    pub fn get(&self, entity: Entity, name: &str) -> Result<ScriptValue, ScriptError> {
+        let reg = self.cloneable(name)?;
+        clone_component(self.world.entity(entity)?, reg)
+    }
+
+    /// Add or replace the component of the type registered as `name` on `entity`
+    ///
+    /// Fails without modifying the world if `value` isn't of the registered type.
+    pub fn set(
+        &mut self,
+        entity: Entity,
+        name: &str,
+        value: ScriptValue,
+    ) -> Result<(), ScriptError> {
+        let registry = self.registry;
+        let reg = self.registration(name)?;
+        if (*value).type_id() != reg.id() {
+            return Err(ScriptError::TypeMismatch(reg.name()));
+        }
+        let layout = reg.type_info().layout();
+        let raw = Box::into_raw(value);
+        unsafe {
+            match self
+                .world
+                .insert_dynamic(entity, registry, reg.id(), raw.cast())
+            {
+                Ok(()) => {
+                    // The value has been moved into the world; only its allocation remains
+                    if layout.size() != 0 {
+                        dealloc(raw.cast(), layout);
+                    }
+                    Ok(())
+                }
+                Err(e) => {
+                    drop(Box::from_raw(raw));
+                    debug_assert_eq!(e, InsertDynamicError::NoSuchEntity);
+                    Err(ScriptError::NoSuchEntity)
+                }
+            }
+        }
+    }
+
+    /// Remove and drop the component of the type registered as `name` from `entity`
+    ///
+    /// Returns whether the component was present.
+    pub fn remove(&mut self, entity: Entity, name: &str) -> Result<bool, ScriptError> {
+        let id = self.registration(name)?.id();
+        Ok(self.world.remove_dynamic(entity, id)?)
+    }
+
+    /// Iterate over every entity having components of all the types registered as `names`,
+    /// along with clones of those components in the same order
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the components is uniquely borrowed.
+    pub fn query(
+        &self,
+        names: &[&str],
+    ) -> Result<impl Iterator<Item = (Entity, Vec<ScriptValue>)> + '_, ScriptError> {
+        let regs = names
+            .iter()
+            .map(|name| self.cloneable(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut builder = QueryBuilder::new();
+        for reg in &regs {
+            builder.read_dynamic(reg.id());
+        }
+        let query = builder.build();
+        Ok(self
+            .world
+            .iter()
+            .filter(move |entity| entity.satisfies_dynamic(&query))
+            .map(move |entity| {
+                let values = regs
+                    .iter()
+                    .map(|reg| clone_component(entity, reg).unwrap())
+                    .collect();
+                (entity.entity(), values)
+            }))
+    }
+
+    fn registration(&self, name: &str) -> Result<&'a ComponentRegistration, ScriptError> {
+        self.registry
+            .get_by_name(name)
+            .ok_or_else(|| ScriptError::UnknownComponent(name.to_string()))
+    }
+
+    fn cloneable(&self, name: &str) -> Result<&'a ComponentRegistration, ScriptError> {
+        let reg = self.registration(name)?;
+        if !reg.has_clone() {
+            return Err(ScriptError::NotCloneable(reg.name()));
+        }
+        Ok(reg)
+    }
+}
+
+/// Clone `entity`'s component of the type described by `reg`, which must have a registered
+/// [`Clone`] implementation
+fn clone_component(
+    entity: EntityRef<'_>,
+    reg: &ComponentRegistration,
+) -> Result<ScriptValue, ScriptError> {
+    let (archetype, index) = entity.raw();
+    let _borrow =
+        RawColumn::new(archetype, reg.id()).ok_or(ScriptError::MissingComponent(reg.name()))?;
+    unsafe {
+        let ptr = archetype
+            .get_dynamic(reg.id(), reg.type_info().layout().size(), index)
+            .unwrap();
+        Ok(reg.clone_boxed(ptr.as_ptr()).unwrap())
+    }
+}
+
+/// Errors that arise when accessing components through a [`ScriptWorld`]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ScriptError {
+    /// The entity was already despawned
+    NoSuchEntity,
+    /// No component type is registered under the name
+    UnknownComponent(String),
+    /// The entity has no component of the named type
+    MissingComponent(&'static str),
+    /// The named component type has no registered [`Clone`] implementation, so can't be read
+    NotCloneable(&'static str),
+    /// The value isn't of the named component type
+    TypeMismatch(&'static str),
+}
+
+#[cfg(feature = "std")]
+impl Error for ScriptError {}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ScriptError::*;
+        match *self {
+            NoSuchEntity => f.write_str("no such entity"),
+            UnknownComponent(ref name) => write!(f, "no component type is registered as {name:?}"),
+            MissingComponent(name) => write!(f, "missing {name} component"),
+            NotCloneable(name) => write!(f, "component type {name} is not registered as cloneable"),
+            TypeMismatch(name) => write!(f, "value is not a {name} component"),
+        }
+    }
+}
+
+impl From<NoSuchEntity> for ScriptError {
+    fn from(NoSuchEntity: NoSuchEntity) -> Self {
+        ScriptError::NoSuchEntity
+    }
+}
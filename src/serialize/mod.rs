@@ -5,12 +5,20 @@
 //! allow serialization and deserialization based on purpose-defined traits to control the
 //! procedures explicitly.
 //!
-//! Backwards-incompatible changes to the serde data models herein are subject to the same semantic
-//! versioning stability guarantees as the hecs API.
+//! Backwards-incompatible changes to the serde and rkyv data models herein are subject to the same
+//! semantic versioning stability guarantees as the hecs API.
 
 #[cfg(feature = "column-serialize")]
 #[cfg_attr(docsrs, doc(cfg(feature = "column-serialize")))]
 pub mod column;
+#[cfg(any(feature = "row-serialize", feature = "column-serialize"))]
+pub mod registry;
+#[cfg(feature = "rkyv")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rkyv")))]
+pub mod rkyv;
 #[cfg(feature = "row-serialize")]
 #[cfg_attr(docsrs, doc(cfg(feature = "row-serialize")))]
 pub mod row;
+#[cfg(feature = "scene")]
+#[cfg_attr(docsrs, doc(cfg(feature = "scene")))]
+pub mod scene;
@@ -0,0 +1,419 @@
+//! Declarative registration of serializable component types
+//!
+//! Implementing [`row`](super::row) and [`column`](super::column) contexts by hand requires
+//! listing every serializable component type several times over. The [`serde_registry!`] macro
+//! instead takes a single list of component types and their stable names, and generates a type
+//! implementing every serialization context enabled by the crate's features.
+//!
+//! [`serde_registry!`]: crate::serde_registry
+
+use core::fmt;
+
+use serde::de::{self, DeserializeSeed, Deserializer, Visitor};
+
+/// Declare a type that serializes a fixed set of components under stable names
+///
+/// Each component type is listed exactly once, together with the string it is identified by in
+/// serialized data. The generated type implements [`row::SerializeContext`] and
+/// [`row::DeserializeContext`] when the `row-serialize` feature is enabled, and
+/// [`column::SerializeContext`] and [`column::DeserializeContext`] when the `column-serialize`
+/// feature is enabled. Components of types that aren't listed are skipped during serialization.
+///
+/// Names must be unique, and should not change once data has been saved with them. The order in
+/// which types are listed determines the order in which they're written.
+///
+/// [`row::SerializeContext`]: crate::serialize::row::SerializeContext
+/// [`row::DeserializeContext`]: crate::serialize::row::DeserializeContext
+/// [`column::SerializeContext`]: crate::serialize::column::SerializeContext
+/// [`column::DeserializeContext`]: crate::serialize::column::DeserializeContext
+///
+/// # Example
+/// ```
+/// # use serde::{Serialize, Deserialize};
+/// # #[derive(Serialize, Deserialize)]
+/// # struct Position([f32; 3]);
+/// # #[derive(Serialize, Deserialize)]
+/// # struct Velocity([f32; 3]);
+/// hecs::serde_registry! {
+///     /// Components saved with a level
+///     pub struct LevelComponents {
+///         "position" => Position,
+///         "velocity" => Velocity,
+///     }
+/// }
+///
+/// assert_eq!(LevelComponents::NAMES, &["position", "velocity"]);
+/// assert_eq!(
+///     LevelComponents::name_of(std::any::TypeId::of::<Velocity>()),
+///     Some("velocity")
+/// );
+/// let mut context = LevelComponents::new();
+/// # let _ = &mut context;
+/// ```
+#[macro_export]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "row-serialize", feature = "column-serialize")))
+)]
+macro_rules! serde_registry {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($key:literal => $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            /// Registry indices of the components of the archetype being deserialized
+            #[allow(dead_code)]
+            column_ids: $crate::alloc::vec::Vec<usize>,
+        }
+
+        #[allow(dead_code)]
+        impl $name {
+            /// Names of the registered component types, in registration order
+            pub const NAMES: &'static [&'static str] = &[$($key),*];
+
+            /// Construct a context for serializing or deserializing a world
+            pub fn new() -> Self {
+                Self {
+                    column_ids: $crate::alloc::vec::Vec::new(),
+                }
+            }
+
+            /// Look up the name a component type is registered under, if any
+            pub fn name_of(id: ::core::any::TypeId) -> ::core::option::Option<&'static str> {
+                $(
+                    if id == ::core::any::TypeId::of::<$ty>() {
+                        return ::core::option::Option::Some($key);
+                    }
+                )*
+                ::core::option::Option::None
+            }
+        }
+
+        impl ::core::default::Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        $crate::__serde_registry_row! { $name { $($key => $ty),* } }
+        $crate::__serde_registry_column! { $name { $($key => $ty),* } }
+    };
+}
+
+#[cfg(feature = "row-serialize")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __serde_registry_row {
+    ($name:ident { $($key:literal => $ty:ty),* }) => {
+        impl $crate::serialize::row::SerializeContext for $name {
+            fn serialize_entity<S>(
+                &mut self,
+                entity: $crate::EntityRef<'_>,
+                mut map: S,
+            ) -> ::core::result::Result<S::Ok, S::Error>
+            where
+                S: $crate::serde::ser::SerializeMap,
+            {
+                $(
+                    $crate::serialize::row::try_serialize::<$ty, _, _>(&entity, $key, &mut map)?;
+                )*
+                map.end()
+            }
+
+            fn component_count(
+                &self,
+                entity: $crate::EntityRef<'_>,
+            ) -> ::core::option::Option<usize> {
+                ::core::option::Option::Some(0 $(+ entity.has::<$ty>() as usize)*)
+            }
+        }
+
+        impl $crate::serialize::row::DeserializeContext for $name {
+            fn deserialize_entity<'de, M>(
+                &mut self,
+                mut map: M,
+                entity: &mut $crate::EntityBuilder,
+            ) -> ::core::result::Result<(), M::Error>
+            where
+                M: $crate::serde::de::MapAccess<'de>,
+            {
+                while let ::core::option::Option::Some(id) = map.next_key_seed(
+                    $crate::serialize::registry::ComponentName::new(Self::NAMES),
+                )? {
+                    let mut index = 0;
+                    $(
+                        if id == index {
+                            entity.add::<$ty>(map.next_value()?);
+                        }
+                        index += 1;
+                    )*
+                    let _ = index;
+                }
+                ::core::result::Result::Ok(())
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "row-serialize"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __serde_registry_row {
+    ($($tt:tt)*) => {};
+}
+
+#[cfg(feature = "column-serialize")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __serde_registry_column {
+    ($name:ident { $($key:literal => $ty:ty),* }) => {
+        impl $crate::serialize::column::SerializeContext for $name {
+            fn component_count(&self, archetype: &$crate::Archetype) -> usize {
+                0 $(+ archetype.has::<$ty>() as usize)*
+            }
+
+            fn serialize_component_ids<S>(
+                &mut self,
+                archetype: &$crate::Archetype,
+                mut out: S,
+            ) -> ::core::result::Result<S::Ok, S::Error>
+            where
+                S: $crate::serde::ser::SerializeTuple,
+            {
+                $(
+                    $crate::serialize::column::try_serialize_id::<$ty, _, _>(
+                        archetype, $key, &mut out,
+                    )?;
+                )*
+                out.end()
+            }
+
+            fn serialize_components<S>(
+                &mut self,
+                archetype: &$crate::Archetype,
+                mut out: S,
+            ) -> ::core::result::Result<S::Ok, S::Error>
+            where
+                S: $crate::serde::ser::SerializeTuple,
+            {
+                $(
+                    $crate::serialize::column::try_serialize::<$ty, _>(archetype, &mut out)?;
+                )*
+                out.end()
+            }
+        }
+
+        impl $crate::serialize::column::DeserializeContext for $name {
+            fn deserialize_component_ids<'de, A>(
+                &mut self,
+                mut seq: A,
+            ) -> ::core::result::Result<$crate::ColumnBatchType, A::Error>
+            where
+                A: $crate::serde::de::SeqAccess<'de>,
+            {
+                self.column_ids.clear();
+                let mut batch = $crate::ColumnBatchType::new();
+                while let ::core::option::Option::Some(id) = seq.next_element_seed(
+                    $crate::serialize::registry::ComponentName::new(Self::NAMES),
+                )? {
+                    let mut index = 0;
+                    $(
+                        if id == index {
+                            batch.add::<$ty>();
+                        }
+                        index += 1;
+                    )*
+                    let _ = index;
+                    self.column_ids.push(id);
+                }
+                ::core::result::Result::Ok(batch)
+            }
+
+            fn deserialize_components<'de, A>(
+                &mut self,
+                entity_count: u32,
+                mut seq: A,
+                batch: &mut $crate::ColumnBatchBuilder,
+            ) -> ::core::result::Result<(), A::Error>
+            where
+                A: $crate::serde::de::SeqAccess<'de>,
+            {
+                for &id in &self.column_ids {
+                    let mut index = 0;
+                    $(
+                        if id == index {
+                            $crate::serialize::column::deserialize_column::<$ty, _>(
+                                entity_count,
+                                &mut seq,
+                                batch,
+                            )?;
+                        }
+                        index += 1;
+                    )*
+                    let _ = index;
+                }
+                ::core::result::Result::Ok(())
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "column-serialize"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __serde_registry_column {
+    ($($tt:tt)*) => {};
+}
+
+/// Deserializes a component name into its index in a list of known names
+///
+/// Used by [`serde_registry!`](crate::serde_registry), and useful for hand-written
+/// deserialization contexts that identify components by string.
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentName {
+    names: &'static [&'static str],
+}
+
+impl ComponentName {
+    /// Construct a deserializer that accepts any of `names`
+    pub fn new(names: &'static [&'static str]) -> Self {
+        Self { names }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for ComponentName {
+    type Value = usize;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<usize, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl Visitor<'_> for ComponentName {
+    type Value = usize;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a component name")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<usize, E>
+    where
+        E: de::Error,
+    {
+        self.names
+            .iter()
+            .position(|&name| name == v)
+            .ok_or_else(|| de::Error::unknown_variant(v, self.names))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::alloc::vec::Vec;
+    use crate::*;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+    struct Position([f32; 3]);
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+    struct Velocity([f32; 3]);
+    struct Unregistered;
+
+    serde_registry! {
+        struct Registry {
+            "position" => Position,
+            "velocity" => Velocity,
+        }
+    }
+
+    fn check(world: &World, e0: Entity, e1: Entity, e2: Entity) {
+        assert_eq!(world.len(), 3);
+        assert_eq!(*world.get::<&Position>(e0).unwrap(), Position([1.0; 3]));
+        assert_eq!(*world.get::<&Velocity>(e0).unwrap(), Velocity([2.0; 3]));
+        assert_eq!(*world.get::<&Position>(e1).unwrap(), Position([3.0; 3]));
+        assert!(world.get::<&Velocity>(e1).is_err());
+        assert!(world.entity(e2).unwrap().is_empty());
+    }
+
+    fn spawn() -> (World, Entity, Entity, Entity) {
+        let mut world = World::new();
+        let e0 = world.spawn((Position([1.0; 3]), Velocity([2.0; 3])));
+        let e1 = world.spawn((Position([3.0; 3]), Unregistered));
+        let e2 = world.spawn((Unregistered,));
+        (world, e0, e1, e2)
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Registry::NAMES, &["position", "velocity"]);
+        assert_eq!(
+            Registry::name_of(core::any::TypeId::of::<Position>()),
+            Some("position")
+        );
+        assert_eq!(
+            Registry::name_of(core::any::TypeId::of::<Unregistered>()),
+            None
+        );
+    }
+
+    #[cfg(feature = "row-serialize")]
+    #[test]
+    fn row_roundtrip() {
+        use crate::serialize::row;
+
+        let (world, e0, e1, e2) = spawn();
+        let mut data = Vec::new();
+        row::serialize(
+            &world,
+            &mut Registry::new(),
+            &mut bincode::Serializer::new(&mut data, bincode::options()),
+        )
+        .unwrap();
+        let world = row::deserialize(
+            &mut Registry::new(),
+            &mut bincode::Deserializer::from_slice(&data, bincode::options()),
+        )
+        .unwrap();
+        check(&world, e0, e1, e2);
+    }
+
+    #[cfg(feature = "column-serialize")]
+    #[test]
+    fn column_roundtrip() {
+        use crate::serialize::column;
+
+        let (world, e0, e1, e2) = spawn();
+        let mut data = Vec::new();
+        column::serialize(
+            &world,
+            &mut Registry::new(),
+            &mut bincode::Serializer::new(&mut data, bincode::options()),
+        )
+        .unwrap();
+        let world = column::deserialize(
+            &mut Registry::new(),
+            &mut bincode::Deserializer::from_slice(&data, bincode::options()),
+        )
+        .unwrap();
+        check(&world, e0, e1, e2);
+    }
+
+    #[test]
+    fn unknown_name() {
+        use serde::de::DeserializeSeed;
+        use serde::de::IntoDeserializer;
+
+        let de: serde::de::value::StrDeserializer<'_, serde::de::value::Error> =
+            "acceleration".into_deserializer();
+        assert!(serialize::registry::ComponentName::new(Registry::NAMES)
+            .deserialize(de)
+            .is_err());
+    }
+}
@@ -0,0 +1,680 @@
+//! Zero-copy snapshots of a [`World`] using [`rkyv`]
+//!
+//! Snapshots store each archetype's component columns. Columns of types registered with
+//! [`ComponentRegistry::register_pod`] are stored as raw bytes, aligned within the archive, which
+//! after the archive has been validated are copied straight from it into archetype storage with a
+//! single copy per column, without decoding individual components. If the world already has an
+//! archetype with the same components, the loaded entities are then appended to it, copying each
+//! column once more. Columns of types registered
+//! with [`ComponentRegistry::register_archive`] are instead archived one component at a time, each
+//! aligned so that it can be validated and deserialized in place when loaded. Components of
+//! unregistered types are omitted.
+//!
+//! Entity handles and the [`World::tick`] are preserved. Snapshots use the native byte order and
+//! layout of the machine that produced them, so they should only be loaded by the same build of an
+//! application on the same platform.
+//!
+//! Snapshots can be loaded all at once with [`load`], or incrementally with [`LazySnapshot`].
+//!
+//! ```
+//! # use hecs::{*, serialize::rkyv};
+//! #[derive(Copy, Clone, Debug, PartialEq)]
+//! #[repr(C)]
+//! struct Position([f32; 3]);
+//!
+//! #[derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize, Debug, PartialEq)]
+//! #[archive(check_bytes)]
+//! struct Name(String);
+//!
+//! let mut registry = ComponentRegistry::new();
+//! unsafe {
+//!     registry.register_pod::<Position>("Position");
+//! }
+//! registry.register_archive::<Name>("Name");
+//!
+//! let mut world = World::new();
+//! let e = world.spawn((Position([1.0, 2.0, 3.0]), Name("player".into())));
+//! let bytes = rkyv::to_bytes(&world, &registry);
+//!
+//! let mut loaded = World::new();
+//! rkyv::load(&mut loaded, &registry, &bytes).unwrap();
+//! assert_eq!(*loaded.get::<&Position>(e).unwrap(), Position([1.0, 2.0, 3.0]));
+//! assert_eq!(loaded.get::<&Name>(e).unwrap().0, "player");
+//! ```
+
+use crate::alloc::{string::String, vec, vec::Vec};
+use core::{fmt, mem};
+
+use ::rkyv::{
+    de::deserializers::SharedDeserializeMap, ser::serializers::AllocSerializer,
+    validation::validators::DefaultValidator, AlignedVec, Archive, CheckBytes, Deserialize,
+    Serialize,
+};
+use hashbrown::HashSet;
+
+use crate::archetype::RawColumn;
+use crate::{
+    Archetype, ColumnBatch, ColumnBatchType, Component, ComponentRegistry, Entity, EntityBits,
+    Fetch, Query, QueryMut, TypeInfo, World,
+};
+
+/// Serialize the components of `world` registered in `registry` into a snapshot
+///
+/// # Panics
+///
+/// Panics if the snapshot would exceed 4GiB, or if any component is uniquely borrowed.
+pub fn to_bytes(world: &World, registry: &ComponentRegistry) -> AlignedVec {
+    let mut archetypes = Vec::new();
+    for archetype in world.archetypes().filter(|x| !x.is_empty()) {
+        let entities = archetype
+            .ids()
+            .iter()
+            .map(|&id| unsafe { world.find_entity_from_id(id) }.to_bits().get())
+            .collect();
+        let mut components = Vec::new();
+        let mut columns = Vec::new();
+        for ty in archetype.component_type_info() {
+            let Some(reg) = registry.get(ty.id()) else {
+                continue;
+            };
+            let column = RawColumn::new(archetype, ty.id()).unwrap();
+            let data = if reg.is_pod() {
+                let bytes = unsafe { column.bytes() };
+                let mut aligned = AlignedVec::with_capacity(bytes.len());
+                aligned.extend_from_slice(bytes);
+                ColumnData::Pod(aligned)
+            } else if let Some(archive) = reg.archive() {
+                let size = ty.layout().size();
+                ColumnData::Archived(
+                    (0..archetype.len() as usize)
+                        .map(|i| unsafe { (archive.serialize)(column.base().add(i * size)) })
+                        .collect(),
+                )
+            } else {
+                continue;
+            };
+            components.push(reg.name().into());
+            columns.push(data);
+        }
+        let record = ArchetypeRecord { entities, columns };
+        archetypes.push(ArchetypeEntry {
+            len: archetype.len(),
+            components,
+            record: ::rkyv::to_bytes::<_, 4096>(&record).expect("snapshot too large"),
+        });
+    }
+    let snapshot = Snapshot {
+        tick: world.tick(),
+        archetypes,
+    };
+    ::rkyv::to_bytes::<_, 4096>(&snapshot).expect("snapshot too large")
+}
+
+/// Spawn the entities stored in a snapshot produced by [`to_bytes`] into `world`
+///
+/// Entities are spawned with their original handles, replacing any existing entities with the
+/// same IDs, and the world's tick is restored. The snapshot is validated before `world` is
+/// modified, so `world` is left unchanged on error.
+pub fn load(
+    world: &mut World,
+    registry: &ComponentRegistry,
+    bytes: &[u8],
+) -> Result<(), LoadError> {
+    let aligned;
+    let bytes = if bytes.as_ptr() as usize % AlignedVec::ALIGNMENT == 0 {
+        bytes
+    } else {
+        let mut copy = AlignedVec::with_capacity(bytes.len());
+        copy.extend_from_slice(bytes);
+        aligned = copy;
+        aligned.as_slice()
+    };
+    let snapshot =
+        ::rkyv::check_archived_root::<Snapshot>(bytes).map_err(|_| LoadError::Invalid)?;
+
+    world.flush();
+    let bits = world.entity_bits();
+    let mut ids = HashSet::new();
+    let mut batches = Vec::with_capacity(snapshot.archetypes.len());
+    for entry in snapshot.archetypes.iter() {
+        let (entities, batch) = build_archetype(bits, registry, entry, &ids)?;
+        ids.extend(entities.iter().map(|x| x.id()));
+        batches.push((entities, batch));
+    }
+    for (entities, batch) in batches {
+        world.spawn_column_batch_at(&entities, batch);
+    }
+    world.set_tick(snapshot.tick);
+    Ok(())
+}
+
+/// A snapshot whose archetypes are validated and spawned into a [`World`] on demand
+///
+/// Intended for snapshots too large to load at once, e.g. streamed open worlds: the snapshot can
+/// be read from a memory-mapped file, so that only the pages holding archetypes which are actually
+/// loaded are read from disk. Opening a snapshot only validates its index of archetypes and their
+/// component names. Each archetype's entities and components are validated and spawned the first
+/// time they're needed: by [`query_mut`](Self::query_mut), which loads every archetype a query
+/// could visit before running it, or explicitly by [`prefetch`](Self::prefetch),
+/// [`prefetch_query`](Self::prefetch_query), or [`prefetch_all`](Self::prefetch_all). Each
+/// archetype is loaded at most once.
+///
+/// ```
+/// # use hecs::{*, serialize::rkyv::{self, LazySnapshot}};
+/// let mut registry = ComponentRegistry::new();
+/// unsafe {
+///     registry.register_pod::<i32>("i32").register_pod::<bool>("bool");
+/// }
+///
+/// let mut world = World::new();
+/// let a = world.spawn((1,));
+/// let b = world.spawn((true,));
+/// let bytes = rkyv::to_bytes(&world, &registry);
+///
+/// let mut loaded = World::new();
+/// let mut snapshot = LazySnapshot::open(&bytes).unwrap();
+/// let total = snapshot
+///     .query_mut::<&i32>(&mut loaded, &registry)
+///     .unwrap()
+///     .into_iter()
+///     .map(|(_, x)| *x)
+///     .sum::<i32>();
+/// assert_eq!(total, 1);
+/// assert!(loaded.contains(a));
+/// assert!(!loaded.contains(b));
+/// ```
+pub struct LazySnapshot<'a> {
+    snapshot: &'a ArchivedSnapshot,
+    /// Whether each archetype has been spawned
+    loaded: Vec<bool>,
+    /// IDs of the entities spawned so far, to reject snapshots that repeat them
+    ids: HashSet<u32>,
+}
+
+impl<'a> LazySnapshot<'a> {
+    /// Validate the index of a snapshot produced by [`to_bytes`] without loading any archetypes
+    ///
+    /// `bytes` must be aligned to 16 bytes, which memory-mapped files always are. Only the names
+    /// and sizes of the snapshot's archetypes are read; their contents are validated when loaded.
+    pub fn open(bytes: &'a [u8]) -> Result<Self, LoadError> {
+        if bytes.as_ptr() as usize % AlignedVec::ALIGNMENT != 0 {
+            return Err(LoadError::Invalid);
+        }
+        let snapshot =
+            ::rkyv::check_archived_root::<Snapshot>(bytes).map_err(|_| LoadError::Invalid)?;
+        Ok(Self {
+            snapshot,
+            loaded: vec![false; snapshot.archetypes.len()],
+            ids: HashSet::new(),
+        })
+    }
+
+    /// The [`World::tick`] at which the snapshot was taken
+    pub fn tick(&self) -> u32 {
+        self.snapshot.tick
+    }
+
+    /// Number of archetypes in the snapshot
+    pub fn archetype_count(&self) -> usize {
+        self.loaded.len()
+    }
+
+    /// Number of entities in the archetype at `index`
+    pub fn archetype_len(&self, index: usize) -> u32 {
+        self.snapshot.archetypes[index].len
+    }
+
+    /// Registered names of the components stored in the archetype at `index`
+    pub fn archetype_components(&self, index: usize) -> impl Iterator<Item = &'a str> {
+        self.snapshot.archetypes[index]
+            .components
+            .iter()
+            .map(|x| x.as_str())
+    }
+
+    /// Whether the archetype at `index` has been loaded
+    pub fn is_loaded(&self, index: usize) -> bool {
+        self.loaded[index]
+    }
+
+    /// Validate and spawn the entities of the archetype at `index` into `world`, unless already
+    /// loaded
+    ///
+    /// Entities are spawned with their original handles, replacing any existing entities with the
+    /// same IDs, except that of entities loaded from other archetypes of the snapshot. `world` is
+    /// left unchanged on error.
+    pub fn prefetch(
+        &mut self,
+        world: &mut World,
+        registry: &ComponentRegistry,
+        index: usize,
+    ) -> Result<(), LoadError> {
+        if self.loaded[index] {
+            return Ok(());
+        }
+        world.flush();
+        let (entities, batch) = build_archetype(
+            world.entity_bits(),
+            registry,
+            &self.snapshot.archetypes[index],
+            &self.ids,
+        )?;
+        self.ids.extend(entities.iter().map(|x| x.id()));
+        world.spawn_column_batch_at(&entities, batch);
+        self.loaded[index] = true;
+        Ok(())
+    }
+
+    /// Load every archetype containing entities that could be visited by the query `Q`
+    pub fn prefetch_query<Q: Query>(
+        &mut self,
+        world: &mut World,
+        registry: &ComponentRegistry,
+    ) -> Result<(), LoadError> {
+        for index in 0..self.loaded.len() {
+            if self.loaded[index] {
+                continue;
+            }
+            let mut types = self
+                .archetype_components(index)
+                .map(|name| {
+                    registry
+                        .get_by_name(name)
+                        .map(|reg| reg.type_info())
+                        .ok_or_else(|| LoadError::UnknownComponent(name.into()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            types.sort_unstable();
+            types.dedup();
+            if Q::Fetch::access(&Archetype::new(types)).is_some() {
+                self.prefetch(world, registry, index)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load every archetype containing entities that could be visited by the query `Q`, then query
+    /// `world`
+    ///
+    /// See [`World::query_mut`].
+    pub fn query_mut<'w, Q: Query>(
+        &mut self,
+        world: &'w mut World,
+        registry: &ComponentRegistry,
+    ) -> Result<QueryMut<'w, Q>, LoadError> {
+        self.prefetch_query::<Q>(world, registry)?;
+        Ok(world.query_mut::<Q>())
+    }
+
+    /// Load every archetype not yet loaded
+    pub fn prefetch_all(
+        &mut self,
+        world: &mut World,
+        registry: &ComponentRegistry,
+    ) -> Result<(), LoadError> {
+        for index in 0..self.loaded.len() {
+            self.prefetch(world, registry, index)?;
+        }
+        Ok(())
+    }
+}
+
+/// Validate and decode the entities and components of an archetype, failing if any entity ID
+/// repeats or is among `ids`
+fn build_archetype(
+    bits: EntityBits,
+    registry: &ComponentRegistry,
+    entry: &ArchivedArchetypeEntry,
+    ids: &HashSet<u32>,
+) -> Result<(Vec<Entity>, ColumnBatch), LoadError> {
+    let record = ::rkyv::check_archived_root::<ArchetypeRecord>(entry.record.as_slice())
+        .map_err(|_| LoadError::Invalid)?;
+    if record.entities.len() != entry.len as usize || record.columns.len() != entry.components.len()
+    {
+        return Err(LoadError::Invalid);
+    }
+    let entities = record
+        .entities
+        .iter()
+        .map(|&x| Entity::from_bits(x).filter(|&entity| bits.contains(entity)))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(LoadError::Invalid)?;
+    let mut unique = HashSet::with_capacity(entities.len());
+    if entities
+        .iter()
+        .any(|x| ids.contains(&x.id()) || !unique.insert(x.id()))
+    {
+        return Err(LoadError::Invalid);
+    }
+    let count = entities.len() as u32;
+
+    let mut ty = ColumnBatchType::new();
+    let mut columns = Vec::with_capacity(record.columns.len());
+    for (name, data) in entry.components.iter().zip(record.columns.iter()) {
+        let reg = registry
+            .get_by_name(name.as_str())
+            .ok_or_else(|| LoadError::UnknownComponent(name.as_str().into()))?;
+        ty.add_dynamic(reg.type_info());
+        columns.push((reg, data));
+    }
+
+    let mut batch = ty.into_batch(count);
+    for (reg, data) in columns {
+        let info = reg.type_info();
+        let malformed = || LoadError::Malformed(reg.name().into());
+        match data {
+            ArchivedColumnData::Pod(data) => {
+                if !reg.is_pod() || data.len() != count as usize * info.layout().size() {
+                    return Err(malformed());
+                }
+                if !unsafe { batch.extend_dynamic(info, data.as_ptr(), count) } {
+                    return Err(malformed());
+                }
+            }
+            ArchivedColumnData::Archived(elements) => {
+                let archive = reg.archive().ok_or_else(malformed)?;
+                if elements.len() != count as usize {
+                    return Err(malformed());
+                }
+                for element in elements.iter() {
+                    let mut pushed = false;
+                    (archive.deserialize)(element.as_slice(), &mut |ptr, ty| unsafe {
+                        pushed = batch.push_dynamic(ty, ptr);
+                        if !pushed {
+                            ty.drop(ptr);
+                        }
+                    });
+                    if !pushed {
+                        return Err(malformed());
+                    }
+                }
+            }
+        }
+    }
+    let batch = batch.build().map_err(|_| LoadError::Invalid)?;
+    Ok((entities, batch))
+}
+
+/// Error produced by [`load`] and [`LazySnapshot`]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum LoadError {
+    /// The snapshot is corrupt or was not produced by [`to_bytes`]
+    Invalid,
+    /// The snapshot contains a component name that isn't registered
+    UnknownComponent(String),
+    /// The data for the named component doesn't match its registration
+    Malformed(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Invalid => f.write_str("invalid snapshot"),
+            LoadError::UnknownComponent(name) => write!(f, "unknown component {name:?}"),
+            LoadError::Malformed(name) => write!(f, "malformed data for component {name:?}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Type-erased [`rkyv`] implementations for a component type
+#[derive(Copy, Clone)]
+pub(crate) struct ArchiveFns {
+    /// Archive a single component
+    serialize: unsafe fn(*const u8) -> AlignedVec,
+    /// Validate and deserialize a single archived component, which must be aligned to 16 bytes,
+    /// passing it to the callback
+    deserialize: fn(&[u8], &mut dyn FnMut(*mut u8, TypeInfo)),
+}
+
+impl ArchiveFns {
+    pub(crate) fn new<T>() -> Self
+    where
+        T: Component + Archive + Serialize<AllocSerializer<256>>,
+        T::Archived:
+            for<'a> CheckBytes<DefaultValidator<'a>> + Deserialize<T, SharedDeserializeMap>,
+    {
+        Self {
+            serialize: |x| {
+                ::rkyv::to_bytes::<T, 256>(unsafe { &*x.cast::<T>() })
+                    .expect("failed to archive component")
+            },
+            deserialize: |bytes, f| {
+                let Ok(archived) = ::rkyv::check_archived_root::<T>(bytes) else {
+                    return;
+                };
+                let Ok(mut x) = archived.deserialize(&mut SharedDeserializeMap::default()) else {
+                    return;
+                };
+                f((&mut x as *mut T).cast(), TypeInfo::of::<T>());
+                mem::forget(x);
+            },
+        }
+    }
+}
+
+#[derive(Archive, Serialize)]
+#[archive(check_bytes)]
+struct Snapshot {
+    tick: u32,
+    archetypes: Vec<ArchetypeEntry>,
+}
+
+/// An archetype's description, which is validated along with the snapshot, and contents, which
+/// are archived separately so they can be validated only when loaded
+#[derive(Archive, Serialize)]
+#[archive(check_bytes)]
+struct ArchetypeEntry {
+    /// Number of entities
+    len: u32,
+    /// Names under which the component types are registered
+    components: Vec<String>,
+    /// Archived [`ArchetypeRecord`], aligned to 16 bytes
+    record: AlignedVec,
+}
+
+#[derive(Archive, Serialize)]
+#[archive(check_bytes)]
+struct ArchetypeRecord {
+    /// [`Entity::to_bits`] of each entity
+    entities: Vec<u64>,
+    /// Contents of each column, in the order of [`ArchetypeEntry::components`]
+    columns: Vec<ColumnData>,
+}
+
+#[derive(Archive, Serialize)]
+#[archive(check_bytes)]
+enum ColumnData {
+    /// Raw bytes of every component, aligned to 16 bytes
+    Pod(AlignedVec),
+    /// Separately archived components, each aligned to 16 bytes
+    Archived(Vec<AlignedVec>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Without;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    #[repr(C, align(16))]
+    struct Position([f32; 4]);
+
+    #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+    #[archive(check_bytes)]
+    struct Name(String);
+
+    fn registry() -> ComponentRegistry {
+        let mut registry = ComponentRegistry::new();
+        unsafe {
+            registry.register_pod::<Position>("Position");
+            registry.register_pod::<u8>("u8");
+        }
+        registry.register_archive::<Name>("Name");
+        registry
+    }
+
+    #[test]
+    fn roundtrip() {
+        let registry = registry();
+        let mut world = World::new();
+        let a = world.spawn((Position([1.0; 4]), Name("a".into()), 7u8));
+        let b = world.spawn((Position([2.0; 4]), "unregistered"));
+        let c = world.spawn((Name("c".into()),));
+        world.despawn(b).unwrap();
+        let b = world.spawn((Position([3.0; 4]),));
+        let d = world.spawn(("unregistered",));
+        world.advance_tick();
+        let bytes = to_bytes(&world, &registry);
+
+        // Columns and archived components are aligned for copying and validation in place
+        let snapshot = ::rkyv::check_archived_root::<Snapshot>(&bytes).unwrap();
+        for entry in snapshot.archetypes.iter() {
+            let record = ::rkyv::check_archived_root::<ArchetypeRecord>(&entry.record).unwrap();
+            for column in record.columns.iter() {
+                match *column {
+                    ArchivedColumnData::Pod(ref data) => {
+                        assert_eq!(data.as_ptr() as usize % AlignedVec::ALIGNMENT, 0);
+                    }
+                    ArchivedColumnData::Archived(ref elements) => {
+                        for element in elements.iter() {
+                            assert_eq!(element.as_ptr() as usize % AlignedVec::ALIGNMENT, 0);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut loaded = World::new();
+        load(&mut loaded, &registry, &bytes).unwrap();
+        assert_eq!(loaded.tick(), 1);
+        assert_eq!(loaded.len(), 4);
+        assert_eq!(*loaded.get::<&Position>(a).unwrap(), Position([1.0; 4]));
+        assert_eq!(*loaded.get::<&Name>(a).unwrap(), Name("a".into()));
+        assert_eq!(*loaded.get::<&u8>(a).unwrap(), 7);
+        assert_eq!(*loaded.get::<&Position>(b).unwrap(), Position([3.0; 4]));
+        assert_eq!(*loaded.get::<&Name>(c).unwrap(), Name("c".into()));
+        assert!(loaded.contains(d));
+        assert_eq!(loaded.entity(d).unwrap().component_types().count(), 0);
+
+        // Misaligned input is accepted
+        let mut shifted = Vec::with_capacity(bytes.len() + 1);
+        shifted.push(0);
+        shifted.extend_from_slice(&bytes);
+        let mut loaded = World::new();
+        load(&mut loaded, &registry, &shifted[1..]).unwrap();
+        assert_eq!(*loaded.get::<&Position>(a).unwrap(), Position([1.0; 4]));
+    }
+
+    #[test]
+    fn errors() {
+        let registry = registry();
+        let mut world = World::new();
+        world.spawn((Position([1.0; 4]), Name("a".into())));
+        let bytes = to_bytes(&world, &registry);
+
+        let mut loaded = World::new();
+        assert_eq!(
+            load(&mut loaded, &registry, &bytes[..bytes.len() - 4]),
+            Err(LoadError::Invalid)
+        );
+        assert_eq!(
+            load(&mut loaded, &ComponentRegistry::new(), &bytes),
+            Err(LoadError::UnknownComponent("Position".into()))
+        );
+        let mut wrong = ComponentRegistry::new();
+        wrong
+            .register::<Position>("Position")
+            .register::<Name>("Name");
+        assert_eq!(
+            load(&mut loaded, &wrong, &bytes),
+            Err(LoadError::Malformed("Position".into()))
+        );
+        assert!(loaded.is_empty());
+    }
+
+    #[test]
+    fn lazy() {
+        let registry = registry();
+        let mut world = World::new();
+        let a = world.spawn((Position([1.0; 4]), 1u8));
+        let b = world.spawn((Position([2.0; 4]),));
+        let c = world.spawn((Name("c".into()),));
+        world.set_tick(5);
+        let bytes = to_bytes(&world, &registry);
+
+        let mut shifted = Vec::with_capacity(bytes.len() + 1);
+        shifted.push(0);
+        shifted.extend_from_slice(&bytes);
+        assert!(LazySnapshot::open(&shifted[1..]).is_err());
+
+        let mut snapshot = LazySnapshot::open(&bytes).unwrap();
+        assert_eq!(snapshot.tick(), 5);
+        assert_eq!(snapshot.archetype_count(), 3);
+        let mut loaded = World::new();
+        snapshot
+            .prefetch_query::<Without<&Position, &u8>>(&mut loaded, &registry)
+            .unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(*loaded.get::<&Position>(b).unwrap(), Position([2.0; 4]));
+
+        let count = snapshot
+            .query_mut::<&Position>(&mut loaded, &registry)
+            .unwrap()
+            .into_iter()
+            .count();
+        assert_eq!(count, 2);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(*loaded.get::<&u8>(a).unwrap(), 1);
+        assert!(!loaded.contains(c));
+
+        // Already loaded archetypes are skipped, so modifications survive
+        *loaded.get::<&mut u8>(a).unwrap() = 2;
+        snapshot.prefetch_all(&mut loaded, &registry).unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(*loaded.get::<&u8>(a).unwrap(), 2);
+        assert_eq!(*loaded.get::<&Name>(c).unwrap(), Name("c".into()));
+        assert!((0..3).all(|i| snapshot.is_loaded(i)));
+    }
+
+    #[test]
+    fn lazy_validation() {
+        let registry = registry();
+        let mut world = World::new();
+        let a = world.spawn((Position([1.0; 4]),));
+        world.spawn((Name("b".into()),));
+        let bytes = to_bytes(&world, &registry);
+
+        // Corrupt the contents of the archetype holding `Name`
+        let mut corrupt = AlignedVec::new();
+        corrupt.extend_from_slice(&bytes);
+        let snapshot = ::rkyv::check_archived_root::<Snapshot>(&bytes).unwrap();
+        let index = snapshot
+            .archetypes
+            .iter()
+            .position(|x| x.components.iter().any(|x| x.as_str() == "Name"))
+            .unwrap();
+        let record = &snapshot.archetypes[index].record;
+        let start = record.as_ptr() as usize - bytes.as_ptr() as usize;
+        corrupt[start..start + record.len()].fill(0xff);
+
+        // Only detected once that archetype is loaded
+        let mut snapshot = LazySnapshot::open(&corrupt).unwrap();
+        let mut loaded = World::new();
+        assert_eq!(
+            snapshot.prefetch(&mut loaded, &registry, index),
+            Err(LoadError::Invalid)
+        );
+        assert!(!snapshot.is_loaded(index));
+        assert!(loaded.is_empty());
+        snapshot
+            .prefetch_query::<&Position>(&mut loaded, &registry)
+            .unwrap();
+        assert_eq!(*loaded.get::<&Position>(a).unwrap(), Position([1.0; 4]));
+        assert_eq!(
+            load(&mut World::new(), &registry, &corrupt),
+            Err(LoadError::Invalid)
+        );
+    }
+}
@@ -8,16 +8,24 @@
 //!
 //! In terms of the serde data model, we treat a [`World`] as a map of entity IDs to user-controlled
 //! maps of component IDs to data.
+//!
+//! Contexts can be written by hand, declared with [`serde_registry!`](crate::serde_registry), or
+//! driven at runtime by a [`ComponentRegistry`] with [`RegistryContext`].
 
-use core::{cell::RefCell, fmt};
+use crate::alloc::{string::String, vec::Vec};
+use core::{any::TypeId, cell::RefCell, fmt, mem};
 
 use serde::{
-    de::{DeserializeSeed, MapAccess, Visitor},
+    de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor},
     ser::SerializeMap,
-    Deserializer, Serialize, Serializer,
+    Deserialize, Deserializer, Serialize, Serializer,
 };
 
-use crate::{Component, EntityBuilder, EntityRef, Query, World};
+use crate::archetype::RawColumn;
+use crate::{
+    Component, ComponentRegistration, ComponentRegistry, EntityBuilder, EntityRef, Query, TypeInfo,
+    World,
+};
 
 /// Implements serialization of individual entities
 ///
@@ -130,7 +138,9 @@ where
     seq.end()
 }
 
-struct SerializeComponents<'a, C>(RefCell<(&'a mut C, Option<EntityRef<'a>>)>);
+pub(crate) struct SerializeComponents<'a, C>(
+    pub(crate) RefCell<(&'a mut C, Option<EntityRef<'a>>)>,
+);
 
 impl<C: SerializeContext> Serialize for SerializeComponents<'_, C> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -153,6 +163,51 @@ where
     deserializer.deserialize_map(WorldVisitor(context))
 }
 
+/// Deserialize a single entity's components into an [`EntityBuilder`] with a
+/// [`DeserializeContext`]
+///
+/// Accepts the same map of component IDs to data that [`deserialize`] expects for each entity,
+/// e.g. a JSON object mapping component names to values when `context` was declared with
+/// [`serde_registry!`](crate::serde_registry). The result can be spawned with
+/// [`World::spawn`] by calling [`EntityBuilder::build`], or extended with further components first.
+///
+/// # Example
+/// ```
+/// # use serde::{Serialize, Deserialize};
+/// # #[derive(Serialize, Deserialize)]
+/// # struct Position([f32; 3]);
+/// # #[derive(Serialize, Deserialize)]
+/// # struct Velocity([f32; 3]);
+/// use hecs::{*, serialize::row::*};
+///
+/// hecs::serde_registry! {
+///     struct Prefab {
+///         "position" => Position,
+///         "velocity" => Velocity,
+///     }
+/// }
+///
+/// fn spawn_prefab<'de, D>(world: &mut World, data: D) -> Result<Entity, D::Error>
+/// where
+///     D: serde::Deserializer<'de>,
+/// {
+///     let mut builder = deserialize_bundle(&mut Prefab::new(), data)?;
+///     Ok(world.spawn(builder.build()))
+/// }
+/// ```
+pub fn deserialize_bundle<'de, C, D>(
+    context: &mut C,
+    deserializer: D,
+) -> Result<EntityBuilder, D::Error>
+where
+    C: DeserializeContext,
+    D: Deserializer<'de>,
+{
+    let mut builder = EntityBuilder::new();
+    DeserializeComponents(context, &mut builder).deserialize(deserializer)?;
+    Ok(builder)
+}
+
 /// Implements deserialization of entities from a serde [`MapAccess`] into an [`EntityBuilder`]
 ///
 /// Data external to the [`World`] can be populated during deserialization by storing mutable
@@ -233,7 +288,10 @@ where
     }
 }
 
-struct DeserializeComponents<'a, C>(&'a mut C, &'a mut EntityBuilder);
+pub(crate) struct DeserializeComponents<'a, C>(
+    pub(crate) &'a mut C,
+    pub(crate) &'a mut EntityBuilder,
+);
 
 impl<'de, C> DeserializeSeed<'de> for DeserializeComponents<'_, C>
 where
@@ -269,6 +327,292 @@ where
     }
 }
 
+/// Encodes individual components as bytes for [`RegistryContext`]
+///
+/// Chosen per component type when it's registered with
+/// [`ComponentRegistry::register_serde`]. Because each component is encoded independently, a
+/// [`RegistryContext`] can skip or preserve components it doesn't recognize without understanding
+/// their encoding.
+///
+/// # Example
+/// ```
+/// use hecs::serialize::row::ComponentCodec;
+///
+/// struct Bincode;
+///
+/// impl ComponentCodec for Bincode {
+///     fn encode<T: serde::Serialize>(component: &T) -> Vec<u8> {
+///         bincode::serialize(component).unwrap()
+///     }
+///
+///     fn decode<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+///         bincode::deserialize(bytes).ok()
+///     }
+/// }
+/// ```
+pub trait ComponentCodec {
+    /// Encode `component` as bytes
+    fn encode<T: Serialize>(component: &T) -> Vec<u8>;
+
+    /// Decode a component encoded by [`encode`](Self::encode), or `None` if `bytes` are invalid
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Option<T>;
+}
+
+/// Type-erased [`ComponentCodec`] functions for a component type
+#[derive(Copy, Clone)]
+pub(crate) struct SerdeFns {
+    /// Encode a single component
+    encode: unsafe fn(*const u8) -> Vec<u8>,
+    decode: DecodeFn,
+}
+
+/// Decode a single component, passing it to the callback, or return `false` if invalid
+type DecodeFn = fn(&[u8], &mut dyn FnMut(*mut u8, TypeInfo)) -> bool;
+
+impl SerdeFns {
+    pub(crate) fn new<T, C>() -> Self
+    where
+        T: Component + Serialize + DeserializeOwned,
+        C: ComponentCodec,
+    {
+        Self {
+            encode: |x| C::encode(unsafe { &*x.cast::<T>() }),
+            decode: |bytes, f| {
+                let Some(mut x) = C::decode::<T>(bytes) else {
+                    return false;
+                };
+                f((&mut x as *mut T).cast(), TypeInfo::of::<T>());
+                mem::forget(x);
+                true
+            },
+        }
+    }
+}
+
+/// Serializes and deserializes components whose types were registered with
+/// [`ComponentRegistry::register_serde`]
+///
+/// Each entity is written as a map of registered component names to encoded components, so save
+/// files remain loadable after component types are added or removed. When deserializing, entries
+/// naming types that aren't registered, e.g. components of a mod that isn't loaded, are skipped, or
+/// preserved in an [`OpaqueComponents`] component if [`preserve_unknown`](Self::preserve_unknown)
+/// is enabled. Preserved components are written back out when the entity is serialized again.
+/// Components of unregistered types are otherwise skipped.
+///
+/// # Example
+/// ```
+/// # use serde::{Serialize, Deserialize};
+/// # struct Bincode;
+/// # impl hecs::serialize::row::ComponentCodec for Bincode {
+/// #     fn encode<T: serde::Serialize>(x: &T) -> Vec<u8> { bincode::serialize(x).unwrap() }
+/// #     fn decode<T: serde::de::DeserializeOwned>(x: &[u8]) -> Option<T> { bincode::deserialize(x).ok() }
+/// # }
+/// use hecs::{*, serialize::row::{self, *}};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Position([f32; 3]);
+///
+/// let mut registry = ComponentRegistry::new();
+/// registry.register_serde::<Position, Bincode>("position");
+///
+/// let mut world = World::new();
+/// let e = world.spawn((Position([1.0; 3]), true));
+///
+/// let mut data = Vec::new();
+/// let mut serializer = bincode::Serializer::new(&mut data, bincode::options());
+/// row::serialize(&world, &mut RegistryContext::new(&registry), &mut serializer).unwrap();
+/// let mut deserializer = bincode::Deserializer::from_slice(&data, bincode::options());
+/// let loaded = row::deserialize(&mut RegistryContext::new(&registry), &mut deserializer).unwrap();
+/// assert_eq!(loaded.get::<&Position>(e).unwrap().0, [1.0; 3]);
+/// assert!(!loaded.satisfies::<&bool>(e).unwrap());
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct RegistryContext<'a> {
+    registry: &'a ComponentRegistry,
+    preserve_unknown: bool,
+}
+
+impl<'a> RegistryContext<'a> {
+    /// Construct a context that serializes the components registered in `registry`
+    pub fn new(registry: &'a ComponentRegistry) -> Self {
+        Self {
+            registry,
+            preserve_unknown: false,
+        }
+    }
+
+    /// Whether to preserve unrecognized components in [`OpaqueComponents`] when deserializing,
+    /// rather than skipping them
+    pub fn preserve_unknown(&mut self, enabled: bool) -> &mut Self {
+        self.preserve_unknown = enabled;
+        self
+    }
+
+    /// The registration of the component type identified by `id`, if it can be serialized
+    fn get(&self, id: TypeId) -> Option<(&'a ComponentRegistration, SerdeFns)> {
+        let reg = self.registry.get(id)?;
+        Some((reg, reg.serde()?))
+    }
+
+    /// Whether a preserved component named `name` is superseded by one of `entity`'s components
+    fn shadowed(&self, entity: &EntityRef<'_>, name: &str) -> bool {
+        self.registry
+            .get_by_name(name)
+            .map_or(false, |reg| entity.raw().0.has_dynamic(reg.id()))
+    }
+}
+
+impl SerializeContext for RegistryContext<'_> {
+    fn serialize_entity<S>(&mut self, entity: EntityRef<'_>, mut map: S) -> Result<S::Ok, S::Error>
+    where
+        S: SerializeMap,
+    {
+        let (archetype, index) = entity.raw();
+        for ty in entity.component_type_info() {
+            let Some((reg, fns)) = self.get(ty.id()) else {
+                continue;
+            };
+            let _borrow = RawColumn::new(archetype, ty.id());
+            let bytes = unsafe {
+                let ptr = archetype
+                    .get_dynamic(ty.id(), ty.layout().size(), index)
+                    .unwrap();
+                (fns.encode)(ptr.as_ptr())
+            };
+            map.serialize_entry(reg.name(), &Bytes(&bytes))?;
+        }
+        if let Some(opaque) = entity.get::<&OpaqueComponents>() {
+            for (name, bytes) in opaque.iter() {
+                if !self.shadowed(&entity, name) {
+                    map.serialize_entry(name, &Bytes(bytes))?;
+                }
+            }
+        }
+        map.end()
+    }
+
+    fn component_count(&self, entity: EntityRef<'_>) -> Option<usize> {
+        let known = entity
+            .component_types()
+            .filter(|&id| self.get(id).is_some())
+            .count();
+        let opaque = entity.get::<&OpaqueComponents>().map_or(0, |x| {
+            x.iter()
+                .filter(|(name, _)| !self.shadowed(&entity, name))
+                .count()
+        });
+        Some(known + opaque)
+    }
+}
+
+impl DeserializeContext for RegistryContext<'_> {
+    fn deserialize_entity<'de, M>(
+        &mut self,
+        mut map: M,
+        entity: &mut EntityBuilder,
+    ) -> Result<(), M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut opaque = OpaqueComponents::default();
+        while let Some(name) = map.next_key::<String>()? {
+            let ByteBuf(bytes) = map.next_value()?;
+            let fns = self.registry.get_by_name(&name).and_then(|x| x.serde());
+            match fns {
+                Some(fns) => {
+                    let valid =
+                        (fns.decode)(&bytes, &mut |ptr, ty| unsafe { entity.add_raw(ptr, ty) });
+                    if !valid {
+                        return Err(de::Error::custom(format_args!("invalid {name} component")));
+                    }
+                }
+                None if self.preserve_unknown => opaque.entries.push((name, bytes)),
+                None => {}
+            }
+        }
+        if !opaque.is_empty() {
+            entity.add(opaque);
+        }
+        Ok(())
+    }
+}
+
+/// Encoded components that a [`RegistryContext`] didn't recognize when deserializing an entity
+///
+/// Added to entities by contexts with [`preserve_unknown`](RegistryContext::preserve_unknown)
+/// enabled, so that data belonging to component types that aren't currently registered survives
+/// being loaded and saved again.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpaqueComponents {
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl OpaqueComponents {
+    /// The encoded component named `name`, if any
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.iter().find(|x| x.0 == name).map(|x| x.1)
+    }
+
+    /// Iterate over the names and encodings of the components, in the order they were read
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&str, &[u8])> + '_ {
+        self.entries.iter().map(|(name, bytes)| (&**name, &**bytes))
+    }
+
+    /// Number of components
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no components
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Serializes as a byte array
+struct Bytes<'a>(&'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+/// Deserializes a byte array, or a sequence of bytes in formats without byte arrays
+struct ByteBuf(Vec<u8>);
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_byte_buf(ByteBufVisitor)
+    }
+}
+
+struct ByteBufVisitor;
+
+impl<'de> Visitor<'de> for ByteBufVisitor {
+    type Value = ByteBuf;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an encoded component")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<ByteBuf, E> {
+        Ok(ByteBuf(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<ByteBuf, E> {
+        Ok(ByteBuf(v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ByteBuf, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(x) = seq.next_element()? {
+            bytes.push(x);
+        }
+        Ok(ByteBuf(bytes))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use core::marker::PhantomData;
@@ -298,7 +642,7 @@ mod tests {
     impl PartialEq for SerWorld {
         fn eq(&self, other: &Self) -> bool {
             fn same_components<T: Component + PartialEq>(x: &EntityRef, y: &EntityRef) -> bool {
-                x.get::<&T>().as_ref().map(|x| &**x) == y.get::<&T>().as_ref().map(|x| &**x)
+                x.get::<&T>().as_deref() == y.get::<&T>().as_deref()
             }
 
             for (x, y) in self.0.iter().zip(other.0.iter()) {
@@ -441,7 +785,7 @@ mod tests {
     impl<Q> PartialEq for SerSatisfyingWorld<Q> {
         fn eq(&self, other: &Self) -> bool {
             fn same_components<T: Component + PartialEq>(x: &EntityRef, y: &EntityRef) -> bool {
-                x.get::<&T>().as_ref().map(|x| &**x) == y.get::<&T>().as_ref().map(|x| &**x)
+                x.get::<&T>().as_deref() == y.get::<&T>().as_deref()
             }
 
             for (x, y) in self.0.iter().zip(other.0.iter()) {
@@ -476,7 +820,7 @@ mod tests {
 
     impl<'a, Q: Query> Serialize for SerSatisfyingWorldInner<'a, Q> {
         fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
-            crate::serialize::row::serialize_satisfying::<Q, Context, S>(&self.0, &mut Context, s)
+            crate::serialize::row::serialize_satisfying::<Q, Context, S>(self.0, &mut Context, s)
         }
     }
 
@@ -579,4 +923,129 @@ mod tests {
             Token::TupleStructEnd,
         ])
     }
+
+    /// Components spawned from a bundle deserialized by [`deserialize_bundle`]
+    #[derive(PartialEq, Debug)]
+    struct SpawnedBundle(Option<Position>, Option<Velocity>);
+
+    impl<'de> Deserialize<'de> for SpawnedBundle {
+        fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+            let mut builder = deserialize_bundle(&mut Context, d)?;
+            let mut world = World::new();
+            let e = world.spawn(builder.build());
+            let e = world.entity(e).unwrap();
+            Ok(SpawnedBundle(
+                e.get::<&Position>().map(|x| *x),
+                e.get::<&Velocity>().map(|x| *x),
+            ))
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn spawn_deserialized_bundle() {
+        use serde_test::{Token, assert_de_tokens};
+
+        assert_de_tokens(&SpawnedBundle(None, Some(Velocity([1.0, 2.0, 3.0]))), &[
+            Token::Map { len: Some(1) },
+
+            Token::UnitVariant { name: "ComponentId", variant: "Velocity" },
+            Token::NewtypeStruct { name: "Velocity" },
+            Token::Tuple { len: 3 },
+            Token::F32(1.0),
+            Token::F32(2.0),
+            Token::F32(3.0),
+            Token::TupleEnd,
+
+            Token::MapEnd,
+        ]);
+        assert_de_tokens(&SpawnedBundle(None, None), &[
+            Token::Map { len: Some(0) },
+            Token::MapEnd,
+        ]);
+    }
+
+    struct Bincode;
+
+    impl ComponentCodec for Bincode {
+        fn encode<T: Serialize>(component: &T) -> Vec<u8> {
+            bincode::serialize(component).unwrap()
+        }
+
+        fn decode<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+            bincode::deserialize(bytes).ok()
+        }
+    }
+
+    fn roundtrip_registry(
+        world: &World,
+        from: RegistryContext<'_>,
+        mut to: RegistryContext<'_>,
+    ) -> World {
+        let mut data = Vec::new();
+        serialize(
+            world,
+            &mut { from },
+            &mut bincode::Serializer::new(&mut data, bincode::options()),
+        )
+        .unwrap();
+        deserialize(
+            &mut to,
+            &mut bincode::Deserializer::from_slice(&data, bincode::options()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn registry_context() {
+        let mut full = ComponentRegistry::new();
+        full.register_serde::<Position, Bincode>("position")
+            .register_serde::<Velocity, Bincode>("velocity");
+        let mut partial = ComponentRegistry::new();
+        partial.register_serde::<Position, Bincode>("position");
+
+        let mut world = World::new();
+        let a = world.spawn((Position([1.0; 3]), Velocity([2.0; 3]), true));
+        let b = world.spawn((Velocity([3.0; 3]),));
+
+        let loaded = roundtrip_registry(
+            &world,
+            RegistryContext::new(&full),
+            RegistryContext::new(&full),
+        );
+        assert_eq!(*loaded.get::<&Position>(a).unwrap(), Position([1.0; 3]));
+        assert_eq!(*loaded.get::<&Velocity>(a).unwrap(), Velocity([2.0; 3]));
+        assert!(!loaded.satisfies::<&bool>(a).unwrap());
+        assert_eq!(*loaded.get::<&Velocity>(b).unwrap(), Velocity([3.0; 3]));
+
+        // Unknown components are skipped by default
+        let skipped = roundtrip_registry(
+            &world,
+            RegistryContext::new(&full),
+            RegistryContext::new(&partial),
+        );
+        assert_eq!(*skipped.get::<&Position>(a).unwrap(), Position([1.0; 3]));
+        assert!(skipped.entity(b).unwrap().is_empty());
+
+        // Preserved components survive a round trip through a context that doesn't know them
+        let mut preserving = RegistryContext::new(&partial);
+        preserving.preserve_unknown(true);
+        let preserved = roundtrip_registry(&world, RegistryContext::new(&full), preserving);
+        assert!(!preserved.satisfies::<&Velocity>(a).unwrap());
+        let opaque = preserved.get::<&OpaqueComponents>(b).unwrap();
+        assert_eq!(opaque.len(), 1);
+        assert_eq!(
+            opaque.get("velocity"),
+            Some(&*bincode::serialize(&Velocity([3.0; 3])).unwrap())
+        );
+        drop(opaque);
+        let restored = roundtrip_registry(
+            &preserved,
+            RegistryContext::new(&partial),
+            RegistryContext::new(&full),
+        );
+        assert_eq!(*restored.get::<&Position>(a).unwrap(), Position([1.0; 3]));
+        assert_eq!(*restored.get::<&Velocity>(a).unwrap(), Velocity([2.0; 3]));
+        assert_eq!(*restored.get::<&Velocity>(b).unwrap(), Velocity([3.0; 3]));
+    }
 }
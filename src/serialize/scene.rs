@@ -0,0 +1,769 @@
+//! Human-readable scene files
+//!
+//! A scene is a list of entities, each with an optional [`Name`] and a map of components written
+//! through the [`row`](super::row) module's [`SerializeContext`] and [`DeserializeContext`], e.g.
+//! as generated by [`serde_registry!`](crate::serde_registry). Entity IDs are not stored, so
+//! scenes can be written and merged by hand, and loading a scene spawns fresh entities into an
+//! existing [`World`].
+//!
+//! [`to_string`] and [`from_str`] use RON, which looks like this:
+//!
+//! ```text
+//! (
+//!     entities: [
+//!         (
+//!             name: "player",
+//!             components: {
+//!                 "position": (1.0, 2.0),
+//!                 "health": 100,
+//!             },
+//!         ),
+//!         (
+//!             components: {
+//!                 "position": (5.0, 0.0),
+//!             },
+//!         ),
+//!     ],
+//! )
+//! ```
+//!
+//! The underlying [`serialize`] and [`deserialize`] functions work with any self-describing serde
+//! format, such as JSON.
+//!
+//! Components can refer to other entities of the same scene by name, rather than by ID, with
+//! [`by_name`].
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::string::String;
+use std::vec::Vec;
+
+use serde::{
+    de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    ser::{SerializeSeq, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use super::row::{
+    DeserializeComponents, DeserializeContext, SerializeComponents, SerializeContext,
+};
+use crate::{CommandBuffer, Entity, EntityBuilder, EntityRef, World};
+
+/// Component holding an entity's name in a scene
+///
+/// Written as the `name` field of the entity when saving, and attached to named entities when
+/// loading.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Name(pub Cow<'static, str>);
+
+impl Name {
+    /// Construct a name
+    pub fn new(name: impl Into<Cow<'static, str>>) -> Self {
+        Self(name.into())
+    }
+
+    /// Borrow the name as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Serialize every entity in `world` as a scene
+pub fn serialize<C, S>(world: &World, context: &mut C, serializer: S) -> Result<S::Ok, S::Error>
+where
+    C: SerializeContext,
+    S: Serializer,
+{
+    serialize_filtered(world, context, |_| true, serializer)
+}
+
+/// Serialize the entities in `world` for which `filter` returns `true` as a scene
+pub fn serialize_filtered<C, F, S>(
+    world: &World,
+    context: &mut C,
+    mut filter: F,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    C: SerializeContext,
+    F: FnMut(EntityRef<'_>) -> bool,
+    S: Serializer,
+{
+    let entities = world.iter().filter(|&e| filter(e)).collect::<Vec<_>>();
+    scoped(State::Save(world), || {
+        let mut scene = serializer.serialize_struct("Scene", 1)?;
+        scene.serialize_field(
+            "entities",
+            &SerializeEntities {
+                entities,
+                context: RefCell::new(context),
+            },
+        )?;
+        scene.end()
+    })
+    .0
+}
+
+struct SerializeEntities<'a, C> {
+    entities: Vec<EntityRef<'a>>,
+    context: RefCell<&'a mut C>,
+}
+
+impl<C: SerializeContext> Serialize for SerializeEntities<'_, C> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut context = self.context.borrow_mut();
+        let mut seq = serializer.serialize_seq(Some(self.entities.len()))?;
+        for &entity in &self.entities {
+            let name = entity.get::<&Name>();
+            seq.serialize_element(&SerializeEntity {
+                name: name.as_deref().map(Name::as_str),
+                components: SerializeComponents(RefCell::new((&mut **context, Some(entity)))),
+            })?;
+        }
+        seq.end()
+    }
+}
+
+struct SerializeEntity<'a, C> {
+    name: Option<&'a str>,
+    components: SerializeComponents<'a, C>,
+}
+
+impl<C: SerializeContext> Serialize for SerializeEntity<'_, C> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut entity = serializer.serialize_struct("Entity", 2)?;
+        match self.name {
+            Some(name) => entity.serialize_field("name", name)?,
+            None => entity.skip_field("name")?,
+        }
+        entity.serialize_field("components", &self.components)?;
+        entity.end()
+    }
+}
+
+/// Spawn the entities of a scene into `world`
+///
+/// Returns the spawned entities in the order they appear in the scene. Entities are only added to
+/// `world` if the whole scene is loaded successfully.
+pub fn deserialize<'de, C, D>(
+    world: &mut World,
+    context: &mut C,
+    deserializer: D,
+) -> Result<Vec<Entity>, D::Error>
+where
+    C: DeserializeContext,
+    D: Deserializer<'de>,
+{
+    load(world, context, |seed| seed.deserialize(deserializer))
+}
+
+/// Serialize every entity in `world` as a pretty-printed RON scene
+pub fn to_string<C: SerializeContext>(world: &World, context: &mut C) -> ron::Result<String> {
+    to_string_filtered(world, context, |_| true)
+}
+
+/// Serialize the entities in `world` for which `filter` returns `true` as a pretty-printed RON
+/// scene
+pub fn to_string_filtered<C, F>(world: &World, context: &mut C, filter: F) -> ron::Result<String>
+where
+    C: SerializeContext,
+    F: FnMut(EntityRef<'_>) -> bool,
+{
+    let mut out = Vec::new();
+    let mut serializer = ron::Serializer::new(&mut out, Some(ron::ser::PrettyConfig::new()))?;
+    serialize_filtered(world, context, filter, &mut serializer)?;
+    // RON output is always UTF-8
+    Ok(String::from_utf8(out).unwrap())
+}
+
+/// Spawn the entities of a RON scene into `world`
+///
+/// See [`deserialize`].
+pub fn from_str<C: DeserializeContext>(
+    world: &mut World,
+    context: &mut C,
+    s: &str,
+) -> ron::error::SpannedResult<Vec<Entity>> {
+    load(world, context, |seed| {
+        ron::Options::default().from_str_seed(s, seed)
+    })
+}
+
+/// Reads a scene with `f`, then spawns its entities if successful
+fn load<C, E>(
+    world: &mut World,
+    context: &mut C,
+    f: impl FnOnce(SceneSeed<'_, C>) -> Result<Vec<Entity>, E>,
+) -> Result<Vec<Entity>, E> {
+    let mut cmd = CommandBuffer::new();
+    let (result, state) = scoped(State::Load(LoadState::new(world)), || {
+        f(SceneSeed {
+            context,
+            cmd: &mut cmd,
+        })
+    });
+    match result {
+        Ok(entities) => {
+            cmd.run_on(world);
+            Ok(entities)
+        }
+        Err(e) => {
+            drop(cmd);
+            // Reserved entities become real when the world is flushed, so get rid of them
+            world.flush();
+            if let State::Load(state) = state {
+                for entity in state.reserved {
+                    world.despawn(entity).unwrap();
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+struct SceneSeed<'a, C> {
+    context: &'a mut C,
+    cmd: &'a mut CommandBuffer,
+}
+
+impl<'de, C: DeserializeContext> DeserializeSeed<'de> for SceneSeed<'_, C> {
+    type Value = Vec<Entity>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Vec<Entity>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("Scene", &["entities"], self)
+    }
+}
+
+impl<'de, C: DeserializeContext> Visitor<'de> for SceneSeed<'_, C> {
+    type Value = Vec<Entity>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a scene")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Vec<Entity>, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entities = None;
+        while let Some(key) = map.next_key::<SceneField>()? {
+            match key {
+                SceneField::Entities => {
+                    if entities.is_some() {
+                        return Err(de::Error::duplicate_field("entities"));
+                    }
+                    entities = Some(map.next_value_seed(EntitiesSeed {
+                        context: &mut *self.context,
+                        cmd: &mut *self.cmd,
+                    })?);
+                }
+            }
+        }
+        if let Some(name) = with_load_state(|state| state.unresolved().map(String::from)) {
+            return Err(de::Error::custom(format_args!(
+                "unresolved entity name {name:?}"
+            )));
+        }
+        entities.ok_or_else(|| de::Error::missing_field("entities"))
+    }
+}
+
+struct EntitiesSeed<'a, C> {
+    context: &'a mut C,
+    cmd: &'a mut CommandBuffer,
+}
+
+impl<'de, C: DeserializeContext> DeserializeSeed<'de> for EntitiesSeed<'_, C> {
+    type Value = Vec<Entity>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Vec<Entity>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, C: DeserializeContext> Visitor<'de> for EntitiesSeed<'_, C> {
+    type Value = Vec<Entity>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of entities")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Vec<Entity>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut entities = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        let mut builder = EntityBuilder::new();
+        while let Some(entity) = seq.next_element_seed(EntitySeed {
+            context: &mut *self.context,
+            builder: &mut builder,
+        })? {
+            self.cmd.insert(entity, builder.build());
+            entities.push(entity);
+        }
+        Ok(entities)
+    }
+}
+
+struct EntitySeed<'a, C> {
+    context: &'a mut C,
+    builder: &'a mut EntityBuilder,
+}
+
+impl<'de, C: DeserializeContext> DeserializeSeed<'de> for EntitySeed<'_, C> {
+    type Value = Entity;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Entity, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("Entity", &["name", "components"], self)
+    }
+}
+
+impl<'de, C: DeserializeContext> Visitor<'de> for EntitySeed<'_, C> {
+    type Value = Entity;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an entity")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Entity, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut name = None;
+        let mut components = false;
+        while let Some(key) = map.next_key::<EntityField>()? {
+            match key {
+                EntityField::Name => {
+                    if name.is_some() {
+                        return Err(de::Error::duplicate_field("name"));
+                    }
+                    name = Some(map.next_value::<String>()?);
+                }
+                EntityField::Components => {
+                    if components {
+                        return Err(de::Error::duplicate_field("components"));
+                    }
+                    map.next_value_seed(DeserializeComponents(
+                        &mut *self.context,
+                        &mut *self.builder,
+                    ))?;
+                    components = true;
+                }
+            }
+        }
+        if !components {
+            return Err(de::Error::missing_field("components"));
+        }
+        match name {
+            Some(name) => {
+                let entity = with_load_state(|state| state.define(&name)).ok_or_else(|| {
+                    de::Error::custom(format_args!("duplicate entity name {name:?}"))
+                })?;
+                self.builder.add(Name::new(name));
+                Ok(entity)
+            }
+            None => Ok(with_load_state(|state| state.reserve())),
+        }
+    }
+}
+
+/// (De)serialize an [`Entity`] by its [`Name`] within a scene
+///
+/// For use with serde's `with` attribute on fields of components that refer to other entities:
+///
+/// ```
+/// # use serde::{Serialize, Deserialize};
+/// # use hecs::Entity;
+/// #[derive(Serialize, Deserialize)]
+/// struct Target {
+///     #[serde(with = "hecs::serialize::scene::by_name")]
+///     entity: Entity,
+/// }
+/// ```
+///
+/// When saving, the referenced entity must have a [`Name`]. When loading, the name must belong to
+/// an entity in the same scene, which may appear before or after the reference. Fails outside of
+/// this module's serialization and deserialization functions.
+pub mod by_name {
+    use serde::{de, ser, Deserialize, Deserializer, Serializer};
+    use std::string::String;
+
+    use super::{with_load_state, State, STATE};
+    use crate::Entity;
+
+    /// Serialize `entity` as its name
+    pub fn serialize<S: Serializer>(entity: &Entity, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = STATE.with(|state| match *state.borrow() {
+            Some(State::Save(world)) => {
+                let world = unsafe { &*world };
+                match world.get::<&super::Name>(*entity) {
+                    Ok(name) => Ok(name.0.clone()),
+                    Err(_) => Err(ser::Error::custom(format_args!(
+                        "referenced entity {entity:?} has no name"
+                    ))),
+                }
+            }
+            _ => Err(ser::Error::custom(
+                "entities can only be referenced by name within a scene",
+            )),
+        })?;
+        serializer.serialize_str(&name)
+    }
+
+    /// Deserialize an entity from its name
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Entity, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        let in_scene = STATE.with(|state| matches!(*state.borrow(), Some(State::Load(_))));
+        if !in_scene {
+            return Err(de::Error::custom(
+                "entities can only be referenced by name within a scene",
+            ));
+        }
+        Ok(with_load_state(|state| state.reference(&name)))
+    }
+}
+
+std::thread_local! {
+    /// Context for [`by_name`] during a scene operation on this thread
+    static STATE: RefCell<Option<State>> = const { RefCell::new(None) };
+}
+
+enum State {
+    /// Saving the world
+    Save(*const World),
+    Load(LoadState),
+}
+
+/// Run `f` with `state` as this thread's scene context, returning the final state
+fn scoped<R>(state: State, f: impl FnOnce() -> R) -> (R, State) {
+    struct Restore(Option<State>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            STATE.with(|x| *x.borrow_mut() = self.0.take());
+        }
+    }
+
+    let restore = Restore(STATE.with(|x| x.borrow_mut().replace(state)));
+    let result = f();
+    let state = STATE.with(|x| x.borrow_mut().take()).unwrap();
+    drop(restore);
+    (result, state)
+}
+
+/// Run `f` on the current [`LoadState`]
+///
+/// Panics outside of a load operation.
+fn with_load_state<R>(f: impl FnOnce(&mut LoadState) -> R) -> R {
+    STATE.with(|state| match *state.borrow_mut() {
+        Some(State::Load(ref mut state)) => f(state),
+        _ => unreachable!("not loading a scene"),
+    })
+}
+
+/// Entity handles allocated while loading a scene
+struct LoadState {
+    /// The world being loaded into, which must not be mutated until loading completes
+    world: *const World,
+    /// Index into `names` of each name encountered
+    index: HashMap<String, usize>,
+    /// Names encountered, their reserved entities, and whether an entity with that name was seen
+    names: Vec<(String, Entity, bool)>,
+    /// Every entity reserved
+    reserved: Vec<Entity>,
+}
+
+impl LoadState {
+    fn new(world: &World) -> Self {
+        Self {
+            world,
+            index: HashMap::new(),
+            names: Vec::new(),
+            reserved: Vec::new(),
+        }
+    }
+
+    fn reserve(&mut self) -> Entity {
+        let entity = unsafe { (*self.world).reserve_entity() };
+        self.reserved.push(entity);
+        entity
+    }
+
+    /// The entity referred to as `name`
+    fn reference(&mut self, name: &str) -> Entity {
+        if let Some(&i) = self.index.get(name) {
+            return self.names[i].1;
+        }
+        let entity = self.reserve();
+        self.index.insert(name.into(), self.names.len());
+        self.names.push((name.into(), entity, false));
+        entity
+    }
+
+    /// The entity to be named `name`, or `None` if another entity already has that name
+    fn define(&mut self, name: &str) -> Option<Entity> {
+        let entity = self.reference(name);
+        let defined = &mut self.names[self.index[name]].2;
+        if *defined {
+            return None;
+        }
+        *defined = true;
+        Some(entity)
+    }
+
+    /// The first name referred to that no entity has
+    fn unresolved(&self) -> Option<&str> {
+        self.names
+            .iter()
+            .find(|x| !x.2)
+            .map(|(name, _, _)| &name[..])
+    }
+}
+
+enum SceneField {
+    Entities,
+}
+
+impl<'de> Deserialize<'de> for SceneField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(FieldVisitor(&["entities"]))?;
+        Ok(SceneField::Entities)
+    }
+}
+
+enum EntityField {
+    Name,
+    Components,
+}
+
+impl<'de> Deserialize<'de> for EntityField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match deserializer.deserialize_identifier(FieldVisitor(&["name", "components"]))? {
+            0 => Ok(EntityField::Name),
+            _ => Ok(EntityField::Components),
+        }
+    }
+}
+
+/// Maps a field name to its index in a list of expected names
+struct FieldVisitor(&'static [&'static str]);
+
+impl Visitor<'_> for FieldVisitor {
+    type Value = usize;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a field name")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<usize, E> {
+        self.0
+            .iter()
+            .position(|&x| x == v)
+            .ok_or_else(|| de::Error::unknown_field(v, self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::serde_registry;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+    struct Position(f32, f32);
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+    struct Health(u32);
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+    struct Follow {
+        #[serde(with = "by_name")]
+        leader: Entity,
+    }
+
+    serde_registry! {
+        struct Registry {
+            "position" => Position,
+            "health" => Health,
+            "follow" => Follow,
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut world = World::new();
+        world.spawn((Name::new("player"), Position(1.0, 2.0), Health(100)));
+        world.spawn((Position(5.0, 0.0), true));
+        world.spawn((false,));
+
+        let text = to_string(&world, &mut Registry::new()).unwrap();
+        let mut loaded = World::new();
+        let entities = from_str(&mut loaded, &mut Registry::new(), &text).unwrap();
+        assert_eq!(entities.len(), 3);
+        assert_eq!(loaded.len(), 3);
+
+        let player = loaded.entity(entities[0]).unwrap();
+        assert_eq!(player.get::<&Name>().unwrap().as_str(), "player");
+        assert_eq!(*player.get::<&Position>().unwrap(), Position(1.0, 2.0));
+        assert_eq!(*player.get::<&Health>().unwrap(), Health(100));
+
+        let other = loaded.entity(entities[1]).unwrap();
+        assert!(!other.has::<Name>());
+        assert_eq!(*other.get::<&Position>().unwrap(), Position(5.0, 0.0));
+        assert!(!other.has::<bool>());
+
+        assert_eq!(loaded.entity(entities[2]).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn hand_written() {
+        let text = r#"(
+            entities: [
+                (
+                    components: {
+                        "health": (3),
+                    },
+                    name: "crate",
+                ),
+                (components: {}),
+            ],
+        )"#;
+        let mut world = World::new();
+        world.spawn((Health(1),));
+        let entities = from_str(&mut world, &mut Registry::new(), text).unwrap();
+        assert_eq!(world.len(), 3);
+        let e = world.entity(entities[0]).unwrap();
+        assert_eq!(e.get::<&Name>().unwrap().as_str(), "crate");
+        assert_eq!(*e.get::<&Health>().unwrap(), Health(3));
+    }
+
+    #[test]
+    fn filtered() {
+        let mut world = World::new();
+        world.spawn((Health(1),));
+        world.spawn((Health(2), Position(0.0, 0.0)));
+        let text =
+            to_string_filtered(&world, &mut Registry::new(), |e| e.has::<Position>()).unwrap();
+        let mut loaded = World::new();
+        from_str(&mut loaded, &mut Registry::new(), &text).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(
+            loaded.query_mut::<&Health>().into_iter().next().unwrap().1,
+            &Health(2)
+        );
+    }
+
+    #[test]
+    fn missing_components() {
+        let mut world = World::new();
+        let err = from_str(
+            &mut world,
+            &mut Registry::new(),
+            r#"(entities: [(name: "x")])"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.code,
+            ron::Error::MissingStructField {
+                field: "components",
+                outer: Some("Entity".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn references_by_name() {
+        let text = r#"(
+            entities: [
+                (components: { "follow": (leader: "b") }),
+                (name: "a", components: { "follow": (leader: "b") }),
+                (name: "b", components: { "follow": (leader: "a") }),
+            ],
+        )"#;
+        let mut world = World::new();
+        let entities = from_str(&mut world, &mut Registry::new(), text).unwrap();
+        assert_eq!(world.len(), 3);
+        let follow = |e| world.get::<&Follow>(e).unwrap().leader;
+        assert_eq!(follow(entities[0]), entities[2]);
+        assert_eq!(follow(entities[1]), entities[2]);
+        assert_eq!(follow(entities[2]), entities[1]);
+
+        let text = to_string(&world, &mut Registry::new()).unwrap();
+        let mut loaded = World::new();
+        let reloaded = from_str(&mut loaded, &mut Registry::new(), &text).unwrap();
+        let follow = |e| loaded.get::<&Follow>(e).unwrap().leader;
+        assert_eq!(follow(reloaded[0]), reloaded[2]);
+        assert_eq!(follow(reloaded[2]), reloaded[1]);
+    }
+
+    #[test]
+    fn unresolved_name() {
+        let mut world = World::new();
+        world.spawn((Health(1),));
+        let err = from_str(
+            &mut world,
+            &mut Registry::new(),
+            r#"(entities: [(name: "a", components: {}), (components: { "follow": (leader: "c") })])"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.code,
+            ron::Error::Message("unresolved entity name \"c\"".into())
+        );
+        // Nothing is spawned by a failed load
+        assert_eq!(world.len(), 1);
+    }
+
+    #[test]
+    fn duplicate_name() {
+        let mut world = World::new();
+        let err = from_str(
+            &mut world,
+            &mut Registry::new(),
+            r#"(entities: [(name: "a", components: {}), (name: "a", components: {})])"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.code,
+            ron::Error::Message("duplicate entity name \"a\"".into())
+        );
+        assert_eq!(world.len(), 0);
+    }
+
+    #[test]
+    fn reference_to_unnamed() {
+        let mut world = World::new();
+        let leader = world.spawn(());
+        world.spawn((Follow { leader },));
+        assert!(to_string(&world, &mut Registry::new()).is_err());
+    }
+}
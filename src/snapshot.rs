@@ -0,0 +1,268 @@
+use crate::alloc::sync::Arc;
+use crate::alloc::vec::Vec;
+use core::any::TypeId;
+
+use crate::archetype::RawColumn;
+use crate::entities::AllocatorState;
+use crate::plugin::AnyPlugin;
+use crate::{
+    Archetype, ColumnBatch, ColumnBatchType, ComponentRegistry, DynamicBundle, DynamicClone,
+    Entity, TypeInfo,
+};
+
+/// A copy of the state of a [`World`](crate::World), captured by
+/// [`World::snapshot`](crate::World::snapshot)
+///
+/// Holds clones of the world's components along with the complete state of its entity allocator,
+/// including the order of the freelist, outstanding reservations, and the generations of despawned
+/// entities, the world's [tick](crate::World::tick), and the state of its
+/// [plugins](crate::Plugin::snapshot).
+#[derive(Clone)]
+pub struct WorldSnapshot {
+    pub(crate) archetypes: Vec<ArchetypeSnapshot>,
+    pub(crate) allocator: AllocatorState,
+    pub(crate) tick: u32,
+    /// ID of the world captured
+    pub(crate) world: u64,
+    /// `ArchetypeSet::generation` of the world captured
+    pub(crate) generation: u32,
+    /// State of each plugin that supports snapshots, by plugin type
+    pub(crate) plugins: Vec<(TypeId, Arc<AnyPlugin>)>,
+}
+
+impl WorldSnapshot {
+    /// The [`World::tick`](crate::World::tick) when the snapshot was captured
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// Number of entities captured, excluding reserved entities
+    pub fn len(&self) -> u32 {
+        self.archetypes
+            .iter()
+            .map(|x| x.entities.len() as u32)
+            .sum()
+    }
+
+    /// Whether no entities were captured
+    pub fn is_empty(&self) -> bool {
+        self.archetypes.is_empty()
+    }
+
+    /// Number of component columns shared with `other`, rather than copied, by
+    /// [`World::snapshot_incremental`](crate::World::snapshot_incremental)
+    pub fn shared_columns(&self, other: &WorldSnapshot) -> usize {
+        self.archetypes
+            .iter()
+            .filter_map(|x| Some(x.shared_columns(other.archetype(x.index)?)))
+            .sum()
+    }
+
+    /// The captured archetype found at `index` in the world, if it had any entities
+    pub(crate) fn archetype(&self, index: u32) -> Option<&ArchetypeSnapshot> {
+        let i = self
+            .archetypes
+            .binary_search_by_key(&index, |x| x.index)
+            .ok()?;
+        Some(&self.archetypes[i])
+    }
+}
+
+/// The entities of one archetype and clones of their components
+#[derive(Clone)]
+pub(crate) struct ArchetypeSnapshot {
+    /// Index of the archetype in the world it was captured from
+    pub(crate) index: u32,
+    pub(crate) entities: Arc<[Entity]>,
+    /// Types of the captured components, in the order of `DynamicBundle::with_ids`
+    ids: Vec<TypeId>,
+    /// Captured columns, shared with other snapshots where unchanged, in the order of `ids`
+    columns: Vec<Arc<SnapshotColumn>>,
+}
+
+/// Clones of the components in one column of an archetype
+struct SnapshotColumn {
+    ty: TypeInfo,
+    clone: DynamicClone,
+    /// Holds only components of type `ty`
+    batch: ColumnBatch,
+    /// The column's `Archetype::column_version` when captured
+    version: u32,
+}
+
+// Components are `Send + Sync`, and the batch is never mutated after capture
+unsafe impl Send for SnapshotColumn {}
+unsafe impl Sync for SnapshotColumn {}
+
+impl SnapshotColumn {
+    fn base(&self) -> *const u8 {
+        unsafe {
+            self.batch
+                .0
+                .get_dynamic(self.ty.id(), self.ty.layout().size(), 0)
+                .unwrap()
+                .as_ptr()
+                .cast_const()
+        }
+    }
+}
+
+impl ArchetypeSnapshot {
+    /// Clone the components of `archetype` whose types were registered with
+    /// [`ComponentRegistry::register_clone`] and satisfy `filter`
+    ///
+    /// `entities` must be the handles of the entities in `archetype`, found at `index`. Panics if
+    /// any cloned component is uniquely borrowed.
+    pub(crate) fn with_filter(
+        archetype: &Archetype,
+        index: u32,
+        entities: Vec<Entity>,
+        registry: &ComponentRegistry,
+        filter: impl FnMut(TypeId) -> bool,
+    ) -> Self {
+        Self::sharing(archetype, index, entities, registry, filter, None)
+    }
+
+    /// Like `with_filter`, but share the columns of `previous` that are unchanged
+    ///
+    /// `previous` must have been captured from the same archetype, with its current index, and with
+    /// no archetypes having been removed from the world since.
+    pub(crate) fn sharing(
+        archetype: &Archetype,
+        index: u32,
+        entities: Vec<Entity>,
+        registry: &ComponentRegistry,
+        mut filter: impl FnMut(TypeId) -> bool,
+        previous: Option<&ArchetypeSnapshot>,
+    ) -> Self {
+        // Column versions don't reflect entities being removed, so only share columns if the
+        // entities are exactly the same
+        let previous = previous.filter(|x| *x.entities == *entities);
+        let mut types = archetype
+            .types()
+            .iter()
+            .filter(|ty| filter(ty.id()))
+            .filter_map(|ty| Some((*ty, registry.get(ty.id())?.dynamic_clone()?)))
+            .collect::<Vec<_>>();
+        types.sort_unstable_by_key(|x| x.0);
+        let columns = types
+            .iter()
+            .map(|&(ty, clone)| {
+                let state = archetype.get_state_dynamic(ty.id()).unwrap();
+                let version = archetype.column_version(state);
+                let shared = previous.and_then(|x| {
+                    x.columns
+                        .iter()
+                        .find(|c| c.ty.id() == ty.id() && c.version == version)
+                });
+                if let Some(column) = shared {
+                    return column.clone();
+                }
+                let raw = RawColumn::new(archetype, ty.id()).unwrap();
+                let batch = unsafe {
+                    clone_columns(
+                        &[(ty, clone)],
+                        core::iter::once(raw.base()),
+                        archetype.len(),
+                    )
+                };
+                Arc::new(SnapshotColumn {
+                    ty,
+                    clone,
+                    batch,
+                    version,
+                })
+            })
+            .collect();
+        Self {
+            index,
+            entities: match previous {
+                Some(x) => x.entities.clone(),
+                None => entities.into(),
+            },
+            ids: types.iter().map(|x| x.0.id()).collect(),
+            columns,
+        }
+    }
+
+    /// Clone the captured components into a new batch
+    pub(crate) fn to_batch(&self) -> ColumnBatch {
+        let clones = self
+            .columns
+            .iter()
+            .map(|x| (x.ty, x.clone))
+            .collect::<Vec<_>>();
+        unsafe {
+            clone_columns(
+                &clones,
+                self.columns.iter().map(|x| x.base()),
+                self.entities.len() as u32,
+            )
+        }
+    }
+
+    /// Number of captured columns shared with `other`
+    pub(crate) fn shared_columns(&self, other: &ArchetypeSnapshot) -> usize {
+        self.columns
+            .iter()
+            .filter(|x| other.columns.iter().any(|y| Arc::ptr_eq(x, y)))
+            .count()
+    }
+
+    /// Clones of the captured components of the entity at `index`
+    pub(crate) fn row(&self, index: usize) -> SnapshotRow<'_> {
+        assert!(index < self.entities.len());
+        SnapshotRow {
+            snapshot: self,
+            index,
+        }
+    }
+}
+
+/// Bundle cloned from one entity of an [`ArchetypeSnapshot`]
+pub(crate) struct SnapshotRow<'a> {
+    snapshot: &'a ArchetypeSnapshot,
+    index: usize,
+}
+
+unsafe impl DynamicBundle for SnapshotRow<'_> {
+    fn with_ids<T>(&self, f: impl FnOnce(&[TypeId]) -> T) -> T {
+        f(&self.snapshot.ids)
+    }
+
+    fn type_info(&self) -> Vec<TypeInfo> {
+        self.snapshot.columns.iter().map(|x| x.ty).collect()
+    }
+
+    unsafe fn put(self, mut f: impl FnMut(*mut u8, TypeInfo)) {
+        for column in &self.snapshot.columns {
+            let size = column.ty.layout().size();
+            (column.clone.func)(column.base().add(self.index * size), &mut f);
+        }
+    }
+}
+
+/// Clone `len` components from each of `sources` into a new batch
+///
+/// Each source must point to `len` contiguous instances of the type of the corresponding element
+/// of `clones`.
+unsafe fn clone_columns(
+    clones: &[(TypeInfo, DynamicClone)],
+    sources: impl Iterator<Item = *const u8>,
+    len: u32,
+) -> ColumnBatch {
+    let mut batch_type = ColumnBatchType::new();
+    for &(ty, _) in clones {
+        batch_type.add_dynamic(ty);
+    }
+    let mut batch = batch_type.into_batch(len);
+    for (&(ty, clone), base) in clones.iter().zip(sources) {
+        let size = ty.layout().size();
+        for i in 0..len as usize {
+            (clone.func)(base.add(i * size), &mut |ptr, ty| {
+                batch.push_dynamic(ty, ptr);
+            });
+        }
+    }
+    batch.build().expect("every column was filled")
+}
@@ -0,0 +1,272 @@
+use crate::alloc::boxed::Box;
+use crate::alloc::string::String;
+use crate::alloc::vec::Vec;
+
+use crate::Entity;
+
+/// Types that can be hashed identically on every platform and in every build
+///
+/// Unlike [`Hash`](core::hash::Hash), implementations must feed [`StableHasher`] the same bytes
+/// regardless of pointer width, endianness, compiler version or process, so that hashes of
+/// separately-run simulations can be compared to detect desyncs. Floats are hashed by bit pattern,
+/// with every NaN treated as the same value.
+///
+/// Components implementing this are registered with
+/// [`ComponentRegistry::register_stable_hash`](crate::ComponentRegistry::register_stable_hash) for
+/// use by [`World::stable_hash`](crate::World::stable_hash). With the `macros` feature, it can be
+/// derived for structs and enums, hashing each field in declaration order.
+///
+/// ```
+/// # use hecs::*;
+/// struct Body {
+///     position: [f32; 2],
+///     parent: Option<Entity>,
+/// }
+///
+/// impl StableHash for Body {
+///     fn stable_hash(&self, hasher: &mut StableHasher) {
+///         self.position.stable_hash(hasher);
+///         self.parent.stable_hash(hasher);
+///     }
+/// }
+/// ```
+pub trait StableHash {
+    /// Feed `self` into `hasher`
+    fn stable_hash(&self, hasher: &mut StableHasher);
+}
+
+/// Accumulates the bytes fed to it by [`StableHash`] implementations into a 64-bit hash
+///
+/// Uses 64-bit FNV-1a, whose output is fixed by its specification. Multi-byte integers are written
+/// in little-endian order.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct StableHasher(u64);
+
+impl StableHasher {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    /// Create a hasher that hasn't been fed anything
+    pub const fn new() -> Self {
+        Self(Self::OFFSET)
+    }
+
+    /// The hash of everything written so far
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+
+    /// Feed `bytes` into the hash
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    /// Feed a `u8` into the hash
+    pub fn write_u8(&mut self, x: u8) {
+        self.write(&[x]);
+    }
+
+    /// Feed a `u32` into the hash
+    pub fn write_u32(&mut self, x: u32) {
+        self.write(&x.to_le_bytes());
+    }
+
+    /// Feed a `u64` into the hash
+    pub fn write_u64(&mut self, x: u64) {
+        self.write(&x.to_le_bytes());
+    }
+
+    /// Feed a length or count into the hash, e.g. to delimit variable-length data
+    ///
+    /// Written as a `u64`, so the hash is the same on 32- and 64-bit platforms.
+    pub fn write_len(&mut self, len: usize) {
+        self.write_u64(len as u64);
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+macro_rules! impl_int {
+    ($($ty:ty),*) => {
+        $(
+            impl StableHash for $ty {
+                fn stable_hash(&self, hasher: &mut StableHasher) {
+                    hasher.write(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl StableHash for usize {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u64(*self as u64);
+    }
+}
+
+impl StableHash for isize {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        (*self as i64).stable_hash(hasher);
+    }
+}
+
+impl StableHash for f32 {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        let x = if self.is_nan() { f32::NAN } else { *self };
+        hasher.write_u32(x.to_bits());
+    }
+}
+
+impl StableHash for f64 {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        let x = if self.is_nan() { f64::NAN } else { *self };
+        hasher.write_u64(x.to_bits());
+    }
+}
+
+impl StableHash for bool {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u8(u8::from(*self));
+    }
+}
+
+impl StableHash for char {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u32(u32::from(*self));
+    }
+}
+
+impl StableHash for () {
+    fn stable_hash(&self, _: &mut StableHasher) {}
+}
+
+impl StableHash for Entity {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u64(self.to_bits().get());
+    }
+}
+
+impl StableHash for str {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_len(self.len());
+        hasher.write(self.as_bytes());
+    }
+}
+
+impl StableHash for String {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.as_str().stable_hash(hasher)
+    }
+}
+
+impl<T: StableHash + ?Sized> StableHash for &T {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        (**self).stable_hash(hasher)
+    }
+}
+
+impl<T: StableHash + ?Sized> StableHash for Box<T> {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        (**self).stable_hash(hasher)
+    }
+}
+
+impl<T: StableHash> StableHash for Option<T> {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        match self {
+            None => hasher.write_u8(0),
+            Some(x) => {
+                hasher.write_u8(1);
+                x.stable_hash(hasher);
+            }
+        }
+    }
+}
+
+impl<T: StableHash> StableHash for [T] {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_len(self.len());
+        for x in self {
+            x.stable_hash(hasher);
+        }
+    }
+}
+
+impl<T: StableHash, const N: usize> StableHash for [T; N] {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        for x in self {
+            x.stable_hash(hasher);
+        }
+    }
+}
+
+impl<T: StableHash> StableHash for Vec<T> {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self[..].stable_hash(hasher)
+    }
+}
+
+macro_rules! impl_tuple {
+    ($($name:ident),*) => {
+        impl<$($name: StableHash),*> StableHash for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn stable_hash(&self, hasher: &mut StableHasher) {
+                let ($($name,)*) = self;
+                $($name.stable_hash(hasher);)*
+            }
+        }
+    };
+}
+
+impl_tuple!(A);
+impl_tuple!(A, B);
+impl_tuple!(A, B, C);
+impl_tuple!(A, B, C, D);
+impl_tuple!(A, B, C, D, E);
+impl_tuple!(A, B, C, D, E, F);
+impl_tuple!(A, B, C, D, E, F, G);
+impl_tuple!(A, B, C, D, E, F, G, H);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(x: impl StableHash) -> u64 {
+        let mut hasher = StableHasher::new();
+        x.stable_hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn fnv_vectors() {
+        assert_eq!(StableHasher::new().finish(), 0xcbf2_9ce4_8422_2325);
+        let mut hasher = StableHasher::new();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(hash(f32::NAN), hash(-f32::NAN));
+        assert_eq!(hash(f32::NAN), hash(f32::from_bits(0x7f80_0001)));
+        assert_eq!(hash(f64::NAN), hash(f64::from_bits(0xfff0_0000_0000_0001)));
+        assert_ne!(hash(0.0f32), hash(-0.0f32));
+        assert_ne!(hash(1.0f64), hash(2.0f64));
+    }
+
+    #[test]
+    fn delimited() {
+        assert_ne!(hash(("ab", "c")), hash(("a", "bc")));
+        assert_ne!(hash(Some(0u8)), hash(None::<u8>));
+        assert_eq!(hash(3usize), hash(3u64));
+    }
+}
@@ -54,9 +54,8 @@ unsafe impl DynamicBundle for TakenEntity<'_> {
 
 impl Drop for TakenEntity<'_> {
     fn drop(&mut self) {
-        if let Some(moved) = unsafe { self.archetype.remove(self.index, self.drop) } {
-            self.entities.meta[moved as usize].location.index = self.index;
-        }
+        let moved = unsafe { self.archetype.remove(self.index, self.drop) };
+        self.entities.relocate(self.archetype, moved);
         self.entities.free(self.entity).unwrap();
     }
 }
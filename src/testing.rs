@@ -0,0 +1,296 @@
+//! Utilities for testing code that operates on a [`World`]
+//!
+//! Includes [`assert_worlds_eq!`](crate::assert_worlds_eq) for comparing worlds and
+//! [`generate`] for building pseudo-random worlds.
+
+use crate::alloc::vec::Vec;
+use core::any::TypeId;
+use core::fmt;
+use core::ops::Range;
+
+use crate::registry::GenerateFn;
+use crate::{ComponentRegistry, DynamicBundle, EntityBuilder, TypeInfo, World, WorldDiff};
+
+/// Assert that two worlds contain the same entities with equal components
+///
+/// Entities are compared by handle and components by value as described in
+/// [`WorldDiff`], so worlds which differ only in internal layout, such as the order in which
+/// archetypes were created, are considered equal. On failure, panics with a
+/// [report](WorldDiff::report) of every difference, followed by the optional custom message.
+///
+/// ```
+/// # use hecs::*;
+/// let mut registry = ComponentRegistry::new();
+/// registry.register_eq::<i32>("i32").register_debug::<i32>("i32");
+///
+/// let mut a = World::new();
+/// let e = a.spawn((1, true));
+/// let mut b = World::new();
+/// b.spawn_at(e, (true, 1));
+/// assert_worlds_eq!(&a, &b, &registry);
+///
+/// *b.get::<&mut i32>(e).unwrap() = 2;
+/// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+///     assert_worlds_eq!(&a, &b, &registry, "after {}", "edit");
+/// }));
+/// assert!(result.is_err());
+/// ```
+#[macro_export]
+macro_rules! assert_worlds_eq {
+    ($left:expr, $right:expr, $registry:expr $(,)?) => {
+        $crate::testing::assert_worlds_eq($left, $right, $registry, ::core::option::Option::None)
+    };
+    ($left:expr, $right:expr, $registry:expr, $($arg:tt)+) => {
+        $crate::testing::assert_worlds_eq(
+            $left,
+            $right,
+            $registry,
+            ::core::option::Option::Some(::core::format_args!($($arg)+)),
+        )
+    };
+}
+
+/// Implementation of [`assert_worlds_eq!`](crate::assert_worlds_eq)
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_worlds_eq(
+    left: &World,
+    right: &World,
+    registry: &ComponentRegistry,
+    message: Option<fmt::Arguments<'_>>,
+) {
+    let diff = WorldDiff::between(left, right, registry);
+    if diff.is_empty() {
+        return;
+    }
+    let report = diff.report(left, right, registry);
+    match message {
+        Some(message) => panic!("worlds differ from left to right: {message}\n{report}"),
+        None => panic!("worlds differ from left to right:\n{report}"),
+    }
+}
+
+/// Deterministically build a pseudo-random world, e.g. for property tests of serialization,
+/// hashing, and merging
+///
+/// The same `seed`, `config`, and `registry` always produce the same world, including entity
+/// handles. Component values are produced by the [`Generate`] implementations registered with
+/// [`ComponentRegistry::register_generate`].
+///
+/// # Panics
+///
+/// Panics if a component named in `config` isn't registered with a [`Generate`] implementation.
+///
+/// ```
+/// # use hecs::{*, testing::*};
+/// let mut registry = ComponentRegistry::new();
+/// registry
+///     .register_generate::<u32>("u32")
+///     .register_generate::<bool>("bool")
+///     .register_eq::<u32>("u32")
+///     .register_eq::<bool>("bool");
+/// let mut config = GenerateConfig::new();
+/// config.entities(10..20).component("u32", 0.9).despawn_chance(0.1);
+///
+/// let world = generate(42, &config, &registry);
+/// assert!(world.len() < 20);
+/// assert!(world.iter().all(|e| !e.has::<bool>()));
+/// assert_worlds_eq!(&world, &generate(42, &config, &registry), &registry);
+/// ```
+pub fn generate(seed: u64, config: &GenerateConfig, registry: &ComponentRegistry) -> World {
+    let components = match config.components {
+        Some(ref components) => components
+            .iter()
+            .map(|&(name, chance)| {
+                let reg = registry
+                    .get_by_name(name)
+                    .filter(|reg| reg.has_generate())
+                    .unwrap_or_else(|| panic!("no generator registered for component {name:?}"));
+                (reg.type_info(), reg.generate_fn().unwrap(), chance)
+            })
+            .collect::<Vec<_>>(),
+        None => registry
+            .iter()
+            .filter_map(|reg| Some((reg.type_info(), reg.generate_fn()?, 0.5)))
+            .collect(),
+    };
+
+    let mut rng = SeededRng::new(seed);
+    let mut world = World::new();
+    let mut builder = EntityBuilder::new();
+    let count = rng.range(config.entities.clone());
+    for _ in 0..count {
+        for &(info, ctor, chance) in &components {
+            if rng.chance(chance) {
+                builder.add_bundle(Generated {
+                    info,
+                    ctor,
+                    rng: &mut rng,
+                });
+            }
+        }
+        let entity = world.spawn(builder.build());
+        if rng.chance(config.despawn_chance) {
+            world.despawn(entity).unwrap();
+        }
+    }
+    world
+}
+
+/// Parameters for [`generate`]
+#[derive(Debug, Clone)]
+pub struct GenerateConfig {
+    entities: Range<u32>,
+    components: Option<Vec<(&'static str, f32)>>,
+    despawn_chance: f32,
+}
+
+impl GenerateConfig {
+    /// Spawn up to 100 entities, giving each component type in the registry with a [`Generate`]
+    /// implementation a 50% chance of being present on each entity
+    pub fn new() -> Self {
+        Self {
+            entities: 0..100,
+            components: None,
+            despawn_chance: 0.0,
+        }
+    }
+
+    /// Spawn a number of entities chosen uniformly from `range`
+    pub fn entities(&mut self, range: Range<u32>) -> &mut Self {
+        self.entities = range;
+        self
+    }
+
+    /// Give each entity a component of the type registered as `name` with probability `chance`
+    ///
+    /// Once called, only the component types named by calls to this method are generated.
+    pub fn component(&mut self, name: &'static str, chance: f32) -> &mut Self {
+        self.components
+            .get_or_insert_with(Vec::new)
+            .push((name, chance));
+        self
+    }
+
+    /// Despawn each entity immediately after spawning it with probability `chance`
+    ///
+    /// Despawned entities aren't counted towards [`entities`](Self::entities). Their IDs are reused
+    /// with later generations, so that the resulting world's handles and freelist are nontrivial.
+    pub fn despawn_chance(&mut self, chance: f32) -> &mut Self {
+        self.despawn_chance = chance;
+        self
+    }
+}
+
+impl Default for GenerateConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Types that can be produced pseudo-randomly by [`generate`]
+///
+/// Implementations must draw only from the supplied [`SeededRng`] to remain deterministic.
+pub trait Generate {
+    /// Produce a pseudo-random value
+    fn generate(rng: &mut SeededRng) -> Self;
+}
+
+macro_rules! generate_int {
+    ($($ty:ty),*) => {
+        $(
+            impl Generate for $ty {
+                fn generate(rng: &mut SeededRng) -> Self {
+                    rng.next_u64() as $ty
+                }
+            }
+        )*
+    };
+}
+
+generate_int!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl Generate for bool {
+    fn generate(rng: &mut SeededRng) -> Self {
+        rng.next_u64() & 1 != 0
+    }
+}
+
+impl Generate for f32 {
+    /// Uniformly distributed in `[0, 1)`
+    fn generate(rng: &mut SeededRng) -> Self {
+        rng.next_f32()
+    }
+}
+
+impl Generate for f64 {
+    /// Uniformly distributed in `[0, 1)`
+    fn generate(rng: &mut SeededRng) -> Self {
+        (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// A small, fast, deterministic pseudo-random number generator
+///
+/// Implements SplitMix64, whose output is identical on every platform. Not suitable for
+/// cryptographic use.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    /// Create a generator whose output is determined by `seed`
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Produce 64 uniformly distributed bits
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Produce a value uniformly distributed in `[0, 1)`
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Produce a value approximately uniformly distributed in `range`, or its start if empty
+    pub fn range(&mut self, range: Range<u32>) -> u32 {
+        let len = range.end.saturating_sub(range.start);
+        if len == 0 {
+            return range.start;
+        }
+        range.start + (((self.next_u64() >> 32) * u64::from(len)) >> 32) as u32
+    }
+
+    /// Return `true` with probability `chance`
+    pub fn chance(&mut self, chance: f32) -> bool {
+        self.next_f32() < chance
+    }
+}
+
+/// A single component produced by a registered [`Generate`] implementation
+struct Generated<'a> {
+    info: TypeInfo,
+    ctor: GenerateFn,
+    rng: &'a mut SeededRng,
+}
+
+unsafe impl DynamicBundle for Generated<'_> {
+    fn with_ids<T>(&self, f: impl FnOnce(&[TypeId]) -> T) -> T {
+        f(&[self.info.id()])
+    }
+
+    fn type_info(&self) -> Vec<TypeInfo> {
+        crate::alloc::vec![self.info]
+    }
+
+    unsafe fn put(self, mut f: impl FnMut(*mut u8, TypeInfo)) {
+        (self.ctor)(self.rng, &mut f);
+    }
+}
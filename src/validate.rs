@@ -0,0 +1,150 @@
+use crate::alloc::borrow::Cow;
+use crate::alloc::boxed::Box;
+use crate::alloc::vec::Vec;
+use core::any::TypeId;
+use core::fmt;
+
+use crate::{Component, Entity, EntityBuilder};
+
+/// Whether a validator registered with [`World::add_validator`](crate::World::add_validator)
+/// runs in release builds
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ValidationMode {
+    /// Only run when `debug_assertions` are enabled, costing nothing otherwise
+    Debug,
+    /// Always run
+    Always,
+}
+
+/// The component types an entity is about to have, presented to validators registered with
+/// [`World::add_validator`](crate::World::add_validator)
+///
+/// Validators can inspect the prospective set, and fix it by scheduling further components to
+/// be added or removed once the operation completes.
+pub struct Transition<'a> {
+    entity: Option<Entity>,
+    components: &'a [TypeId],
+    added: &'a [TypeId],
+    removed: &'a [TypeId],
+    fixes: &'a mut Fixes,
+}
+
+impl<'a> Transition<'a> {
+    pub(crate) fn new(
+        entity: Option<Entity>,
+        components: &'a [TypeId],
+        added: &'a [TypeId],
+        removed: &'a [TypeId],
+        fixes: &'a mut Fixes,
+    ) -> Self {
+        Self {
+            entity,
+            components,
+            added,
+            removed,
+            fixes,
+        }
+    }
+
+    /// The entity changing, or `None` if it's being spawned
+    pub fn entity(&self) -> Option<Entity> {
+        self.entity
+    }
+
+    /// Types of all components the entity will have, sorted
+    pub fn components(&self) -> &[TypeId] {
+        self.components
+    }
+
+    /// Types of the components being added, including ones the entity already has and that are
+    /// being replaced
+    pub fn added(&self) -> &[TypeId] {
+        self.added
+    }
+
+    /// Types of the components being removed
+    pub fn removed(&self) -> &[TypeId] {
+        self.removed
+    }
+
+    /// Whether the entity will have a `T` component
+    pub fn has<T: Component>(&self) -> bool {
+        self.has_dynamic(TypeId::of::<T>())
+    }
+
+    /// Whether the entity will have a component of the type identified by `id`
+    pub fn has_dynamic(&self, id: TypeId) -> bool {
+        self.components.binary_search(&id).is_ok()
+    }
+
+    /// Whether a `T` component is being added
+    pub fn adds<T: Component>(&self) -> bool {
+        self.added.contains(&TypeId::of::<T>())
+    }
+
+    /// Add `component` to the entity once the operation completes
+    pub fn add<T: Component>(&mut self, component: T) -> &mut Self {
+        self.fixes.add.add(component);
+        self
+    }
+
+    /// Remove the entity's `T` component, if any, once the operation completes
+    pub fn remove<T: Component>(&mut self) -> &mut Self {
+        self.remove_dynamic(TypeId::of::<T>())
+    }
+
+    /// Remove the entity's component of the type identified by `id`, if any, once the operation
+    /// completes
+    pub fn remove_dynamic(&mut self, id: TypeId) -> &mut Self {
+        self.fixes.remove.push(id);
+        self
+    }
+}
+
+impl fmt::Debug for Transition<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transition")
+            .field("entity", &self.entity)
+            .field("components", &self.components)
+            .field("added", &self.added)
+            .field("removed", &self.removed)
+            .finish()
+    }
+}
+
+/// Error returned by a validator to reject a [`Transition`]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ValidationError(pub Cow<'static, str>);
+
+impl ValidationError {
+    /// Reject a transition for `reason`
+    pub fn new(reason: impl Into<Cow<'static, str>>) -> Self {
+        Self(reason.into())
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "validation failed: {}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationError {}
+
+/// A validator registered with [`World::add_validator`](crate::World::add_validator)
+pub(crate) type Validator =
+    Box<dyn Fn(&mut Transition<'_>) -> Result<(), ValidationError> + Send + Sync>;
+
+/// Changes scheduled by validators
+#[derive(Default)]
+pub(crate) struct Fixes {
+    pub(crate) add: EntityBuilder,
+    pub(crate) remove: Vec<TypeId>,
+}
+
+impl Fixes {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.add.component_types().next().is_none() && self.remove.is_empty()
+    }
+}
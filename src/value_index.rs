@@ -0,0 +1,168 @@
+use crate::alloc::vec::Vec;
+use core::hash::Hash;
+
+use hashbrown::HashMap;
+use spin::Mutex;
+
+use crate::{ArchetypesGeneration, Component, Entity, World};
+
+/// How many entities a [`World::register_index`] index expects to share a key
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum IndexKind {
+    /// Each key is held by at most one entity
+    ///
+    /// [`World::lookup`] panics if several entities hold the key looked up, exposing the bug that
+    /// allowed it.
+    Unique,
+    /// Any number of entities may hold the same key
+    Multi,
+}
+
+/// Map from values of the `K` components of a world to the entities holding them
+///
+/// Kept up to date lazily: each lookup first rescans the columns of `K` modified since the
+/// previous lookup, as identified by their versions, and validates the entities it finds, so no
+/// operation on the world needs to know about the index.
+pub(crate) struct ValueIndex<K> {
+    kind: IndexKind,
+    state: Mutex<State<K>>,
+}
+
+struct State<K> {
+    by_key: HashMap<K, Vec<Entity>>,
+    /// Key under which each entity, identified by ID, is listed in `by_key`
+    by_id: HashMap<u32, (Entity, K)>,
+    /// Version of the `K` column of each archetype as of the previous scan
+    versions: Vec<Option<u32>>,
+    /// Archetypes present at the previous scan; archetype indices are only stable until it changes
+    generation: Option<ArchetypesGeneration>,
+}
+
+impl<K: Component + Hash + Eq + Clone> ValueIndex<K> {
+    pub fn new(kind: IndexKind) -> Self {
+        Self {
+            kind,
+            state: Mutex::new(State {
+                by_key: HashMap::new(),
+                by_id: HashMap::new(),
+                versions: Vec::new(),
+                generation: None,
+            }),
+        }
+    }
+
+    pub fn kind(&self) -> IndexKind {
+        self.kind
+    }
+
+    /// Live entities whose `K` component equals `key`, in ascending order
+    pub fn lookup(&self, world: &World, key: &K) -> Vec<Entity> {
+        let mut state = self.state.lock();
+        state.refresh(world);
+        let State { by_key, by_id, .. } = &mut *state;
+        let Some(entities) = by_key.get_mut(key) else {
+            return Vec::new();
+        };
+        // Entities that have since been despawned or lost `K` aren't otherwise noticed
+        entities.retain(|&entity| {
+            let live = world
+                .get::<&K>(entity)
+                .map_or(false, |current| *current == *key);
+            if !live {
+                by_id.remove(&entity.id());
+            }
+            live
+        });
+        let result = entities.clone();
+        if entities.is_empty() {
+            by_key.remove(key);
+        }
+        result
+    }
+}
+
+impl<K: Component + Hash + Eq + Clone> State<K> {
+    fn refresh(&mut self, world: &World) {
+        let generation = world.archetypes_generation();
+        if self.generation != Some(generation) {
+            self.versions.clear();
+            self.generation = Some(generation);
+        }
+        let archetypes = world.archetypes_inner();
+        self.versions.resize(archetypes.len(), None);
+        let mut live = 0;
+        for (archetype, seen) in archetypes.iter().zip(&mut self.versions) {
+            let Some(state) = archetype.get_state::<K>() else {
+                continue;
+            };
+            live += archetype.len() as usize;
+            let version = archetype.column_version(state);
+            if *seen == Some(version) {
+                continue;
+            }
+            *seen = Some(version);
+            let column = archetype.get::<&K>().unwrap();
+            for (&id, key) in archetype.ids().iter().zip(column.iter()) {
+                let entity = unsafe { world.find_entity_from_id(id) };
+                match self.by_id.get(&id) {
+                    Some((old_entity, old_key)) if *old_entity == entity && old_key == key => {
+                        continue
+                    }
+                    Some((old_entity, old_key)) => {
+                        unlist(&mut self.by_key, old_key, *old_entity);
+                    }
+                    None => {}
+                }
+                self.by_id.insert(id, (entity, key.clone()));
+                let entities = self.by_key.entry(key.clone()).or_default();
+                let index = entities.binary_search(&entity).unwrap_or_else(|x| x);
+                entities.insert(index, entity);
+            }
+        }
+        if self.by_id.len() > 2 * live {
+            // Forget entities that have been despawned or lost `K` without being looked up, so
+            // that memory use is proportional to the number of live entries
+            let by_key = &mut self.by_key;
+            self.by_id.retain(|_, (entity, key)| {
+                let live = world
+                    .get::<&K>(*entity)
+                    .map_or(false, |current| *current == *key);
+                if !live {
+                    unlist(by_key, key, *entity);
+                }
+                live
+            });
+        }
+    }
+}
+
+/// Remove `entity` from the list of entities holding `key`
+fn unlist<K: Hash + Eq>(by_key: &mut HashMap<K, Vec<Entity>>, key: &K, entity: Entity) {
+    let Some(entities) = by_key.get_mut(key) else {
+        return;
+    };
+    entities.retain(|&x| x != entity);
+    if entities.is_empty() {
+        by_key.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forgets_unseen_removals() {
+        let mut world = World::new();
+        let index = ValueIndex::<u32>::new(IndexKind::Unique);
+        let entities = (0..100u32).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+        assert_eq!(index.lookup(&world, &5), [entities[5]]);
+        for &entity in &entities[..90] {
+            world.despawn(entity).unwrap();
+        }
+        assert_eq!(index.lookup(&world, &95), [entities[95]]);
+        let state = index.state.lock();
+        assert_eq!(state.by_id.len(), 10);
+        assert_eq!(state.by_key.len(), 10);
+    }
+}
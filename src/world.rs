@@ -1,8 +1,11 @@
 use crate::alloc::{vec, vec::Vec};
-use core::any::TypeId;
+use core::any::{Any, TypeId};
 use core::borrow::Borrow;
 use core::convert::TryFrom;
-use core::hash::{BuildHasherDefault, Hasher};
+use core::hash::{BuildHasherDefault, Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use spin::Mutex;
 
 use core::{fmt, ptr};
@@ -13,12 +16,30 @@ use std::error::Error;
 use hashbrown::hash_map::{Entry, HashMap};
 
 use crate::alloc::boxed::Box;
-use crate::archetype::{Archetype, TypeIdMap, TypeInfo};
+use crate::alloc::sync::Arc;
+use crate::archetype::{Archetype, KeyCmp, RawColumn, TypeIdMap, TypeInfo};
+use crate::budget::{BudgetCallback, SoftBudget};
 use crate::entities::{Entities, EntityMeta, Location, ReserveEntitiesIterator};
-use crate::query::{assert_borrow, assert_distinct};
+use crate::op_log::{Op, OpRecorder};
+use crate::patch::PatchArchetype;
+use crate::plugin::PluginEntry;
+use crate::query::{assert_borrow, assert_distinct, EntityLocation};
+use crate::registry::Requirement;
+use crate::snapshot::ArchetypeSnapshot;
+use crate::validate::{Fixes, Validator};
+use crate::value_index::ValueIndex;
+#[cfg(feature = "audit-log")]
+use crate::AuditEvent;
 use crate::{
-    Bundle, ColumnBatch, ComponentRef, DynamicBundle, Entity, EntityRef, Fetch, MissingComponent,
-    NoSuchEntity, Query, QueryBorrow, QueryMut, QueryOne, TakenEntity, View, ViewBorrow,
+    AllocError, AllocatorState, AllocatorStateMismatch, Budget, BudgetKind, BudgetUsage, Bundle,
+    ChangeKind, ColumnBatch, ColumnBatchType, CommandBuffer, ComponentKey, ComponentRef,
+    ComponentRegistry, DespawnQueue, DynamicBatch, DynamicBundle, Entity, EntityBits,
+    EntityBuilder, EntityRef, EntityRemap, Fetch, FrameAlloc, GenerationWrapStats, IndexKind,
+    MetricsSink, MissingClone, MissingComponent, MissingDefault, NoSuchEntity, OpLog,
+    OperationCounts, OrderToken, Plugin, Prefab, PrefabDiff, PrefabInstance, Query, QueryBorrow,
+    QueryDescription, QueryMut, QueryOne, RegisterDynamicError, Scope, StableHash, StableHasher,
+    TakenEntity, Transition, ValidationError, ValidationMode, View, ViewBorrow, WorldDiff,
+    WorldPatch, WorldSnapshot,
 };
 
 /// An unordered collection of entities, each having any number of distinctly typed components
@@ -39,6 +60,15 @@ use crate::{
 /// following spawns and despawns, that handle may, in rare circumstances, collide with a
 /// newly-allocated `Entity` handle. Very long-lived applications should therefore limit the period
 /// over which they may retain handles of despawned entities.
+///
+/// ### Threading
+///
+/// Worlds are `Send` and `Sync` unless built with `RUSTFLAGS="--cfg hecs_single_threaded"`, which
+/// removes the atomic operations otherwise needed to [reserve](Self::reserve_entity) entities
+/// through a shared reference, for targets without threads such as `wasm32-unknown-unknown`. Code
+/// that moves or shares a world between threads then fails to compile. This is a rustflag rather
+/// than a Cargo feature so that only the final binary's builder can opt in; a dependency can't
+/// make worlds unsendable for every other crate in the graph.
 pub struct World {
     entities: Entities,
     archetypes: ArchetypeSet,
@@ -50,6 +80,32 @@ pub struct World {
     /// Maps source archetype and static bundle types to the archetype that an entity is moved to
     /// after removing the components from that bundle.
     remove_edges: IndexTypeIdMap<u32>,
+    /// Operations performed during the current tick, excluding `column_grows`, which is tracked
+    /// by each archetype
+    counts: OperationCounts,
+    metrics: Option<Box<dyn MetricsSink>>,
+    frame: FrameAlloc,
+    despawn_queue: DespawnQueue,
+    /// Entities that lost each component type registered with `track_removals`, awaiting
+    /// `removed`
+    removals: TypeIdMap<Vec<Entity>>,
+    /// `ValueIndex<K>` for each component type `K` registered with `register_index`
+    value_indexes: TypeIdMap<Box<dyn Any + Send + Sync>>,
+    /// Policy and name of each component type registered with `register_singleton`
+    singletons: TypeIdMap<(SingletonPolicy, &'static str)>,
+    /// Component types registered with `register_dynamic`, by name
+    dynamic_types: HashMap<Box<str>, ComponentKey>,
+    /// Sets of component types registered with `register_exclusive`, sorted, with their policy and
+    /// name
+    exclusions: Vec<(Box<[TypeId]>, ExclusionPolicy, &'static str)>,
+    /// Dependencies installed by `set_requirements`
+    requirements: Vec<Requirement>,
+    validators: Vec<Validator>,
+    plugins: Vec<PluginEntry>,
+    budget: Budget,
+    soft_budget: Option<SoftBudget>,
+    /// Recording started by `start_op_log`
+    op_log: Option<Box<OpRecorder>>,
     id: u64,
 }
 
@@ -71,10 +127,345 @@ impl World {
             bundle_to_archetype: HashMap::default(),
             insert_edges: HashMap::default(),
             remove_edges: HashMap::default(),
+            counts: OperationCounts::default(),
+            metrics: None,
+            frame: FrameAlloc::new(),
+            despawn_queue: DespawnQueue::new(),
+            removals: HashMap::default(),
+            value_indexes: HashMap::default(),
+            singletons: HashMap::default(),
+            dynamic_types: HashMap::default(),
+            exclusions: Vec::new(),
+            requirements: Vec::new(),
+            validators: Vec::new(),
+            plugins: Vec::new(),
+            budget: Budget::default(),
+            soft_budget: None,
+            op_log: None,
             id,
         }
     }
 
+    /// Configure a world before constructing it
+    ///
+    /// See [`WorldBuilder`].
+    pub fn builder() -> WorldBuilder {
+        WorldBuilder::new()
+    }
+
+    /// The order in which queries visit entities, as configured by
+    /// [`WorldBuilder::iteration_order`]
+    pub fn iteration_order(&self) -> IterationOrder {
+        if self.entities.tracks_spawn_order() {
+            IterationOrder::SpawnOrder
+        } else {
+            IterationOrder::Storage
+        }
+    }
+
+    /// Limits on the IDs and generations of this world's entities
+    ///
+    /// See [`WorldBuilder::entity_bits`].
+    pub fn entity_bits(&self) -> EntityBits {
+        self.entities.bits()
+    }
+
+    /// The current simulation tick
+    ///
+    /// Starts at 0 and is advanced only by [`advance_tick`](Self::advance_tick), so that change
+    /// detection with [`QueryBorrow::changed_since`] is tied to the application's notion of time
+    /// rather than to which code happened to run last. Modifications made during a tick are
+    /// recorded with that tick.
+    pub fn tick(&self) -> u32 {
+        self.archetypes.tick
+    }
+
+    /// Advance to the next simulation tick, returning it
+    ///
+    /// Also releases everything allocated from [`frame_alloc`](Self::frame_alloc) for reuse.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tick would overflow a `u32`.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let e = world.spawn((1,));
+    /// let last = world.advance_tick();
+    /// assert_eq!(world.query::<&i32>().changed_since::<i32>(last).iter().count(), 0);
+    /// *world.get::<&mut i32>(e).unwrap() += 1;
+    /// assert_eq!(world.query::<&i32>().changed_since::<i32>(last).iter().count(), 1);
+    /// ```
+    pub fn advance_tick(&mut self) -> u32 {
+        let tick = self.archetypes.tick.checked_add(1).expect("tick overflow");
+        let mut counts = mem::take(&mut self.counts);
+        for archetype in &mut self.archetypes.archetypes {
+            counts.column_grows += archetype.take_column_grows();
+        }
+        if let Some(ref mut metrics) = self.metrics {
+            metrics.record(self.archetypes.tick, &counts);
+        }
+        self.archetypes.set_tick(tick);
+        self.entities.tick = tick;
+        self.frame.reset();
+        tick
+    }
+
+    /// Scratch memory for temporary data that's only needed until the end of the current tick
+    ///
+    /// Reset by every call to [`advance_tick`](Self::advance_tick). See [`FrameAlloc`].
+    pub fn frame_alloc(&self) -> &FrameAlloc {
+        &self.frame
+    }
+
+    /// Operations performed since the current tick began
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((1,));
+    /// world.insert_one(a, true).unwrap();
+    /// let counts = world.operation_counts();
+    /// assert_eq!(counts.spawns, 1);
+    /// assert_eq!(counts.archetype_moves, 1);
+    /// world.advance_tick();
+    /// assert_eq!(world.operation_counts(), OperationCounts::default());
+    /// ```
+    pub fn operation_counts(&self) -> OperationCounts {
+        let mut counts = self.counts;
+        for archetype in &self.archetypes.archetypes {
+            counts.column_grows += archetype.column_grows();
+        }
+        counts
+    }
+
+    /// Install `sink` to receive the [`OperationCounts`] of each tick ended by
+    /// [`advance_tick`](Self::advance_tick), replacing any previous sink
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// # use std::sync::{Arc, Mutex};
+    /// let log = Arc::new(Mutex::new(Vec::new()));
+    /// let mut world = World::new();
+    /// let sink = log.clone();
+    /// world.set_metrics_sink(move |tick, counts: &OperationCounts| {
+    ///     sink.lock().unwrap().push((tick, counts.spawns));
+    /// });
+    /// world.spawn((1,));
+    /// world.advance_tick();
+    /// world.advance_tick();
+    /// assert_eq!(*log.lock().unwrap(), [(0, 1), (1, 0)]);
+    /// ```
+    pub fn set_metrics_sink(&mut self, sink: impl MetricsSink + 'static) {
+        self.metrics = Some(Box::new(sink));
+    }
+
+    /// Remove the sink installed by [`set_metrics_sink`](Self::set_metrics_sink), if any
+    pub fn take_metrics_sink(&mut self) -> Option<Box<dyn MetricsSink>> {
+        self.metrics.take()
+    }
+
+    /// Cap the resources that fallible methods may use, replacing any previous caps
+    ///
+    /// [`try_spawn`](Self::try_spawn), [`try_spawn_batch`](Self::try_spawn_batch) and
+    /// [`try_insert`](Self::try_insert) fail with [`AllocError::BudgetExceeded`] rather than
+    /// exceed `budget`, making them suitable for operations driven by untrusted input. Other
+    /// methods aren't limited, but their usage counts towards the caps.
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.budget = budget;
+    }
+
+    /// The caps installed by [`set_budget`](Self::set_budget)
+    pub fn budget(&self) -> Budget {
+        self.budget
+    }
+
+    /// Call `callback` whenever an operation checked against the [hard caps](Self::set_budget)
+    /// would take a resource beyond its threshold in `budget`, replacing any previous thresholds
+    ///
+    /// The callback is passed the resource and the usage the operation would result in. It runs
+    /// once per crossing: a resource must drop back to its threshold before it's reported again.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// # use std::sync::{Arc, Mutex};
+    /// let warnings = Arc::new(Mutex::new(Vec::new()));
+    /// let mut world = World::new();
+    /// let sink = warnings.clone();
+    /// let soft = Budget {
+    ///     entities: Some(1),
+    ///     ..Budget::default()
+    /// };
+    /// world.set_soft_budget(soft, move |kind, usage| {
+    ///     sink.lock().unwrap().push((kind, usage.entities));
+    /// });
+    /// for i in 0..3 {
+    ///     world.try_spawn((i,)).unwrap();
+    /// }
+    /// assert_eq!(*warnings.lock().unwrap(), [(BudgetKind::Entities, 2)]);
+    /// ```
+    pub fn set_soft_budget(
+        &mut self,
+        budget: Budget,
+        callback: impl FnMut(BudgetKind, &BudgetUsage) + Send + Sync + 'static,
+    ) {
+        let callback: BudgetCallback = Box::new(callback);
+        self.soft_budget = Some(SoftBudget::new(budget, callback));
+    }
+
+    /// Remove the thresholds installed by [`set_soft_budget`](Self::set_soft_budget), if any
+    pub fn clear_soft_budget(&mut self) {
+        self.soft_budget = None;
+    }
+
+    /// Resources currently used, as limited by [`set_budget`](Self::set_budget)
+    pub fn budget_usage(&self) -> BudgetUsage {
+        BudgetUsage {
+            entities: self.entities.len(),
+            component_bytes: self.archetypes.reserved_bytes(),
+            archetypes: self.archetypes.archetypes.len(),
+        }
+    }
+
+    /// Fail if adding `entities` entities, reserving `component_bytes` more bytes of components
+    /// and creating `archetypes` archetypes would exceed the budget, and notify the soft budget's callback
+    fn check_budget(
+        &mut self,
+        entities: u32,
+        component_bytes: usize,
+        archetypes: usize,
+    ) -> Result<(), AllocError> {
+        if self.budget.is_unlimited() && self.soft_budget.is_none() {
+            return Ok(());
+        }
+        let usage = self.budget_usage();
+        let usage = BudgetUsage {
+            entities: usage.entities.saturating_add(entities),
+            component_bytes: usage.component_bytes.saturating_add(component_bytes),
+            archetypes: usage.archetypes.saturating_add(archetypes),
+        };
+        if let Some(kind) = self.budget.exceeded(&usage) {
+            return Err(AllocError::BudgetExceeded(kind));
+        }
+        if let Some(ref mut soft) = self.soft_budget {
+            soft.check(&usage);
+        }
+        Ok(())
+    }
+
+    /// Whether checking the budget is necessary
+    fn has_budget(&self) -> bool {
+        !self.budget.is_unlimited() || self.soft_budget.is_some()
+    }
+
+    /// Set the current simulation tick, e.g. when restoring saved state
+    ///
+    /// Change ticks already recorded are left as-is, so moving the tick backwards may cause
+    /// [`QueryBorrow::changed_since`] to report components as changed in the future.
+    pub fn set_tick(&mut self, tick: u32) {
+        self.archetypes.set_tick(tick);
+        self.entities.tick = tick;
+    }
+
+    /// Track additions and mutable accesses of `T` components per entity, for use with the
+    /// [`Added`](crate::Added) and [`Changed`](crate::Changed) queries
+    ///
+    /// Unlike the per-column [`Archetype::change_tick`], this costs memory and time for every
+    /// entity having a `T`, so is opt-in. Existing `T` components are considered neither added nor
+    /// changed. Has no effect if `T` is already tracked.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// world.track_changes::<i32>();
+    /// let a = world.spawn((1,));
+    /// let b = world.spawn((2,));
+    /// world.clear_trackers();
+    /// for (_, x) in world.query_mut::<With<&mut i32, &bool>>() {
+    ///     *x += 1;
+    /// }
+    /// world.insert_one(b, true).unwrap();
+    /// for (_, x) in world.query_mut::<With<&mut i32, &bool>>() {
+    ///     *x += 1;
+    /// }
+    /// let mut changed = world.query::<Changed<i32>>();
+    /// let changed = changed.iter().filter(|&(_, x)| x).map(|(e, _)| e).collect::<Vec<_>>();
+    /// assert_eq!(changed, [b]);
+    /// ```
+    pub fn track_changes<T: Component>(&mut self) {
+        self.track_changes_by_id(TypeId::of::<T>());
+    }
+
+    fn track_changes_by_id(&mut self, id: TypeId) {
+        if self.archetypes.tracked.contains(&id) {
+            return;
+        }
+        self.archetypes.tracked.push(id);
+        for archetype in &mut self.archetypes.archetypes {
+            archetype.track(id, 0);
+        }
+    }
+
+    /// Begin a new change tracking epoch, so that [`Added`](crate::Added) and
+    /// [`Changed`](crate::Changed) only report components added or mutably accessed after this
+    /// call
+    ///
+    /// # Panics
+    ///
+    /// Panics if called more than `u32::MAX - 1` times.
+    pub fn clear_trackers(&mut self) {
+        let epoch = self
+            .archetypes
+            .epoch
+            .checked_add(1)
+            .expect("epoch overflow");
+        self.archetypes.epoch = epoch;
+        for archetype in &mut self.archetypes.archetypes {
+            archetype.set_epoch(epoch);
+        }
+    }
+
+    /// Start recording entities that lose their `T` component, whether by removal or despawning,
+    /// to be drained by [`removed`](Self::removed)
+    ///
+    /// Has no effect if `T` is already tracked.
+    pub fn track_removals<T: Component>(&mut self) {
+        self.removals.entry(TypeId::of::<T>()).or_default();
+    }
+
+    /// Drain the entities that lost their `T` component since the previous call, in the order the
+    /// components were removed
+    ///
+    /// Always empty unless [`track_removals`](Self::track_removals) was called for `T`, after
+    /// which entities accumulate until drained. An entity may appear more than once, and may have
+    /// since been despawned, or regained a `T`.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// world.track_removals::<u32>();
+    /// let a = world.spawn((1u32, true));
+    /// let b = world.spawn((2u32,));
+    /// world.remove_one::<bool>(a).unwrap();
+    /// world.remove_one::<u32>(a).unwrap();
+    /// world.despawn(b).unwrap();
+    /// assert_eq!(world.removed::<u32>().collect::<Vec<_>>(), [a, b]);
+    /// assert_eq!(world.removed::<u32>().count(), 0);
+    /// ```
+    pub fn removed<T: Component>(&mut self) -> impl Iterator<Item = Entity> + '_ {
+        self.removals
+            .get_mut(&TypeId::of::<T>())
+            .into_iter()
+            .flat_map(|x| x.drain(..))
+    }
+
     /// Create an entity with certain components
     ///
     /// Returns the ID of the newly created entity.
@@ -94,14 +485,21 @@ impl World {
     /// let a = world.spawn((123, "abc"));
     /// let b = world.spawn((456, true));
     /// ```
+    #[cfg_attr(feature = "audit-log", track_caller)]
     pub fn spawn(&mut self, components: impl DynamicBundle) -> Entity {
         // Ensure all entity allocations are accounted for so `self.entities` can realloc if
         // necessary
         self.flush();
+        self.resolve_exclusions(None, &components, &[]);
+        self.claim_singletons(None, &components);
+        let fixes = self.check_transition(None, &components, &[]);
 
         let entity = self.entities.alloc();
 
         self.spawn_inner(entity, components);
+        self.counts.spawns += 1;
+        self.apply_fixes(entity, fixes);
+        self.fulfil_requirements_of(entity);
 
         entity
     }
@@ -127,25 +525,116 @@ impl World {
     /// world.spawn_at(a, (789, "ABC"));
     /// assert!(world.contains(a));
     /// ```
+    #[cfg_attr(feature = "audit-log", track_caller)]
     pub fn spawn_at(&mut self, handle: Entity, components: impl DynamicBundle) {
         // Ensure all entity allocations are accounted for so `self.entities` can realloc if
         // necessary
         self.flush();
+        self.resolve_exclusions(None, &components, &[]);
+        self.claim_singletons(Some(handle), &components);
+        let fixes = self.check_transition(None, &components, &[]);
 
+        let replaced = self.entities.resolve(handle.id);
         let loc = self.entities.alloc_at(handle);
         if let Some(loc) = loc {
-            if let Some(moved) = unsafe {
-                self.archetypes.archetypes[loc.archetype as usize].remove(loc.index, true)
-            } {
-                self.entities.meta[moved as usize].location.index = loc.index;
-            }
+            self.record_removals(replaced.unwrap(), loc.archetype, None);
+            let archetype = &mut self.archetypes.archetypes[loc.archetype as usize];
+            let moved = unsafe { archetype.remove(loc.index, true) };
+            self.entities.relocate(archetype, moved);
+            self.counts.despawns += 1;
         }
 
         self.spawn_inner(handle, components);
+        self.counts.spawns += 1;
+        self.apply_fixes(handle, fixes);
+        self.fulfil_requirements_of(handle);
+    }
+
+    /// Like [`spawn`](Self::spawn), but fails rather than panicking or aborting if the entity or
+    /// its storage can't be allocated, if `components` contain a
+    /// [singleton](Self::register_singleton) held by another entity under
+    /// [`SingletonPolicy::Reject`], or if they contain several
+    /// [mutually exclusive](Self::register_exclusive) components
+    ///
+    /// On failure, `components` are dropped and the world is left unchanged, except that the
+    /// archetype for `components` may have been created.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::builder().entity_bits(EntityBits::new(1, 32)).build();
+    /// world.try_spawn((1,)).unwrap();
+    /// world.try_spawn((2,)).unwrap();
+    /// assert_eq!(
+    ///     world.try_spawn((3,)),
+    ///     Err(TrySpawnError::Alloc(AllocError::TooManyEntities))
+    /// );
+    /// ```
+    #[cfg_attr(feature = "audit-log", track_caller)]
+    pub fn try_spawn(&mut self, components: impl DynamicBundle) -> Result<Entity, TrySpawnError> {
+        self.flush();
+        if let Some((id, _)) = components.with_ids(|ids| self.self_exclusion(ids)) {
+            return Err(TrySpawnError::Excluded(id));
+        }
+        if let Some(holder) = self.singleton_conflict(None, &components) {
+            return Err(TrySpawnError::SingletonHeld(holder));
+        }
+        self.resolve_exclusions(None, &components, &[]);
+        self.claim_singletons(None, &components);
+        let fixes = self.check_transition(None, &components, &[]);
+        if self.has_budget() {
+            let (bytes, new) = components
+                .with_ids(|ids| self.reserve_bytes(ids, 1, || self.bundle_bytes(&components)));
+            self.check_budget(1, bytes, new as usize)?;
+        }
+        self.entities.try_reserve(1)?;
+        let archetype_id = self.bundle_archetype(&components);
+        self.archetypes.archetypes[archetype_id as usize].try_reserve(1)?;
+
+        let entity = self.entities.alloc();
+        self.spawn_into(entity, archetype_id, components);
+        self.log_spawn(entity);
+        self.counts.spawns += 1;
+        self.apply_fixes(entity, fixes);
+        self.fulfil_requirements_of(entity);
+        Ok(entity)
     }
 
     fn spawn_inner(&mut self, entity: Entity, components: impl DynamicBundle) {
-        let archetype_id = match components.key() {
+        let archetype_id = self.bundle_archetype(&components);
+        self.spawn_into(entity, archetype_id, components);
+        self.log_spawn(entity);
+    }
+
+    /// Bytes of component storage that reserving `additional` entities in the archetype of `ids`
+    /// would allocate, with `row_size` computing the size of one entity's components, and whether
+    /// the archetype would be created
+    fn reserve_bytes(
+        &self,
+        ids: &[TypeId],
+        additional: u32,
+        row_size: impl FnOnce() -> usize,
+    ) -> (usize, bool) {
+        match self.archetypes.index.get(ids) {
+            Some(&x) => (
+                self.archetypes.archetypes[x as usize].reserve_bytes(additional),
+                false,
+            ),
+            None => (Archetype::new_reserve_bytes(row_size(), additional), true),
+        }
+    }
+
+    /// Total size of the components in `components`
+    fn bundle_bytes(&self, components: &impl DynamicBundle) -> usize {
+        components
+            .type_info()
+            .iter()
+            .map(|ty| ty.layout().size())
+            .sum()
+    }
+
+    /// Find or create the archetype with exactly the component types of `components`
+    fn bundle_archetype(&mut self, components: &impl DynamicBundle) -> u32 {
+        let archetype = match components.key() {
             Some(k) => {
                 let archetypes = &mut self.archetypes;
                 *self.bundle_to_archetype.entry(k).or_insert_with(|| {
@@ -154,7 +643,12 @@ impl World {
             }
             None => components.with_ids(|ids| self.archetypes.get(ids, || components.type_info())),
         };
+        let [archetype] = self.settle_archetypes([archetype]);
+        archetype
+    }
 
+    /// Store `components`, whose types must be exactly those of `archetype_id`, as `entity`
+    fn spawn_into(&mut self, entity: Entity, archetype_id: u32, components: impl DynamicBundle) {
         let archetype = &mut self.archetypes.archetypes[archetype_id as usize];
         unsafe {
             let index = archetype.allocate(entity.id);
@@ -165,6 +659,134 @@ impl World {
                 archetype: archetype_id,
                 index,
             };
+            let moved = archetype.reposition(index);
+            self.entities.relocate(archetype, moved);
+        }
+    }
+
+    /// Record the spawning of `entity` in the op log, if any
+    fn log_spawn(&mut self, entity: Entity) {
+        if let Some(ref mut log) = self.op_log {
+            let loc = self.entities.meta[entity.id as usize].location;
+            let archetype = &self.archetypes.archetypes[loc.archetype as usize];
+            log.spawned(self.archetypes.tick, entity, archetype, loc.index);
+        }
+    }
+
+    /// Record the insertion of the components of `ids` into `entity` in the op log, if any
+    fn log_insert(&mut self, entity: Entity, ids: Option<&[TypeId]>) {
+        if let (Some(log), Some(ids)) = (&mut self.op_log, ids) {
+            let loc = self.entities.meta[entity.id as usize].location;
+            let archetype = &self.archetypes.archetypes[loc.archetype as usize];
+            log.inserted(self.archetypes.tick, entity, archetype, loc.index, ids);
+        }
+    }
+
+    /// Look up the archetype of entities having exactly the components of `B`, for use with
+    /// [`spawn_with_id`](Self::spawn_with_id) and [`insert_with_id`](Self::insert_with_id)
+    ///
+    /// Creates the archetype if necessary. The returned ID remains valid for the lifetime of this
+    /// world.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let id = world.register_bundle::<(i32, bool)>();
+    /// for i in 0..10 {
+    ///     world.spawn_with_id(id, (i, true));
+    /// }
+    /// let e = world.reserve_entity();
+    /// world.insert_with_id(e, id, (10, false)).unwrap();
+    /// assert_eq!(world.query_mut::<&i32>().into_iter().count(), 11);
+    /// ```
+    pub fn register_bundle<B: Bundle + 'static>(&mut self) -> BundleId<B> {
+        let archetype = self.bundle_archetype_static::<B>();
+        BundleId {
+            archetype,
+            world: self.id,
+            renumbered: self.archetypes.renumbered,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Like [`spawn`](Self::spawn), but skips looking up the archetype of `B`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` was registered with a different world.
+    #[cfg_attr(feature = "audit-log", track_caller)]
+    pub fn spawn_with_id<B: Bundle + 'static>(&mut self, id: BundleId<B>, components: B) -> Entity {
+        self.check_bundle_id(id);
+        self.flush();
+        let archetype = self.bundle_id_archetype(id);
+        self.resolve_exclusions(None, &components, &[]);
+        self.claim_singletons(None, &components);
+        let fixes = self.check_transition(None, &components, &[]);
+        let entity = self.entities.alloc();
+        self.spawn_into(entity, archetype, components);
+        self.log_spawn(entity);
+        self.counts.spawns += 1;
+        self.apply_fixes(entity, fixes);
+        self.fulfil_requirements_of(entity);
+        entity
+    }
+
+    /// Like [`insert`](Self::insert), but skips looking up the archetype of `B` when `entity` has
+    /// no components, e.g. because it was just [reserved](Self::reserve_entity)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` was registered with a different world.
+    pub fn insert_with_id<B: Bundle + 'static>(
+        &mut self,
+        entity: Entity,
+        id: BundleId<B>,
+        components: B,
+    ) -> Result<(), NoSuchEntity> {
+        self.check_bundle_id(id);
+        self.flush();
+        self.entities.get(entity)?;
+        self.resolve_exclusions(Some(entity), &components, &[]);
+        self.claim_singletons(Some(entity), &components);
+        let fixes = self.check_transition(Some(entity), &components, &[]);
+        let loc = self.entities.get(entity)?;
+        if loc.archetype != 0 {
+            self.insert_inner(entity, components, loc.archetype, loc);
+            self.apply_fixes(entity, fixes);
+            self.fulfil_requirements_of(entity);
+            return Ok(());
+        }
+        let archetype = self.bundle_id_archetype(id);
+        let empty = &mut self.archetypes.archetypes[0];
+        let moved = unsafe { empty.remove(loc.index, false) };
+        self.entities.relocate(empty, moved);
+        let ids = self
+            .op_log
+            .is_some()
+            .then(|| components.with_ids(|ids| ids.to_vec()));
+        self.spawn_into(entity, archetype, components);
+        self.log_insert(entity, ids.as_deref());
+        self.counts.archetype_moves += 1;
+        self.apply_fixes(entity, fixes);
+        self.fulfil_requirements_of(entity);
+        Ok(())
+    }
+
+    fn check_bundle_id<B>(&self, id: BundleId<B>) {
+        assert_eq!(
+            id.world, self.id,
+            "bundle ID was registered with a different world"
+        );
+    }
+
+    /// The current index of the archetype identified by `id`, looking it up again if archetypes
+    /// have been renumbered since it was registered
+    fn bundle_id_archetype<B: Bundle + 'static>(&mut self, id: BundleId<B>) -> u32 {
+        if id.renumbered == self.archetypes.renumbered {
+            id.archetype
+        } else {
+            self.bundle_archetype_static::<B>()
         }
     }
 
@@ -173,6 +795,12 @@ impl World {
     /// Faster than calling [`spawn`](Self::spawn) repeatedly with the same components, but requires
     /// that component types are known at compile time.
     ///
+    /// # Panics
+    ///
+    /// Panics if the components include a type registered with
+    /// [`register_singleton`](Self::register_singleton), which must be spawned individually, or
+    /// several of a set registered with [`register_exclusive`](Self::register_exclusive).
+    ///
     /// # Example
     /// ```
     /// # use hecs::*;
@@ -190,6 +818,12 @@ impl World {
         // Ensure all entity allocations are accounted for so `self.entities` can realloc if
         // necessary
         self.flush();
+        if let Some((_, name)) = I::Item::with_static_ids(|ids| self.self_exclusion(ids)) {
+            panic!("bundle contains several of the components of {}", name);
+        }
+        if let Some((_, name)) = I::Item::with_static_ids(|ids| self.batch_singleton(ids)) {
+            panic!("batch contains singleton component {}", name);
+        }
 
         let iter = iter.into_iter();
         let (lower, upper) = iter.size_hint();
@@ -202,18 +836,82 @@ impl World {
             entities: &mut self.entities,
             archetype_id,
             archetype: &mut self.archetypes.archetypes[archetype_id as usize],
+            spawns: &mut self.counts.spawns,
+            log: self.op_log.as_deref_mut(),
+            tick: self.archetypes.tick,
+        }
+    }
+
+    /// Like [`spawn_batch`](Self::spawn_batch), but fails rather than panicking or aborting if
+    /// the entities or their storage can't be allocated
+    ///
+    /// Allocates space for as many entities as the upper bound of `iter`'s size hint, or the lower
+    /// bound if there is none, before spawning any. Entities beyond that are allocated infallibly
+    /// as by `spawn_batch`. On failure, no entities are spawned.
+    pub fn try_spawn_batch<I>(
+        &mut self,
+        iter: I,
+    ) -> Result<SpawnBatchIter<'_, I::IntoIter>, TrySpawnError>
+    where
+        I: IntoIterator,
+        I::Item: Bundle + 'static,
+    {
+        self.flush();
+        if let Some((id, _)) = I::Item::with_static_ids(|ids| self.self_exclusion(ids)) {
+            return Err(TrySpawnError::Excluded(id));
+        }
+        if let Some((id, _)) = I::Item::with_static_ids(|ids| self.batch_singleton(ids)) {
+            return Err(TrySpawnError::SingletonBatch(id));
+        }
+
+        let iter = iter.into_iter();
+        let (lower, upper) = iter.size_hint();
+        let count =
+            u32::try_from(upper.unwrap_or(lower)).map_err(|_| AllocError::TooManyEntities)?;
+        if self.has_budget() {
+            let (bytes, new) = I::Item::with_static_ids(|ids| {
+                self.reserve_bytes(ids, count, || {
+                    I::Item::with_static_type_info(|info| {
+                        info.iter().map(|ty| ty.layout().size()).sum::<usize>()
+                    })
+                })
+            });
+            self.check_budget(count, bytes, new as usize)?;
         }
+        self.entities.try_reserve(count)?;
+        let archetype_id = self.bundle_archetype_static::<I::Item>();
+        self.archetypes.archetypes[archetype_id as usize].try_reserve(count)?;
+
+        Ok(SpawnBatchIter {
+            inner: iter,
+            entities: &mut self.entities,
+            archetype_id,
+            archetype: &mut self.archetypes.archetypes[archetype_id as usize],
+            spawns: &mut self.counts.spawns,
+            log: self.op_log.as_deref_mut(),
+            tick: self.archetypes.tick,
+        })
     }
 
     /// Super-efficiently spawn the contents of a [`ColumnBatch`]
     ///
     /// The fastest, but most specialized, way to spawn large numbers of entities. Useful for high
     /// performance deserialization. Supports dynamic component types.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch` holds several entities and its components include a type registered with
+    /// [`register_singleton`](Self::register_singleton), or if they include several of a set
+    /// registered with [`register_exclusive`](Self::register_exclusive). A batch of one entity is
+    /// otherwise handled like [`spawn`](Self::spawn).
+    #[cfg_attr(feature = "audit-log", track_caller)]
     pub fn spawn_column_batch(&mut self, batch: ColumnBatch) -> SpawnColumnBatchIter<'_> {
         self.flush();
 
         let archetype = batch.0;
+        self.admit_batch(&archetype);
         let entity_count = archetype.len();
+        self.counts.spawns += u64::from(entity_count);
         // Store component data
         let (archetype_id, base) = self.archetypes.insert_batch(archetype);
 
@@ -227,6 +925,17 @@ impl World {
             archetype.set_entity_id(index, id);
             index += 1;
         }
+        let moved = archetype.sort();
+        self.entities.relocate(archetype, moved);
+        let [archetype_id] = self.settle_archetypes([archetype_id]);
+        if self.op_log.is_some() {
+            let mut ids = id_alloc.clone();
+            while let Some(id) = ids.next(&self.entities) {
+                let entity = unsafe { self.entities.resolve_unknown_gen(id) };
+                self.log_spawn(entity);
+            }
+        }
+        self.fulfil_archetype_requirements(archetype_id);
 
         // Return iterator over new IDs
         SpawnColumnBatchIter {
@@ -236,41 +945,393 @@ impl World {
         }
     }
 
-    /// Hybrid of [`spawn_column_batch`](Self::spawn_column_batch) and [`spawn_at`](Self::spawn_at)
-    pub fn spawn_column_batch_at(&mut self, handles: &[Entity], batch: ColumnBatch) {
-        let archetype = batch.0;
-        assert_eq!(
-            handles.len(),
-            archetype.len() as usize,
-            "number of entity IDs {} must match number of entities {}",
-            handles.len(),
-            archetype.len()
-        );
+    /// Spawn `n` copies of `prototype`
+    ///
+    /// Every component of `prototype` whose type was registered in `registry` with
+    /// [`ComponentRegistry::register_clone`] is cloned into each copy. Other components are
+    /// skipped. The copies are stored with the same fast path as
+    /// [`spawn_column_batch`](Self::spawn_column_batch), which is much cheaper than spawning each
+    /// copy individually.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_clone::<i32>("i32");
+    /// let mut world = World::new();
+    /// let prototype = world.spawn((42, true));
+    /// let copies = world.spawn_clones(prototype, 3, &registry).unwrap().collect::<Vec<_>>();
+    /// assert_eq!(copies.len(), 3);
+    /// for copy in copies {
+    ///     assert_eq!(*world.get::<&i32>(copy).unwrap(), 42);
+    ///     assert!(!world.satisfies::<&bool>(copy).unwrap());
+    /// }
+    /// ```
+    pub fn spawn_clones(
+        &mut self,
+        prototype: Entity,
+        n: u32,
+        registry: &ComponentRegistry,
+    ) -> Result<SpawnColumnBatchIter<'_>, NoSuchEntity> {
+        self.flush();
+        let loc = self.entities.get(prototype)?;
+        let source = &self.archetypes.archetypes[loc.archetype as usize];
 
-        // Drop components of entities that will be replaced
-        for &handle in handles {
-            let loc = self.entities.alloc_at(handle);
-            if let Some(loc) = loc {
-                if let Some(moved) = unsafe {
-                    self.archetypes.archetypes[loc.archetype as usize].remove(loc.index, true)
-                } {
-                    self.entities.meta[moved as usize].location.index = loc.index;
+        let clones = source
+            .types()
+            .iter()
+            .filter_map(|ty| Some((*ty, registry.get(ty.id())?.dynamic_clone()?)))
+            .collect::<Vec<_>>();
+        let mut batch_type = ColumnBatchType::new();
+        for &(ty, _) in &clones {
+            batch_type.add_dynamic(ty);
+        }
+        let mut batch = batch_type.into_batch(n);
+        for &(ty, clone) in &clones {
+            unsafe {
+                let src = source
+                    .get_dynamic(ty.id(), ty.layout().size(), loc.index)
+                    .unwrap();
+                for _ in 0..n {
+                    (clone.func)(src.as_ptr(), &mut |ptr, ty| {
+                        batch.push_dynamic(ty, ptr);
+                    });
                 }
             }
         }
-
-        // Store components
-        let (archetype_id, base) = self.archetypes.insert_batch(archetype);
-
-        // Fix up entity IDs
-        let archetype = &mut self.archetypes.archetypes[archetype_id as usize];
-        for (&handle, index) in handles.iter().zip(base as usize..) {
-            archetype.set_entity_id(index, handle.id());
-            self.entities.meta[handle.id() as usize].location = Location {
-                archetype: archetype_id,
-                index: index as u32,
-            };
-        }
+        let batch = batch.build().expect("every column was filled");
+        Ok(self.spawn_column_batch(batch))
+    }
+
+    /// Rewrite the entity handles within components according to `remap`, returning the number of
+    /// handles changed
+    ///
+    /// Visits every component whose type was registered in `registry` with
+    /// [`ComponentRegistry::register_entities`]. Useful after copying entities between worlds,
+    /// renumbering them, or instantiating groups of entities that refer to each other, whenever
+    /// references must follow entities to their new handles. Columns containing a rewritten handle
+    /// are marked as changed.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Parent(Entity);
+    ///
+    /// impl VisitEntities for Parent {
+    ///     fn visit_entities(&mut self, f: &mut dyn FnMut(&mut Entity)) {
+    ///         f(&mut self.0)
+    ///     }
+    /// }
+    ///
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_entities::<Parent>("Parent");
+    /// let mut world = World::new();
+    /// let old = world.spawn(());
+    /// let new = world.spawn(());
+    /// let child = world.spawn((Parent(old),));
+    /// let remap = [(old, new)].into_iter().collect::<EntityRemap>();
+    /// assert_eq!(world.remap_entities(&remap, &registry), 1);
+    /// assert_eq!(world.get::<&Parent>(child).unwrap().0, new);
+    /// ```
+    pub fn remap_entities(&mut self, remap: &EntityRemap, registry: &ComponentRegistry) -> usize {
+        self.flush();
+        let mut total = 0;
+        if remap.is_empty() {
+            return total;
+        }
+        for archetype in &mut self.archetypes.archetypes {
+            for (state, ty) in archetype.types().iter().enumerate() {
+                let Some(visit) = registry.get(ty.id()).and_then(|x| x.visit_fn()) else {
+                    continue;
+                };
+                let mut changed = 0;
+                for index in 0..archetype.len() {
+                    unsafe {
+                        let component = archetype
+                            .get_dynamic(ty.id(), ty.layout().size(), index)
+                            .unwrap();
+                        visit(component.as_ptr(), &mut |entity| {
+                            changed += remap.apply(entity) as usize;
+                        });
+                    }
+                }
+                if changed != 0 {
+                    archetype.mark_mutated(state);
+                    archetype.mark_rows_changed(state);
+                }
+                total += changed;
+            }
+        }
+        total
+    }
+
+    /// Move every entity of `other` into this world under a fresh handle, returning the mapping
+    /// from old to new handles
+    ///
+    /// References between the moved entities are rewritten with
+    /// [`remap_entities`](Self::remap_entities) before they're moved, so a world deserialized from
+    /// a save, which preserves the saved handles, can be merged into a live world without its
+    /// handles colliding with or referring to unrelated live entities. Handles to entities outside of `other` are left unchanged. Entities are moved in
+    /// ascending order of their handles in `other`.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Parent(Entity);
+    ///
+    /// impl VisitEntities for Parent {
+    ///     fn visit_entities(&mut self, f: &mut dyn FnMut(&mut Entity)) {
+    ///         f(&mut self.0)
+    ///     }
+    /// }
+    ///
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_entities::<Parent>("Parent");
+    /// let mut saved = World::new();
+    /// let parent = saved.spawn(("parent",));
+    /// let child = saved.spawn((Parent(parent),));
+    ///
+    /// let mut world = World::new();
+    /// world.spawn(("unrelated",));
+    /// let remap = world.merge(saved, &registry);
+    /// let (parent, child) = (remap.get(parent).unwrap(), remap.get(child).unwrap());
+    /// assert_eq!(world.get::<&Parent>(child).unwrap().0, parent);
+    /// assert_eq!(*world.get::<&&str>(parent).unwrap(), "parent");
+    /// ```
+    pub fn merge(&mut self, mut other: World, registry: &ComponentRegistry) -> EntityRemap {
+        other.flush();
+        let mut old = other.iter().map(|x| x.entity()).collect::<Vec<_>>();
+        old.sort_unstable();
+        let remap = old
+            .iter()
+            .copied()
+            .zip(self.reserve_entities(old.len() as u32))
+            .collect::<EntityRemap>();
+        other.remap_entities(&remap, registry);
+        for entity in old {
+            let components = other.take(entity).unwrap();
+            self.insert(remap.get(entity).unwrap(), components).unwrap();
+        }
+        remap
+    }
+
+    /// Spawn an instance of `prefab`, replacing or extending its components with `overrides`
+    ///
+    /// The new entity receives a clone of each of the prefab's components, followed by
+    /// `overrides`, followed by a [`PrefabInstance`] recording the prefab's ID and the types
+    /// supplied by `overrides`. Pass `()` to spawn an unmodified instance.
+    pub fn spawn_prefab(&mut self, prefab: &Prefab, overrides: impl DynamicBundle) -> Entity {
+        let instance = PrefabInstance::new(prefab.id(), overrides.with_ids(|ids| ids.to_vec()));
+        let mut builder = EntityBuilder::new();
+        builder
+            .add_bundle(prefab.components())
+            .add_bundle(overrides)
+            .add(instance);
+        self.spawn(builder.build())
+    }
+
+    /// Spawn an instance of `prefab` with overrides added to an [`EntityBuilder`] by `f`
+    ///
+    /// `f` is passed an empty builder. Equivalent to [`spawn_prefab`](Self::spawn_prefab) with
+    /// the components `f` adds as overrides.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// let mut builder = EntityBuilderClone::new();
+    /// builder.add(100u32);
+    /// let prefab = Prefab::new(PrefabId(7), builder);
+    ///
+    /// let mut world = World::new();
+    /// let e = world.spawn_prefab_with(&prefab, |b| {
+    ///     b.add(true);
+    /// });
+    /// assert_eq!(*world.get::<&u32>(e).unwrap(), 100);
+    /// assert!(world.get::<&PrefabInstance>(e).unwrap().is_overridden(std::any::TypeId::of::<bool>()));
+    /// ```
+    pub fn spawn_prefab_with(
+        &mut self,
+        prefab: &Prefab,
+        f: impl FnOnce(&mut EntityBuilder),
+    ) -> Entity {
+        let mut overrides = EntityBuilder::new();
+        f(&mut overrides);
+        self.spawn_prefab(prefab, overrides.build())
+    }
+
+    /// Update existing instances of a prefab to a modified version, `new`
+    ///
+    /// For each of `instances` spawned from the prefab described by `diff`, components added or
+    /// changed in `new` are cloned from it, and components removed from it are dropped. Components
+    /// listed in the instance's [`PrefabInstance::overrides`] are left untouched, as are components
+    /// that `diff` reports unchanged, so values that have since diverged at runtime are preserved.
+    /// Each instance's [`PrefabInstance`] is updated to refer to `new`. Entities that no longer
+    /// exist or were not spawned from the old version, as identified by [`PrefabDiff::old`], are
+    /// skipped.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_eq::<u32>("u32").register_eq::<&str>("name");
+    ///
+    /// let mut builder = EntityBuilderClone::new();
+    /// builder.add(100u32).add("goblin");
+    /// let old = Prefab::new(PrefabId(1), builder);
+    ///
+    /// let mut world = World::new();
+    /// let a = world.spawn_prefab(&old, ());
+    /// let b = world.spawn_prefab(&old, ("boss goblin",));
+    ///
+    /// let mut builder = EntityBuilderClone::new();
+    /// builder.add(100u32).add("hobgoblin");
+    /// let new = Prefab::new(PrefabId(1), builder);
+    /// let diff = old.diff(&new, &registry);
+    /// world.apply_prefab_diff(&new, &diff, [a, b]);
+    /// assert_eq!(*world.get::<&&str>(a).unwrap(), "hobgoblin");
+    /// assert_eq!(*world.get::<&&str>(b).unwrap(), "boss goblin");
+    /// ```
+    pub fn apply_prefab_diff(
+        &mut self,
+        new: &Prefab,
+        diff: &PrefabDiff,
+        instances: impl IntoIterator<Item = Entity>,
+    ) {
+        self.flush();
+        for entity in instances {
+            let Ok(instance) = self.get::<&PrefabInstance>(entity).map(|x| (*x).clone()) else {
+                continue;
+            };
+            if instance.prefab() != diff.old() {
+                continue;
+            }
+            let removed = diff
+                .removed()
+                .iter()
+                .copied()
+                .filter(|&id| !instance.is_overridden(id))
+                .collect::<Vec<_>>();
+            self.drop_components(entity, &removed);
+            let updated = diff
+                .added()
+                .iter()
+                .chain(diff.changed())
+                .copied()
+                .filter(|&id| !instance.is_overridden(id))
+                .collect::<Vec<_>>();
+            self.insert(entity, new.components().subset(&updated))
+                .unwrap();
+            self.get::<&mut PrefabInstance>(entity)
+                .unwrap()
+                .set_prefab(new.id());
+        }
+    }
+
+    /// Drop `entity`'s components of the types in `ids`, ignoring types it doesn't have
+    fn drop_components(&mut self, entity: Entity, ids: &[TypeId]) {
+        let loc = self.entities.get(entity).unwrap();
+        let old_index = loc.index;
+        let source_arch = &self.archetypes.archetypes[loc.archetype as usize];
+        let (removed, kept) = source_arch
+            .types()
+            .iter()
+            .copied()
+            .partition::<Vec<_>, _>(|ty| ids.contains(&ty.id()));
+        if removed.is_empty() {
+            return;
+        }
+
+        let elements = kept.iter().map(|x| x.id()).collect::<Box<_>>();
+        let target = self.archetypes.get(&*elements, move || kept);
+        let [source, target] = self.settle_archetypes([loc.archetype, target]);
+        self.counts.archetype_moves += 1;
+        let (source_arch, target_arch) = index2(
+            &mut self.archetypes.archetypes,
+            source as usize,
+            target as usize,
+        );
+        let loc = self.entities.get_mut(entity).unwrap();
+        let target_index = unsafe { target_arch.allocate(entity.id) };
+        loc.archetype = target;
+        loc.index = target_index;
+        source_arch.copy_ticks(
+            old_index,
+            target_arch,
+            target_index,
+            target_arch.type_ids().iter().copied(),
+        );
+        let moved = unsafe {
+            source_arch.move_to(old_index, |src, ty, size| {
+                match target_arch.get_dynamic(ty, size, target_index) {
+                    Some(dst) => ptr::copy_nonoverlapping(src, dst.as_ptr(), size),
+                    None => removed.iter().find(|x| x.id() == ty).unwrap().drop(src),
+                }
+            })
+        };
+        self.entities.relocate(source_arch, moved);
+        let moved = target_arch.reposition(target_index);
+        self.entities.relocate(target_arch, moved);
+        self.record_removals(entity, source, Some(target));
+    }
+
+    /// Record that `entity` lost the components of tracked types in the archetype at `source` that
+    /// are absent from the archetype at `target`, or all of them if it was despawned
+    fn record_removals(&mut self, entity: Entity, source: u32, target: Option<u32>) {
+        let source = &self.archetypes.archetypes[source as usize];
+        let target = target.map(|x| &self.archetypes.archetypes[x as usize]);
+        if let Some(ref mut log) = self.op_log {
+            log.removed(self.archetypes.tick, entity, source, target);
+        }
+        for (&id, queue) in &mut self.removals {
+            if source.has_dynamic(id) && !target.map_or(false, |x| x.has_dynamic(id)) {
+                queue.push(entity);
+            }
+        }
+    }
+
+    /// Hybrid of [`spawn_column_batch`](Self::spawn_column_batch) and [`spawn_at`](Self::spawn_at)
+    #[cfg_attr(feature = "audit-log", track_caller)]
+    pub fn spawn_column_batch_at(&mut self, handles: &[Entity], batch: ColumnBatch) {
+        let archetype = batch.0;
+        assert_eq!(
+            handles.len(),
+            archetype.len() as usize,
+            "number of entity IDs {} must match number of entities {}",
+            handles.len(),
+            archetype.len()
+        );
+
+        // Drop components of entities that will be replaced
+        for &handle in handles {
+            let replaced = self.entities.resolve(handle.id);
+            let loc = self.entities.alloc_at(handle);
+            if let Some(loc) = loc {
+                self.record_removals(replaced.unwrap(), loc.archetype, None);
+                let archetype = &mut self.archetypes.archetypes[loc.archetype as usize];
+                let moved = unsafe { archetype.remove(loc.index, true) };
+                self.entities.relocate(archetype, moved);
+                self.counts.despawns += 1;
+            }
+        }
+        self.counts.spawns += handles.len() as u64;
+        self.admit_batch(&archetype);
+
+        // Store components
+        let (archetype_id, base) = self.archetypes.insert_batch(archetype);
+
+        // Fix up entity IDs
+        let archetype = &mut self.archetypes.archetypes[archetype_id as usize];
+        for (&handle, index) in handles.iter().zip(base as usize..) {
+            archetype.set_entity_id(index, handle.id());
+            self.entities.meta[handle.id() as usize].location = Location {
+                archetype: archetype_id,
+                index: index as u32,
+            };
+        }
+        let moved = archetype.sort();
+        self.entities.relocate(archetype, moved);
+        let [archetype_id] = self.settle_archetypes([archetype_id]);
+        for &handle in handles {
+            self.log_spawn(handle);
+        }
+        self.fulfil_archetype_requirements(archetype_id);
     }
 
     /// Allocate many entities ID concurrently
@@ -283,31 +1344,76 @@ impl World {
     /// can also be done explicitly by calling [`flush`](Self::flush).
     ///
     /// Useful for reserving an ID that will later have components attached to it with `insert`.
-    pub fn reserve_entities(&self, count: u32) -> ReserveEntitiesIterator {
+    #[cfg_attr(feature = "audit-log", track_caller)]
+    pub fn reserve_entities(&self, count: u32) -> ReserveEntitiesIterator<'_> {
         self.entities.reserve_entities(count)
     }
 
     /// Allocate an entity ID concurrently
     ///
     /// See [`reserve_entities`](Self::reserve_entities).
+    #[cfg_attr(feature = "audit-log", track_caller)]
     pub fn reserve_entity(&self) -> Entity {
         self.entities.reserve_entity()
     }
 
+    /// Like [`reserve_entities`](Self::reserve_entities), but fails rather than panicking if
+    /// there aren't enough entity IDs left
+    ///
+    /// On failure, no IDs are reserved. Storage for reserved entities is allocated, infallibly, by
+    /// [`flush`](Self::flush).
+    #[cfg_attr(feature = "audit-log", track_caller)]
+    pub fn try_reserve_entities(
+        &self,
+        count: u32,
+    ) -> Result<ReserveEntitiesIterator<'_>, AllocError> {
+        self.entities.try_reserve_entities(count)
+    }
+
+    /// Like [`reserve_entity`](Self::reserve_entity), but fails rather than panicking if there are
+    /// no entity IDs left
+    #[cfg_attr(feature = "audit-log", track_caller)]
+    pub fn try_reserve_entity(&self) -> Result<Entity, AllocError> {
+        Ok(self.entities.try_reserve_entities(1)?.next().unwrap())
+    }
+
     /// Destroy an entity and all its components
     ///
     /// See also [`take`](Self::take).
+    #[cfg_attr(feature = "audit-log", track_caller)]
     pub fn despawn(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
         self.flush();
         let loc = self.entities.free(entity)?;
-        if let Some(moved) =
-            unsafe { self.archetypes.archetypes[loc.archetype as usize].remove(loc.index, true) }
-        {
-            self.entities.meta[moved as usize].location.index = loc.index;
-        }
+        self.record_removals(entity, loc.archetype, None);
+        let archetype = &mut self.archetypes.archetypes[loc.archetype as usize];
+        let moved = unsafe { archetype.remove(loc.index, true) };
+        self.entities.relocate(archetype, moved);
+        self.counts.despawns += 1;
         Ok(())
     }
 
+    /// Entities to be despawned by the next call to [`despawn_queued`](Self::despawn_queued) or
+    /// [`maintain`](Self::maintain)
+    ///
+    /// See [`DespawnQueue`].
+    pub fn despawn_queue(&self) -> &DespawnQueue {
+        &self.despawn_queue
+    }
+
+    /// Despawn every entity in the [`despawn_queue`](Self::despawn_queue), in order of [`Entity`]
+    ///
+    /// Returns the number of entities despawned, excluding any that no longer existed.
+    #[cfg_attr(feature = "audit-log", track_caller)]
+    pub fn despawn_queued(&mut self) -> u32 {
+        let mut count = 0;
+        for entity in self.despawn_queue.drain() {
+            if self.despawn(entity).is_ok() {
+                count += 1;
+            }
+        }
+        count
+    }
+
     /// Ensure at least `additional` entities with exact components `T` can be spawned without reallocating
     pub fn reserve<T: Bundle + 'static>(&mut self, additional: u32) {
         self.reserve_inner::<T>(additional);
@@ -316,9 +1422,15 @@ impl World {
     fn reserve_inner<T: Bundle + 'static>(&mut self, additional: u32) -> u32 {
         self.flush();
         self.entities.reserve(additional);
+        let archetype_id = self.bundle_archetype_static::<T>();
+        self.archetypes.archetypes[archetype_id as usize].reserve(additional);
+        archetype_id
+    }
 
+    /// Find or create the archetype with exactly the component types of `T`
+    fn bundle_archetype_static<T: Bundle + 'static>(&mut self) -> u32 {
         let archetypes = &mut self.archetypes;
-        let archetype_id = *self
+        let archetype = *self
             .bundle_to_archetype
             .entry(TypeId::of::<T>())
             .or_insert_with(|| {
@@ -326,19 +1438,35 @@ impl World {
                     archetypes.get(ids, || T::with_static_type_info(|info| info.to_vec()))
                 })
             });
-
-        self.archetypes.archetypes[archetype_id as usize].reserve(additional);
-        archetype_id
+        let [archetype] = self.settle_archetypes([archetype]);
+        archetype
     }
 
     /// Despawn all entities
     ///
     /// Preserves allocated storage for reuse but clears metadata so that [`Entity`] values will repeat (in contrast to [`despawn`][Self::despawn]).
     pub fn clear(&mut self) {
+        self.counts.despawns += u64::from(self.entities.len());
+        for (&id, queue) in &mut self.removals {
+            for archetype in self
+                .archetypes
+                .archetypes
+                .iter()
+                .filter(|x| x.has_dynamic(id))
+            {
+                queue.extend(
+                    archetype
+                        .ids()
+                        .iter()
+                        .map(|&id| unsafe { self.entities.resolve_unknown_gen(id) }),
+                );
+            }
+        }
         for x in &mut self.archetypes.archetypes {
             x.clear();
         }
         self.entities.clear();
+        self.despawn_queue.clear();
     }
 
     /// Whether `entity` still exists
@@ -413,6 +1541,115 @@ impl World {
         QueryMut::new(self)
     }
 
+    /// Iterate over the entities having components of all the types identified by `keys`, one
+    /// archetype at a time
+    ///
+    /// For editor tooling and script systems, which can't name the component types they access at
+    /// compile time. Each [`DynamicBatch`] exposes its entities and a type-erased column of
+    /// components for each key, in the same order as `keys`. Empty archetypes are skipped.
+    ///
+    /// The world is borrowed uniquely, so the components may be both read and written through the
+    /// columns' pointers. Writes aren't recorded by change detection unless marked with
+    /// [`DynamicBatch::mark_changed`].
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// # use core::alloc::Layout;
+    /// let mut world = World::new();
+    /// let key = world
+    ///     .register_dynamic(Layout::new::<u32>(), None, "Score")
+    ///     .unwrap();
+    /// let mut builder = EntityBuilder::new();
+    /// for score in [1u32, 2] {
+    ///     unsafe {
+    ///         builder.add_dynamic(key, &score.to_ne_bytes());
+    ///     }
+    ///     world.spawn(builder.build());
+    /// }
+    ///
+    /// let mut total = 0;
+    /// for batch in world.query_dynamic(&[key]) {
+    ///     let scores = batch.column(0);
+    ///     for (i, _entity) in batch.entities().enumerate() {
+    ///         let score = scores.get(i).unwrap().cast::<u32>();
+    ///         unsafe {
+    ///             total += score.as_ptr().read();
+    ///             score.as_ptr().write(0);
+    ///         }
+    ///     }
+    /// }
+    /// assert_eq!(total, 3);
+    /// ```
+    pub fn query_dynamic<'a>(
+        &'a mut self,
+        keys: &'a [ComponentKey],
+    ) -> impl Iterator<Item = DynamicBatch<'a>> + 'a {
+        self.flush();
+        let meta = &self.entities.meta[..];
+        self.archetypes
+            .archetypes
+            .iter()
+            .filter(move |x| !x.is_empty() && keys.iter().all(|key| x.has_dynamic(key.id())))
+            .map(move |x| DynamicBatch::new(x, meta, keys))
+    }
+
+    /// Access the world through a [`Scope`] which defers structural changes until it ends
+    ///
+    /// Entities can't be spawned, despawned, or have components added or removed while the world
+    /// is being queried. Within `f`, such changes are instead recorded by the scope, then applied
+    /// in the order they were made once `f` returns.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((1,));
+    /// let b = world.spawn((-1,));
+    /// let spawned = world.iter_scope(|scope| {
+    ///     for (entity, &x) in scope.query::<&i32>().iter() {
+    ///         if x < 0 {
+    ///             scope.despawn(entity);
+    ///         } else {
+    ///             scope.insert_one(entity, true);
+    ///         }
+    ///     }
+    ///     scope.spawn((2,))
+    /// });
+    /// assert!(!world.contains(b));
+    /// assert!(*world.get::<&bool>(a).unwrap());
+    /// assert_eq!(*world.get::<&i32>(spawned).unwrap(), 2);
+    /// ```
+    pub fn iter_scope<R>(&mut self, f: impl FnOnce(&Scope<'_>) -> R) -> R {
+        let scope = Scope::new(self);
+        let result = f(&scope);
+        scope.into_commands().run_on(self);
+        result
+    }
+
+    /// Query the world while recording changes into a [`CommandBuffer`], then apply them
+    ///
+    /// A lighter-weight alternative to [`iter_scope`](Self::iter_scope) for code that already
+    /// works in terms of command buffers.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((1,));
+    /// world.run_deferred(|world, cmd| {
+    ///     for (entity, &x) in world.query::<&i32>().iter() {
+    ///         cmd.insert_one(entity, x == 1);
+    ///     }
+    /// });
+    /// assert!(*world.get::<&bool>(a).unwrap());
+    /// ```
+    pub fn run_deferred<R>(&mut self, f: impl FnOnce(&World, &mut CommandBuffer) -> R) -> R {
+        let mut cmd = CommandBuffer::new();
+        let result = f(self, &mut cmd);
+        cmd.run_on(self);
+        result
+    }
+
     pub(crate) fn memo(&self) -> (u64, u32) {
         (self.id, self.archetypes.generation())
     }
@@ -423,6 +1660,11 @@ impl World {
     }
 
     #[inline(always)]
+    /// Position of the live entity with ID `id` in spawn order, when iterating in spawn order
+    pub(crate) fn spawn_seq(&self, id: u32) -> u64 {
+        self.entities.spawn_seq(id)
+    }
+
     pub(crate) fn archetypes_inner(&self) -> &[Archetype] {
         &self.archetypes.archetypes
     }
@@ -473,6 +1715,7 @@ impl World {
         let loc = self.entities.get(entity)?;
         let archetype = &self.archetypes.archetypes[loc.archetype as usize];
         let state = Q::Fetch::prepare(archetype).ok_or(QueryOneError::Unsatisfied)?;
+        archetype.mark_queried();
         let fetch = Q::Fetch::execute(archetype, state);
         unsafe { Ok(Q::get(&fetch, loc.index as usize)) }
     }
@@ -493,6 +1736,7 @@ impl World {
             let loc = self.entities.get(entity)?;
             let archetype = &self.archetypes.archetypes[loc.archetype as usize];
             let state = Q::Fetch::prepare(archetype).ok_or(QueryOneError::Unsatisfied)?;
+            archetype.mark_queried();
             let fetch = Q::Fetch::execute(archetype, state);
             unsafe { Ok(Q::get(&fetch, loc.index as usize)) }
         })
@@ -514,6 +1758,15 @@ impl World {
         Ok(self.entity(entity)?.satisfies::<Q>())
     }
 
+    /// Short-hand for [`entity`](Self::entity) followed by [`EntityRef::satisfies_dynamic`]
+    pub fn satisfies_dynamic(
+        &self,
+        entity: Entity,
+        query: &QueryDescription,
+    ) -> Result<bool, NoSuchEntity> {
+        Ok(self.entity(entity)?.satisfies_dynamic(query))
+    }
+
     /// Access an entity regardless of its component types
     ///
     /// Does not immediately borrow any component.
@@ -528,6 +1781,43 @@ impl World {
         }
     }
 
+    /// Capture the order in which queries currently visit entities, for use with
+    /// [`QueryBorrow::iter_frozen`]
+    ///
+    /// Capturing once at the start of a tick and iterating every query with the token makes all
+    /// systems in that tick visit entities in one canonical order, unaffected by rows being moved
+    /// as entities are despawned or change archetype mid-tick. See [`OrderToken`].
+    pub fn freeze_order(&self) -> OrderToken {
+        OrderToken::new(self.iter().map(|entity| entity.entity()).collect())
+    }
+
+    /// Access an entity through a location obtained from [`QueryBorrow::iter_located`]
+    ///
+    /// Returns `None` if the entity has since been despawned or moved, in which case the location
+    /// should be discarded. Cheaper than [`entity`](Self::entity) when many entities are accessed
+    /// through locations cached across frames.
+    pub fn entity_at(&self, location: EntityLocation) -> Option<EntityRef<'_>> {
+        let entity = location.entity();
+        let archetype = self
+            .archetypes
+            .archetypes
+            .get(location.archetype() as usize)?;
+        if location.row() >= archetype.len() || archetype.entity_id(location.row()) != entity.id() {
+            return None;
+        }
+        let meta = self.entities.meta.get(entity.id as usize)?;
+        if meta.generation != entity.generation {
+            return None;
+        }
+        unsafe { Some(EntityRef::new(archetype, entity, location.row())) }
+    }
+
+    /// The current location of `entity`, for later use with [`entity_at`](Self::entity_at)
+    pub fn locate(&self, entity: Entity) -> Result<EntityLocation, NoSuchEntity> {
+        let loc = self.entities.get(entity)?;
+        Ok(EntityLocation::new(entity, loc.archetype, loc.index))
+    }
+
     /// Given an id obtained from [`Entity::id`], reconstruct the still-live [`Entity`].
     ///
     /// # Safety
@@ -538,401 +1828,2354 @@ impl World {
         self.entities.resolve_unknown_gen(id)
     }
 
-    /// Iterate over all entities in the world
+    /// Like [`find_entity_from_id`](Self::find_entity_from_id), but for many IDs at once, and
+    /// safe
     ///
-    /// Entities are yielded in arbitrary order. Prefer [`query`](Self::query) for better
-    /// performance when components will be accessed in predictable patterns.
+    /// Replaces the contents of `out` with the live entity for each of `ids`, in order, or `None`
+    /// for IDs that aren't live. Entities reserved but not yet [flushed](Self::flush) aren't
+    /// considered live. Reusing `out` across calls avoids reallocating it.
     ///
-    /// # Example
     /// ```
     /// # use hecs::*;
     /// let mut world = World::new();
     /// let a = world.spawn(());
     /// let b = world.spawn(());
-    /// let ids = world.iter().map(|entity_ref| entity_ref.entity()).collect::<Vec<_>>();
-    /// assert_eq!(ids.len(), 2);
-    /// assert!(ids.contains(&a));
-    /// assert!(ids.contains(&b));
+    /// world.despawn(a).unwrap();
+    /// let mut out = Vec::new();
+    /// world.find_entities_from_ids(&[b.id(), a.id(), 1000], &mut out);
+    /// assert_eq!(out, [Some(b), None, None]);
     /// ```
-    pub fn iter(&self) -> Iter<'_> {
-        Iter::new(&self.archetypes.archetypes, &self.entities)
+    pub fn find_entities_from_ids(&self, ids: &[u32], out: &mut Vec<Option<Entity>>) {
+        out.clear();
+        out.extend(ids.iter().map(|&id| self.entities.resolve(id)));
     }
 
-    /// Add `components` to `entity`
+    /// Keep the entities of every archetype containing `K` sorted by their `K` component
     ///
-    /// Computational cost is proportional to the number of components `entity` has. If an entity
-    /// already has a component of a certain type, it is dropped and replaced.
+    /// Enables [`find_by_key`](Self::find_by_key) to locate entities by binary search rather than
+    /// a linear scan. Entities are moved into position as they're spawned or gain a `K`, and
+    /// removal preserves the order of the remaining entities. An archetype containing several
+    /// keys is sorted by whichever was registered first.
     ///
-    /// When inserting a single component, see [`insert_one`](Self::insert_one) for convenience.
+    /// Mutating a `K` component in place, e.g. through a query, leaves its archetype's lookups
+    /// falling back to a linear scan until the next entity is added to it, at which point it is
+    /// sorted again.
+    pub fn keep_sorted_by_key<K: Component + Ord>(&mut self) {
+        self.flush();
+        let id = TypeId::of::<K>();
+        if self.archetypes.sort_keys.iter().any(|&(x, _)| x == id) {
+            return;
+        }
+        let cmp: KeyCmp = |a, b| unsafe { (*a.cast::<K>()).cmp(&*b.cast::<K>()) };
+        self.archetypes.sort_keys.push((id, cmp));
+        for archetype in &mut self.archetypes.archetypes {
+            if archetype.sort_key().is_some() || !archetype.set_sort_key(id, cmp) {
+                continue;
+            }
+            let moved = archetype.sort();
+            self.entities.relocate(archetype, moved);
+        }
+    }
+
+    /// Find an entity whose `K` component is equal to `key`
+    ///
+    /// Uses binary search in archetypes kept sorted by `K` with
+    /// [`keep_sorted_by_key`](Self::keep_sorted_by_key), and a linear scan elsewhere. If several
+    /// entities match, which is returned is unspecified.
     ///
     /// # Example
     /// ```
     /// # use hecs::*;
+    /// #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    /// struct Cell(i32, i32);
+    ///
     /// let mut world = World::new();
-    /// let e = world.spawn((123, "abc"));
-    /// world.insert(e, (456, true));
-    /// assert_eq!(*world.get::<&i32>(e).unwrap(), 456);
-    /// assert_eq!(*world.get::<&bool>(e).unwrap(), true);
+    /// world.keep_sorted_by_key::<Cell>();
+    /// let cells = (0..100)
+    ///     .map(|i| world.spawn((Cell(i % 10, i / 10), 0.5f32)))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(world.find_by_key(&Cell(3, 4)), Some(cells[43]));
+    /// assert_eq!(world.find_by_key(&Cell(3, 10)), None);
     /// ```
-    pub fn insert(
-        &mut self,
-        entity: Entity,
-        components: impl DynamicBundle,
-    ) -> Result<(), NoSuchEntity> {
-        self.flush();
+    pub fn find_by_key<K: Component + Ord>(&self, key: &K) -> Option<Entity> {
+        self.archetypes().find_map(|archetype| {
+            let column = archetype.get::<&K>()?;
+            let index = if archetype.is_sorted_by::<K>() {
+                column.binary_search(key).ok()?
+            } else {
+                column.iter().position(|x| x == key)?
+            };
+            Some(unsafe { self.find_entity_from_id(archetype.entity_id(index as u32)) })
+        })
+    }
 
-        let loc = self.entities.get(entity)?;
-        self.insert_inner(entity, components, loc.archetype, loc);
-        Ok(())
+    /// Maintain an index from values of `K` components to the entities holding them, enabling
+    /// [`lookup`](Self::lookup) and [`lookup_all`](Self::lookup_all)
+    ///
+    /// The index stays in sync with spawning, despawning, inserting, removing, and mutating `K`
+    /// components without further effort. It's updated lazily by each lookup, at a cost
+    /// proportional to the number of entities sharing an archetype with any `K` component modified
+    /// since the previous lookup. Replaces any index of `K` previously registered.
+    pub fn register_index<K: Component + Hash + Eq + Clone>(&mut self, kind: IndexKind) {
+        self.value_indexes
+            .insert(TypeId::of::<K>(), Box::new(ValueIndex::<K>::new(kind)));
     }
 
-    /// The implementation backing [`insert`](Self::insert) exposed so that it can also be used by [`exchange`](Self::exchange).
+    /// Find the entity whose `K` component is equal to `key`
     ///
-    /// Note that `graph_origin` is always equal to `loc.archetype` during insertion. Only for exchange, `graph_origin` identifies
-    /// the intermediate archetype which would be reached after removal and before insertion even though
-    /// the actual component data still resides in `loc.archetype`.
-    fn insert_inner(
-        &mut self,
-        entity: Entity,
-        components: impl DynamicBundle,
-        graph_origin: u32,
-        loc: Location,
-    ) {
-        let target_storage;
-        let target = match components.key() {
-            None => {
-                target_storage = self.archetypes.get_insert_target(graph_origin, &components);
-                &target_storage
-            }
-            Some(key) => match self.insert_edges.entry((graph_origin, key)) {
-                Entry::Occupied(entry) => entry.into_mut(),
-                Entry::Vacant(entry) => {
-                    let target = self.archetypes.get_insert_target(graph_origin, &components);
-                    entry.insert(target)
-                }
+    /// If several entities match, which requires the index to be [`IndexKind::Multi`], the one
+    /// that compares least is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no index of `K` was registered with [`register_index`](Self::register_index), if
+    /// the index is [`IndexKind::Unique`] and several entities match, or if `K` components are
+    /// uniquely borrowed.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    /// struct NetworkId(u64);
+    ///
+    /// let mut world = World::new();
+    /// world.register_index::<NetworkId>(IndexKind::Unique);
+    /// let a = world.spawn((NetworkId(7), 1.0f32));
+    /// assert_eq!(world.lookup(&NetworkId(7)), Some(a));
+    /// world.get::<&mut NetworkId>(a).unwrap().0 = 8;
+    /// assert_eq!(world.lookup(&NetworkId(7)), None);
+    /// assert_eq!(world.lookup(&NetworkId(8)), Some(a));
+    /// world.despawn(a).unwrap();
+    /// assert_eq!(world.lookup(&NetworkId(8)), None);
+    /// ```
+    pub fn lookup<K: Component + Hash + Eq + Clone>(&self, key: &K) -> Option<Entity> {
+        let index = self.value_index::<K>();
+        let entities = index.lookup(self, key);
+        if index.kind() == IndexKind::Unique {
+            assert!(
+                entities.len() <= 1,
+                "{} entities share a key in the unique index of {}",
+                entities.len(),
+                core::any::type_name::<K>()
+            );
+        }
+        entities.first().copied()
+    }
+
+    /// Find every entity whose `K` component is equal to `key`, in ascending order
+    ///
+    /// # Panics
+    ///
+    /// Panics if no index of `K` was registered with [`register_index`](Self::register_index), or
+    /// if `K` components are uniquely borrowed.
+    pub fn lookup_all<K: Component + Hash + Eq + Clone>(&self, key: &K) -> Vec<Entity> {
+        self.value_index::<K>().lookup(self, key)
+    }
+
+    fn value_index<K: Component + Hash + Eq + Clone>(&self) -> &ValueIndex<K> {
+        self.value_indexes
+            .get(&TypeId::of::<K>())
+            .and_then(|x| x.downcast_ref())
+            .unwrap_or_else(|| panic!("no index of {} registered", core::any::type_name::<K>()))
+    }
+
+    /// Allow at most one entity at a time to have a `T` component
+    ///
+    /// Spawning an entity with a `T`, or adding a `T` to an entity, while another entity has one
+    /// is then handled according to `policy`. Enforced by [`spawn`](Self::spawn),
+    /// [`insert`](Self::insert), [`exchange`](Self::exchange) and their variants, including those
+    /// used by [`CommandBuffer`]. [`spawn_batch`](Self::spawn_batch) rejects batches with `T`
+    /// components, and [`spawn_column_batch`](Self::spawn_column_batch) those of several entities.
+    ///
+    /// # Panics
+    ///
+    /// Panics if several entities already have `T` components.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Camera;
+    ///
+    /// let mut world = World::new();
+    /// world.register_singleton::<Camera>(SingletonPolicy::Move);
+    /// let a = world.spawn((Camera,));
+    /// let b = world.spawn((Camera, true));
+    /// assert_eq!(world.singleton::<Camera>().unwrap().0, b);
+    /// assert!(!world.satisfies::<&Camera>(a).unwrap());
+    /// ```
+    pub fn register_singleton<T: Component>(&mut self, policy: SingletonPolicy) {
+        let id = TypeId::of::<T>();
+        let held = self
+            .archetypes()
+            .filter(|x| x.has_dynamic(id))
+            .map(|x| x.len())
+            .sum::<u32>();
+        assert!(
+            held <= 1,
+            "{} entities already have {} components",
+            held,
+            core::any::type_name::<T>()
+        );
+        self.singletons
+            .insert(id, (policy, core::any::type_name::<T>()));
+    }
+
+    /// The entity having a `T` component, and that component
+    ///
+    /// If several entities have `T` components, which is returned is unspecified. Register `T`
+    /// with [`register_singleton`](Self::register_singleton) to prevent this.
+    pub fn singleton<T: Component>(&self) -> Option<(Entity, crate::Ref<'_, T>)> {
+        let entity = self.singleton_holder(TypeId::of::<T>())?;
+        Some((entity, self.get::<&T>(entity).unwrap()))
+    }
+
+    /// Like [`singleton`](Self::singleton), but without borrow checking
+    pub fn singleton_mut<T: Component>(&mut self) -> Option<(Entity, &mut T)> {
+        let entity = self.singleton_holder(TypeId::of::<T>())?;
+        Some((entity, self.query_one_mut::<&mut T>(entity).unwrap()))
+    }
+
+    /// Find an entity having a component of the type identified by `id`
+    fn singleton_holder(&self, id: TypeId) -> Option<Entity> {
+        let archetype = self
+            .archetypes()
+            .find(|x| !x.is_empty() && x.has_dynamic(id))?;
+        Some(unsafe { self.find_entity_from_id(archetype.entity_id(0)) })
+    }
+
+    /// Entity other than `entity` holding a singleton component in `components` that's registered
+    /// with [`SingletonPolicy::Reject`]
+    fn singleton_conflict(
+        &self,
+        entity: Option<Entity>,
+        components: &impl DynamicBundle,
+    ) -> Option<Entity> {
+        if self.singletons.is_empty() {
+            return None;
+        }
+        components.with_ids(|ids| {
+            ids.iter().find_map(|id| match self.singletons.get(id) {
+                Some((SingletonPolicy::Reject, _)) => self
+                    .singleton_holder(*id)
+                    .filter(|holder| entity.map_or(true, |x| holder.id() != x.id())),
+                _ => None,
+            })
+        })
+    }
+
+    /// A singleton component type among `ids`, the components of a batch, and its name
+    fn batch_singleton(&self, ids: &[TypeId]) -> Option<(TypeId, &'static str)> {
+        if self.singletons.is_empty() {
+            return None;
+        }
+        ids.iter()
+            .find_map(|id| Some((*id, self.singletons.get(id)?.1)))
+    }
+
+    /// Apply the policies of the singleton and exclusive component types in `batch`, which is
+    /// about to be spawned
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch` holds several entities with a singleton component, or components of
+    /// several types of an exclusive set.
+    fn admit_batch(&mut self, batch: &Archetype) {
+        if batch.is_empty() {
+            return;
+        }
+        let ids = batch.types().iter().map(|ty| ty.id()).collect::<Vec<_>>();
+        if let Some((_, name)) = self.self_exclusion(&ids) {
+            panic!("bundle contains several of the components of {}", name);
+        }
+        let Some((_, name)) = self.batch_singleton(&ids) else {
+            return;
+        };
+        assert!(
+            batch.len() == 1,
+            "batch of {} entities contains singleton component {}",
+            batch.len(),
+            name
+        );
+        self.claim_singleton_ids(None, &ids);
+    }
+
+    /// Apply the policies of the singleton component types in `components`, which are about to be
+    /// added to `entity`, or to a new entity if `None`
+    ///
+    /// Must be called before looking up `entity`'s location, which this may change.
+    fn claim_singletons(&mut self, entity: Option<Entity>, components: &impl DynamicBundle) {
+        if self.singletons.is_empty() {
+            return;
+        }
+        let ids = components.with_ids(|ids| ids.to_vec());
+        self.claim_singleton_ids(entity, &ids);
+    }
+
+    /// Like [`claim_singletons`](Self::claim_singletons), for components of the types in `ids`
+    fn claim_singleton_ids(&mut self, entity: Option<Entity>, ids: &[TypeId]) {
+        let claimed = ids
+            .iter()
+            .filter_map(|id| Some((*id, *self.singletons.get(id)?)))
+            .collect::<Vec<_>>();
+        for (id, (policy, name)) in claimed {
+            let Some(holder) = self.singleton_holder(id) else {
+                continue;
+            };
+            if entity.map_or(false, |x| x.id() == holder.id()) {
+                continue;
+            }
+            match policy {
+                SingletonPolicy::Reject => {
+                    panic!(
+                        "singleton component {} is already held by {:?}",
+                        name, holder
+                    )
+                }
+                SingletonPolicy::Move => self.drop_components(holder, &[id]),
+            }
+        }
+    }
+
+    /// Forbid entities from having more than one of the component types in `S` at a time
+    ///
+    /// Adding one of them to an entity that has another is then handled according to `policy`,
+    /// wherever [singletons](Self::register_singleton) are enforced. Bundles and batches
+    /// containing several of them are always rejected, with a panic, or an error from
+    /// [`try_spawn`](Self::try_spawn), [`try_spawn_batch`](Self::try_spawn_batch) and
+    /// [`try_insert`](Self::try_insert). Useful for state machines expressed as marker components.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an entity already has several of the component types in `S`.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Alive;
+    /// struct Dead;
+    ///
+    /// let mut world = World::new();
+    /// world.register_exclusive::<(Alive, Dead)>(ExclusionPolicy::Replace);
+    /// let e = world.spawn((Alive,));
+    /// world.insert_one(e, Dead).unwrap();
+    /// assert!(!world.satisfies::<&Alive>(e).unwrap());
+    /// ```
+    pub fn register_exclusive<S: Bundle + 'static>(&mut self, policy: ExclusionPolicy) {
+        let mut ids = S::with_static_ids(|ids| Box::<[TypeId]>::from(ids));
+        ids.sort_unstable();
+        let name = core::any::type_name::<S>();
+        assert!(
+            self.archetypes()
+                .all(|x| x.is_empty() || ids.iter().filter(|&&id| x.has_dynamic(id)).count() <= 1),
+            "entities already have several of the components of {}",
+            name
+        );
+        self.exclusions.push((ids, policy, name));
+    }
+
+    /// A component type among `ids` that's mutually exclusive with an earlier one, and the name of
+    /// their exclusive set
+    fn self_exclusion(&self, ids: &[TypeId]) -> Option<(TypeId, &'static str)> {
+        self.exclusions.iter().find_map(|(set, _, name)| {
+            let mut members = ids.iter().filter(|id| set.binary_search(id).is_ok());
+            members.next()?;
+            Some((*members.next()?, *name))
+        })
+    }
+
+    /// Components of `entity`, or of a new entity if `None`, excluded by adding `components` to
+    /// it, with the policy and name of the exclusive set responsible
+    ///
+    /// Components of the types in `removing` are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `components` contains several types of an exclusive set.
+    fn excluded_by(
+        &self,
+        entity: Option<Entity>,
+        components: &impl DynamicBundle,
+        removing: &[TypeId],
+    ) -> Vec<(ExclusionPolicy, &'static str, TypeId)> {
+        let mut excluded = Vec::new();
+        if self.exclusions.is_empty() {
+            return excluded;
+        }
+        let archetype = entity.map(|x| {
+            let loc = self.entities.get(x).unwrap();
+            &self.archetypes.archetypes[loc.archetype as usize]
+        });
+        components.with_ids(|ids| {
+            for (set, policy, name) in &self.exclusions {
+                let mut members = ids.iter().filter(|id| set.binary_search(id).is_ok());
+                let Some(added) = members.next() else {
+                    continue;
+                };
+                assert!(
+                    members.next().is_none(),
+                    "bundle contains several of the components of {}",
+                    name
+                );
+                let Some(archetype) = archetype else {
+                    continue;
+                };
+                excluded.extend(
+                    set.iter()
+                        .filter(|&id| {
+                            id != added && archetype.has_dynamic(*id) && !removing.contains(id)
+                        })
+                        .map(|&id| (*policy, *name, id)),
+                );
+            }
+        });
+        excluded
+    }
+
+    /// Apply the policies of the exclusive sets that `components`, which are about to be added to
+    /// `entity`, or to a new entity if `None`, belong to
+    ///
+    /// Must be called before looking up `entity`'s location, which this may change.
+    fn resolve_exclusions(
+        &mut self,
+        entity: Option<Entity>,
+        components: &impl DynamicBundle,
+        removing: &[TypeId],
+    ) {
+        let excluded = self.excluded_by(entity, components, removing);
+        let mut replaced = Vec::new();
+        for (policy, name, id) in excluded {
+            match policy {
+                ExclusionPolicy::Reject => panic!(
+                    "{:?} already has a component mutually exclusive with those added in {}",
+                    entity.unwrap(),
+                    name
+                ),
+                ExclusionPolicy::Replace => replaced.push(id),
+            }
+        }
+        if !replaced.is_empty() {
+            self.drop_components(entity.unwrap(), &replaced);
+        }
+    }
+
+    /// Run `validator` whenever an entity is spawned or gains or loses components
+    ///
+    /// Validators see the full set of component types the entity is about to have as a
+    /// [`Transition`], and can reject it, causing a panic, or an error from
+    /// [`try_insert`](Self::try_insert), before the entity changes. They can instead fix it by
+    /// scheduling components to be added or removed after the operation completes; such fixes
+    /// aren't validated themselves. Validators run in registration order, after
+    /// [exclusions](Self::register_exclusive) and [singletons](Self::register_singleton) have been
+    /// enforced and before [requirements](Self::set_requirements) are fulfilled, for the same
+    /// operations as those checks, as well as [`remove`](Self::remove) and
+    /// [`remove_dynamic`](Self::remove_dynamic).
+    ///
+    /// Under [`ValidationMode::Debug`], `validator` is discarded unless `debug_assertions` are
+    /// enabled.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Sprite;
+    /// struct Transform;
+    ///
+    /// let mut world = World::new();
+    /// world.add_validator(ValidationMode::Always, |t| {
+    ///     if t.has::<Sprite>() && !t.has::<Transform>() {
+    ///         return Err(ValidationError::new("sprites need transforms"));
+    ///     }
+    ///     Ok(())
+    /// });
+    /// let e = world.spawn((Transform,));
+    /// world.insert_one(e, Sprite).unwrap();
+    /// let f = world.spawn(());
+    /// assert!(world.try_insert(f, (Sprite,)).is_err());
+    /// ```
+    pub fn add_validator(
+        &mut self,
+        mode: ValidationMode,
+        validator: impl Fn(&mut Transition<'_>) -> Result<(), ValidationError> + Send + Sync + 'static,
+    ) {
+        if mode == ValidationMode::Always || cfg!(debug_assertions) {
+            self.validators.push(Box::new(validator));
+        }
+    }
+
+    /// Run the validators on `entity`, or a new entity if `None`, gaining components of the types
+    /// `added` and losing those of the types in `removed`
+    fn validate(
+        &self,
+        entity: Option<Entity>,
+        added: &[TypeId],
+        removed: &[TypeId],
+    ) -> Result<Fixes, ValidationError> {
+        let mut fixes = Fixes::default();
+        if self.validators.is_empty() {
+            return Ok(fixes);
+        }
+        let mut components = match entity {
+            Some(entity) => {
+                let loc = self.entities.get(entity).unwrap();
+                self.archetypes.archetypes[loc.archetype as usize]
+                    .component_types()
+                    .filter(|id| !removed.contains(id))
+                    .collect::<Vec<_>>()
+            }
+            None => Vec::new(),
+        };
+        components.extend_from_slice(added);
+        components.sort_unstable();
+        components.dedup();
+        for validator in &self.validators {
+            validator(&mut Transition::new(
+                entity,
+                &components,
+                added,
+                removed,
+                &mut fixes,
+            ))?;
+        }
+        Ok(fixes)
+    }
+
+    /// Like [`validate`](Self::validate), but panics on rejection
+    fn check_transition(
+        &self,
+        entity: Option<Entity>,
+        added: &impl DynamicBundle,
+        removed: &[TypeId],
+    ) -> Fixes {
+        match added.with_ids(|ids| self.validate(entity, ids, removed)) {
+            Ok(fixes) => fixes,
+            Err(e) => match entity {
+                Some(entity) => panic!("{} for {:?}", e, entity),
+                None => panic!("{} for spawned entity", e),
+            },
+        }
+    }
+
+    /// Apply changes scheduled by validators to `entity`
+    fn apply_fixes(&mut self, entity: Entity, mut fixes: Fixes) {
+        if fixes.is_empty() {
+            return;
+        }
+        self.drop_components(entity, &fixes.remove);
+        let components = fixes.add.build();
+        let loc = self.entities.get(entity).unwrap();
+        self.insert_inner(entity, components, loc.archetype, loc);
+    }
+
+    /// Add `plugin` to the world, letting it [install its hooks](Plugin::build)
+    ///
+    /// # Panics
+    ///
+    /// Panics if a plugin of the same type was already added.
+    pub fn add_plugin<P: Plugin>(&mut self, mut plugin: P) {
+        assert!(
+            self.plugin::<P>().is_none(),
+            "plugin {} already added",
+            core::any::type_name::<P>()
+        );
+        plugin.build(self);
+        self.plugins.push(PluginEntry::new(plugin));
+    }
+
+    /// The plugin of type `P`, if added
+    pub fn plugin<P: Plugin>(&self) -> Option<&P> {
+        let entry = self.plugins.iter().find(|x| x.id == TypeId::of::<P>())?;
+        entry.plugin.downcast_ref()
+    }
+
+    /// Like [`plugin`](Self::plugin), but for mutation
+    pub fn plugin_mut<P: Plugin>(&mut self) -> Option<&mut P> {
+        let entry = self
+            .plugins
+            .iter_mut()
+            .find(|x| x.id == TypeId::of::<P>())?;
+        entry.plugin.downcast_mut()
+    }
+
+    /// Enforce the component dependencies declared in `registry` with
+    /// [`ComponentRegistry::require`], replacing any previously set
+    ///
+    /// Whenever an entity gains a component whose type requires another that the entity lacks,
+    /// the missing component is constructed and added too, transitively. This is done by
+    /// [`spawn`](Self::spawn), [`insert`](Self::insert), [`exchange`](Self::exchange),
+    /// [`spawn_column_batch`](Self::spawn_column_batch) and their variants, and therefore also by
+    /// [`CommandBuffer`] and the [`serialize`](crate::serialize) modules. Batches spawned by
+    /// [`spawn_batch`](Self::spawn_batch) and entities already in the world are only brought into
+    /// line by [`fulfil_requirements`](Self::fulfil_requirements).
+    ///
+    /// Removing a required component is permitted.
+    pub fn set_requirements(&mut self, registry: &ComponentRegistry) {
+        self.requirements = registry.requirements().to_vec();
+    }
+
+    /// Add the components required by [`set_requirements`](Self::set_requirements) to every
+    /// entity that lacks them
+    pub fn fulfil_requirements(&mut self) {
+        self.flush();
+        for archetype in 0..self.archetypes.archetypes.len() {
+            self.fulfil_archetype_requirements(archetype as u32);
+        }
+    }
+
+    /// First requirement that entities in `archetype` fail to meet
+    fn unmet_requirement(&self, archetype: u32) -> Option<&Requirement> {
+        let archetype = &self.archetypes.archetypes[archetype as usize];
+        self.requirements
+            .iter()
+            .find(|x| archetype.has_dynamic(x.dependent) && !archetype.has_dynamic(x.required.id()))
+    }
+
+    /// Add the components `entity` is missing according to `self.requirements`
+    fn fulfil_requirements_of(&mut self, entity: Entity) {
+        if self.requirements.is_empty() {
+            return;
+        }
+        // Terminates because each iteration adds a component type `entity` didn't have
+        loop {
+            let loc = self.entities.get(entity).unwrap();
+            let Some(requirement) = self.unmet_requirement(loc.archetype).cloned() else {
+                return;
+            };
+            let component = requirement.component();
+            self.resolve_exclusions(Some(entity), &component, &[]);
+            self.claim_singletons(Some(entity), &component);
+            let loc = self.entities.get(entity).unwrap();
+            self.insert_inner(entity, component, loc.archetype, loc);
+        }
+    }
+
+    /// Fulfil the requirements of every entity in `archetype`
+    fn fulfil_archetype_requirements(&mut self, archetype: u32) {
+        if self.requirements.is_empty() || self.unmet_requirement(archetype).is_none() {
+            return;
+        }
+        let entities = self.archetypes.archetypes[archetype as usize]
+            .ids()
+            .iter()
+            .map(|&id| unsafe { self.find_entity_from_id(id) })
+            .collect::<Vec<_>>();
+        for entity in entities {
+            self.fulfil_requirements_of(entity);
+        }
+    }
+
+    /// Iterate over all entities in the world
+    ///
+    /// Entities are yielded in arbitrary order. Prefer [`query`](Self::query) for better
+    /// performance when components will be accessed in predictable patterns.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn(());
+    /// let b = world.spawn(());
+    /// let ids = world.iter().map(|entity_ref| entity_ref.entity()).collect::<Vec<_>>();
+    /// assert_eq!(ids.len(), 2);
+    /// assert!(ids.contains(&a));
+    /// assert!(ids.contains(&b));
+    /// ```
+    pub fn iter(&self) -> Iter<'_> {
+        Iter::new(&self.archetypes.archetypes, &self.entities)
+    }
+
+    /// Add `components` to `entity`
+    ///
+    /// Computational cost is proportional to the number of components `entity` has. If an entity
+    /// already has a component of a certain type, it is dropped and replaced.
+    ///
+    /// When inserting a single component, see [`insert_one`](Self::insert_one) for convenience.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let e = world.spawn((123, "abc"));
+    /// world.insert(e, (456, true));
+    /// assert_eq!(*world.get::<&i32>(e).unwrap(), 456);
+    /// assert_eq!(*world.get::<&bool>(e).unwrap(), true);
+    /// ```
+    pub fn insert(
+        &mut self,
+        entity: Entity,
+        components: impl DynamicBundle,
+    ) -> Result<(), NoSuchEntity> {
+        self.flush();
+
+        self.entities.get(entity)?;
+        self.resolve_exclusions(Some(entity), &components, &[]);
+        self.claim_singletons(Some(entity), &components);
+        let fixes = self.check_transition(Some(entity), &components, &[]);
+        let loc = self.entities.get(entity)?;
+        self.insert_inner(entity, components, loc.archetype, loc);
+        self.apply_fixes(entity, fixes);
+        self.fulfil_requirements_of(entity);
+        Ok(())
+    }
+
+    /// Like [`insert`](Self::insert), but fails rather than aborting if storage for `entity` in its
+    /// new archetype can't be allocated
+    ///
+    /// On failure, `components` are dropped and `entity` is left unchanged.
+    pub fn try_insert(
+        &mut self,
+        entity: Entity,
+        components: impl DynamicBundle,
+    ) -> Result<(), TryInsertError> {
+        self.flush();
+
+        self.entities.get(entity)?;
+        if let Some((id, _)) = components.with_ids(|ids| self.self_exclusion(ids)) {
+            return Err(TryInsertError::Excluded(id));
+        }
+        if let Some(holder) = self.singleton_conflict(Some(entity), &components) {
+            return Err(TryInsertError::SingletonHeld(holder));
+        }
+        if let Some((_, _, held)) = self
+            .excluded_by(Some(entity), &components, &[])
+            .into_iter()
+            .find(|x| x.0 == ExclusionPolicy::Reject)
+        {
+            return Err(TryInsertError::Excluded(held));
+        }
+        self.resolve_exclusions(Some(entity), &components, &[]);
+        self.claim_singletons(Some(entity), &components);
+        let fixes = components
+            .with_ids(|ids| self.validate(Some(entity), ids, &[]))
+            .map_err(TryInsertError::Invalid)?;
+        let loc = self.entities.get(entity)?;
+        if self.has_budget() {
+            let source = &self.archetypes.archetypes[loc.archetype as usize];
+            let mut ids = source.types().iter().map(|ty| ty.id()).collect::<Vec<_>>();
+            components.with_ids(|x| ids.extend_from_slice(x));
+            ids.sort_unstable();
+            ids.dedup();
+            let (bytes, new) = if ids.len() == source.types().len() {
+                (0, false)
+            } else {
+                self.reserve_bytes(&ids, 1, || {
+                    let added = components
+                        .type_info()
+                        .iter()
+                        .filter(|ty| !source.has_dynamic(ty.id()))
+                        .map(|ty| ty.layout().size())
+                        .sum::<usize>();
+                    added
+                        + source
+                            .types()
+                            .iter()
+                            .map(|ty| ty.layout().size())
+                            .sum::<usize>()
+                })
+            };
+            self.check_budget(0, bytes, new as usize)?;
+        }
+        let target = match components.key() {
+            None => {
+                self.archetypes
+                    .get_insert_target(loc.archetype, &components)
+                    .index
+            }
+            Some(key) => {
+                let archetypes = &mut self.archetypes;
+                self.insert_edges
+                    .entry((loc.archetype, key))
+                    .or_insert_with(|| archetypes.get_insert_target(loc.archetype, &components))
+                    .index
+            }
+        };
+        let [source, target] = self.settle_archetypes([loc.archetype, target]);
+        let loc = Location {
+            archetype: source,
+            ..loc
+        };
+        if target != loc.archetype {
+            self.archetypes.archetypes[target as usize].try_reserve(1)?;
+        }
+        self.insert_inner(entity, components, loc.archetype, loc);
+        self.apply_fixes(entity, fixes);
+        self.fulfil_requirements_of(entity);
+        Ok(())
+    }
+
+    /// The implementation backing [`insert`](Self::insert) exposed so that it can also be used by [`exchange`](Self::exchange).
+    ///
+    /// Note that `graph_origin` is always equal to `loc.archetype` during insertion. Only for exchange, `graph_origin` identifies
+    /// the intermediate archetype which would be reached after removal and before insertion even though
+    /// the actual component data still resides in `loc.archetype`.
+    ///
+    /// Returns the indices of the source and target archetypes, which differ from those passed in
+    /// if archetypes were put in canonical order.
+    fn insert_inner(
+        &mut self,
+        entity: Entity,
+        components: impl DynamicBundle,
+        graph_origin: u32,
+        loc: Location,
+    ) -> [u32; 2] {
+        let ids = self
+            .op_log
+            .is_some()
+            .then(|| components.with_ids(|ids| ids.to_vec()));
+        let archetypes = self.insert_unlogged(entity, components, graph_origin, loc);
+        self.log_insert(entity, ids.as_deref());
+        archetypes
+    }
+
+    fn insert_unlogged(
+        &mut self,
+        entity: Entity,
+        components: impl DynamicBundle,
+        graph_origin: u32,
+        mut loc: Location,
+    ) -> [u32; 2] {
+        let mut target_storage;
+        let mut target = match components.key() {
+            None => {
+                target_storage = self.archetypes.get_insert_target(graph_origin, &components);
+                &target_storage
+            }
+            Some(key) => match self.insert_edges.entry((graph_origin, key)) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let target = self.archetypes.get_insert_target(graph_origin, &components);
+                    entry.insert(target)
+                }
             },
         };
+        if self.archetypes.unsorted {
+            // The cached target is discarded along with the rest of the insert graph
+            target_storage = target.clone();
+            [loc.archetype, target_storage.index] =
+                self.settle_archetypes([loc.archetype, target_storage.index]);
+            target = &target_storage;
+        }
+
+        let source_arch = &mut self.archetypes.archetypes[loc.archetype as usize];
+        unsafe {
+            // Drop the components we're overwriting
+            for &ty in &target.replaced {
+                let ptr = source_arch
+                    .get_dynamic(ty.id(), ty.layout().size(), loc.index)
+                    .unwrap();
+                ty.drop(ptr.as_ptr());
+            }
+
+            if target.index == loc.archetype {
+                // Update components in the current archetype
+                let arch = &mut self.archetypes.archetypes[loc.archetype as usize];
+                components.put(|ptr, ty| {
+                    arch.put_dynamic(ptr, ty.id(), ty.layout().size(), loc.index);
+                });
+                let moved = arch.reposition(loc.index);
+                self.entities.relocate(arch, moved);
+                return [loc.archetype, loc.archetype];
+            }
+
+            let (source_arch, target_arch) = index2(
+                &mut self.archetypes.archetypes,
+                loc.archetype as usize,
+                target.index as usize,
+            );
+
+            // Allocate storage in the archetype and update the entity's location to address it
+            let target_index = target_arch.allocate(entity.id);
+            self.counts.archetype_moves += 1;
+            let meta = &mut self.entities.meta[entity.id as usize];
+            meta.location.archetype = target.index;
+            meta.location.index = target_index;
+
+            // Move the new components
+            components.put(|ptr, ty| {
+                target_arch.put_dynamic(ptr, ty.id(), ty.layout().size(), target_index);
+            });
+
+            // Move the components we're keeping
+            for &ty in &target.retained {
+                let src = source_arch
+                    .get_dynamic(ty.id(), ty.layout().size(), loc.index)
+                    .unwrap();
+                target_arch.put_dynamic(src.as_ptr(), ty.id(), ty.layout().size(), target_index)
+            }
+            source_arch.copy_ticks(
+                loc.index,
+                target_arch,
+                target_index,
+                target.retained.iter().map(|ty| ty.id()),
+            );
+            let moved = target_arch.reposition(target_index);
+            self.entities.relocate(target_arch, moved);
+
+            // Free storage in the old archetype
+            let moved = source_arch.remove(loc.index, false);
+            self.entities.relocate(source_arch, moved);
+        }
+        [loc.archetype, target.index]
+    }
+
+    /// Add `component` to `entity`
+    ///
+    /// See [`insert`](Self::insert).
+    pub fn insert_one(
+        &mut self,
+        entity: Entity,
+        component: impl Component,
+    ) -> Result<(), NoSuchEntity> {
+        self.insert(entity, (component,))
+    }
+
+    /// Add a default-constructed component of the type identified by `id` to `entity`
+    ///
+    /// The type's [`Default`] implementation must have been registered in `registry` with
+    /// [`ComponentRegistry::register_default`]. Otherwise behaves like [`insert`](Self::insert).
+    pub fn insert_default_dynamic(
+        &mut self,
+        entity: Entity,
+        registry: &ComponentRegistry,
+        id: TypeId,
+    ) -> Result<(), InsertDefaultError> {
+        let component = registry.default_bundle(id)?;
+        self.insert(entity, component)?;
+        Ok(())
+    }
+
+    /// Move the component at `component`, of the type identified by `id`, into `entity`
+    ///
+    /// The type must have been registered in `registry`, which supplies its layout and destructor.
+    /// Otherwise behaves like [`insert`](Self::insert). Useful for scripting layers and network
+    /// code that manipulate components without knowing their types at compile time.
+    ///
+    /// # Safety
+    ///
+    /// `component` must point to a valid instance of the type identified by `id`. The pointee must
+    /// not be used or dropped afterwards if `Ok` is returned, and is left untouched otherwise.
+    pub unsafe fn insert_dynamic(
+        &mut self,
+        entity: Entity,
+        registry: &ComponentRegistry,
+        id: TypeId,
+        component: *mut u8,
+    ) -> Result<(), InsertDynamicError> {
+        let info = registry
+            .get(id)
+            .ok_or(InsertDynamicError::Unregistered(id))?
+            .type_info();
+        self.flush();
+        let component = RawComponent {
+            info,
+            ptr: component,
+        };
+        self.entities.get(entity)?;
+        self.resolve_exclusions(Some(entity), &component, &[]);
+        self.claim_singletons(Some(entity), &component);
+        let fixes = self.check_transition(Some(entity), &component, &[]);
+        let loc = self.entities.get(entity)?;
+        self.insert_inner(entity, component, loc.archetype, loc);
+        self.apply_fixes(entity, fixes);
+        self.fulfil_requirements_of(entity);
+        Ok(())
+    }
+
+    /// Copy a component of the type identified by `id` into `entity` from its raw bytes
+    ///
+    /// The type must have been registered in `registry` as plain old data with
+    /// [`ComponentRegistry::register_pod`], and `bytes` must be exactly the size of the type, but
+    /// need not be aligned. Otherwise behaves like [`insert`](Self::insert).
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// let mut registry = ComponentRegistry::new();
+    /// unsafe {
+    ///     registry.register_pod::<u32>("u32");
+    /// }
+    /// let mut world = World::new();
+    /// let e = world.spawn(());
+    /// let id = registry.id_of("u32").unwrap();
+    /// world.insert_dynamic_bytes(e, &registry, id, &7u32.to_ne_bytes()).unwrap();
+    /// assert_eq!(*world.get::<&u32>(e).unwrap(), 7);
+    /// world.remove_dynamic(e, id).unwrap();
+    /// assert!(!world.satisfies::<&u32>(e).unwrap());
+    /// ```
+    pub fn insert_dynamic_bytes(
+        &mut self,
+        entity: Entity,
+        registry: &ComponentRegistry,
+        id: TypeId,
+        bytes: &[u8],
+    ) -> Result<(), InsertDynamicError> {
+        let reg = registry
+            .get(id)
+            .ok_or(InsertDynamicError::Unregistered(id))?;
+        if !reg.is_pod() || reg.type_info().layout().size() != bytes.len() {
+            return Err(InsertDynamicError::InvalidBytes(reg.name()));
+        }
+        // Plain old data is only ever copied bytewise, so neither alignment nor mutability matter
+        unsafe { self.insert_dynamic(entity, registry, id, bytes.as_ptr().cast_mut()) }
+    }
+
+    /// Remove and drop the component of the type identified by `id` from `entity`
+    ///
+    /// Returns whether the component was present. Like [`insert_dynamic`](Self::insert_dynamic),
+    /// but no registry is needed, since the component's destructor is known to the world.
+    pub fn remove_dynamic(&mut self, entity: Entity, id: TypeId) -> Result<bool, NoSuchEntity> {
+        self.flush();
+        let loc = self.entities.get(entity)?;
+        if !self.archetypes.archetypes[loc.archetype as usize].has_dynamic(id) {
+            return Ok(false);
+        }
+        let fixes = self.check_transition(Some(entity), &(), &[id]);
+        self.drop_components(entity, &[id]);
+        self.apply_fixes(entity, fixes);
+        Ok(true)
+    }
+
+    /// Define a component type at runtime, e.g. for a scripting layer
+    ///
+    /// The type's values occupy `layout`, and are dropped by calling `drop`, if any, on a pointer
+    /// to them. Components of the type are added with [`EntityBuilder::add_dynamic`] and accessed
+    /// through the returned key, e.g. with [`EntityRef::component_ptr`], [`Archetype::column_ptr`]
+    /// or [`QueryBuilder::read_dynamic`](crate::QueryBuilder::read_dynamic). `name` identifies
+    /// the type within this world, for [`dynamic_key`](Self::dynamic_key).
+    ///
+    /// Keys are unique across all worlds, and never reused, so at most [`ComponentKey::MAX`]
+    /// dynamic types can be registered over the life of the process.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// # use core::alloc::Layout;
+    /// let mut world = World::new();
+    /// let key = world
+    ///     .register_dynamic(Layout::new::<[f32; 2]>(), None, "Velocity")
+    ///     .unwrap();
+    /// assert_eq!(world.dynamic_key("Velocity"), Some(key));
+    /// assert_eq!(
+    ///     world.register_dynamic(Layout::new::<u8>(), None, "Velocity"),
+    ///     Err(RegisterDynamicError::NameTaken)
+    /// );
+    /// ```
+    pub fn register_dynamic(
+        &mut self,
+        layout: core::alloc::Layout,
+        drop: Option<unsafe fn(*mut u8)>,
+        name: &str,
+    ) -> Result<ComponentKey, RegisterDynamicError> {
+        match self.dynamic_types.entry(name.into()) {
+            Entry::Occupied(_) => Err(RegisterDynamicError::NameTaken),
+            Entry::Vacant(entry) => {
+                let key =
+                    ComponentKey::allocate(layout, drop).ok_or(RegisterDynamicError::Exhausted)?;
+                Ok(*entry.insert(key))
+            }
+        }
+    }
+
+    /// The key of the component type registered with [`register_dynamic`](Self::register_dynamic)
+    /// under `name`
+    pub fn dynamic_key(&self, name: &str) -> Option<ComponentKey> {
+        self.dynamic_types.get(name).copied()
+    }
+
+    /// Remove components from `entity`
+    ///
+    /// Computational cost is proportional to the number of components `entity` has. The entity
+    /// itself is not removed, even if no components remain; use `despawn` for that. If any
+    /// component in `T` is not present in `entity`, no components are removed and an error is
+    /// returned.
+    ///
+    /// When removing a single component, see [`remove_one`](Self::remove_one) for convenience.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let e = world.spawn((123, "abc", true));
+    /// assert_eq!(world.remove::<(i32, &str)>(e), Ok((123, "abc")));
+    /// assert!(world.get::<&i32>(e).is_err());
+    /// assert!(world.get::<&&str>(e).is_err());
+    /// assert_eq!(*world.get::<&bool>(e).unwrap(), true);
+    /// ```
+    pub fn remove<T: Bundle + 'static>(&mut self, entity: Entity) -> Result<T, ComponentError> {
+        self.flush();
+
+        let loc = self.entities.get(entity)?;
+        let source_arch = &self.archetypes.archetypes[loc.archetype as usize];
+        let fixes = if self.validators.is_empty()
+            || !T::with_static_ids(|ids| ids.iter().all(|&id| source_arch.has_dynamic(id)))
+        {
+            Fixes::default()
+        } else {
+            T::with_static_ids(|ids| self.check_transition(Some(entity), &(), ids))
+        };
+
+        // Gather current metadata
+        let loc = self.entities.get(entity)?;
+        let old_index = loc.index;
+        let source_arch = &self.archetypes.archetypes[loc.archetype as usize];
+
+        // Move out of the source archetype, or bail out if a component is missing
+        let bundle = unsafe {
+            T::get(|ty| source_arch.get_dynamic(ty.id(), ty.layout().size(), old_index))?
+        };
+
+        // Find the target archetype ID
+        let target =
+            Self::remove_target::<T>(&mut self.archetypes, &mut self.remove_edges, loc.archetype);
+        let [source, target] = self.settle_archetypes([loc.archetype, target]);
+
+        // Store components to the target archetype and update metadata
+        if source != target {
+            // If we actually removed any components, the entity needs to be moved into a new archetype
+            let (source_arch, target_arch) = index2(
+                &mut self.archetypes.archetypes,
+                source as usize,
+                target as usize,
+            );
+            let loc = self.entities.get_mut(entity).unwrap();
+            let target_index = unsafe { target_arch.allocate(entity.id) };
+            loc.archetype = target;
+            loc.index = target_index;
+            self.counts.archetype_moves += 1;
+            source_arch.copy_ticks(
+                old_index,
+                target_arch,
+                target_index,
+                target_arch.type_ids().iter().copied(),
+            );
+            let moved = unsafe {
+                source_arch.move_to(old_index, |src, ty, size| {
+                    // Only move the components present in the target archetype, i.e. the non-removed ones.
+                    if let Some(dst) = target_arch.get_dynamic(ty, size, target_index) {
+                        ptr::copy_nonoverlapping(src, dst.as_ptr(), size);
+                    }
+                })
+            };
+            self.entities.relocate(source_arch, moved);
+            let moved = target_arch.reposition(target_index);
+            self.entities.relocate(target_arch, moved);
+        }
+        self.record_removals(entity, source, Some(target));
+        self.apply_fixes(entity, fixes);
+
+        Ok(bundle)
+    }
+
+    fn remove_target<T: Bundle + 'static>(
+        archetypes: &mut ArchetypeSet,
+        remove_edges: &mut IndexTypeIdMap<u32>,
+        old_archetype: u32,
+    ) -> u32 {
+        match remove_edges.entry((old_archetype, TypeId::of::<T>())) {
+            Entry::Occupied(entry) => *entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let info = T::with_static_type_info(|removed| {
+                    archetypes.archetypes[old_archetype as usize]
+                        .types()
+                        .iter()
+                        .filter(|x| removed.binary_search(x).is_err())
+                        .cloned()
+                        .collect::<Vec<_>>()
+                });
+                let elements = info.iter().map(|x| x.id()).collect::<Box<_>>();
+                let index = archetypes.get(&*elements, move || info);
+                *entry.insert(index)
+            }
+        }
+    }
+
+    /// Remove the `T` component from `entity`
+    ///
+    /// See [`remove`](Self::remove).
+    pub fn remove_one<T: Component>(&mut self, entity: Entity) -> Result<T, ComponentError> {
+        self.remove::<(T,)>(entity).map(|(x,)| x)
+    }
+
+    /// Remove `S` components from `entity` and then add `components`
+    ///
+    /// This has the same effect as calling [`remove::<S>`](Self::remove) and then [`insert::<T>`](Self::insert),
+    /// but is more efficient as the intermediate archetype after removal but before insertion is skipped.
+    pub fn exchange<S: Bundle + 'static, T: DynamicBundle>(
+        &mut self,
+        entity: Entity,
+        components: T,
+    ) -> Result<S, ComponentError> {
+        self.flush();
+
+        // Gather current metadata
+        self.entities.get(entity)?;
+        let removing = if self.exclusions.is_empty() && self.validators.is_empty() {
+            Vec::new()
+        } else {
+            S::with_static_ids(|ids| ids.to_vec())
+        };
+        self.resolve_exclusions(Some(entity), &components, &removing);
+        self.claim_singletons(Some(entity), &components);
+        let loc = self.entities.get(entity)?;
+        let source_arch = &self.archetypes.archetypes[loc.archetype as usize];
+        let fixes = if removing.iter().all(|&id| source_arch.has_dynamic(id)) {
+            self.check_transition(Some(entity), &components, &removing)
+        } else {
+            // Fails below
+            Fixes::default()
+        };
 
-        let source_arch = &mut self.archetypes.archetypes[loc.archetype as usize];
+        // Move out of the source archetype, or bail out if a component is missing
+        let source_arch = &self.archetypes.archetypes[loc.archetype as usize];
+
+        let bundle = unsafe {
+            S::get(|ty| source_arch.get_dynamic(ty.id(), ty.layout().size(), loc.index))?
+        };
+
+        // Find the intermediate archetype ID
+        let intermediate =
+            Self::remove_target::<S>(&mut self.archetypes, &mut self.remove_edges, loc.archetype);
+        let [source, intermediate] = self.settle_archetypes([loc.archetype, intermediate]);
+        let loc = Location {
+            archetype: source,
+            ..loc
+        };
+
+        let [source, target] = self.insert_inner(entity, components, intermediate, loc);
+        self.record_removals(entity, source, Some(target));
+        self.apply_fixes(entity, fixes);
+        self.fulfil_requirements_of(entity);
+
+        Ok(bundle)
+    }
+
+    /// Remove the `S` component from `entity` and then add `component`
+    ///
+    /// See [`exchange`](Self::exchange).
+    pub fn exchange_one<S: Component, T: Component>(
+        &mut self,
+        entity: Entity,
+        component: T,
+    ) -> Result<S, ComponentError> {
+        self.exchange::<(S,), (T,)>(entity, (component,))
+            .map(|(x,)| x)
+    }
+
+    /// Borrow a single component of `entity` without safety checks
+    ///
+    /// `T` must be a shared or unique reference to a component type.
+    ///
+    /// Should only be used as a building block for safe abstractions.
+    ///
+    /// # Safety
+    ///
+    /// `entity` must have been previously obtained from this [`World`], and no unique borrow of the
+    /// same component of `entity` may be live simultaneous to the returned reference.
+    pub unsafe fn get_unchecked<'a, T: ComponentRef<'a>>(
+        &'a self,
+        entity: Entity,
+    ) -> Result<T, ComponentError> {
+        let loc = self.entities.get(entity)?;
+        let archetype = &self.archetypes.archetypes[loc.archetype as usize];
+        let state = archetype
+            .get_state::<T::Component>()
+            .ok_or_else(MissingComponent::new::<T::Component>)?;
+        Ok(T::from_raw(
+            archetype
+                .get_base::<T::Component>(state)
+                .as_ptr()
+                .add(loc.index as usize),
+        ))
+    }
+
+    /// Convert all reserved entities into empty entities that can be iterated and accessed
+    ///
+    /// Invoked implicitly by operations that add or remove components or entities, i.e. all
+    /// variations of `spawn`, `despawn`, `insert`, and `remove`.
+    pub fn flush(&mut self) {
+        let arch = &mut self.archetypes.archetypes[0];
+        let mut count = 0;
+        let mut flushed = Vec::new();
+        let logging = self.op_log.is_some();
+        self.entities.flush(|id, location| {
+            location.index = unsafe { arch.allocate(id) };
+            count += 1;
+            if logging {
+                flushed.push(id);
+            }
+        });
+        if count != 0 {
+            self.counts.spawns += count;
+            self.counts.flushes += 1;
+        }
+        if let (Some(log), false) = (&mut self.op_log, flushed.is_empty()) {
+            let meta = &self.entities.meta;
+            let entities = flushed
+                .into_iter()
+                .map(|id| Entity {
+                    id,
+                    generation: meta[id as usize].generation,
+                })
+                .collect();
+            log.push(self.archetypes.tick, Op::Flush { entities });
+        }
+    }
+
+    /// Forget which entities have been accessed by queries
+    ///
+    /// Entities are then reported by [`LeakDetector::report`](crate::LeakDetector::report) until a
+    /// query accesses them again. Call this once the world has reached a steady state, so that
+    /// entities queried only during startup can be detected.
+    pub fn reset_query_tracking(&mut self) {
+        for archetype in &mut self.archetypes.archetypes {
+            archetype.clear_queried();
+        }
+    }
+
+    /// Inspect the archetypes that entities are organized into
+    ///
+    /// Useful for dynamically scheduling concurrent queries by checking borrows in advance, and for
+    /// efficient serialization.
+    #[inline(always)]
+    pub fn archetypes(&self) -> impl ExactSizeIterator<Item = &'_ Archetype> + '_ {
+        self.archetypes_inner().iter()
+    }
+
+    /// Despawn `entity`, yielding a [`DynamicBundle`] of its components
+    ///
+    /// Useful for moving entities between worlds.
+    pub fn take(&mut self, entity: Entity) -> Result<TakenEntity<'_>, NoSuchEntity> {
+        self.flush();
+        let loc = self.entities.get(entity)?;
+        self.record_removals(entity, loc.archetype, None);
+        let archetype = &mut self.archetypes.archetypes[loc.archetype as usize];
+        self.counts.despawns += 1;
         unsafe {
-            // Drop the components we're overwriting
-            for &ty in &target.replaced {
-                let ptr = source_arch
-                    .get_dynamic(ty.id(), ty.layout().size(), loc.index)
-                    .unwrap();
-                ty.drop(ptr.as_ptr());
+            Ok(TakenEntity::new(
+                &mut self.entities,
+                entity,
+                archetype,
+                loc.index,
+            ))
+        }
+    }
+
+    /// Despawn `entity`, yielding the components in `T` and dropping any others
+    ///
+    /// Equivalent to, but cheaper than, [`remove`](Self::remove) followed by
+    /// [`despawn`](Self::despawn), as the entity's other components aren't moved to a new
+    /// archetype first. If any component in `T` is not present in `entity`, the entity is left
+    /// unchanged and an error is returned.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let e = world.spawn((123, "abc", true));
+    /// assert_eq!(world.despawn_take::<(i32, &str)>(e), Ok((123, "abc")));
+    /// assert!(!world.contains(e));
+    /// ```
+    #[cfg_attr(feature = "audit-log", track_caller)]
+    pub fn despawn_take<T: Bundle + 'static>(
+        &mut self,
+        entity: Entity,
+    ) -> Result<T, ComponentError> {
+        self.flush();
+        let loc = self.entities.get(entity)?;
+        let archetype = &mut self.archetypes.archetypes[loc.archetype as usize];
+        let bundle =
+            unsafe { T::get(|ty| archetype.get_dynamic(ty.id(), ty.layout().size(), loc.index))? };
+        T::with_static_ids(|ids| {
+            for ty in archetype
+                .types()
+                .iter()
+                .filter(|ty| !ids.contains(&ty.id()))
+            {
+                unsafe {
+                    let ptr = archetype
+                        .get_dynamic(ty.id(), ty.layout().size(), loc.index)
+                        .unwrap();
+                    ty.drop(ptr.as_ptr());
+                }
             }
+        });
+        let moved = unsafe { archetype.remove(loc.index, false) };
+        self.entities.relocate(archetype, moved);
+        self.entities.free(entity).unwrap();
+        self.record_removals(entity, loc.archetype, None);
+        self.counts.despawns += 1;
+        Ok(bundle)
+    }
 
-            if target.index == loc.archetype {
-                // Update components in the current archetype
-                let arch = &mut self.archetypes.archetypes[loc.archetype as usize];
-                components.put(|ptr, ty| {
-                    arch.put_dynamic(ptr, ty.id(), ty.layout().size(), loc.index);
+    /// Despawn `entity`, yielding all of its components in an [`EntityBuilder`]
+    ///
+    /// Like [`take`](Self::take), but the components can be stored, inspected, and later spawned
+    /// into any world without borrowing this one.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let e = world.spawn((123, "abc"));
+    /// let mut components = world.despawn_take_dynamic(e).unwrap();
+    /// assert!(!world.contains(e));
+    /// assert_eq!(*components.get::<&i32>().unwrap(), 123);
+    /// let f = world.spawn(components.build());
+    /// assert_eq!(*world.get::<&&str>(f).unwrap(), "abc");
+    /// ```
+    pub fn despawn_take_dynamic(&mut self, entity: Entity) -> Result<EntityBuilder, NoSuchEntity> {
+        let mut builder = EntityBuilder::new();
+        builder.add_bundle(self.take(entity)?);
+        Ok(builder)
+    }
+
+    /// Returns a distinct value after `archetypes` is changed
+    ///
+    /// Store the current value after deriving information from [`archetypes`](Self::archetypes),
+    /// then check whether the value returned by this function differs before attempting an
+    /// operation that relies on its correctness. Useful for determining whether e.g. a concurrent
+    /// query execution plan is still correct.
+    ///
+    /// The generation may be, but is not necessarily, changed as a result of adding or removing any
+    /// entity or component.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let initial_gen = world.archetypes_generation();
+    /// world.spawn((123, "abc"));
+    /// assert_ne!(initial_gen, world.archetypes_generation());
+    /// ```
+    pub fn archetypes_generation(&self) -> ArchetypesGeneration {
+        ArchetypesGeneration(self.archetypes.generation())
+    }
+
+    /// Incrementally release memory held by archetypes, doing at most about `budget` units of work
+    ///
+    /// Successive calls visit each archetype in turn, releasing the storage of empty archetypes and
+    /// shrinking archetypes whose capacity exceeds twice their length. Visiting an archetype costs
+    /// one unit, plus one per entity moved if it's shrunk. Once every archetype has been visited,
+    /// all empty archetypes are removed, which costs one unit per archetype plus one per entity in
+    /// an archetype whose index changes; this is only deferred to a later call if it exceeds the
+    /// remaining budget and other work was already done. Returns `true` when a full pass is
+    /// complete, after which the next call begins a new pass.
+    ///
+    /// Calling this with a small budget once per frame amortizes the cost of maintenance, rather
+    /// than causing a hitch when done all at once.
+    ///
+    /// [Plugins](Plugin::maintain) are maintained first, then entities in the
+    /// [`despawn_queue`](Self::despawn_queue) are despawned, regardless of `budget`.
+    ///
+    /// Removing archetypes changes the [`archetypes_generation`](Self::archetypes_generation), and
+    /// the order of [`archetypes`](Self::archetypes) may differ from that of a world where the
+    /// removed archetypes never existed once they are recreated, unless archetypes are kept in
+    /// [canonical order](WorldBuilder::canonical_archetype_order).
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((1, true));
+    /// world.remove_one::<bool>(a).unwrap();
+    /// assert_eq!(world.archetypes().len(), 3);
+    /// while !world.maintain(1) {}
+    /// assert_eq!(world.archetypes().len(), 2);
+    /// assert_eq!(*world.get::<&i32>(a).unwrap(), 1);
+    /// ```
+    pub fn maintain(&mut self, budget: u32) -> bool {
+        // Plugins may add further plugins while detached
+        let mut plugins = mem::take(&mut self.plugins);
+        for plugin in &mut plugins {
+            plugin.maintain(self);
+        }
+        plugins.append(&mut self.plugins);
+        self.plugins = plugins;
+
+        self.despawn_queued();
+        let mut cursor = self.archetypes.maintain_cursor;
+        let done = self.compact(&mut cursor, budget);
+        self.archetypes.maintain_cursor = cursor;
+        done
+    }
+
+    /// The archetype work of [`maintain`](Self::maintain), resuming from the archetype at
+    /// `cursor`, which is reset to 0 once a pass is complete
+    pub(crate) fn compact(&mut self, cursor: &mut u32, budget: u32) -> bool {
+        self.flush();
+        let mut spent = 0u32;
+        while spent < budget {
+            let Some(archetype) = self.archetypes.archetypes.get_mut(*cursor as usize) else {
+                break;
+            };
+            spent = spent.saturating_add(1);
+            if archetype.capacity() / 2 > archetype.len() {
+                spent = spent.saturating_add(archetype.len());
+                archetype.shrink_to_fit();
+            }
+            *cursor += 1;
+        }
+        if (*cursor as usize) < self.archetypes.archetypes.len() {
+            return false;
+        }
+
+        let archetypes = &self.archetypes.archetypes;
+        if let Some(first) = archetypes.iter().skip(1).position(|x| x.is_empty()) {
+            let cost = archetypes[first + 1..]
+                .iter()
+                .fold(archetypes.len() as u32, |acc, x| {
+                    acc.saturating_add(x.len())
                 });
-                return;
+            if spent != 0 && spent.saturating_add(cost) > budget {
+                return false;
             }
+            self.remove_empty_archetypes();
+        }
+        *cursor = 0;
+        true
+    }
 
-            let (source_arch, target_arch) = index2(
-                &mut self.archetypes.archetypes,
-                loc.archetype as usize,
-                target.index as usize,
-            );
+    /// Remove every empty archetype other than the root, updating all references to the rest
+    fn remove_empty_archetypes(&mut self) {
+        let set = &mut self.archetypes;
+        let mut next = 0;
+        let remap = set
+            .archetypes
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                if i != 0 && x.is_empty() {
+                    return u32::MAX;
+                }
+                next += 1;
+                next - 1
+            })
+            .collect::<Vec<_>>();
+        let first = remap
+            .iter()
+            .position(|&x| x == u32::MAX)
+            .unwrap_or(remap.len());
+        let mut i = 0;
+        let counts = &mut self.counts;
+        set.archetypes.retain_mut(|x| {
+            i += 1;
+            if remap[i - 1] == u32::MAX {
+                counts.column_grows += x.take_column_grows();
+                return false;
+            }
+            true
+        });
+        set.index.retain(|_, x| {
+            *x = remap[*x as usize];
+            *x != u32::MAX
+        });
+        set.generation = set.generation.wrapping_add(1);
+        set.renumbered = set.renumbered.wrapping_add(1);
+        for (new, archetype) in set.archetypes.iter().enumerate().skip(first) {
+            for &id in archetype.ids() {
+                self.entities.meta[id as usize].location.archetype = new as u32;
+            }
+        }
+        self.bundle_to_archetype.clear();
+        self.insert_edges.clear();
+        self.remove_edges.clear();
+        // Indices visited by an interrupted pass no longer correspond
+        self.archetypes.maintain_cursor = 0;
+    }
 
-            // Allocate storage in the archetype and update the entity's location to address it
-            let target_index = target_arch.allocate(entity.id);
-            let meta = &mut self.entities.meta[entity.id as usize];
-            meta.location.archetype = target.index;
-            meta.location.index = target_index;
+    /// Order [`archetypes`](Self::archetypes) canonically, by their sorted sets of component
+    /// [`TypeId`]s
+    ///
+    /// Archetypes are otherwise ordered by creation, so worlds with the same content can iterate
+    /// their entities in different orders depending on the history of operations that built them.
+    /// Sorting makes the order of archetypes, and hence of query iteration across archetypes,
+    /// depend only on which archetypes exist. Empty archetypes are retained until
+    /// [`maintain`](Self::maintain), so may need to be removed before comparing worlds. `TypeId`s,
+    /// and hence the order, may differ between builds of a program.
+    ///
+    /// To keep archetypes in canonical order as they're created, see
+    /// [`WorldBuilder::canonical_archetype_order`].
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut a = World::new();
+    /// a.spawn((1, true));
+    /// a.spawn((2,));
+    /// let mut b = World::new();
+    /// b.spawn((2,));
+    /// b.spawn((1, true));
+    /// a.sort_archetypes();
+    /// b.sort_archetypes();
+    /// let ids = |world: &World| {
+    ///     world.archetypes().map(|x| x.component_types().collect::<Vec<_>>()).collect::<Vec<_>>()
+    /// };
+    /// assert_eq!(ids(&a), ids(&b));
+    /// ```
+    pub fn sort_archetypes(&mut self) {
+        self.flush();
+        self.archetypes.unsorted = false;
+        self.reorder_archetypes(&mut []);
+    }
 
-            // Move the new components
-            components.put(|ptr, ty| {
-                target_arch.put_dynamic(ptr, ty.id(), ty.layout().size(), target_index);
-            });
+    /// Restore canonical order if it's maintained and was disturbed by adding an archetype,
+    /// returning the new indices of the archetypes at `held`
+    ///
+    /// Must be called after creating an archetype, before any other archetype is referenced.
+    fn settle_archetypes<const N: usize>(&mut self, mut held: [u32; N]) -> [u32; N] {
+        if mem::take(&mut self.archetypes.unsorted) {
+            self.reorder_archetypes(&mut held);
+        }
+        held
+    }
 
-            // Move the components we're keeping
-            for &ty in &target.retained {
-                let src = source_arch
-                    .get_dynamic(ty.id(), ty.layout().size(), loc.index)
-                    .unwrap();
-                target_arch.put_dynamic(src.as_ptr(), ty.id(), ty.layout().size(), target_index)
-            }
+    /// Sort archetypes canonically, updating the archetype indices in `held` and all references
+    /// owned by the world
+    fn reorder_archetypes(&mut self, held: &mut [u32]) {
+        let set = &mut self.archetypes;
+        let keys = set
+            .archetypes
+            .iter()
+            .map(|x| {
+                let mut ids = x.type_ids().to_vec();
+                ids.sort_unstable();
+                ids
+            })
+            .collect::<Vec<_>>();
+        // Position `i` of the result holds archetype `order[i]`
+        let mut order = (0..set.archetypes.len() as u32).collect::<Vec<_>>();
+        order.sort_unstable_by(|&a, &b| keys[a as usize].cmp(&keys[b as usize]));
+        if order.iter().enumerate().all(|(i, &x)| i as u32 == x) {
+            return;
+        }
 
-            // Free storage in the old archetype
-            if let Some(moved) = source_arch.remove(loc.index, false) {
-                self.entities.meta[moved as usize].location.index = loc.index;
+        let mut remap = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            remap[old as usize] = new as u32;
+        }
+        let mut old = mem::take(&mut set.archetypes)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        set.archetypes.reserve_exact(old.len());
+        set.archetypes
+            .extend(order.iter().map(|&x| old[x as usize].take().unwrap()));
+        for x in set.index.values_mut() {
+            *x = remap[*x as usize];
+        }
+        set.generation = set.generation.wrapping_add(1);
+        set.renumbered = set.renumbered.wrapping_add(1);
+        for (new, archetype) in set.archetypes.iter().enumerate() {
+            if order[new] as usize == new {
+                continue;
             }
+            for &id in archetype.ids() {
+                self.entities.meta[id as usize].location.archetype = new as u32;
+            }
+        }
+        for x in held {
+            *x = remap[*x as usize];
         }
+        self.bundle_to_archetype.clear();
+        self.insert_edges.clear();
+        self.remove_edges.clear();
+        self.archetypes.maintain_cursor = 0;
+    }
+
+    /// Number of currently live entities
+    #[inline]
+    pub fn len(&self) -> u32 {
+        self.entities.len()
+    }
+
+    /// Whether no entities are live
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Entity handles that will be yielded by future spawns
+    ///
+    /// [`Entity`] handles will be allocated deterministically between different
+    /// worlds (e.g. across serialization) if they have the same live entities
+    /// and the same freelist.
+    ///
+    /// To deserialize a world that will match the entity allocation behavior of
+    /// a previously serialized world, first create a new world, then use
+    /// [`spawn_at`](Self::spawn_at) or similar to reconstruct the serialized
+    /// world's entities in place, then call
+    /// [`set_freelist`](Self::set_freelist) with the serialized world's
+    /// freelist.
+    ///
+    /// IDs held back from reuse by [`WorldBuilder::id_reuse_delay`] are included first, newest
+    /// first, so that a world restored from the freelist reuses them only after every other free ID.
+    pub fn freelist(&self) -> impl ExactSizeIterator<Item = Entity> + '_ {
+        self.entities.freelist()
     }
 
-    /// Add `component` to `entity`
+    /// Designate entity IDs to be reused by future spawns
     ///
-    /// See [`insert`](Self::insert).
-    pub fn insert_one(
-        &mut self,
-        entity: Entity,
-        component: impl Component,
-    ) -> Result<(), NoSuchEntity> {
-        self.insert(entity, (component,))
+    /// See [`freelist`](Self::freelist). May only be called when the set of
+    /// live entity IDs exactly matches those that were live when the supplied
+    /// freelist was captured.
+    ///
+    /// IDs in `freelist` that this world is currently holding back from reuse with
+    /// [`WorldBuilder::id_reuse_delay`] stay held back; held-back IDs missing from `freelist` are
+    /// discarded.
+    pub fn set_freelist(&mut self, freelist: &[Entity]) {
+        self.entities.set_freelist(freelist);
     }
 
-    /// Remove components from `entity`
+    /// Capture the state of the entity allocator, which determines the handles of future spawns
     ///
-    /// Computational cost is proportional to the number of components `entity` has. The entity
-    /// itself is not removed, even if no components remain; use `despawn` for that. If any
-    /// component in `T` is not present in `entity`, no components are removed and an error is
-    /// returned.
+    /// Unlike the [`freelist`](Self::freelist), this includes the generations of despawned
+    /// entities, entities that were reserved but not yet [flushed](Self::flush), and IDs held back
+    /// from reuse by [`WorldBuilder::id_reuse_delay`], so that restoring it with
+    /// [`set_allocator_state`](Self::set_allocator_state) reproduces the original world's
+    /// allocations exactly. With the `serde` feature, it can be serialized alongside a world's
+    /// components.
+    pub fn allocator_state(&self) -> AllocatorState {
+        self.entities.save()
+    }
+
+    /// Restore the state of the entity allocator captured by
+    /// [`allocator_state`](Self::allocator_state)
     ///
-    /// When removing a single component, see [`remove_one`](Self::remove_one) for convenience.
+    /// Call this after reconstructing the captured world's live entities with
+    /// [`spawn_at`](Self::spawn_at) or similar, in a world built with the same
+    /// [`WorldBuilder`] configuration. Reserved entities are flushed first.
     ///
-    /// # Example
     /// ```
     /// # use hecs::*;
     /// let mut world = World::new();
-    /// let e = world.spawn((123, "abc", true));
-    /// assert_eq!(world.remove::<(i32, &str)>(e), Ok((123, "abc")));
-    /// assert!(world.get::<&i32>(e).is_err());
-    /// assert!(world.get::<&&str>(e).is_err());
-    /// assert_eq!(*world.get::<&bool>(e).unwrap(), true);
+    /// let a = world.spawn((1,));
+    /// let b = world.spawn((2,));
+    /// world.despawn(a).unwrap();
+    /// let state = world.allocator_state();
+    ///
+    /// let mut loaded = World::new();
+    /// loaded.spawn_at(b, (2,));
+    /// loaded.set_allocator_state(&state).unwrap();
+    /// assert_eq!(loaded.spawn((3,)), world.spawn((3,)));
     /// ```
-    pub fn remove<T: Bundle + 'static>(&mut self, entity: Entity) -> Result<T, ComponentError> {
+    ///
+    /// # Errors
+    ///
+    /// Fails, leaving the allocator unchanged, if `state` doesn't match the live entities, i.e.
+    /// disagrees with the generation of any of them, marks any of them free, or counts a different
+    /// number of them.
+    pub fn set_allocator_state(
+        &mut self,
+        state: &AllocatorState,
+    ) -> Result<(), AllocatorStateMismatch> {
         self.flush();
-
-        // Gather current metadata
-        let loc = self.entities.get_mut(entity)?;
-        let old_index = loc.index;
-        let source_arch = &self.archetypes.archetypes[loc.archetype as usize];
-
-        // Move out of the source archetype, or bail out if a component is missing
-        let bundle = unsafe {
-            T::get(|ty| source_arch.get_dynamic(ty.id(), ty.layout().size(), old_index))?
-        };
-
-        // Find the target archetype ID
-        let target =
-            Self::remove_target::<T>(&mut self.archetypes, &mut self.remove_edges, loc.archetype);
-
-        // Store components to the target archetype and update metadata
-        if loc.archetype != target {
-            // If we actually removed any components, the entity needs to be moved into a new archetype
-            let (source_arch, target_arch) = index2(
-                &mut self.archetypes.archetypes,
-                loc.archetype as usize,
-                target as usize,
-            );
-            let target_index = unsafe { target_arch.allocate(entity.id) };
-            loc.archetype = target;
-            loc.index = target_index;
-            if let Some(moved) = unsafe {
-                source_arch.move_to(old_index, |src, ty, size| {
-                    // Only move the components present in the target archetype, i.e. the non-removed ones.
-                    if let Some(dst) = target_arch.get_dynamic(ty, size, target_index) {
-                        ptr::copy_nonoverlapping(src, dst.as_ptr(), size);
-                    }
-                })
-            } {
-                self.entities.meta[moved as usize].location.index = old_index;
-            }
+        if !self.entities.can_restore(state) {
+            return Err(AllocatorStateMismatch);
         }
+        self.entities.restore(state);
+        Ok(())
+    }
 
-        Ok(bundle)
+    /// How often the generations of entity IDs have wrapped around
+    ///
+    /// Counts only IDs still tracked by the world: [`clear`](Self::clear) and
+    /// [`compact_meta`](Self::compact_meta) discard the counts of the IDs they release.
+    pub fn generation_wrap_stats(&self) -> GenerationWrapStats {
+        self.entities.generation_wrap_stats()
     }
 
-    fn remove_target<T: Bundle + 'static>(
-        archetypes: &mut ArchetypeSet,
-        remove_edges: &mut IndexTypeIdMap<u32>,
-        old_archetype: u32,
-    ) -> u32 {
-        match remove_edges.entry((old_archetype, TypeId::of::<T>())) {
-            Entry::Occupied(entry) => *entry.into_mut(),
-            Entry::Vacant(entry) => {
-                let info = T::with_static_type_info(|removed| {
-                    archetypes.archetypes[old_archetype as usize]
-                        .types()
-                        .iter()
-                        .filter(|x| removed.binary_search(x).is_err())
-                        .cloned()
-                        .collect::<Vec<_>>()
-                });
-                let elements = info.iter().map(|x| x.id()).collect::<Box<_>>();
-                let index = archetypes.get(&*elements, move || info);
-                *entry.insert(index)
-            }
-        }
+    /// Number of times the generation of the entity ID `id` has wrapped around
+    pub fn generation_wraps(&self, id: u32) -> u32 {
+        self.entities.generation_wraps(id)
     }
 
-    /// Remove the `T` component from `entity`
+    /// Call `hook` whenever despawning an entity wraps the generation of its ID around
     ///
-    /// See [`remove`](Self::remove).
-    pub fn remove_one<T: Component>(&mut self, entity: Entity) -> Result<T, ComponentError> {
-        self.remove::<(T,)>(entity).map(|(x,)| x)
+    /// `hook` receives the despawned entity and the number of times its ID's generation has now
+    /// wrapped, and may e.g. log a warning or schedule a [`compact_meta`](Self::compact_meta).
+    /// Replaces any previously set hook.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::builder().entity_bits(EntityBits::new(8, 2)).build();
+    /// world.set_generation_wrap_hook(|entity, wraps| {
+    ///     assert_eq!((entity.id(), wraps), (0, 1));
+    /// });
+    /// for _ in 0..3 {
+    ///     let e = world.spawn(());
+    ///     world.despawn(e).unwrap();
+    /// }
+    /// assert_eq!(world.generation_wraps(0), 1);
+    /// ```
+    pub fn set_generation_wrap_hook(
+        &mut self,
+        hook: impl FnMut(Entity, u32) + Send + Sync + 'static,
+    ) {
+        self.entities.wrap_hook = Some(Box::new(hook));
     }
 
-    /// Remove `S` components from `entity` and then add `components`
+    /// Release the memory used to track despawned entity IDs above the highest live ID
     ///
-    /// This has the same effect as calling [`remove::<S>`](Self::remove) and then [`insert::<T>`](Self::insert),
-    /// but is more efficient as the intermediate archetype after removal but before insertion is skipped.
-    pub fn exchange<S: Bundle + 'static, T: DynamicBundle>(
-        &mut self,
-        entity: Entity,
-        components: T,
-    ) -> Result<S, ComponentError> {
+    /// After a mass despawn, such as unloading a level, the world retains the generation of every
+    /// ID ever used. This discards that state for the dead IDs at the high end of the ID space,
+    /// removing them from the [`freelist`](Self::freelist) and from any reuse delay, without
+    /// renumbering any live entity. Returns the number of IDs discarded.
+    ///
+    /// Discarded IDs are allocated afresh in ascending order once the freelist is exhausted,
+    /// starting again from generation 1. Stale handles to them may therefore alias entities
+    /// spawned later, so only compact once such handles have been discarded.
+    ///
+    /// Does nothing and returns 0 for worlds built with [`WorldBuilder::monotonic_ids`], whose IDs
+    /// must never be allocated twice.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let entities = world.spawn_batch((0..100).map(|i| (i,))).collect::<Vec<_>>();
+    /// for &e in &entities[1..] {
+    ///     world.despawn(e).unwrap();
+    /// }
+    /// assert_eq!(world.compact_meta(), 99);
+    /// assert_eq!(world.freelist().len(), 0);
+    /// assert_eq!(world.spawn(()).id(), 1);
+    /// ```
+    pub fn compact_meta(&mut self) -> u32 {
         self.flush();
+        self.entities.compact_meta()
+    }
 
-        // Gather current metadata
-        let loc = self.entities.get(entity)?;
-
-        // Move out of the source archetype, or bail out if a component is missing
-        let source_arch = &self.archetypes.archetypes[loc.archetype as usize];
+    /// Recorded allocations and frees of any generation of the entity ID `id`, oldest first
+    ///
+    /// Answers questions like "who despawned my entity?" in development builds. Each event records
+    /// the source location of the `World` method that caused it and the tag set by
+    /// [`set_audit_tag`](Self::set_audit_tag) at the time. Only the most recent events across all
+    /// entities are kept; see [`set_audit_capacity`](Self::set_audit_capacity).
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((1,));
+    /// world.set_audit_tag(Some("cleanup"));
+    /// world.despawn(a).unwrap();
+    ///
+    /// let history = world.audit_history(a.id());
+    /// assert_eq!(history.len(), 2);
+    /// assert_eq!(history[1].op(), AuditOp::Free);
+    /// assert_eq!(history[1].tag(), Some("cleanup"));
+    /// assert_eq!(history[1].caller().file(), file!());
+    /// ```
+    #[cfg(feature = "audit-log")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "audit-log")))]
+    pub fn audit_history(&self, id: u32) -> Vec<AuditEvent> {
+        self.entities.audit.lock().history(id)
+    }
 
-        let bundle = unsafe {
-            S::get(|ty| source_arch.get_dynamic(ty.id(), ty.layout().size(), loc.index))?
-        };
+    /// Attach `tag` to subsequently recorded audit events, until changed
+    #[cfg(feature = "audit-log")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "audit-log")))]
+    pub fn set_audit_tag(&mut self, tag: Option<&'static str>) {
+        self.entities.audit.get_mut().set_tag(tag);
+    }
 
-        // Find the intermediate archetype ID
-        let intermediate =
-            Self::remove_target::<S>(&mut self.archetypes, &mut self.remove_edges, loc.archetype);
+    /// Retain at most `capacity` audit events, discarding the oldest first
+    ///
+    /// Defaults to 4096. Zero disables recording.
+    #[cfg(feature = "audit-log")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "audit-log")))]
+    pub fn set_audit_capacity(&mut self, capacity: usize) {
+        self.entities.audit.get_mut().set_capacity(capacity);
+    }
 
-        self.insert_inner(entity, components, intermediate, loc);
+    /// Start recording structural operations into an [`OpLog`], discarding any recording in
+    /// progress
+    ///
+    /// Components whose types are registered in `registry` with
+    /// [`ComponentRegistry::register_clone`] are cloned into the log as they're added.
+    pub fn start_op_log(&mut self, registry: &ComponentRegistry) {
+        self.op_log = Some(Box::new(OpRecorder::new(registry)));
+    }
 
-        Ok(bundle)
+    /// Stop recording structural operations, returning those recorded since
+    /// [`start_op_log`](Self::start_op_log), if it was called
+    pub fn take_op_log(&mut self) -> Option<OpLog> {
+        self.op_log.take().map(|x| x.finish())
     }
 
-    /// Remove the `S` component from `entity` and then add `component`
+    /// Hash the world's entities and their components of the types registered in `registry` with
+    /// [`ComponentRegistry::register_stable_hash`]
     ///
-    /// See [`exchange`](Self::exchange).
-    pub fn exchange_one<S: Component, T: Component>(
-        &mut self,
-        entity: Entity,
-        component: T,
-    ) -> Result<S, ComponentError> {
-        self.exchange::<(S,), (T,)>(entity, (component,))
-            .map(|(x,)| x)
+    /// The hash depends only on entity handles and on the registered names and values of
+    /// components, not on storage order, archetype layout or the current tick, and is the same on
+    /// every platform. Comparing the hashes of separately-run simulations after each tick pinpoints
+    /// when they desynced. Components of other types are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any component of a hashed type is uniquely borrowed.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_stable_hash::<f32>("f32");
+    ///
+    /// let mut a = World::new();
+    /// let e = a.spawn((1.0f32, true));
+    /// let mut b = World::new();
+    /// b.spawn_at(e, (1.0f32,));
+    /// assert_eq!(a.stable_hash(&registry), b.stable_hash(&registry));
+    /// *b.get::<&mut f32>(e).unwrap() = 2.0;
+    /// assert_ne!(a.stable_hash(&registry), b.stable_hash(&registry));
+    /// ```
+    pub fn stable_hash(&self, registry: &ComponentRegistry) -> u64 {
+        let mut types = registry
+            .iter()
+            .filter_map(|reg| Some((reg, reg.stable_hash_fn()?)))
+            .collect::<Vec<_>>();
+        types.sort_unstable_by_key(|(reg, _)| reg.name());
+        let mut entities = self.iter().collect::<Vec<_>>();
+        entities.sort_unstable_by_key(|x| x.entity().id());
+
+        let mut hasher = StableHasher::new();
+        hasher.write_len(entities.len());
+        for entity in entities {
+            let (archetype, index) = entity.raw();
+            entity.entity().stable_hash(&mut hasher);
+            let present = types
+                .iter()
+                .filter(|(reg, _)| archetype.has_dynamic(reg.id()));
+            hasher.write_len(present.clone().count());
+            for &(reg, hash) in present {
+                reg.name().stable_hash(&mut hasher);
+                let _borrow = RawColumn::new(archetype, reg.id());
+                unsafe {
+                    let size = reg.type_info().layout().size();
+                    let ptr = archetype.get_dynamic(reg.id(), size, index).unwrap();
+                    hash(ptr.as_ptr(), &mut hasher);
+                }
+            }
+        }
+        hasher.finish()
     }
 
-    /// Borrow a single component of `entity` without safety checks
+    /// Duplicate the world, cloning every component with the [`Clone`] implementation registered
+    /// in `registry` with [`ComponentRegistry::register_clone`]
     ///
-    /// `T` must be a shared or unique reference to a component type.
+    /// The copy has exactly the same entity handles, allocator state, archetypes, storage order,
+    /// tick and change ticks, so it allocates the same handles and visits entities in the same
+    /// order as the original from then on, e.g. for rollback or speculative simulation. Its
+    /// configuration, registered dynamic component types, singletons, exclusions and requirements
+    /// are copied too. Value indexes, validators, [plugins](Plugin), metrics sinks, soft budgets,
+    /// queued despawns and [op logs](Self::start_op_log) are not, and must be set up again if
+    /// needed. Fails if any entity has a component of a type with no registered `Clone`
+    /// implementation.
     ///
-    /// Should only be used as a building block for safe abstractions.
+    /// Unlike [`snapshot`](Self::snapshot), which can be restored into this world repeatedly, this
+    /// produces an independent world in one step.
     ///
-    /// # Safety
+    /// # Panics
     ///
-    /// `entity` must have been previously obtained from this [`World`], and no unique borrow of the
-    /// same component of `entity` may be live simultaneous to the returned reference.
-    pub unsafe fn get_unchecked<'a, T: ComponentRef<'a>>(
-        &'a self,
-        entity: Entity,
-    ) -> Result<T, ComponentError> {
-        let loc = self.entities.get(entity)?;
-        let archetype = &self.archetypes.archetypes[loc.archetype as usize];
-        let state = archetype
-            .get_state::<T::Component>()
-            .ok_or_else(MissingComponent::new::<T::Component>)?;
-        Ok(T::from_raw(
-            archetype
-                .get_base::<T::Component>(state)
-                .as_ptr()
-                .add(loc.index as usize),
-        ))
+    /// Panics if any component is uniquely borrowed.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_clone::<i32>("i32").register_clone::<String>("String");
+    /// let mut world = World::new();
+    /// let a = world.spawn((1, String::from("a")));
+    /// let b = world.spawn((2,));
+    /// world.despawn(a).unwrap();
+    ///
+    /// let mut copy = world.try_clone(&registry).unwrap();
+    /// assert_eq!(*copy.get::<&i32>(b).unwrap(), 2);
+    /// assert_eq!(copy.spawn(()), world.spawn(()));
+    ///
+    /// world.spawn((true,));
+    /// assert_eq!(
+    ///     world.try_clone(&registry).err(),
+    ///     Some(MissingClone(std::any::TypeId::of::<bool>()))
+    /// );
+    /// ```
+    pub fn try_clone(&self, registry: &ComponentRegistry) -> Result<World, MissingClone> {
+        let archetypes = self
+            .archetypes
+            .archetypes
+            .iter()
+            .map(|archetype| {
+                let clones = archetype
+                    .types()
+                    .iter()
+                    .map(
+                        |ty| match registry.get(ty.id()).and_then(|x| x.dynamic_clone()) {
+                            None if !archetype.is_empty() => Err(MissingClone(ty.id())),
+                            clone => Ok(clone),
+                        },
+                    )
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(archetype.clone_with(&clones))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut world = World::new();
+        world.entities = self.entities.clone_state();
+        world.archetypes = self.archetypes.clone_with(archetypes);
+        world.removals = self.removals.clone();
+        world.singletons = self.singletons.clone();
+        world.dynamic_types = self.dynamic_types.clone();
+        world.exclusions = self.exclusions.clone();
+        world.requirements = self.requirements.clone();
+        world.budget = self.budget;
+        Ok(world)
     }
 
-    /// Convert all reserved entities into empty entities that can be iterated and accessed
+    /// Capture the world's entities and allocator state
     ///
-    /// Invoked implicitly by operations that add or remove components or entities, i.e. all
-    /// variations of `spawn`, `despawn`, `insert`, and `remove`.
-    pub fn flush(&mut self) {
-        let arch = &mut self.archetypes.archetypes[0];
-        self.entities
-            .flush(|id, location| location.index = unsafe { arch.allocate(id) });
+    /// Every component whose type was registered in `registry` with
+    /// [`ComponentRegistry::register_clone`] is cloned into the snapshot. Other components are
+    /// skipped. The state of [plugins](Plugin::snapshot) is captured too.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any cloned component is uniquely borrowed.
+    pub fn snapshot(&self, registry: &ComponentRegistry) -> WorldSnapshot {
+        self.snapshot_sharing(registry, None)
     }
 
-    /// Inspect the archetypes that entities are organized into
+    /// Like [`snapshot`](Self::snapshot), but share the clones of components that haven't changed
+    /// since `previous` was captured
     ///
-    /// Useful for dynamically scheduling concurrent queries by checking borrows in advance, and for
-    /// efficient serialization.
-    #[inline(always)]
-    pub fn archetypes(&self) -> impl ExactSizeIterator<Item = &'_ Archetype> + '_ {
-        self.archetypes_inner().iter()
+    /// Taking a snapshot every tick this way only copies the archetype columns that were mutably
+    /// accessed, or whose entities changed, since the previous tick, making it practical to keep a
+    /// window of snapshots for rollback. Columns are only shared if `previous` was captured from
+    /// this world and no archetypes were added or removed since; otherwise, this is equivalent to
+    /// `snapshot`. Snapshots remain independent of each other, whether or not they share columns.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_clone::<i32>("i32").register_clone::<bool>("bool");
+    /// let mut world = World::new();
+    /// let a = world.spawn((1, true));
+    /// let first = world.snapshot(&registry);
+    /// *world.get::<&mut i32>(a).unwrap() = 2;
+    /// let second = world.snapshot_incremental(&first, &registry);
+    /// // Only the `i32` column was copied
+    /// assert_eq!(second.shared_columns(&first), 1);
+    /// world.restore_exact(&first);
+    /// assert_eq!(*world.get::<&i32>(a).unwrap(), 1);
+    /// ```
+    pub fn snapshot_incremental(
+        &self,
+        previous: &WorldSnapshot,
+        registry: &ComponentRegistry,
+    ) -> WorldSnapshot {
+        let generation = self.archetypes.generation();
+        let shareable = previous.world == self.id && previous.generation == generation;
+        self.snapshot_sharing(registry, Some(previous).filter(|_| shareable))
     }
 
-    /// Despawn `entity`, yielding a [`DynamicBundle`] of its components
+    fn snapshot_sharing(
+        &self,
+        registry: &ComponentRegistry,
+        previous: Option<&WorldSnapshot>,
+    ) -> WorldSnapshot {
+        let archetypes = self
+            .archetypes()
+            .enumerate()
+            .filter(|(_, x)| !x.is_empty())
+            .map(|(index, archetype)| {
+                let index = index as u32;
+                let entities = archetype
+                    .ids()
+                    .iter()
+                    .map(|&id| unsafe { self.find_entity_from_id(id) })
+                    .collect();
+                let previous = previous.and_then(|x| x.archetype(index));
+                ArchetypeSnapshot::sharing(archetype, index, entities, registry, |_| true, previous)
+            })
+            .collect();
+        let plugins = self
+            .plugins
+            .iter()
+            .filter_map(|x| Some((x.id, x.snapshot()?)))
+            .collect();
+        WorldSnapshot {
+            archetypes,
+            allocator: self.entities.save(),
+            tick: self.tick(),
+            world: self.id,
+            generation: self.archetypes.generation(),
+            plugins,
+        }
+    }
+
+    /// Replace the contents of the world with clones of those in `snapshot`, and restore the exact
+    /// state of its entity allocator
     ///
-    /// Useful for moving entities between worlds.
-    pub fn take(&mut self, entity: Entity) -> Result<TakenEntity<'_>, NoSuchEntity> {
-        self.flush();
-        let loc = self.entities.get(entity)?;
-        let archetype = &mut self.archetypes.archetypes[loc.archetype as usize];
-        unsafe {
-            Ok(TakenEntity::new(
-                &mut self.entities,
-                entity,
-                archetype,
-                loc.index,
-            ))
+    /// Unlike reconstructing entities with [`spawn_at`](Self::spawn_at) and
+    /// [`set_freelist`](Self::set_freelist), this also restores entities that were reserved but
+    /// not yet [flushed](Self::flush), the generations of despawned entities, and IDs held back
+    /// from reuse by [`WorldBuilder::id_reuse_delay`], so that the world allocates exactly the same
+    /// entity handles from then on as the world the snapshot was taken from. Entities are stored,
+    /// and hence visited by queries, in the same order as when the snapshot was taken, and the
+    /// [tick](Self::tick) is reset to the snapshot's, with every restored component considered
+    /// changed during it. Together, these make rolling back to a snapshot and replaying the same
+    /// inputs reproduce the original world.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_clone::<i32>("i32");
+    /// let mut world = World::new();
+    /// let a = world.spawn((1,));
+    /// let b = world.spawn((2,));
+    /// world.despawn(a).unwrap();
+    /// let snapshot = world.snapshot(&registry);
+    ///
+    /// let c = world.spawn((3,));
+    /// world.despawn(b).unwrap();
+    /// world.restore_exact(&snapshot);
+    /// assert_eq!(*world.get::<&i32>(b).unwrap(), 2);
+    /// assert!(!world.contains(c));
+    /// assert_eq!(world.spawn((3,)), c);
+    /// ```
+    pub fn restore_exact(&mut self, snapshot: &WorldSnapshot) {
+        self.clear();
+        self.set_tick(snapshot.tick);
+        for archetype in &snapshot.archetypes {
+            self.spawn_column_batch_at(&archetype.entities, archetype.to_batch());
+        }
+        self.entities.restore(&snapshot.allocator);
+        for (id, state) in &snapshot.plugins {
+            if let Some(plugin) = self.plugins.iter_mut().find(|x| x.id == *id) {
+                plugin.restore(&**state);
+            }
         }
     }
 
-    /// Returns a distinct value after `archetypes` is changed
+    /// Capture the entities spawned, despawned, or whose components were modified, added or
+    /// removed at or after `tick`
     ///
-    /// Store the current value after deriving information from [`archetypes`](Self::archetypes),
-    /// then check whether the value returned by this function differs before attempting an
-    /// operation that relies on its correctness. Useful for determining whether e.g. a concurrent
-    /// query execution plan is still correct.
+    /// See [`WorldPatch`] for what's included. Despawns are tracked per ID, and forgotten if
+    /// [`compact_meta`](Self::compact_meta) or [`clear`](Self::clear) discard the ID's metadata.
     ///
-    /// The generation may be, but is not necessarily, changed as a result of adding or removing any
-    /// entity or component.
+    /// # Panics
+    ///
+    /// Panics if any captured component is uniquely borrowed.
     ///
     /// # Example
     /// ```
     /// # use hecs::*;
+    /// let mut registry = ComponentRegistry::new();
+    /// registry.register_clone::<i32>("i32");
     /// let mut world = World::new();
-    /// let initial_gen = world.archetypes_generation();
-    /// world.spawn((123, "abc"));
-    /// assert_ne!(initial_gen, world.archetypes_generation());
+    /// let a = world.spawn((1,));
+    /// let b = world.spawn((2,));
+    /// let mut mirror = World::new();
+    /// mirror.apply_patch(&world.serialize_changes_since(0, &registry));
+    ///
+    /// let since = world.advance_tick();
+    /// *world.get::<&mut i32>(a).unwrap() = 10;
+    /// world.despawn(b).unwrap();
+    /// let patch = world.serialize_changes_since(since, &registry);
+    /// mirror.apply_patch(&patch);
+    /// assert_eq!(*mirror.get::<&i32>(a).unwrap(), 10);
+    /// assert!(!mirror.contains(b));
     /// ```
-    pub fn archetypes_generation(&self) -> ArchetypesGeneration {
-        ArchetypesGeneration(self.archetypes.generation())
+    pub fn serialize_changes_since(&self, tick: u32, registry: &ComponentRegistry) -> WorldPatch {
+        let scope = {
+            let mut scope = registry
+                .iter()
+                .filter(|x| x.has_clone())
+                .map(|x| x.id())
+                .collect::<Vec<_>>();
+            scope.sort_unstable();
+            scope
+        };
+        let in_scope = |id: &TypeId| scope.binary_search(id).is_ok();
+        let archetypes = self
+            .archetypes()
+            .enumerate()
+            .filter(|(_, x)| !x.is_empty())
+            .filter_map(|(index, archetype)| {
+                let entered = archetype.entered_since(tick);
+                if !entered
+                    && !archetype
+                        .component_types()
+                        .any(|id| in_scope(&id) && archetype.changed_since(id, tick))
+                {
+                    return None;
+                }
+                let mut types = archetype
+                    .component_types()
+                    .filter(in_scope)
+                    .collect::<Vec<_>>();
+                types.sort_unstable();
+                let entities = archetype
+                    .ids()
+                    .iter()
+                    .map(|&id| unsafe { self.find_entity_from_id(id) })
+                    .collect();
+                let snapshot = ArchetypeSnapshot::with_filter(
+                    archetype,
+                    index as u32,
+                    entities,
+                    registry,
+                    |id| entered || archetype.changed_since(id, tick),
+                );
+                Some(PatchArchetype {
+                    entered,
+                    types,
+                    snapshot,
+                })
+            })
+            .collect();
+        WorldPatch {
+            since: tick,
+            tick: self.tick(),
+            despawned: self.entities.despawned_since(tick).collect(),
+            scope,
+            archetypes,
+        }
     }
 
-    /// Number of currently live entities
-    #[inline]
-    pub fn len(&self) -> u32 {
-        self.entities.len()
+    /// Bring the world up to date with the changes captured in `patch`
+    ///
+    /// Entities are spawned with the same handles as in the world the patch was captured from,
+    /// replacing any existing entities with the same IDs. Components outside the patch's scope,
+    /// i.e. of types not registered for cloning when it was captured, are left untouched.
+    pub fn apply_patch(&mut self, patch: &WorldPatch) {
+        self.flush();
+        for &id in &patch.despawned {
+            if let Some(entity) = self.entities.resolve(id) {
+                self.despawn(entity).unwrap();
+            }
+        }
+        for archetype in &patch.archetypes {
+            for (index, &entity) in archetype.snapshot.entities.iter().enumerate() {
+                if !self.contains(entity) {
+                    self.spawn_at(entity, ());
+                }
+                if archetype.entered {
+                    let loc = self.entities.get(entity).unwrap();
+                    let stale = self.archetypes.archetypes[loc.archetype as usize]
+                        .component_types()
+                        .filter(|id| {
+                            patch.scope.binary_search(id).is_ok()
+                                && archetype.types.binary_search(id).is_err()
+                        })
+                        .collect::<Vec<_>>();
+                    self.drop_components(entity, &stale);
+                }
+                self.insert(entity, archetype.snapshot.row(index)).unwrap();
+            }
+        }
     }
 
-    /// Whether no entities are live
-    #[inline]
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
+    /// Bring a copy of the old version of a world up to date with the differences in `diff`
+    ///
+    /// Entities despawned in the new version are despawned, and components removed from surviving
+    /// entities are dropped. Spawned entities, and added or changed components, are recreated from
+    /// the values cloned into the diff; see [`WorldDiff::patches`]. Entities that are already gone
+    /// are skipped, and spawned entities replace any existing entities with the same IDs.
+    pub fn apply_diff(&mut self, diff: &WorldDiff) {
+        self.flush();
+        for &entity in diff.despawned() {
+            let _ = self.despawn(entity);
+        }
+        for &entity in diff.spawned() {
+            self.spawn_at(entity, ());
+        }
+        for change in diff.changes() {
+            if change.kind == ChangeKind::Removed && self.contains(change.entity) {
+                self.remove_dynamic(change.entity, change.component)
+                    .unwrap();
+            }
+        }
+        for (entity, patch) in diff.patches() {
+            let _ = self.insert(entity, patch);
+        }
     }
 
-    /// Entity handles that will be yielded by future spawns
+    /// Spawn copies of every entity in `other` whose archetype satisfies `filter`
     ///
-    /// [`Entity`] handles will be allocated deterministically between different
-    /// worlds (e.g. across serialization) if they have the same live entities
-    /// and the same freelist.
+    /// Components are copied a whole column at a time: bitwise for types registered in `registry`
+    /// as plain old data with [`ComponentRegistry::register_pod`], and by their registered
+    /// [`Clone`] implementation for types registered with [`ComponentRegistry::register_clone`].
+    /// Other components are skipped. Much faster than spawning a clone of each entity individually,
+    /// e.g. when initializing a prediction world from an authoritative one.
     ///
-    /// To deserialize a world that will match the entity allocation behavior of
-    /// a previously serialized world, first create a new world, then use
-    /// [`spawn_at`](Self::spawn_at) or similar to reconstruct the serialized
-    /// world's entities in place, then call
-    /// [`set_freelist`](Self::set_freelist) with the serialized world's
-    /// freelist.
-    pub fn freelist(&self) -> impl ExactSizeIterator<Item = Entity> + '_ {
-        self.entities.freelist()
+    /// Copies are allocated fresh handles in this world. Returns the handle of each entity in
+    /// `other` paired with the handle of its copy. Components that hold [`Entity`] handles are
+    /// copied unchanged.
+    ///
+    /// ```
+    /// # use hecs::*;
+    /// let mut registry = ComponentRegistry::new();
+    /// unsafe {
+    ///     registry.register_pod::<[f32; 2]>("Position");
+    /// }
+    /// registry.register_clone::<String>("Name");
+    ///
+    /// let mut authoritative = World::new();
+    /// let a = authoritative.spawn(([1.0f32, 2.0], String::from("a")));
+    /// authoritative.spawn((true,));
+    ///
+    /// let mut prediction = World::new();
+    /// let copied = prediction.copy_archetypes_from(
+    ///     &authoritative,
+    ///     |archetype| archetype.has::<[f32; 2]>(),
+    ///     &registry,
+    /// );
+    /// assert_eq!(copied.len(), 1);
+    /// let (source, copy) = copied[0];
+    /// assert_eq!(source, a);
+    /// assert_eq!(*prediction.get::<&[f32; 2]>(copy).unwrap(), [1.0, 2.0]);
+    /// assert_eq!(*prediction.get::<&String>(copy).unwrap(), "a");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if any copied component in `other` is uniquely borrowed.
+    pub fn copy_archetypes_from(
+        &mut self,
+        other: &World,
+        mut filter: impl FnMut(&Archetype) -> bool,
+        registry: &ComponentRegistry,
+    ) -> Vec<(Entity, Entity)> {
+        let mut copied = Vec::new();
+        for archetype in other.archetypes() {
+            if archetype.is_empty() || !filter(archetype) {
+                continue;
+            }
+            let batch = copy_columns(archetype, registry);
+            let sources = archetype
+                .ids()
+                .iter()
+                .map(|&id| unsafe { other.find_entity_from_id(id) });
+            copied.extend(sources.zip(self.spawn_column_batch(batch)));
+        }
+        copied
     }
 
-    /// Designate entity IDs to be reused by future spawns
+    /// Begin a new ID reuse cycle
     ///
-    /// See [`freelist`](Self::freelist). May only be called when the set of
-    /// live entity IDs exactly matches those that were live when the supplied
-    /// freelist was captured.
-    pub fn set_freelist(&mut self, freelist: &[Entity]) {
-        self.entities.set_freelist(freelist);
+    /// IDs freed at least [`WorldBuilder::id_reuse_delay`] cycles ago become available for reuse
+    /// by future spawns. Typically called once per frame or simulation step. Has no effect if no
+    /// delay was configured.
+    pub fn advance_id_reuse(&mut self) {
+        self.flush();
+        self.entities.advance_reuse_cycle();
+    }
+
+    /// Number of freed entity IDs that are being held back from reuse
+    ///
+    /// See [`WorldBuilder::id_reuse_delay`].
+    pub fn quarantined_ids(&self) -> usize {
+        self.entities.quarantined()
     }
 }
 
+// With `hecs_single_threaded`, `World` is neither `Send` nor `Sync`, since entities may
+// be reserved through a shared reference without synchronization
+#[cfg(not(hecs_single_threaded))]
 unsafe impl Send for World {}
+#[cfg(not(hecs_single_threaded))]
 unsafe impl Sync for World {}
 
 impl Default for World {
@@ -957,6 +4200,182 @@ fn index2<T>(x: &mut [T], i: usize, j: usize) -> (&mut T, &mut T) {
     unsafe { (&mut *ptr.add(i), &mut *ptr.add(j)) }
 }
 
+/// Configures a [`World`] before it's constructed
+///
+/// Obtained from [`World::builder`]. Options that affect allocation order make the resulting
+/// world's behavior easier to reproduce, at some cost to performance.
+///
+/// There's no hasher seed to configure: the world's internal hash maps are only used for lookups,
+/// never iterated in an order that affects its behavior, so their hashers can't make otherwise
+/// identical worlds diverge.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut world = World::builder()
+///     .entity_capacity(1024)
+///     .sorted_freelist(true)
+///     .stable_removal(true)
+///     .track_changes::<i32>()
+///     .build();
+/// let a = world.spawn((1,));
+/// let b = world.spawn((2,));
+/// let c = world.spawn((3,));
+/// world.despawn(c).unwrap();
+/// world.despawn(a).unwrap();
+/// // The lowest free ID is reused first, regardless of despawn order
+/// assert_eq!(world.spawn((4,)).id(), a.id());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WorldBuilder {
+    entity_capacity: u32,
+    archetype_capacity: usize,
+    entity_bits: EntityBits,
+    id_reuse_delay: u32,
+    monotonic_ids: bool,
+    sorted_freelist: bool,
+    stable_removal: bool,
+    canonical_archetype_order: bool,
+    iteration_order: IterationOrder,
+    tracked_changes: Vec<TypeId>,
+    tracked_removals: Vec<TypeId>,
+}
+
+impl WorldBuilder {
+    /// Create a builder with the same configuration as [`World::new`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocate storage for the metadata of at least `capacity` entities up front
+    pub fn entity_capacity(&mut self, capacity: u32) -> &mut Self {
+        self.entity_capacity = capacity;
+        self
+    }
+
+    /// Allocate storage for at least `capacity` distinct archetypes up front
+    pub fn archetype_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.archetype_capacity = capacity;
+        self
+    }
+
+    /// Limit the IDs and generations of entities so they can be packed according to `bits`
+    ///
+    /// Spawning more entities than fit in [`EntityBits::id_bits`] panics, and generations wrap
+    /// around after [`EntityBits::max_generation`]. Handles passed to [`World::spawn_at`] must fit.
+    pub fn entity_bits(&mut self, bits: EntityBits) -> &mut Self {
+        self.entity_bits = bits;
+        self
+    }
+
+    /// Hold despawned entities' IDs back from reuse for `cycles` calls to
+    /// [`World::advance_id_reuse`]
+    ///
+    /// By default, an ID can be reused by the very next spawn after its entity is despawned, with
+    /// only the generation distinguishing the old and new handles. Delaying reuse ensures that
+    /// external systems which track entities by [`Entity::id`] alone can't confuse a newly spawned
+    /// entity with one despawned during the same cycle, at the cost of a larger entity table.
+    pub fn id_reuse_delay(&mut self, cycles: u32) -> &mut Self {
+        self.id_reuse_delay = cycles;
+        self
+    }
+
+    /// Whether to never reuse the IDs of despawned entities
+    ///
+    /// Guarantees that every entity spawned by [`World::spawn`] and similar methods over the
+    /// lifetime of the world has a distinct [`Entity::id`], which is useful for keying long-lived
+    /// external data such as analytics or replays. The entity table grows with the total number of
+    /// entities ever spawned, rather than the peak number of live entities. Panics once the ID
+    /// space is exhausted.
+    pub fn monotonic_ids(&mut self, enabled: bool) -> &mut Self {
+        self.monotonic_ids = enabled;
+        self
+    }
+
+    /// Whether to reuse the lowest free entity ID first, rather than the most recently freed one
+    ///
+    /// Makes the IDs handed out by [`World::spawn`] depend only on which IDs are free, not on the
+    /// order in which entities were despawned. Despawning becomes linear in the number of free IDs.
+    pub fn sorted_freelist(&mut self, enabled: bool) -> &mut Self {
+        self.sorted_freelist = enabled;
+        self
+    }
+
+    /// Whether removing an entity from an archetype preserves the order of the remaining entities
+    ///
+    /// By default, the last entity in an archetype is moved into the gap left by a removed entity,
+    /// so iteration order depends on removal history. When enabled, later entities are shifted down
+    /// instead, so entities of the same archetype are always iterated in the order they were added
+    /// to it. Removal becomes linear in the size of the archetype.
+    pub fn stable_removal(&mut self, enabled: bool) -> &mut Self {
+        self.stable_removal = enabled;
+        self
+    }
+
+    /// Whether to keep archetypes in the canonical order established by [`World::sort_archetypes`]
+    ///
+    /// By default, archetypes are ordered by creation. When enabled, archetypes are sorted
+    /// whenever one is created, so that the order of [`World::archetypes`] and of query iteration
+    /// across archetypes doesn't depend on the order in which operations happened to create them.
+    /// Creating an archetype becomes linear in the number of entities in the archetypes that sort
+    /// after it, and discards cached archetype transitions.
+    pub fn canonical_archetype_order(&mut self, enabled: bool) -> &mut Self {
+        self.canonical_archetype_order = enabled;
+        self
+    }
+
+    /// The order in which queries visit entities
+    ///
+    /// See [`IterationOrder`].
+    pub fn iteration_order(&mut self, order: IterationOrder) -> &mut Self {
+        self.iteration_order = order;
+        self
+    }
+
+    /// Track additions and mutable accesses of `T` components per entity from the start
+    ///
+    /// See [`World::track_changes`].
+    pub fn track_changes<T: Component>(&mut self) -> &mut Self {
+        self.tracked_changes.push(TypeId::of::<T>());
+        self
+    }
+
+    /// Record entities that lose their `T` component from the start
+    ///
+    /// See [`World::track_removals`].
+    pub fn track_removals<T: Component>(&mut self) -> &mut Self {
+        self.tracked_removals.push(TypeId::of::<T>());
+        self
+    }
+
+    /// Construct a [`World`] with this configuration
+    pub fn build(&self) -> World {
+        let mut world = World::new();
+        world.entities.meta.reserve(self.entity_capacity as usize);
+        world.entities.set_bits(self.entity_bits);
+        world.entities.set_reuse_delay(self.id_reuse_delay);
+        world.entities.set_monotonic(self.monotonic_ids);
+        world.entities.set_sorted_freelist(self.sorted_freelist);
+        world
+            .archetypes
+            .archetypes
+            .reserve(self.archetype_capacity.saturating_sub(1));
+        world.archetypes.index.reserve(self.archetype_capacity);
+        world.archetypes.set_stable_removal(self.stable_removal);
+        world.archetypes.canonical = self.canonical_archetype_order;
+        world
+            .entities
+            .set_track_spawn_order(self.iteration_order == IterationOrder::SpawnOrder);
+        for &id in &self.tracked_changes {
+            world.track_changes_by_id(id);
+        }
+        for &id in &self.tracked_removals {
+            world.removals.entry(id).or_default();
+        }
+        world
+    }
+}
+
 /// Errors that arise when accessing components
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum ComponentError {
@@ -1019,10 +4438,291 @@ impl From<NoSuchEntity> for QueryOneError {
     }
 }
 
+/// Errors that arise when inserting a default-constructed component by type
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum InsertDefaultError {
+    /// The entity was already despawned
+    NoSuchEntity,
+    /// The component type has no registered [`Default`] implementation
+    MissingDefault(MissingDefault),
+}
+
+#[cfg(feature = "std")]
+impl Error for InsertDefaultError {}
+
+impl fmt::Display for InsertDefaultError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use InsertDefaultError::*;
+        match *self {
+            NoSuchEntity => f.write_str("no such entity"),
+            MissingDefault(ref x) => x.fmt(f),
+        }
+    }
+}
+
+impl From<NoSuchEntity> for InsertDefaultError {
+    fn from(NoSuchEntity: NoSuchEntity) -> Self {
+        InsertDefaultError::NoSuchEntity
+    }
+}
+
+impl From<MissingDefault> for InsertDefaultError {
+    fn from(x: MissingDefault) -> Self {
+        InsertDefaultError::MissingDefault(x)
+    }
+}
+
+/// Identifies the archetype of entities having exactly the components of the bundle type `B` in
+/// a particular world
+///
+/// Obtained from [`World::register_bundle`].
+pub struct BundleId<B> {
+    archetype: u32,
+    world: u64,
+    /// Value of `ArchetypeSet::renumbered` when `archetype` was looked up
+    renumbered: u32,
+    _marker: PhantomData<fn(B)>,
+}
+
+impl<B> Clone for BundleId<B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<B> Copy for BundleId<B> {}
+
+impl<B> fmt::Debug for BundleId<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BundleId")
+            .field("archetype", &self.archetype)
+            .finish()
+    }
+}
+
+/// Errors that arise when inserting a component by [`TypeId`]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum InsertDynamicError {
+    /// The entity was already despawned
+    NoSuchEntity,
+    /// The component type isn't registered
+    Unregistered(TypeId),
+    /// The named component type isn't plain old data, or the bytes aren't the size of the type
+    InvalidBytes(&'static str),
+}
+
+#[cfg(feature = "std")]
+impl Error for InsertDynamicError {}
+
+impl fmt::Display for InsertDynamicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use InsertDynamicError::*;
+        match *self {
+            NoSuchEntity => f.write_str("no such entity"),
+            Unregistered(id) => write!(f, "component type {id:?} is not registered"),
+            InvalidBytes(name) => write!(f, "bytes are not a valid {name} component"),
+        }
+    }
+}
+
+impl From<NoSuchEntity> for InsertDynamicError {
+    fn from(NoSuchEntity: NoSuchEntity) -> Self {
+        InsertDynamicError::NoSuchEntity
+    }
+}
+
+/// The order in which queries visit entities, configured by [`WorldBuilder::iteration_order`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub enum IterationOrder {
+    /// Visit archetypes in order, and the entities in each archetype in storage order
+    ///
+    /// The fastest order, but one that changes as entities move between archetypes.
+    #[default]
+    Storage,
+    /// Visit entities in the order they were spawned, regardless of archetype
+    ///
+    /// Gives gameplay rules that must resolve simultaneous events, such as attacks landing in the
+    /// same tick, an order that's stable under insertions and removals of components. Applies to
+    /// [`QueryBorrow::iter`](crate::QueryBorrow::iter), [`World::query_mut`] and the iterators
+    /// derived from them, which sort the matching entities before visiting them, taking time
+    /// proportional to `n log n` and temporary memory proportional to `n` for `n` matches.
+    /// Batched and parallel iteration still divide work by archetype. Respawning an entity with
+    /// [`World::spawn_at`] moves it to the end of the order.
+    SpawnOrder,
+}
+
+/// How [`World::register_singleton`] handles a component type being added to a second entity
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SingletonPolicy {
+    /// Panic, or fail with [`TryInsertError::SingletonHeld`] from [`World::try_insert`] and
+    /// [`TrySpawnError::SingletonHeld`] from [`World::try_spawn`]
+    Reject,
+    /// Remove and drop the component of the entity that had it
+    Move,
+}
+
+/// How [`World::register_exclusive`] handles a component being added to an entity that has
+/// another of the same exclusive set
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ExclusionPolicy {
+    /// Panic, or fail with [`TryInsertError::Excluded`] from [`World::try_insert`]
+    Reject,
+    /// Remove and drop the component the entity had
+    Replace,
+}
+
+/// Errors that arise from [`World::try_insert`]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum TryInsertError {
+    /// The entity was already despawned
+    NoSuchEntity,
+    /// Storage for the entity in its new archetype couldn't be allocated
+    Alloc(AllocError),
+    /// A component type registered with [`SingletonPolicy::Reject`] is already held by this
+    /// entity
+    SingletonHeld(Entity),
+    /// A component of this type is mutually exclusive with one being inserted, and is either also
+    /// being inserted or held by the entity under [`ExclusionPolicy::Reject`]
+    Excluded(TypeId),
+    /// A validator registered with [`World::add_validator`] rejected the insertion
+    Invalid(ValidationError),
+}
+
+#[cfg(feature = "std")]
+impl Error for TryInsertError {}
+
+impl fmt::Display for TryInsertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use TryInsertError::*;
+        match *self {
+            NoSuchEntity => f.write_str("no such entity"),
+            Alloc(ref e) => e.fmt(f),
+            SingletonHeld(holder) => write!(f, "singleton component already held by {:?}", holder),
+            Excluded(id) => write!(f, "mutually exclusive component {:?}", id),
+            Invalid(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl From<NoSuchEntity> for TryInsertError {
+    fn from(NoSuchEntity: NoSuchEntity) -> Self {
+        TryInsertError::NoSuchEntity
+    }
+}
+
+impl From<AllocError> for TryInsertError {
+    fn from(x: AllocError) -> Self {
+        TryInsertError::Alloc(x)
+    }
+}
+
+/// Errors that arise from [`World::try_spawn`] and [`World::try_spawn_batch`]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum TrySpawnError {
+    /// The entities or their storage couldn't be allocated
+    Alloc(AllocError),
+    /// A component type registered with [`SingletonPolicy::Reject`] is already held by this
+    /// entity
+    SingletonHeld(Entity),
+    /// The batch has components of this type, registered with [`World::register_singleton`]
+    SingletonBatch(TypeId),
+    /// The components include this type and another registered with
+    /// [`World::register_exclusive`] as mutually exclusive with it
+    Excluded(TypeId),
+}
+
+#[cfg(feature = "std")]
+impl Error for TrySpawnError {}
+
+impl fmt::Display for TrySpawnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use TrySpawnError::*;
+        match *self {
+            Alloc(ref e) => e.fmt(f),
+            SingletonHeld(holder) => write!(f, "singleton component already held by {:?}", holder),
+            SingletonBatch(id) => write!(f, "batch contains singleton component {:?}", id),
+            Excluded(id) => write!(f, "mutually exclusive component {:?}", id),
+        }
+    }
+}
+
+impl From<AllocError> for TrySpawnError {
+    fn from(x: AllocError) -> Self {
+        TrySpawnError::Alloc(x)
+    }
+}
+
+/// Copy or clone every component of `archetype` whose type is registered in `registry` as plain
+/// old data or with a [`Clone`] implementation into a new batch
+fn copy_columns(archetype: &Archetype, registry: &ComponentRegistry) -> ColumnBatch {
+    let columns = archetype
+        .types()
+        .iter()
+        .filter_map(|&ty| {
+            let reg = registry.get(ty.id())?;
+            let clone = if reg.is_pod() {
+                None
+            } else {
+                Some(reg.dynamic_clone()?)
+            };
+            Some((ty, clone, RawColumn::new(archetype, ty.id()).unwrap()))
+        })
+        .collect::<Vec<_>>();
+    let mut batch_type = ColumnBatchType::new();
+    for &(ty, _, _) in &columns {
+        batch_type.add_dynamic(ty);
+    }
+    let len = archetype.len();
+    let mut batch = batch_type.into_batch(len);
+    for (ty, clone, column) in &columns {
+        unsafe {
+            match clone {
+                None => {
+                    batch.extend_dynamic(*ty, column.base(), len);
+                }
+                Some(clone) => {
+                    let size = ty.layout().size();
+                    for i in 0..len as usize {
+                        (clone.func)(column.base().add(i * size), &mut |ptr, ty| {
+                            batch.push_dynamic(ty, ptr);
+                        });
+                    }
+                }
+            }
+        }
+    }
+    batch.build().expect("every column was filled")
+}
+
+/// A single component of a type known only at runtime, moved from `ptr` when inserted
+struct RawComponent {
+    info: TypeInfo,
+    ptr: *mut u8,
+}
+
+unsafe impl DynamicBundle for RawComponent {
+    fn with_ids<T>(&self, f: impl FnOnce(&[TypeId]) -> T) -> T {
+        f(&[self.info.id()])
+    }
+
+    fn type_info(&self) -> Vec<TypeInfo> {
+        vec![self.info]
+    }
+
+    unsafe fn put(self, mut f: impl FnMut(*mut u8, TypeInfo)) {
+        f(self.ptr, self.info);
+    }
+}
+
 /// Types that can be components, implemented automatically for all `Send + Sync + 'static` types
 ///
 /// This is just a convenient shorthand for `Send + Sync + 'static`, and never needs to be
 /// implemented manually.
+///
+/// Components of any alignment are supported, including over-aligned types such as
+/// `#[repr(align(64))]` SIMD vectors. Every component is stored at an address satisfying its
+/// type's alignment, whether in a [`World`], an [`EntityBuilder`], or a
+/// [`CommandBuffer`](crate::CommandBuffer).
 pub trait Component: Send + Sync + 'static {}
 impl<T: Send + Sync + 'static> Component for T {}
 
@@ -1125,6 +4825,9 @@ where
     entities: &'a mut Entities,
     archetype_id: u32,
     archetype: &'a mut Archetype,
+    spawns: &'a mut u64,
+    log: Option<&'a mut OpRecorder>,
+    tick: u32,
 }
 
 impl<I> Drop for SpawnBatchIter<'_, I>
@@ -1158,6 +4861,13 @@ where
             archetype: self.archetype_id,
             index,
         };
+        let moved = self.archetype.reposition(index);
+        self.entities.relocate(self.archetype, moved);
+        if let Some(ref mut log) = self.log {
+            let index = self.entities.meta[entity.id as usize].location.index;
+            log.spawned(self.tick, entity, self.archetype, index);
+        }
+        *self.spawns += 1;
         Some(entity)
     }
 
@@ -1213,14 +4923,110 @@ struct ArchetypeSet {
     /// Maps sorted component type sets to archetypes
     index: HashMap<Box<[TypeId]>, u32>,
     archetypes: Vec<Archetype>,
+    /// Whether archetypes preserve entity order on removal
+    stable_removal: bool,
+    /// See `World::tick`
+    tick: u32,
+    /// Components by which archetypes are kept sorted, in order of precedence
+    sort_keys: Vec<(TypeId, KeyCmp)>,
+    /// Incremented whenever archetypes are added or removed
+    generation: u32,
+    /// Incremented whenever existing archetypes are moved to different indices
+    renumbered: u32,
+    /// Whether archetypes are kept in canonical order
+    canonical: bool,
+    /// Whether an archetype was added since archetypes were last put in canonical order
+    unsorted: bool,
+    /// Index of the next archetype to be visited by `World::maintain`
+    maintain_cursor: u32,
+    /// See `World::clear_trackers`
+    epoch: u32,
+    /// Components whose changes are tracked per entity
+    tracked: Vec<TypeId>,
+    /// Total component bytes reserved by `archetypes`, maintained by the archetypes themselves
+    reserved_bytes: Arc<AtomicUsize>,
 }
 
 impl ArchetypeSet {
     fn new() -> Self {
+        let reserved_bytes = Arc::new(AtomicUsize::new(0));
+        let mut empty = Archetype::new(Vec::new());
+        empty.set_reserved_bytes(reserved_bytes.clone());
         // `flush` assumes archetype 0 always exists, representing entities with no components.
         Self {
             index: Some((Box::default(), 0)).into_iter().collect(),
-            archetypes: vec![Archetype::new(Vec::new())],
+            archetypes: vec![empty],
+            stable_removal: false,
+            tick: 0,
+            sort_keys: Vec::new(),
+            generation: 1,
+            renumbered: 0,
+            canonical: false,
+            unsorted: false,
+            maintain_cursor: 0,
+            epoch: 1,
+            tracked: Vec::new(),
+            reserved_bytes,
+        }
+    }
+
+    /// Copy the set, substituting `archetypes`, which must be copies of its archetypes in order
+    fn clone_with(&self, mut archetypes: Vec<Archetype>) -> Self {
+        let reserved_bytes = Arc::new(AtomicUsize::new(0));
+        for archetype in &mut archetypes {
+            archetype.set_reserved_bytes(reserved_bytes.clone());
+        }
+        Self {
+            index: self.index.clone(),
+            archetypes,
+            stable_removal: self.stable_removal,
+            tick: self.tick,
+            sort_keys: self.sort_keys.clone(),
+            generation: self.generation,
+            renumbered: self.renumbered,
+            canonical: self.canonical,
+            unsorted: self.unsorted,
+            maintain_cursor: self.maintain_cursor,
+            epoch: self.epoch,
+            tracked: self.tracked.clone(),
+            reserved_bytes,
+        }
+    }
+
+    /// Total component bytes reserved by all archetypes
+    fn reserved_bytes(&self) -> usize {
+        self.reserved_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Apply the current epoch and tracked components to a newly created archetype, recording
+    /// any entities it already has as added
+    fn init_tracking(epoch: u32, tracked: &[TypeId], archetype: &mut Archetype) {
+        archetype.set_epoch(epoch);
+        for &id in tracked {
+            archetype.track(id, epoch);
+        }
+    }
+
+    /// Apply the first applicable sort key to a newly created archetype
+    fn init_sort_key(sort_keys: &[(TypeId, KeyCmp)], archetype: &mut Archetype) {
+        for &(id, cmp) in sort_keys {
+            if archetype.set_sort_key(id, cmp) {
+                break;
+            }
+        }
+    }
+
+    fn set_tick(&mut self, tick: u32) {
+        self.tick = tick;
+        for archetype in &mut self.archetypes {
+            archetype.set_tick(tick);
+        }
+    }
+
+    fn set_stable_removal(&mut self, stable: bool) {
+        self.stable_removal = stable;
+        for archetype in &mut self.archetypes {
+            archetype.set_stable_removal(stable);
         }
     }
 
@@ -1238,14 +5044,22 @@ impl ArchetypeSet {
 
     fn insert(&mut self, components: Box<[TypeId]>, info: Vec<TypeInfo>) -> u32 {
         let x = self.archetypes.len() as u32;
-        self.archetypes.push(Archetype::new(info));
+        let mut archetype = Archetype::new(info);
+        archetype.set_reserved_bytes(self.reserved_bytes.clone());
+        archetype.set_stable_removal(self.stable_removal);
+        archetype.set_tick(self.tick);
+        Self::init_tracking(self.epoch, &self.tracked, &mut archetype);
+        Self::init_sort_key(&self.sort_keys, &mut archetype);
+        self.archetypes.push(archetype);
+        self.generation = self.generation.wrapping_add(1);
+        self.unsorted |= self.canonical;
         let old = self.index.insert(components, x);
         debug_assert!(old.is_none(), "inserted duplicate archetype");
         x
     }
 
     /// Returns archetype ID and starting location index
-    fn insert_batch(&mut self, archetype: Archetype) -> (u32, u32) {
+    fn insert_batch(&mut self, mut archetype: Archetype) -> (u32, u32) {
         let ids = archetype
             .types()
             .iter()
@@ -1265,7 +5079,16 @@ impl ArchetypeSet {
             Entry::Vacant(x) => {
                 // Brand new archetype
                 let id = self.archetypes.len() as u32;
+                archetype.set_reserved_bytes(self.reserved_bytes.clone());
+                archetype.set_stable_removal(self.stable_removal);
+                archetype.set_tick(self.tick);
+                archetype.mark_entered();
+                archetype.mark_all_changed();
+                Self::init_tracking(self.epoch, &self.tracked, &mut archetype);
+                Self::init_sort_key(&self.sort_keys, &mut archetype);
                 self.archetypes.push(archetype);
+                self.generation = self.generation.wrapping_add(1);
+                self.unsorted |= self.canonical;
                 x.insert(id);
                 (id, 0)
             }
@@ -1273,7 +5096,7 @@ impl ArchetypeSet {
     }
 
     fn generation(&self) -> u32 {
-        self.archetypes.len() as u32
+        self.generation
     }
 
     fn get_insert_target(&mut self, src: u32, components: &impl DynamicBundle) -> InsertTarget {
@@ -1315,6 +5138,7 @@ impl ArchetypeSet {
 }
 
 /// Metadata cached for inserting components into entities from this archetype
+#[derive(Clone)]
 struct InsertTarget {
     /// Components from the current archetype that are replaced by the insert
     replaced: Vec<TypeInfo>,
@@ -0,0 +1,382 @@
+use crate::alloc::vec::Vec;
+use core::any::TypeId;
+use core::fmt;
+
+use crate::archetype::{Archetype, RawColumn};
+use crate::{BuiltEntityClone, ComponentRegistry, Entity, EntityBuilderClone, EntityRef, World};
+
+/// Entity- and component-level differences from one [`World`] to a modified version
+///
+/// Entities are matched by their [`Entity`] handles, so differences in internal layout such as the
+/// order in which archetypes were created or entities were stored are ignored. Components present
+/// in both versions are compared using the [`PartialEq`] implementations registered with
+/// [`ComponentRegistry::register_eq`]; components of other types are compared only by presence.
+///
+/// Differences are listed in ascending order of entity ID, then of registered component name, with
+/// unregistered types last.
+///
+/// The new values of spawned entities' components and of added and changed components are cloned
+/// into the diff if their types were registered with [`ComponentRegistry::register_clone`], so that
+/// [`World::apply_diff`] can bring a copy of the old version up to date, e.g. for delta-compressed
+/// state sync. Equality ignores these values.
+///
+/// ```
+/// # use hecs::*;
+/// let mut registry = ComponentRegistry::new();
+/// registry.register_eq::<i32>("i32").register_debug::<i32>("i32");
+///
+/// let mut old = World::new();
+/// let a = old.spawn((1,));
+/// let mut new = World::new();
+/// new.spawn_at(a, (2,));
+///
+/// let diff = WorldDiff::between(&old, &new, &registry);
+/// assert_eq!(
+///     diff.changes(),
+///     [ComponentChange {
+///         entity: a,
+///         component: registry.id_of("i32").unwrap(),
+///         kind: ChangeKind::Changed,
+///     }]
+/// );
+/// assert_eq!(diff.report(&old, &new, &registry).to_string(), "~ 0v1 i32: 1 -> 2\n");
+///
+/// registry.register_clone::<i32>("i32");
+/// let diff = WorldDiff::between(&old, &new, &registry);
+/// old.apply_diff(&diff);
+/// assert_eq!(*old.get::<&i32>(a).unwrap(), 2);
+/// ```
+#[derive(Clone, Default)]
+pub struct WorldDiff {
+    spawned: Vec<Entity>,
+    despawned: Vec<Entity>,
+    changes: Vec<ComponentChange>,
+    /// Clones of the new values of spawned entities' components and of added or changed
+    /// components, sorted by entity ID
+    patches: Vec<(Entity, BuiltEntityClone)>,
+}
+
+impl PartialEq for WorldDiff {
+    fn eq(&self, other: &Self) -> bool {
+        self.spawned == other.spawned
+            && self.despawned == other.despawned
+            && self.changes == other.changes
+    }
+}
+
+impl Eq for WorldDiff {}
+
+impl fmt::Debug for WorldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorldDiff")
+            .field("spawned", &self.spawned)
+            .field("despawned", &self.despawned)
+            .field("changes", &self.changes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WorldDiff {
+    /// Compute the differences from `old` to `new`
+    ///
+    /// # Panics
+    ///
+    /// Panics if any component is uniquely borrowed.
+    pub fn between(old: &World, new: &World, registry: &ComponentRegistry) -> Self {
+        let mut diff = WorldDiff::default();
+        for old_entity in old {
+            let entity = old_entity.entity();
+            let Ok(new_entity) = new.entity(entity) else {
+                diff.despawned.push(entity);
+                continue;
+            };
+            let (old_arch, old_index) = old_entity.raw();
+            let (new_arch, new_index) = new_entity.raw();
+            let first_change = diff.changes.len();
+            for id in old_entity.component_types() {
+                if !new_arch.has_dynamic(id) {
+                    diff.changes.push(ComponentChange {
+                        entity,
+                        component: id,
+                        kind: ChangeKind::Removed,
+                    });
+                    continue;
+                }
+                let Some(reg) = registry.get(id) else {
+                    continue;
+                };
+                let _old_borrow = RawColumn::new(old_arch, id);
+                let _new_borrow = RawColumn::new(new_arch, id);
+                let equal = unsafe {
+                    reg.eq(
+                        component_ptr(old_arch, id, old_index),
+                        component_ptr(new_arch, id, new_index),
+                    )
+                };
+                if equal == Some(false) {
+                    diff.changes.push(ComponentChange {
+                        entity,
+                        component: id,
+                        kind: ChangeKind::Changed,
+                    });
+                }
+            }
+            diff.changes.extend(
+                new_entity
+                    .component_types()
+                    .filter(|&id| !old_arch.has_dynamic(id))
+                    .map(|id| ComponentChange {
+                        entity,
+                        component: id,
+                        kind: ChangeKind::Added,
+                    }),
+            );
+            let updated = diff.changes[first_change..]
+                .iter()
+                .filter(|x| x.kind != ChangeKind::Removed)
+                .map(|x| x.component);
+            if let Some(patch) = clone_components(registry, new_arch, new_index, updated) {
+                diff.patches.push((entity, patch));
+            }
+        }
+        for new_entity in new.iter().filter(|x| !old.contains(x.entity())) {
+            let entity = new_entity.entity();
+            diff.spawned.push(entity);
+            let (arch, index) = new_entity.raw();
+            if let Some(patch) = clone_components(registry, arch, index, arch.component_types()) {
+                diff.patches.push((entity, patch));
+            }
+        }
+
+        diff.spawned.sort_unstable_by_key(|x| x.id());
+        diff.despawned.sort_unstable_by_key(|x| x.id());
+        diff.patches.sort_unstable_by_key(|x| x.0.id());
+        diff.changes.sort_unstable_by(|x, y| {
+            x.entity
+                .id()
+                .cmp(&y.entity.id())
+                .then_with(|| component_order(registry, x.component, y.component))
+        });
+        diff
+    }
+
+    /// Entities present only in the new version
+    pub fn spawned(&self) -> &[Entity] {
+        &self.spawned
+    }
+
+    /// Entities present only in the old version
+    pub fn despawned(&self) -> &[Entity] {
+        &self.despawned
+    }
+
+    /// Changes to the components of entities present in both versions
+    pub fn changes(&self) -> &[ComponentChange] {
+        &self.changes
+    }
+
+    /// Clones of the new values of each entity's components that were spawned, added or changed,
+    /// in ascending order of entity ID
+    ///
+    /// Only components whose types were registered with [`ComponentRegistry::register_clone`] are
+    /// included, and entities with none are omitted.
+    pub fn patches(&self) -> impl ExactSizeIterator<Item = (Entity, &BuiltEntityClone)> + '_ {
+        self.patches.iter().map(|(entity, patch)| (*entity, patch))
+    }
+
+    /// Whether the versions are identical
+    pub fn is_empty(&self) -> bool {
+        self.spawned.is_empty() && self.despawned.is_empty() && self.changes.is_empty()
+    }
+
+    /// Render the differences as a human-readable report, e.g. for test failure messages
+    ///
+    /// `old` and `new` must be the worlds the diff was computed from, unmodified since. Component
+    /// values are shown using the [`Debug`](fmt::Debug) implementations registered with
+    /// [`ComponentRegistry::register_debug`]. Each difference is shown on its own line, prefixed by
+    /// `+` for additions, `-` for removals, and `~` for changes.
+    ///
+    /// # Panics
+    ///
+    /// Formatting panics if any component is uniquely borrowed, or if the worlds don't match the
+    /// diff.
+    pub fn report<'a>(
+        &'a self,
+        old: &'a World,
+        new: &'a World,
+        registry: &'a ComponentRegistry,
+    ) -> DiffReport<'a> {
+        DiffReport {
+            diff: self,
+            old,
+            new,
+            registry,
+        }
+    }
+}
+
+/// A change to one component of an entity, listed by [`WorldDiff::changes`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ComponentChange {
+    /// The entity whose component changed
+    pub entity: Entity,
+    /// The type of the component
+    pub component: TypeId,
+    /// How the component changed
+    pub kind: ChangeKind,
+}
+
+/// How a component changed between two versions of a world
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ChangeKind {
+    /// The component is present only in the new version
+    Added,
+    /// The component is present only in the old version
+    Removed,
+    /// The component is present in both versions with different values
+    Changed,
+}
+
+/// Human-readable rendering of a [`WorldDiff`], produced by [`WorldDiff::report`]
+pub struct DiffReport<'a> {
+    diff: &'a WorldDiff,
+    old: &'a World,
+    new: &'a World,
+    registry: &'a ComponentRegistry,
+}
+
+impl fmt::Display for DiffReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for &entity in &self.diff.despawned {
+            write!(f, "- {entity:?}")?;
+            self.fmt_entity(f, self.old.entity(entity).unwrap())?;
+        }
+        for &entity in &self.diff.spawned {
+            write!(f, "+ {entity:?}")?;
+            self.fmt_entity(f, self.new.entity(entity).unwrap())?;
+        }
+        for change in &self.diff.changes {
+            let old = self.old.entity(change.entity).unwrap();
+            let new = self.new.entity(change.entity).unwrap();
+            let name = ComponentName(self.registry, change.component);
+            match change.kind {
+                ChangeKind::Added => {
+                    let new = Value(self.registry, new, change.component);
+                    writeln!(f, "+ {:?} {name}: {new}", change.entity)?;
+                }
+                ChangeKind::Removed => {
+                    let old = Value(self.registry, old, change.component);
+                    writeln!(f, "- {:?} {name}: {old}", change.entity)?;
+                }
+                ChangeKind::Changed => {
+                    let old = Value(self.registry, old, change.component);
+                    let new = Value(self.registry, new, change.component);
+                    writeln!(f, "~ {:?} {name}: {old} -> {new}", change.entity)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DiffReport<'_> {
+    /// Write the components of an entire entity, ending the line
+    fn fmt_entity(&self, f: &mut fmt::Formatter<'_>, entity: EntityRef<'_>) -> fmt::Result {
+        let mut ids = entity.component_types().collect::<Vec<_>>();
+        ids.sort_unstable_by(|&x, &y| component_order(self.registry, x, y));
+        for (i, id) in ids.into_iter().enumerate() {
+            let separator = if i == 0 { ": " } else { ", " };
+            let name = ComponentName(self.registry, id);
+            write!(f, "{separator}{name} {}", Value(self.registry, entity, id))?;
+        }
+        writeln!(f)
+    }
+}
+
+impl fmt::Debug for DiffReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Registered name of a component type, or its [`TypeId`] if unregistered
+struct ComponentName<'a>(&'a ComponentRegistry, TypeId);
+
+impl fmt::Display for ComponentName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.get(self.1) {
+            Some(reg) => f.write_str(reg.name()),
+            None => write!(f, "{:?}", self.1),
+        }
+    }
+}
+
+/// An entity's component, formatted with its registered [`Debug`](fmt::Debug) implementation
+struct Value<'a>(&'a ComponentRegistry, EntityRef<'a>, TypeId);
+
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (archetype, index) = self.1.raw();
+        let _borrow = RawColumn::new(archetype, self.2);
+        let result = self
+            .0
+            .get(self.2)
+            .and_then(|reg| unsafe { reg.fmt_debug(component_ptr(archetype, self.2, index), f) });
+        result.unwrap_or_else(|| f.write_str(".."))
+    }
+}
+
+/// Order component types by registered name, with unregistered types last
+fn component_order(registry: &ComponentRegistry, x: TypeId, y: TypeId) -> core::cmp::Ordering {
+    let name = |id| registry.get(id).map(|reg| reg.name());
+    match (name(x), name(y)) {
+        (Some(x), Some(y)) => x.cmp(y),
+        (Some(_), None) => core::cmp::Ordering::Less,
+        (None, Some(_)) => core::cmp::Ordering::Greater,
+        (None, None) => x.cmp(&y),
+    }
+}
+
+/// Clone the components of types `ids` at `index` in `archetype` that have registered clone
+/// functions, or `None` if there are none
+///
+/// # Panics
+///
+/// Panics if any of the components are uniquely borrowed.
+fn clone_components(
+    registry: &ComponentRegistry,
+    archetype: &Archetype,
+    index: u32,
+    ids: impl Iterator<Item = TypeId>,
+) -> Option<BuiltEntityClone> {
+    let mut builder = EntityBuilderClone::new();
+    let mut empty = true;
+    for id in ids {
+        let Some(clone) = registry.get(id).and_then(|reg| reg.dynamic_clone()) else {
+            continue;
+        };
+        let _borrow = RawColumn::new(archetype, id);
+        unsafe {
+            builder.add_cloned(component_ptr(archetype, id, index), clone);
+        }
+        empty = false;
+    }
+    (!empty).then(|| builder.build())
+}
+
+/// Address of the component of type `id` at `index` in `archetype`, which must exist
+fn component_ptr(archetype: &Archetype, id: TypeId, index: u32) -> *const u8 {
+    let size = archetype
+        .component_type_info()
+        .find(|x| x.id() == id)
+        .unwrap()
+        .layout()
+        .size();
+    unsafe {
+        archetype
+            .get_dynamic(id, size, index)
+            .unwrap()
+            .as_ptr()
+            .cast_const()
+    }
+}
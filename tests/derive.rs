@@ -1,12 +1,15 @@
-//!
-
 #[cfg(feature = "macros")]
 #[cfg_attr(miri, ignore)]
 #[test]
 fn derive() {
     const TEST_DIR: &str = "tests/derive";
     let t = trybuild::TestCases::new();
-    let failures = &["enum_unsupported.rs", "union.rs", "wrong_lifetime.rs"];
+    let failures = &[
+        "enum_unsupported.rs",
+        "union.rs",
+        "wrong_lifetime.rs",
+        "aliasing.rs",
+    ];
     let successes = &[
         "enum_query.rs",
         "unit_structs.rs",
@@ -0,0 +1,13 @@
+use hecs::Query;
+
+#[derive(Query)]
+struct Foo<'a> {
+    foo: &'a mut i32,
+    bar: &'a bool,
+    baz: &'a i32,
+}
+
+#[derive(Query)]
+struct Bar<'a>(&'a String, &'a mut String);
+
+fn main() {}
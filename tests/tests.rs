@@ -1,5 +1,6 @@
 #![allow(deprecated)]
 
+use std::any::TypeId;
 use std::borrow::Cow;
 
 use hecs::*;
@@ -550,6 +551,78 @@ fn build_entity_bundle() {
     assert_eq!(*world.get::<&i32>(f).unwrap(), 789);
 }
 
+#[test]
+fn over_aligned_components() {
+    #[derive(Debug, Clone, PartialEq)]
+    #[repr(align(64))]
+    struct Wide([f32; 3]);
+
+    #[derive(Debug, Clone, PartialEq)]
+    #[repr(align(32))]
+    struct Half(u8);
+
+    fn check(world: &World) {
+        for (_, (wide, half)) in world.query::<(&Wide, Option<&Half>)>().iter() {
+            assert_eq!(wide as *const Wide as usize % 64, 0);
+            if let Some(half) = half {
+                assert_eq!(half as *const Half as usize % 32, 0);
+            }
+        }
+        for archetype in world.archetypes() {
+            if let Some(layout) = archetype.column_layout(std::any::TypeId::of::<Wide>()) {
+                assert_eq!(layout.align(), 64);
+            }
+        }
+    }
+
+    let mut world = World::new();
+    let mut entities = Vec::new();
+    for i in 0..100 {
+        entities.push(world.spawn((i as u8, Wide([i as f32; 3]))));
+    }
+    entities.extend(world.spawn_batch((0..100).map(|i| (Wide([i as f32; 3]), Half(i), true))));
+    check(&world);
+
+    let mut builder = EntityBuilder::new();
+    builder.add(1u8).add(Wide([1.0; 3])).add(Half(2)).add(3u16);
+    entities.push(world.spawn(builder.build()));
+    let mut builder = EntityBuilderClone::new();
+    builder.add(1u8).add(Half(2)).add(Wide([2.0; 3]));
+    let built = builder.build();
+    entities.push(world.spawn(&built));
+
+    let mut cmd = CommandBuffer::new();
+    cmd.spawn((1u8, Wide([3.0; 3])));
+    cmd.insert_one(entities[0], Half(7));
+    cmd.run_on(&mut world);
+    check(&world);
+
+    for &e in entities.iter().step_by(3) {
+        world.remove_one::<u8>(e).ok();
+    }
+    for &e in entities.iter().step_by(5) {
+        world.insert_one(e, Half(9)).unwrap();
+    }
+    check(&world);
+
+    let mut batch = ColumnBatchType::new();
+    batch.add::<Wide>().add::<u8>();
+    let mut batch = batch.into_batch(10);
+    let mut wide = batch.writer::<Wide>().unwrap();
+    for i in 0..10 {
+        wide.push(Wide([i as f32; 3])).unwrap();
+    }
+    let mut bytes = batch.writer::<u8>().unwrap();
+    for i in 0..10 {
+        bytes.push(i).unwrap();
+    }
+    world.spawn_column_batch(batch.build().unwrap());
+    check(&world);
+
+    assert_eq!(*world.get::<&Wide>(entities[0]).unwrap(), Wide([0.0; 3]));
+    assert_eq!(*world.get::<&Half>(entities[0]).unwrap(), Half(9));
+}
+
 #[test]
 fn dynamic_components() {
     let mut world = World::new();
@@ -630,6 +703,543 @@ fn remove_buffered_component() {
     assert!(world.get::<&i32>(ent).is_err());
 }
 
+#[test]
+fn iter_scope_defers_changes() {
+    let mut world = World::new();
+    let a = world.spawn((1, "a"));
+    let b = world.spawn((2, "b"));
+    let c = world.spawn((3,));
+    let d = world.iter_scope(|scope| {
+        let mut spawned = None;
+        for (e, &x) in scope.query::<&i32>().iter() {
+            match x {
+                1 => scope.remove_one::<&str>(e),
+                2 => scope.despawn(e),
+                _ => spawned = Some(scope.spawn((x + 1, "d"))),
+            }
+        }
+        assert!(scope.contains(b));
+        assert!(scope.get::<&&str>(a).is_ok());
+        spawned.unwrap()
+    });
+    assert!(world.get::<&&str>(a).is_err());
+    assert!(!world.contains(b));
+    assert!(world.contains(c));
+    assert_eq!(*world.get::<&i32>(d).unwrap(), 4);
+    assert_eq!(*world.get::<&&str>(d).unwrap(), "d");
+}
+
+#[test]
+fn run_deferred() {
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let b = world.spawn((2,));
+    let count = world.run_deferred(|world, cmd| {
+        for (e, &x) in world.query::<&i32>().iter() {
+            if x == 1 {
+                cmd.despawn(e);
+            } else {
+                cmd.insert_one(e, "two");
+            }
+        }
+        world.len()
+    });
+    assert_eq!(count, 2);
+    assert!(!world.contains(a));
+    assert_eq!(*world.get::<&&str>(b).unwrap(), "two");
+}
+
+#[test]
+fn insert_default_dynamic() {
+    #[derive(Default, Debug, PartialEq)]
+    struct Health(u32);
+
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_default::<Health>("Health")
+        .register::<i32>("i32")
+        .register_default::<String>("String");
+    assert_eq!(registry.len(), 3);
+    assert!(registry
+        .get(std::any::TypeId::of::<Health>())
+        .unwrap()
+        .has_default());
+    assert!(!registry.get_by_name("i32").unwrap().has_default());
+
+    let mut world = World::new();
+    let e = world.spawn((true,));
+    world
+        .insert_default_dynamic(e, &registry, registry.id_of("Health").unwrap())
+        .unwrap();
+    assert_eq!(*world.get::<&Health>(e).unwrap(), Health(0));
+    assert_eq!(
+        world.insert_default_dynamic(e, &registry, std::any::TypeId::of::<i32>()),
+        Err(InsertDefaultError::MissingDefault(MissingDefault(
+            std::any::TypeId::of::<i32>()
+        )))
+    );
+    assert!(world.get::<&i32>(e).is_err());
+
+    let mut builder = EntityBuilder::new();
+    builder
+        .add(1u8)
+        .add_default_dynamic(&registry, std::any::TypeId::of::<String>())
+        .unwrap();
+    assert!(builder
+        .add_default_dynamic(&registry, std::any::TypeId::of::<u64>())
+        .is_err());
+    let f = world.spawn(builder.build());
+    assert_eq!(*world.get::<&String>(f).unwrap(), "");
+    assert_eq!(*world.get::<&u8>(f).unwrap(), 1);
+
+    world.despawn(e).unwrap();
+    assert_eq!(
+        world.insert_default_dynamic(e, &registry, std::any::TypeId::of::<Health>()),
+        Err(InsertDefaultError::NoSuchEntity)
+    );
+}
+
+#[test]
+fn spawn_clones() {
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_clone::<String>("String")
+        .register_clone::<i32>("i32");
+
+    let mut world = World::new();
+    let prototype = world.spawn(("abc".to_string(), 7, true));
+    let copies = world
+        .spawn_clones(prototype, 4, &registry)
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(copies.len(), 4);
+    for &copy in &copies {
+        assert_ne!(copy, prototype);
+        assert_eq!(*world.get::<&String>(copy).unwrap(), "abc");
+        assert_eq!(*world.get::<&i32>(copy).unwrap(), 7);
+        assert!(world.get::<&bool>(copy).is_err());
+    }
+    *world.get::<&mut String>(copies[0]).unwrap() = "def".into();
+    assert_eq!(*world.get::<&String>(prototype).unwrap(), "abc");
+    assert_eq!(world.len(), 5);
+
+    assert_eq!(
+        world.spawn_clones(prototype, 0, &registry).unwrap().len(),
+        0
+    );
+    world.despawn(prototype).unwrap();
+    assert!(world.spawn_clones(prototype, 1, &registry).is_err());
+}
+
+#[test]
+fn prefab_instances() {
+    use std::any::TypeId;
+
+    let mut builder = EntityBuilderClone::new();
+    builder.add(10u32).add("orc".to_string());
+    let prefab = Prefab::new(PrefabId(3), builder);
+    assert_eq!(prefab.component_types().len(), 2);
+
+    let mut world = World::new();
+    let a = world.spawn_prefab(&prefab, ());
+    let b = world.spawn_prefab(&prefab, (20u32, true));
+    let c = world.spawn_prefab_with(&prefab, |b| {
+        b.add("troll".to_string());
+    });
+
+    assert_eq!(*world.get::<&u32>(a).unwrap(), 10);
+    assert_eq!(*world.get::<&String>(a).unwrap(), "orc");
+    assert!(world
+        .get::<&PrefabInstance>(a)
+        .unwrap()
+        .overrides()
+        .is_empty());
+
+    assert_eq!(*world.get::<&u32>(b).unwrap(), 20);
+    assert_eq!(*world.get::<&String>(b).unwrap(), "orc");
+    assert!(*world.get::<&bool>(b).unwrap());
+    let instance = world.get::<&PrefabInstance>(b).unwrap();
+    assert_eq!(instance.prefab(), PrefabId(3));
+    assert!(instance.is_overridden(TypeId::of::<u32>()));
+    assert!(instance.is_overridden(TypeId::of::<bool>()));
+    assert!(!instance.is_overridden(TypeId::of::<String>()));
+    drop(instance);
+
+    assert_eq!(*world.get::<&String>(c).unwrap(), "troll");
+    assert_eq!(
+        world.get::<&PrefabInstance>(c).unwrap().overrides(),
+        &[TypeId::of::<String>()]
+    );
+}
+
+#[test]
+fn prefab_from_entity() {
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_clone::<u32>("u32")
+        .register_clone::<String>("String");
+
+    let mut world = World::new();
+    let template = world.spawn((5u32, "knight".to_string(), 1.5f32));
+    let prefab = Prefab::from_entity(PrefabId(9), world.entity(template).unwrap(), &registry);
+    assert_eq!(prefab.component_types().len(), 2);
+
+    let e = world.spawn_prefab(&prefab, ());
+    assert_eq!(*world.get::<&u32>(e).unwrap(), 5);
+    assert_eq!(*world.get::<&String>(e).unwrap(), "knight");
+    assert!(world.get::<&f32>(e).is_err());
+}
+
+#[test]
+fn prefab_reapply() {
+    use std::any::TypeId;
+
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_eq::<u32>("u32")
+        .register_eq::<String>("String")
+        .register_eq::<bool>("bool");
+
+    let mut builder = EntityBuilderClone::new();
+    builder.add(10u32).add("orc".to_string()).add(true);
+    let old = Prefab::new(PrefabId(1), builder);
+
+    let mut builder = EntityBuilderClone::new();
+    builder.add(10u32).add("uruk".to_string()).add(2.5f32);
+    let new = Prefab::new(PrefabId(2), builder);
+
+    let diff = old.diff(&new, &registry);
+    assert_eq!(diff.added(), &[TypeId::of::<f32>()]);
+    assert_eq!(diff.removed(), &[TypeId::of::<bool>()]);
+    assert_eq!(diff.changed(), &[TypeId::of::<String>()]);
+    assert!(old.diff(&old, &registry).is_empty());
+
+    let mut world = World::new();
+    let plain = world.spawn_prefab(&old, ());
+    let damaged = world.spawn_prefab(&old, ());
+    *world.get::<&mut u32>(damaged).unwrap() = 3;
+    let named = world.spawn_prefab(&old, ("grishnakh".to_string(), false));
+    let unrelated = world.spawn((1u32, true));
+    let mut builder = EntityBuilderClone::new();
+    builder.add(10u32).add("goblin".to_string()).add(true);
+    let other = world.spawn_prefab(&Prefab::new(PrefabId(7), builder), ());
+
+    assert_eq!(diff.old(), PrefabId(1));
+    world.apply_prefab_diff(&new, &diff, [plain, damaged, named, unrelated, other]);
+
+    assert_eq!(*world.get::<&String>(plain).unwrap(), "uruk");
+    assert_eq!(*world.get::<&f32>(plain).unwrap(), 2.5);
+    assert!(world.get::<&bool>(plain).is_err());
+    assert_eq!(
+        world.get::<&PrefabInstance>(plain).unwrap().prefab(),
+        PrefabId(2)
+    );
+
+    assert_eq!(*world.get::<&u32>(damaged).unwrap(), 3);
+    assert_eq!(*world.get::<&String>(damaged).unwrap(), "uruk");
+
+    assert_eq!(*world.get::<&String>(named).unwrap(), "grishnakh");
+    assert!(!*world.get::<&bool>(named).unwrap());
+    assert_eq!(*world.get::<&f32>(named).unwrap(), 2.5);
+
+    assert!(*world.get::<&bool>(unrelated).unwrap());
+    assert!(world.get::<&f32>(unrelated).is_err());
+
+    assert_eq!(*world.get::<&String>(other).unwrap(), "goblin");
+    assert!(world.get::<&f32>(other).is_err());
+    assert_eq!(
+        world.get::<&PrefabInstance>(other).unwrap().prefab(),
+        PrefabId(7)
+    );
+}
+
+#[test]
+fn changed_since_tick() {
+    let mut world = World::new();
+    assert_eq!(world.tick(), 0);
+    let a = world.spawn((1, true));
+    let b = world.spawn((2, "b"));
+    let start = world.advance_tick();
+    assert_eq!(start, 1);
+
+    let changed = |world: &World, tick| {
+        let mut xs = world
+            .query::<()>()
+            .changed_since::<i32>(tick)
+            .iter()
+            .map(|(e, ())| e)
+            .collect::<Vec<_>>();
+        xs.sort();
+        xs
+    };
+    assert_eq!(changed(&world, 0), {
+        let mut xs = vec![a, b];
+        xs.sort();
+        xs
+    });
+    assert!(changed(&world, start).is_empty());
+    assert_eq!(
+        world
+            .query::<&i32>()
+            .changed_since::<i32>(start)
+            .iter()
+            .len(),
+        0
+    );
+
+    // Read-only access doesn't count as a change
+    assert_eq!(*world.get::<&i32>(a).unwrap(), 1);
+    for (_, x) in world.query::<&i32>().iter() {
+        let _ = x;
+    }
+    assert!(changed(&world, start).is_empty());
+
+    // Mutable access through queries
+    for (_, x) in world.query_mut::<&mut i32>().with::<&bool>() {
+        *x += 1;
+    }
+    assert_eq!(changed(&world, start), [a]);
+    assert_eq!(
+        world
+            .query::<&i32>()
+            .changed_since::<i32>(start)
+            .iter()
+            .len(),
+        1
+    );
+    assert_eq!(
+        world
+            .query_mut::<&i32>()
+            .changed_since::<i32>(start)
+            .into_iter()
+            .count(),
+        1
+    );
+
+    // Mutable access to a single entity
+    let next = world.advance_tick();
+    assert!(changed(&world, next).is_empty());
+    *world.get::<&mut i32>(b).unwrap() += 1;
+    assert_eq!(changed(&world, next), [b]);
+    assert_eq!(
+        world
+            .archetypes()
+            .find(|x| x.has::<&str>())
+            .unwrap()
+            .change_tick(std::any::TypeId::of::<i32>()),
+        Some(next)
+    );
+
+    // Insertion counts as a change, and other components are unaffected
+    let next = world.advance_tick();
+    let c = world.spawn((3,));
+    assert_eq!(changed(&world, next), [c]);
+    assert_eq!(
+        world
+            .query::<()>()
+            .changed_since::<bool>(next)
+            .iter()
+            .count(),
+        0
+    );
+    let batches = world
+        .query::<()>()
+        .changed_since::<i32>(next)
+        .iter_batched(1)
+        .flatten()
+        .count();
+    assert_eq!(batches, 1);
+}
+
+#[test]
+fn iter_masked() {
+    let mut world = World::new();
+    let entities = (0..200)
+        .map(|i| {
+            if i % 2 == 0 {
+                world.spawn((i, true))
+            } else {
+                world.spawn((i,))
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut near = EntityMask::new();
+    let mut visible = EntityMask::new();
+    for (i, &e) in entities.iter().enumerate() {
+        if i < 100 {
+            near.insert(e);
+        }
+        if i % 3 == 0 {
+            visible.insert(e);
+        }
+    }
+    let mask = &near & &visible;
+
+    let mut query = world.query::<&i32>();
+    let mut masked = query
+        .iter_masked(&mask)
+        .map(|(e, &i)| {
+            assert_eq!(entities[i as usize], e);
+            i
+        })
+        .collect::<Vec<_>>();
+    masked.sort();
+    assert_eq!(masked, (0..100).step_by(3).collect::<Vec<_>>());
+
+    let flagged = world
+        .query::<&i32>()
+        .with::<&bool>()
+        .iter_masked(&mask)
+        .count();
+    assert_eq!(flagged, (0..100).step_by(6).count());
+
+    assert_eq!(
+        world
+            .query::<&i32>()
+            .iter_masked(&EntityMask::new())
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn collect_mask() {
+    let mut world = World::new();
+    let a = world.spawn((1, true, "a"));
+    let b = world.spawn((2, true));
+    let c = world.spawn((3, "c"));
+    let d = world.spawn((true,));
+
+    let mut numbers = EntityMask::new();
+    world.query::<&i32>().collect_mask(&mut numbers);
+    assert_eq!(numbers, [a, b, c].into_iter().collect());
+
+    let mut flags = EntityMask::new();
+    world.query::<&bool>().collect_mask(&mut flags);
+    let mut named = EntityMask::new();
+    world.query::<&&str>().collect_mask(&mut named);
+
+    let mask = &(&numbers & &flags) - &named;
+    assert_eq!(mask.ids().collect::<Vec<_>>(), [b.id()]);
+
+    // Masks accumulate across queries
+    let mut either = EntityMask::new();
+    world.query::<&bool>().collect_mask(&mut either);
+    world.query::<&&str>().collect_mask(&mut either);
+    assert_eq!(either, [a, b, c, d].into_iter().collect());
+
+    let mut query = world.query::<&i32>();
+    let values = query
+        .iter_masked(&mask)
+        .map(|(_, &x)| x)
+        .collect::<Vec<_>>();
+    assert_eq!(values, [2]);
+}
+
+#[test]
+fn sorted_by_key() {
+    fn check(world: &World, expected: &[(Entity, u32)]) {
+        for archetype in world.archetypes() {
+            if let Some(keys) = archetype.get::<&u32>() {
+                assert_eq!(archetype.sort_key(), Some(std::any::TypeId::of::<u32>()));
+                assert!(keys.windows(2).all(|x| x[0] <= x[1]), "{:?}", &*keys);
+            }
+        }
+        for &(e, key) in expected {
+            assert_eq!(*world.get::<&u32>(e).unwrap(), key);
+            assert_eq!(world.find_by_key(&key), Some(e));
+        }
+    }
+
+    let mut world = World::new();
+    // Existing archetypes are sorted when the key is registered
+    let mut expected = (0..10u32)
+        .map(|i| {
+            let key = (i * 7) % 10;
+            (world.spawn((key, "early")), key)
+        })
+        .collect::<Vec<_>>();
+    world.keep_sorted_by_key::<u32>();
+    check(&world, &expected);
+
+    for i in 0..50u32 {
+        let key = 100 + (i * 37) % 50;
+        let e = if i % 2 == 0 {
+            world.spawn((key,))
+        } else {
+            world.spawn((key, true))
+        };
+        expected.push((e, key));
+    }
+    check(&world, &expected);
+
+    expected.extend(
+        world
+            .spawn_batch((200..210u32).rev().map(|key| (key, 1.5f32)))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .zip((200..210u32).rev()),
+    );
+    check(&world, &expected);
+
+    // Despawning preserves order
+    let (gone, _) = expected.remove(3);
+    world.despawn(gone).unwrap();
+    check(&world, &expected);
+
+    // Moving between archetypes
+    for &(e, _) in expected.iter().step_by(3) {
+        world.insert_one(e, 'x').unwrap();
+    }
+    check(&world, &expected);
+    for &(e, _) in expected.iter().step_by(6) {
+        world.remove_one::<char>(e).unwrap();
+    }
+    check(&world, &expected);
+
+    // Replacing a key in place
+    let (e, _) = expected[20];
+    world.insert_one(e, 1000u32).unwrap();
+    expected[20].1 = 1000;
+    check(&world, &expected);
+
+    // Mutating keys in place falls back to a linear scan until the archetype is re-sorted
+    for (_, key) in world.query_mut::<&mut u32>().with::<&bool>() {
+        *key = 2000 - *key;
+    }
+    for x in &mut expected {
+        if world.get::<&bool>(x.0).is_ok() {
+            x.1 = 2000 - x.1;
+        }
+    }
+    for &(e, key) in &expected {
+        assert_eq!(world.find_by_key(&key), Some(e));
+    }
+    let e = world.spawn((499u32, true));
+    expected.push((e, 499));
+    check(&world, &expected);
+
+    // Column batches
+    let mut batch = ColumnBatchType::new();
+    batch.add::<u32>().add::<i64>();
+    let mut batch = batch.into_batch(3);
+    let mut keys = batch.writer::<u32>().unwrap();
+    for key in [302, 300, 301] {
+        keys.push(key).unwrap();
+    }
+    let mut others = batch.writer::<i64>().unwrap();
+    for x in 0..3 {
+        others.push(x).unwrap();
+    }
+    let spawned = world
+        .spawn_column_batch(batch.build().unwrap())
+        .collect::<Vec<_>>();
+    expected.extend(spawned.into_iter().zip([302, 300, 301]));
+    check(&world, &expected);
+
+    assert_eq!(world.find_by_key(&12345u32), None);
+}
+
 #[test]
 #[should_panic(expected = "already borrowed")]
 fn illegal_borrow() {
@@ -1012,6 +1622,150 @@ fn empty_entity_ref() {
     assert_eq!(r.entity(), e);
 }
 
+#[test]
+fn entity_ref_component_type_info() {
+    let mut world = World::new();
+    let e = world.spawn(("abc", 123u64));
+    let r = world.entity(e).unwrap();
+    let info = r.component_type_info().collect::<Vec<_>>();
+    assert_eq!(info.len(), r.len());
+    assert!(info.contains(&TypeInfo::of::<&str>()));
+    assert!(info.contains(&TypeInfo::of::<u64>()));
+    assert!(info.iter().map(|ty| ty.id()).eq(r.component_types()));
+}
+
+#[test]
+fn builder_sorted_freelist() {
+    let mut world = World::builder().sorted_freelist(true).build();
+    let entities = (0..4).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    world.despawn(entities[2]).unwrap();
+    world.despawn(entities[0]).unwrap();
+    world.despawn(entities[3]).unwrap();
+    assert_eq!(world.spawn(()).id(), entities[0].id());
+    assert_eq!(world.spawn(()).id(), entities[2].id());
+    assert_eq!(world.spawn(()).id(), entities[3].id());
+}
+
+#[test]
+fn builder_id_reuse_delay() {
+    let mut world = World::builder().id_reuse_delay(2).build();
+    let a = world.spawn(());
+    world.despawn(a).unwrap();
+    assert_eq!(world.quarantined_ids(), 1);
+    let b = world.spawn(());
+    assert_ne!(a.id(), b.id());
+    world.advance_id_reuse();
+    assert_ne!(world.spawn(()).id(), a.id());
+    world.advance_id_reuse();
+    assert_eq!(world.quarantined_ids(), 0);
+    let c = world.spawn(());
+    assert_eq!(c.id(), a.id());
+    assert!(!world.contains(a));
+    assert!(world.contains(c));
+}
+
+#[test]
+fn freelist_quarantined_ids() {
+    let mut world = World::builder().id_reuse_delay(1).build();
+    let entities = world.spawn_batch([(); 3]).collect::<Vec<_>>();
+    world.despawn(entities[0]).unwrap();
+    world.advance_id_reuse();
+    world.despawn(entities[1]).unwrap();
+    assert_eq!(world.quarantined_ids(), 1);
+    let freelist = world.freelist().collect::<Vec<_>>();
+    assert_eq!(freelist.len(), 2);
+
+    // Round trip on the same world keeps the quarantine
+    world.set_freelist(&freelist);
+    assert_eq!(world.quarantined_ids(), 1);
+    assert_eq!(world.freelist().collect::<Vec<_>>(), freelist);
+
+    // A restored world reuses the quarantined ID last
+    let mut restored = World::new();
+    restored.spawn_at(entities[2], ());
+    restored.set_freelist(&freelist);
+    assert_eq!(restored.spawn(()).id(), entities[0].id());
+    let b = restored.spawn(());
+    assert_eq!(b.id(), entities[1].id());
+    assert_ne!(b, entities[1]);
+
+    world.advance_id_reuse();
+    assert_eq!(world.spawn(()).id(), entities[1].id());
+}
+
+#[test]
+fn builder_monotonic_ids() {
+    let mut world = World::builder().monotonic_ids(true).build();
+    let a = world.spawn((1,));
+    world.despawn(a).unwrap();
+    let b = world.spawn((2,));
+    world.take(b).unwrap();
+    let c = world.spawn_batch([(3,), (4,)]).collect::<Vec<_>>();
+    assert_eq!(world.freelist().len(), 0);
+    let mut ids = vec![a.id(), b.id(), c[0].id(), c[1].id()];
+    ids.sort_unstable();
+    ids.dedup();
+    assert_eq!(ids.len(), 4);
+
+    world.spawn_at(a, (5,));
+    assert_eq!(world.len(), 3);
+    assert_eq!(*world.get::<&i32>(a).unwrap(), 5);
+}
+
+#[test]
+fn spawn_at_quarantined() {
+    let mut world = World::builder().id_reuse_delay(1).build();
+    let a = world.spawn((1,));
+    world.despawn(a).unwrap();
+    world.spawn_at(a, (2,));
+    assert_eq!(world.quarantined_ids(), 0);
+    assert_eq!(world.len(), 1);
+    assert_eq!(*world.get::<&i32>(a).unwrap(), 2);
+    world.advance_id_reuse();
+    assert_ne!(world.spawn(()).id(), a.id());
+}
+
+#[test]
+fn builder_stable_removal() {
+    let mut world = World::builder().stable_removal(true).build();
+    let entities = (0..5).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    world.despawn(entities[1]).unwrap();
+    world.insert_one(entities[3], true).unwrap();
+    world.remove_one::<i32>(entities[0]).unwrap();
+    assert_eq!(
+        world
+            .query_mut::<&i32>()
+            .into_iter()
+            .map(|(_, &i)| i)
+            .collect::<Vec<_>>(),
+        [2, 4, 3]
+    );
+    for &e in &entities[2..] {
+        assert!(world.get::<&i32>(e).is_ok());
+    }
+}
+
+#[test]
+fn builder_tracking() {
+    let mut world = World::builder()
+        .track_changes::<i32>()
+        .track_removals::<bool>()
+        .build();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2,));
+    world.clear_trackers();
+    *world.get::<&mut i32>(b).unwrap() += 1;
+    world.remove_one::<bool>(a).unwrap();
+    let changed = world
+        .query_mut::<Changed<i32>>()
+        .into_iter()
+        .filter(|&(_, x)| x)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(changed, [b]);
+    assert_eq!(world.removed::<bool>().collect::<Vec<_>>(), [a]);
+}
+
 #[test]
 fn query_or() {
     let mut world = World::new();
@@ -1117,3 +1871,3168 @@ fn query_many_duplicate() {
     let e = world.spawn(());
     _ = world.query_many_mut::<(), 2>([e, e]);
 }
+
+#[test]
+fn maintain() {
+    let mut world = World::new();
+    let mut query = PreparedQuery::<&i32>::default();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2, "b"));
+    let c = world.spawn((3u8,));
+    let batch = world
+        .spawn_batch((0..100).map(|i| (i as u16,)))
+        .collect::<Vec<_>>();
+    for &e in &batch[10..] {
+        world.despawn(e).unwrap();
+    }
+    world.remove_one::<bool>(a).unwrap();
+    world.despawn(b).unwrap();
+    assert_eq!(query.query_mut(&mut world).count(), 1);
+    let archetypes = world.archetypes().len();
+    let generation = world.archetypes_generation();
+
+    // Each call visits at most one archetype
+    for _ in 0..archetypes {
+        assert!(!world.maintain(1));
+    }
+    assert!(world.maintain(1));
+    assert_eq!(world.archetypes().len(), archetypes - 2);
+    assert_ne!(world.archetypes_generation(), generation);
+    assert!(world.archetypes().all(|x| x.capacity() / 2 <= x.len()));
+
+    assert_eq!(*world.get::<&i32>(a).unwrap(), 1);
+    assert_eq!(*world.get::<&u8>(c).unwrap(), 3);
+    for (i, &e) in batch[..10].iter().enumerate() {
+        assert_eq!(*world.get::<&u16>(e).unwrap(), i as u16);
+    }
+    assert_eq!(query.query_mut(&mut world).collect::<Vec<_>>(), [(a, &1)]);
+
+    // Removed archetypes are recreated as needed
+    world.insert_one(a, false).unwrap();
+    world.insert_one(c, 4).unwrap();
+    let d = world.spawn((5, "d"));
+    assert_eq!(world.query_mut::<(&i32, &bool)>().into_iter().count(), 1);
+    assert_eq!(*world.get::<&i32>(c).unwrap(), 4);
+    assert_eq!(*world.get::<&&str>(d).unwrap(), "d");
+    assert_eq!(query.query_mut(&mut world).count(), 3);
+    assert!(world.maintain(u32::MAX));
+}
+
+#[test]
+fn operation_counts() {
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let b = world.reserve_entity();
+    world.insert_one(a, true).unwrap();
+    world.insert_one(a, false).unwrap();
+    world.remove_one::<bool>(a).unwrap();
+    world.despawn(a).unwrap();
+    world.spawn_at(b, ("b",));
+    world.spawn_batch((0..100).map(|i| (i,))).for_each(drop);
+    assert_eq!(
+        world.operation_counts(),
+        OperationCounts {
+            spawns: 103,
+            despawns: 2,
+            archetype_moves: 2,
+            column_grows: 5,
+            flushes: 1,
+        }
+    );
+
+    let log = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = log.clone();
+    world.set_metrics_sink(move |tick, counts: &OperationCounts| {
+        sink.lock().unwrap().push((tick, *counts));
+    });
+    world.advance_tick();
+    assert_eq!(world.operation_counts(), OperationCounts::default());
+    world.clear();
+    world.advance_tick();
+    assert!(world.take_metrics_sink().is_some());
+    world.advance_tick();
+    let log = log.lock().unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[0].0, 0);
+    assert_eq!(log[0].1.spawns, 103);
+    assert_eq!(log[1].0, 1);
+    assert_eq!(log[1].1.despawns, 101);
+}
+
+#[test]
+fn column_export() {
+    let mut registry = ComponentRegistry::new();
+    unsafe {
+        registry
+            .register_pod::<u16>("u16")
+            .register_pod::<()>("unit");
+    }
+    registry.register::<String>("String");
+    let mut world = World::new();
+    let a = world.spawn((1u16, ()));
+    let b = world.spawn((2u16, (), "b".to_string()));
+    world.spawn((3u16,));
+
+    let export = ColumnExport::new(&world, &registry, &["unit", "u16"]).unwrap();
+    assert_eq!(export.len(), 2);
+    assert_eq!(export.entities(), [a.to_bits().get(), b.to_bits().get()]);
+    assert_eq!(export.columns()[0].name(), "unit");
+    assert_eq!(export.columns()[0].to_vec::<()>().unwrap().len(), 2);
+    let column = export.column("u16").unwrap();
+    assert_eq!(column.layout(), core::alloc::Layout::new::<u16>());
+    assert_eq!(column.to_vec::<u16>().unwrap(), [1, 2]);
+    assert_eq!(column.to_vec::<u32>(), None);
+
+    assert_eq!(
+        ColumnExport::new(&world, &registry, &["String"]).err(),
+        Some(ExportError::NotPod("String"))
+    );
+    assert_eq!(
+        ColumnExport::new(&world, &registry, &["f32"]).err(),
+        Some(ExportError::UnknownComponent("f32".into()))
+    );
+}
+
+#[test]
+fn world_diff_report() {
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_eq::<Health>("Health")
+        .register_debug::<Health>("Health")
+        .register_debug::<&'static str>("Name")
+        .register::<bool>("bool");
+
+    let mut old = World::new();
+    let a = old.spawn((Health(10), "a"));
+    let b = old.spawn((Health(5), true));
+    let c = old.spawn((Health(1),));
+    let d = old.spawn(("d",));
+
+    // Same contents, different archetype creation order
+    let mut new = World::new();
+    new.spawn_at(d, ("d",));
+    new.spawn_at(b, (Health(5), true));
+    new.spawn_at(a, ("a", Health(10)));
+    new.spawn_at(c, (Health(1),));
+    assert!(WorldDiff::between(&old, &new, &registry).is_empty());
+
+    *new.get::<&mut Health>(a).unwrap() = Health(7);
+    new.remove_one::<bool>(b).unwrap();
+    new.insert_one(d, Health(3)).unwrap();
+    new.despawn(c).unwrap();
+    let e = new.spawn((Health(2), "e"));
+    let diff = WorldDiff::between(&old, &new, &registry);
+    assert_eq!(diff.despawned(), [c]);
+    assert_eq!(diff.spawned(), [e]);
+    assert_eq!(diff.changes().len(), 3);
+    assert_eq!(
+        diff.report(&old, &new, &registry).to_string(),
+        "- 2v1: Health Health(1)\n\
+         + 2v2: Health Health(2), Name \"e\"\n\
+         ~ 0v1 Health: Health(10) -> Health(7)\n\
+         - 1v1 bool: ..\n\
+         + 3v1 Health: Health(3)\n"
+    );
+}
+
+#[test]
+#[should_panic(expected = "worlds differ from left to right: tick 3\n~ 0v1 i32: 1 -> 2\n")]
+fn assert_worlds_eq_fails() {
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_eq::<i32>("i32")
+        .register_debug::<i32>("i32");
+    let mut a = World::new();
+    let e = a.spawn((1, "a"));
+    let mut b = World::new();
+    b.spawn_at(e, ("a", 1));
+    assert_worlds_eq!(&a, &b, &registry);
+    *b.get::<&mut i32>(e).unwrap() = 2;
+    assert_worlds_eq!(&a, &b, &registry, "tick {}", 3);
+}
+
+#[test]
+fn generate_world() {
+    use hecs::testing::{generate, GenerateConfig};
+
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_generate::<u32>("u32")
+        .register_generate::<f32>("f32")
+        .register_eq::<u32>("u32")
+        .register_eq::<f32>("f32");
+    let mut config = GenerateConfig::new();
+    config.entities(50..60).despawn_chance(0.25);
+
+    let world = generate(7, &config, &registry);
+    assert_worlds_eq!(&world, &generate(7, &config, &registry), &registry);
+    assert!(!WorldDiff::between(&world, &generate(8, &config, &registry), &registry).is_empty());
+    assert!(world.len() < 60);
+    assert!(world.query::<&u32>().iter().count() > 0);
+    assert!(world
+        .query::<&f32>()
+        .iter()
+        .all(|(_, x)| (0.0..1.0).contains(x)));
+    assert!(world.iter().any(|e| e.entity().to_bits().get() >> 32 > 1));
+
+    let mut config = GenerateConfig::new();
+    config.entities(5..6).component("u32", 1.0);
+    let world = generate(7, &config, &registry);
+    assert_eq!(world.len(), 5);
+    assert_eq!(world.query::<&u32>().iter().count(), 5);
+    assert_eq!(world.query::<&f32>().iter().count(), 0);
+}
+
+#[test]
+fn restore_exact() {
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_clone::<i32>("i32")
+        .register_clone::<String>("String")
+        .register_clone::<bool>("bool")
+        .register_eq::<i32>("i32")
+        .register_eq::<String>("String");
+    let mut world = World::builder().id_reuse_delay(1).build();
+    let ids = (0..6)
+        .map(|i| world.spawn((i, i.to_string())))
+        .collect::<Vec<_>>();
+    world.spawn((true,));
+    world.despawn(ids[3]).unwrap();
+    world.despawn(ids[1]).unwrap();
+    world.advance_id_reuse();
+    world.despawn(ids[4]).unwrap();
+    let reserved = world.reserve_entity();
+
+    let snapshot = world.snapshot(&registry);
+    assert_eq!(snapshot.len(), 4);
+    let mut restored = World::builder().id_reuse_delay(1).build();
+    restored.spawn((7, "x".to_string()));
+    restored.restore_exact(&snapshot);
+    assert_worlds_eq!(&restored, &world, &registry);
+    assert!(restored.contains(reserved));
+    assert_eq!(*restored.get::<&String>(ids[5]).unwrap(), "5");
+    assert!(!restored.contains(ids[4]));
+
+    let mut expected = Vec::new();
+    world.flush();
+    world.advance_id_reuse();
+    for i in 0..5 {
+        expected.push(world.spawn((i,)));
+    }
+    world.despawn(ids[0]).unwrap();
+    world.restore_exact(&snapshot.clone());
+    for world in [&mut world, &mut restored] {
+        world.flush();
+        assert_eq!(world.query::<()>().iter().count(), 5);
+        world.advance_id_reuse();
+        for &entity in &expected {
+            assert_eq!(world.spawn((0,)), entity);
+        }
+    }
+}
+
+#[test]
+fn interpolate_worlds() {
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_lerp::<f32>("f32")
+        .register_lerp::<[f64; 2]>("[f64; 2]");
+    let mut previous = World::new();
+    let a = previous.spawn((0.0f32, [1.0f64, -1.0]));
+    let b = previous.spawn((10.0f32,));
+    let mut current = World::new();
+    current.spawn_at(a, (4.0f32, [3.0f64, 1.0]));
+    current.spawn_at(b, (20.0f32, [5.0f64, 5.0]));
+    let c = current.spawn((8.0f32,));
+
+    let mut extracted = Vec::new();
+    interpolate::<f32>(&previous, &current, 0.25, |e, x| extracted.push((e, x)));
+    extracted.sort_by_key(|x| x.0.id());
+    assert_eq!(extracted, [(a, 1.0), (b, 12.5), (c, 8.0)]);
+
+    let mut render = World::new();
+    render.spawn_at(a, (0.0f32, [0.0f64, 0.0], "a"));
+    render.spawn_at(b, (0.0f32,));
+    let tick = render.advance_tick();
+    interpolate_into(&previous, &current, 0.5, &registry, &mut render);
+    assert_eq!(*render.get::<&f32>(a).unwrap(), 2.0);
+    assert_eq!(*render.get::<&[f64; 2]>(a).unwrap(), [2.0, 0.0]);
+    assert_eq!(*render.get::<&&str>(a).unwrap(), "a");
+    assert_eq!(*render.get::<&f32>(b).unwrap(), 15.0);
+    assert!(!render.contains(c));
+    assert_eq!(
+        render
+            .query::<&f32>()
+            .changed_since::<f32>(tick)
+            .iter()
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn despawn_take() {
+    let dropped = std::sync::Arc::new(());
+    let mut world = World::new();
+    let a = world.spawn((1, "a", dropped.clone()));
+    let b = world.spawn((2, "b", dropped.clone()));
+    let c = world.spawn((3, "c", dropped.clone()));
+
+    assert_eq!(
+        world.despawn_take::<(i32, bool)>(a),
+        Err(ComponentError::MissingComponent(MissingComponent::new::<
+            bool,
+        >()))
+    );
+    assert!(world.contains(a));
+    assert_eq!(world.despawn_take::<(&str, i32)>(a), Ok(("a", 1)));
+    assert!(!world.contains(a));
+    assert_eq!(std::sync::Arc::strong_count(&dropped), 3);
+    assert_eq!(
+        world.despawn_take::<(i32,)>(a),
+        Err(ComponentError::NoSuchEntity)
+    );
+    assert_eq!(*world.get::<&i32>(c).unwrap(), 3);
+
+    let components = world.despawn_take_dynamic(b).unwrap();
+    assert!(!world.contains(b));
+    assert_eq!(*components.get::<&&str>().unwrap(), "b");
+    assert_eq!(std::sync::Arc::strong_count(&dropped), 3);
+    drop(components);
+    assert_eq!(std::sync::Arc::strong_count(&dropped), 2);
+    assert_eq!(world.len(), 1);
+    assert_eq!(*world.get::<&i32>(c).unwrap(), 3);
+}
+
+#[test]
+fn satisfies_dynamic() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2, 'x'));
+    let c = world.spawn((3, "c"));
+
+    let all = QueryBuilder::new().build();
+    assert!(world.satisfies_dynamic(a, &all).unwrap());
+
+    let query = QueryBuilder::new()
+        .write::<i32>()
+        .any_of(&[TypeId::of::<bool>(), TypeId::of::<char>()])
+        .without::<&str>()
+        .build();
+    assert_eq!(query.writes(), [TypeId::of::<i32>()]);
+    assert!(world.satisfies_dynamic(a, &query).unwrap());
+    assert!(world.satisfies_dynamic(b, &query).unwrap());
+    assert!(!world.satisfies_dynamic(c, &query).unwrap());
+
+    let query = QueryBuilder::new()
+        .read::<i32>()
+        .with_dynamic(TypeId::of::<char>())
+        .build();
+    for (entity, expected) in [(a, false), (b, true), (c, false)] {
+        assert_eq!(
+            world.entity(entity).unwrap().satisfies_dynamic(&query),
+            expected
+        );
+        assert_eq!(
+            world
+                .entity(entity)
+                .unwrap()
+                .satisfies::<(&i32, With<(), &char>)>(),
+            expected
+        );
+    }
+
+    world.despawn(a).unwrap();
+    assert_eq!(world.satisfies_dynamic(a, &all), Err(NoSuchEntity));
+}
+
+#[test]
+fn error_context() {
+    fn heal(world: &mut World, entity: Entity) -> Result<(), EntityError> {
+        *world.get::<&mut u32>(entity).context(entity, "heal")? += 10;
+        Ok(())
+    }
+
+    fn heal_compact(world: &mut World, entity: Entity) -> Result<(), ComponentError> {
+        Ok(heal(world, entity)?)
+    }
+
+    let mut world = World::new();
+    let a = world.spawn((90u32,));
+    let b = world.spawn(());
+    heal(&mut world, a).unwrap();
+    assert_eq!(*world.get::<&u32>(a).unwrap(), 100);
+
+    let err = heal(&mut world, b).unwrap_err();
+    assert_eq!(err.entity(), b);
+    assert_eq!(err.operation(), "heal");
+    match err.kind() {
+        EntityErrorKind::MissingComponent(x) => assert_eq!(x.type_name(), "u32"),
+        x => panic!("unexpected error {x:?}"),
+    }
+    assert_eq!(err.to_string(), "failed to heal 1v1: missing u32 component");
+
+    world.despawn(a).unwrap();
+    assert_eq!(
+        heal_compact(&mut world, a),
+        Err(ComponentError::NoSuchEntity)
+    );
+    assert_eq!(
+        world.entity(a).map(|_| ()).context(a, "inspect"),
+        Err(EntityError::new(a, "inspect", NoSuchEntity))
+    );
+}
+
+#[test]
+fn insert_remove_dynamic() {
+    let mut registry = ComponentRegistry::new();
+    registry.register::<std::sync::Arc<()>>("Arc");
+    unsafe {
+        registry.register_pod::<[u16; 2]>("[u16; 2]");
+    }
+    let arc_id = registry.id_of("Arc").unwrap();
+    let pod_id = registry.id_of("[u16; 2]").unwrap();
+
+    let mut world = World::new();
+    let e = world.spawn((true,));
+    let shared = std::sync::Arc::new(());
+    let mut component = std::mem::ManuallyDrop::new(shared.clone());
+    unsafe {
+        world
+            .insert_dynamic(
+                e,
+                &registry,
+                arc_id,
+                (&mut *component as *mut std::sync::Arc<()>).cast(),
+            )
+            .unwrap();
+    }
+    assert_eq!(std::sync::Arc::strong_count(&shared), 2);
+    assert!(world.satisfies::<(&bool, &std::sync::Arc<()>)>(e).unwrap());
+
+    let bytes = [1u8, 0, 2, 0, 3];
+    assert_eq!(
+        world.insert_dynamic_bytes(e, &registry, pod_id, &bytes[1..]),
+        Ok(())
+    );
+    assert_eq!(
+        *world.get::<&[u16; 2]>(e).unwrap(),
+        [u16::from_ne_bytes([0, 2]), u16::from_ne_bytes([0, 3])]
+    );
+    assert_eq!(
+        world.insert_dynamic_bytes(e, &registry, pod_id, &bytes),
+        Err(InsertDynamicError::InvalidBytes("[u16; 2]"))
+    );
+    assert_eq!(
+        world.insert_dynamic_bytes(e, &registry, arc_id, &[0; 8]),
+        Err(InsertDynamicError::InvalidBytes("Arc"))
+    );
+    assert_eq!(
+        world.insert_dynamic_bytes(e, &registry, TypeId::of::<u8>(), &[0]),
+        Err(InsertDynamicError::Unregistered(TypeId::of::<u8>()))
+    );
+
+    assert_eq!(world.remove_dynamic(e, arc_id), Ok(true));
+    assert_eq!(std::sync::Arc::strong_count(&shared), 1);
+    assert_eq!(world.remove_dynamic(e, arc_id), Ok(false));
+    assert!(world.satisfies::<(&bool, &[u16; 2])>(e).unwrap());
+
+    world.despawn(e).unwrap();
+    assert_eq!(world.remove_dynamic(e, pod_id), Err(NoSuchEntity));
+    assert_eq!(
+        world.insert_dynamic_bytes(e, &registry, pod_id, &[0; 4]),
+        Err(InsertDynamicError::NoSuchEntity)
+    );
+}
+
+#[test]
+fn script_world() {
+    use std::sync::Arc;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Marker;
+
+    let shared = Arc::new(());
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_clone::<Arc<()>>("Arc")
+        .register_clone::<Marker>("Marker")
+        .register_clone::<u32>("u32")
+        .register::<char>("char");
+
+    let mut world = World::new();
+    let a = world.spawn((1u32, Marker));
+    let b = world.spawn((2u32, 'x'));
+    let mut script = ScriptWorld::new(&mut world, &registry);
+
+    assert_eq!(script.set(a, "Arc", Box::new(shared.clone())), Ok(()));
+    assert_eq!(Arc::strong_count(&shared), 2);
+    let value = script.get(a, "Arc").unwrap();
+    assert_eq!(Arc::strong_count(&shared), 3);
+    drop(value);
+    assert_eq!(
+        script.set(a, "Arc", Box::new(shared.clone())),
+        Ok(()),
+        "replacing drops the old value"
+    );
+    assert_eq!(Arc::strong_count(&shared), 2);
+    assert_eq!(
+        script.set(b, "Arc", Box::new(7u32)),
+        Err(ScriptError::TypeMismatch("Arc"))
+    );
+
+    assert_eq!(script.set(b, "Marker", Box::new(Marker)), Ok(()));
+    let mut rows = script
+        .query(&["Marker", "u32"])
+        .unwrap()
+        .map(|(e, values)| (e, *values[1].downcast_ref::<u32>().unwrap()))
+        .collect::<Vec<_>>();
+    rows.sort_unstable();
+    assert_eq!(rows, [(a, 1), (b, 2)]);
+
+    assert_eq!(script.has(b, "char"), Ok(true));
+    assert_eq!(
+        script.get(b, "char").err(),
+        Some(ScriptError::NotCloneable("char"))
+    );
+    assert_eq!(
+        script.get(a, "Health").err(),
+        Some(ScriptError::UnknownComponent("Health".into()))
+    );
+    assert_eq!(
+        script.get(b, "Arc").err(),
+        Some(ScriptError::MissingComponent("Arc"))
+    );
+
+    assert_eq!(script.remove(a, "Arc"), Ok(true));
+    assert_eq!(script.remove(a, "Arc"), Ok(false));
+    assert_eq!(Arc::strong_count(&shared), 1);
+
+    script.world_mut().despawn(a).unwrap();
+    assert_eq!(
+        script.set(a, "Arc", Box::new(shared.clone())),
+        Err(ScriptError::NoSuchEntity)
+    );
+    assert_eq!(Arc::strong_count(&shared), 1);
+    assert_eq!(script.get(a, "u32").err(), Some(ScriptError::NoSuchEntity));
+}
+
+#[test]
+fn copy_archetypes_from() {
+    use std::sync::Arc;
+
+    let shared = Arc::new(());
+    let mut registry = ComponentRegistry::new();
+    unsafe {
+        registry.register_pod::<u32>("u32");
+    }
+    registry.register_clone::<Arc<()>>("Arc");
+
+    let mut source = World::new();
+    let a = source.spawn((1u32, shared.clone(), 'a'));
+    let b = source.spawn((2u32, shared.clone(), 'b'));
+    let c = source.spawn((3u32,));
+    let d = source.spawn((true,));
+    source.despawn(a).unwrap();
+    let e = source.spawn((4u32, shared.clone(), 'e'));
+
+    let mut target = World::new();
+    let existing = target.spawn((0u32,));
+    let copied = target.copy_archetypes_from(&source, |x| !x.has::<bool>(), &registry);
+    assert_eq!(copied.len(), 3);
+    assert_eq!(Arc::strong_count(&shared), 5);
+    assert!(!copied.iter().any(|&(from, _)| from == d));
+    for &(from, to) in &copied {
+        assert_ne!(to, existing);
+        assert_eq!(
+            *target.get::<&u32>(to).unwrap(),
+            *source.get::<&u32>(from).unwrap()
+        );
+        assert_eq!(
+            target.satisfies::<&Arc<()>>(to).unwrap(),
+            from == b || from == e
+        );
+        assert!(!target.satisfies::<&char>(to).unwrap());
+    }
+    assert!(copied.iter().any(|&(from, _)| from == c));
+    assert_eq!(target.len(), 4);
+
+    drop(target);
+    assert_eq!(Arc::strong_count(&shared), 3);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn par_fold_ordered() {
+    let mut world = World::new();
+    world.spawn_batch((0..500).map(|i| (1.0 / (i as f32 + 1.0),)));
+    world.spawn_batch((0..300).map(|i| (i as f32 * 1e6, true)));
+    world.spawn_batch((0..10).map(|i| (i,)));
+
+    let sum = |world: &World, executor: &dyn Executor| {
+        world.query::<&f32>().par_fold_ordered(
+            7,
+            executor,
+            || 0.0f32,
+            |acc, _, &x| acc + x,
+            |a, b| a + b,
+        )
+    };
+    let expected = sum(&world, &SerialExecutor);
+    for threads in 1..8 {
+        assert_eq!(
+            sum(&world, &ThreadExecutor::new(threads)).to_bits(),
+            expected.to_bits()
+        );
+    }
+
+    let count = world.query::<&mut i32>().par_fold_ordered(
+        3,
+        &ThreadExecutor::new(4),
+        || 0,
+        |acc, _, x| {
+            *x += 1;
+            acc + 1
+        },
+        |a, b| a + b,
+    );
+    assert_eq!(count, 10);
+    assert_eq!(
+        world
+            .query_mut::<&i32>()
+            .into_iter()
+            .map(|(_, &x)| x)
+            .sum::<i32>(),
+        55
+    );
+
+    let empty = world.query::<&char>().par_fold_ordered(
+        4,
+        &SerialExecutor,
+        || 42,
+        |acc, _, _| acc,
+        |a, _| a,
+    );
+    assert_eq!(empty, 42);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn par_for_each_batch() {
+    let mut world = World::new();
+    world.spawn_batch((0..100).map(|i| (i, true)));
+    world.spawn_batch((0..37).map(|i| (i,)));
+    world.spawn_batch((0..5).map(|i| (i as f32,)));
+
+    let gather = |world: &World, executor: &dyn Executor| {
+        let mut query = world.query::<&i32>();
+        assert_eq!(query.batch_count(8), 13 + 5);
+        let mut outputs = vec![(0, Vec::new()); query.batch_count(8)];
+        query.par_for_each_batch(8, executor, &mut outputs, |index, batch, out| {
+            out.0 = index;
+            out.1.extend(batch.map(|(e, &x)| (e, x * 2)));
+        });
+        outputs
+    };
+    let expected = gather(&world, &SerialExecutor);
+    assert!(expected.iter().enumerate().all(|(i, x)| x.0 == i));
+    assert_eq!(expected.iter().map(|x| x.1.len()).sum::<usize>(), 137);
+    for threads in 1..6 {
+        assert_eq!(gather(&world, &ThreadExecutor::new(threads)), expected);
+    }
+}
+
+#[test]
+#[should_panic(expected = "one output is required per batch")]
+fn par_for_each_batch_wrong_outputs() {
+    let mut world = World::new();
+    world.spawn_batch((0..10).map(|i| (i,)));
+    world
+        .query::<&i32>()
+        .par_for_each_batch(4, &SerialExecutor, &mut [(); 2], |_, _, _| {});
+}
+
+#[test]
+fn frame_alloc() {
+    let mut world = World::new();
+    let a = world.spawn((3, true));
+    let b = world.spawn((1,));
+    let c = world.spawn((2, true));
+    world.spawn((false,));
+
+    let mut query = world.query::<&i32>();
+    let all = query.collect_entities_in(world.frame_alloc());
+    let sorted = query.sorted_entities_in(world.frame_alloc(), |&x| x);
+    assert_eq!(all.len(), 3);
+    assert_eq!(sorted, [b, c, a]);
+    drop(query);
+    let flagged = world
+        .query::<&bool>()
+        .with::<&i32>()
+        .collect_entities_in(world.frame_alloc());
+    flagged.sort();
+    assert_eq!(flagged, [a, c]);
+
+    let used = world.frame_alloc().used();
+    assert!(used > 0);
+    let tick = world.advance_tick();
+    assert_eq!(world.frame_alloc().used(), 0);
+    world
+        .query::<&i32>()
+        .changed_since::<i32>(tick)
+        .collect_entities_in(world.frame_alloc());
+    *world.get::<&mut i32>(b).unwrap() = 0;
+    assert_eq!(
+        world
+            .query::<&i32>()
+            .changed_since::<i32>(tick)
+            .collect_entities_in(world.frame_alloc()),
+        [b]
+    );
+}
+
+#[test]
+fn bundle_id() {
+    let mut world = World::new();
+    let existing = world.spawn((0, false));
+    let id = world.register_bundle::<(i32, bool)>();
+    let archetypes = world.archetypes().len();
+    let same = world.register_bundle::<(bool, i32)>();
+    assert_eq!(world.archetypes().len(), archetypes);
+
+    let a = world.spawn_with_id(id, (1, true));
+    let b = world.spawn_with_id(same, (true, 2));
+    let reserved = world.reserve_entity();
+    world.insert_with_id(reserved, id, (3, true)).unwrap();
+    let tagged = world.spawn(("tag",));
+    world.insert_with_id(tagged, id, (4, true)).unwrap();
+
+    let mut values = world
+        .query_mut::<(&i32, &bool)>()
+        .into_iter()
+        .map(|(e, (&x, _))| (e, x))
+        .collect::<Vec<_>>();
+    values.sort();
+    assert_eq!(
+        values,
+        [(existing, 0), (a, 1), (b, 2), (reserved, 3), (tagged, 4)]
+    );
+    assert_eq!(*world.get::<&&str>(tagged).unwrap(), "tag");
+    assert_eq!(world.len(), 5);
+
+    world.despawn(a).unwrap();
+    assert_eq!(world.insert_with_id(a, id, (5, true)), Err(NoSuchEntity));
+}
+
+#[test]
+#[should_panic(expected = "bundle ID was registered with a different world")]
+fn bundle_id_wrong_world() {
+    let id = World::new().register_bundle::<(i32,)>();
+    World::new().spawn_with_id(id, (1,));
+}
+
+#[test]
+fn query_iter_reverse() {
+    let mut world = World::new();
+    let entities = (0..10)
+        .map(|i| {
+            if i % 3 == 0 {
+                world.spawn((i, true))
+            } else {
+                world.spawn((i,))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let forward = world
+        .query::<&i32>()
+        .iter()
+        .map(|(e, &i)| (e, i))
+        .collect::<Vec<_>>();
+    let mut backward = world
+        .query::<&i32>()
+        .iter()
+        .rev()
+        .map(|(e, &i)| (e, i))
+        .collect::<Vec<_>>();
+    backward.reverse();
+    assert_eq!(forward, backward);
+    assert_eq!(forward.len(), entities.len());
+
+    // Meeting in the middle of an archetype visits every entity exactly once
+    for split in 0..=forward.len() {
+        let mut query = world.query::<&i32>();
+        let mut iter = query.iter();
+        let mut front = iter
+            .by_ref()
+            .take(split)
+            .map(|(e, _)| e)
+            .collect::<Vec<_>>();
+        assert_eq!(iter.len(), forward.len() - split);
+        let back = iter.rev().map(|(e, _)| e).collect::<Vec<_>>();
+        front.extend(back.into_iter().rev());
+        assert!(front.iter().copied().eq(forward.iter().map(|&(e, _)| e)));
+    }
+
+    let mut query = PreparedQuery::<&i32>::default();
+    let prepared = query
+        .query(&world)
+        .iter()
+        .rev()
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert!(prepared.iter().rev().eq(forward.iter().map(|(e, _)| e)));
+
+    let batched = world
+        .query::<&i32>()
+        .iter_batched(2)
+        .rev()
+        .flat_map(|batch| batch.rev().map(|(e, _)| e))
+        .collect::<Vec<_>>();
+    assert!(batched.iter().rev().eq(forward.iter().map(|(e, _)| e)));
+
+    let mut query = world.query::<&i32>();
+    let mut batches = query.iter_batched(2);
+    let first = batches.next().unwrap().map(|(e, _)| e).collect::<Vec<_>>();
+    let rest = batches
+        .rev()
+        .flat_map(|batch| batch.rev().map(|(e, _)| e))
+        .collect::<Vec<_>>();
+    assert_eq!(first.len() + rest.len(), forward.len());
+    assert!(first
+        .iter()
+        .chain(rest.iter().rev())
+        .eq(forward.iter().map(|(e, _)| e)));
+}
+
+#[test]
+fn query_nth_and_at() {
+    let mut world = World::new();
+    for i in 0..20 {
+        match i % 3 {
+            0 => world.spawn((i, true)),
+            1 => world.spawn((i, "a")),
+            _ => world.spawn((i,)),
+        };
+    }
+    world.spawn(("no match",));
+
+    let all = world
+        .query::<&i32>()
+        .iter()
+        .map(|(e, &i)| (e, i))
+        .collect::<Vec<_>>();
+    for n in 0..=all.len() {
+        let mut query = world.query::<&i32>();
+        let mut iter = query.iter();
+        assert_eq!(iter.nth(n).map(|(e, &i)| (e, i)), all.get(n).copied());
+        assert_eq!(iter.len(), all.len().saturating_sub(n + 1));
+        assert_eq!(
+            iter.map(|(e, &i)| (e, i)).collect::<Vec<_>>(),
+            all[(n + 1).min(all.len())..]
+        );
+
+        let mut query = world.query::<&i32>();
+        let mut iter = query.iter();
+        let expected = all.len().checked_sub(n + 1).map(|i| all[i]);
+        assert_eq!(iter.nth_back(n).map(|(e, &i)| (e, i)), expected);
+
+        let mut query = PreparedQuery::<&i32>::new();
+        let mut query = query.query(&world);
+        assert_eq!(
+            query.iter().nth(n).map(|(e, &i)| (e, i)),
+            all.get(n).copied()
+        );
+
+        let mut query = world.query::<&i32>();
+        let view = query.view();
+        assert_eq!(view.at(n).map(|(e, &i)| (e, i)), all.get(n).copied());
+    }
+
+    let mut query = world.query_mut::<&mut i32>();
+    let mut view = query.view();
+    *view.at_mut(4).unwrap().1 = 100;
+    assert_eq!(*world.get::<&i32>(all[4].0).unwrap(), 100);
+}
+
+#[test]
+fn query_iter_split_at() {
+    let mut world = World::new();
+    for i in 0..20 {
+        match i % 3 {
+            0 => world.spawn((i, true)),
+            1 => world.spawn((i, "a")),
+            _ => world.spawn((i,)),
+        };
+    }
+
+    let all = world
+        .query::<&i32>()
+        .iter()
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    for index in 0..=all.len() + 1 {
+        let mut query = world.query::<&i32>();
+        let (left, right) = query.iter().split_at(index);
+        assert_eq!(left.len(), index.min(all.len()));
+        assert_eq!(right.len(), all.len() - left.len());
+        assert!(left.chain(right).map(|(e, _)| e).eq(all.iter().copied()));
+
+        // Splitting a partially consumed iterator, in reverse
+        let mut query = world.query::<&i32>();
+        let mut iter = query.iter();
+        iter.next();
+        iter.next_back();
+        let inner = &all[1..all.len() - 1];
+        let (left, right) = iter.split_at(index);
+        assert_eq!(left.len(), index.min(inner.len()));
+        let visited = right.rev().chain(left.rev()).map(|(e, _)| e);
+        assert!(visited.eq(inner.iter().rev().copied()));
+    }
+
+    let mut query = world.query::<&mut i32>();
+    let (left, right) = query.iter().split_half();
+    assert_eq!(left.len(), 10);
+    std::thread::scope(|scope| {
+        scope.spawn(|| right.for_each(|(_, x)| *x += 100));
+        left.for_each(|(_, x)| *x += 100);
+    });
+    drop(query);
+    assert!(world.query::<&i32>().iter().all(|(_, &x)| x >= 100));
+}
+
+#[test]
+#[cfg(feature = "audit-log")]
+fn audit_log() {
+    let mut world = World::new();
+    let a = world.reserve_entity();
+    let b = world.spawn((1,));
+    world.despawn(b).unwrap();
+    world.set_audit_tag(Some("restore"));
+    world.spawn_at(b, (2,));
+    world.set_audit_tag(None);
+    let batch = world.spawn_batch((0..3).map(|i| (i,))).collect::<Vec<_>>();
+    world.despawn(a).unwrap();
+
+    let ops = |id| {
+        world
+            .audit_history(id)
+            .iter()
+            .map(|event| (event.entity(), event.op(), event.tag()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        ops(a.id()),
+        [(a, AuditOp::Reserve, None), (a, AuditOp::Free, None)]
+    );
+    let reborn = ops(b.id());
+    assert_eq!(reborn.len(), 3);
+    assert_eq!(reborn[2], (b, AuditOp::AllocAt, Some("restore")));
+    for &e in &batch {
+        assert_eq!(ops(e.id())[0].1, AuditOp::Alloc);
+    }
+    assert_eq!(world.audit_history(b.id())[0].caller().file(), file!());
+
+    world.set_audit_capacity(0);
+    assert!(world.audit_history(a.id()).is_empty());
+}
+
+#[test]
+fn leak_detector() {
+    let mut world = World::new();
+    let mut detector = LeakDetector::new(3);
+    let empty = world.spawn(());
+    let marker = world.spawn((true,));
+    for i in 0..4 {
+        world.spawn((i,));
+        if i % 2 == 0 {
+            world.spawn(("flicker",));
+        } else {
+            let e = world
+                .query::<&&str>()
+                .iter()
+                .map(|(e, _)| e)
+                .next()
+                .unwrap();
+            world.despawn(e).unwrap();
+        }
+        world.query::<&i32>().iter().for_each(|_| {});
+        detector.sample(&world);
+    }
+
+    let report = detector.report(&world);
+    assert_eq!(report.empty_entities(), [empty]);
+    assert_eq!(report.unqueried_entities(), [marker]);
+    let growing = report.growing_components();
+    assert_eq!(growing.len(), 1);
+    assert_eq!(growing[0].ty.id(), core::any::TypeId::of::<i32>());
+    assert_eq!((growing[0].first, growing[0].last), (2, 4));
+
+    world.query::<&bool>().iter().for_each(|_| {});
+    assert!(detector.report(&world).unqueried_entities().is_empty());
+    world.reset_query_tracking();
+    let report = detector.report(&world);
+    assert_eq!(report.unqueried_entities().len(), 5);
+    assert!(!report.is_empty());
+}
+
+#[test]
+fn compact_meta_monotonic() {
+    let mut world = World::builder().monotonic_ids(true).build();
+    let a = world.spawn(());
+    let b = world.spawn(());
+    world.despawn(b).unwrap();
+    assert_eq!(world.compact_meta(), 0);
+    let c = world.spawn(());
+    assert_ne!(c.id(), b.id());
+    assert!(!world.contains(b));
+    assert!(world.contains(a));
+}
+
+#[test]
+fn compact_meta() {
+    let mut world = World::builder().id_reuse_delay(1).build();
+    let entities = (0..10).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    world.despawn(entities[2]).unwrap();
+    world.advance_id_reuse();
+    for &e in &entities[5..] {
+        world.despawn(e).unwrap();
+    }
+    world.despawn(entities[4]).unwrap();
+    let reserved = world.reserve_entity();
+
+    // The reserved entity is flushed into the lowest free ID, leaving 4..10 dead
+    assert_eq!(reserved.id(), 2);
+    assert_eq!(world.compact_meta(), 6);
+    assert_eq!(world.compact_meta(), 0);
+    assert!(world.contains(reserved));
+    assert_eq!(world.freelist().len(), 0);
+    assert_eq!(world.quarantined_ids(), 0);
+    for &e in &entities[4..] {
+        assert!(!world.contains(e));
+    }
+
+    world.advance_id_reuse();
+    let next = (0..3).map(|_| world.spawn(()).id()).collect::<Vec<_>>();
+    assert_eq!(next, [4, 5, 6]);
+    assert_eq!(world.len(), 7);
+}
+
+#[test]
+fn generation_wrap_stats() {
+    let mut world = World::builder().entity_bits(EntityBits::new(8, 2)).build();
+    let wrapped = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let log = wrapped.clone();
+    world.set_generation_wrap_hook(move |entity, wraps| log.lock().unwrap().push((entity, wraps)));
+
+    let keep = world.spawn(());
+    let churn = world.spawn(());
+    world.despawn(churn).unwrap();
+    for _ in 0..6 {
+        let e = world.spawn(());
+        assert_eq!(e.id(), churn.id());
+        world.despawn(e).unwrap();
+    }
+    assert_eq!(world.generation_wraps(churn.id()), 2);
+    assert_eq!(world.generation_wraps(keep.id()), 0);
+    assert_eq!(
+        world.generation_wrap_stats(),
+        GenerationWrapStats {
+            wrapped_ids: 1,
+            total_wraps: 2,
+            max_wraps: 2,
+        }
+    );
+    let wrapped = wrapped.lock().unwrap().clone();
+    assert_eq!(wrapped.len(), 2);
+    assert_eq!(wrapped[1].0.id(), churn.id());
+    assert_eq!(wrapped[1].1, 2);
+
+    world.compact_meta();
+    assert_eq!(
+        world.generation_wrap_stats(),
+        GenerationWrapStats::default()
+    );
+}
+
+#[test]
+fn try_spawn_at_capacity() {
+    let mut world = World::builder().entity_bits(EntityBits::new(2, 30)).build();
+    let a = world.try_spawn((1,)).unwrap();
+    assert_eq!(
+        world.try_spawn_batch((0..4).map(|i| (i,))).err(),
+        Some(TrySpawnError::Alloc(AllocError::TooManyEntities))
+    );
+    assert_eq!(world.len(), 1);
+    let reserved = world.try_reserve_entities(2).unwrap().collect::<Vec<_>>();
+    assert_eq!(reserved.len(), 2);
+    assert_eq!(
+        world.try_reserve_entities(2).err(),
+        Some(AllocError::TooManyEntities)
+    );
+    let b = world.try_reserve_entity().unwrap();
+    assert_eq!(world.try_reserve_entity(), Err(AllocError::TooManyEntities));
+    world.despawn(a).unwrap();
+    assert_eq!(world.try_spawn(()).map(|e| e.id()), Ok(a.id()));
+    assert_eq!(
+        world.try_spawn(()),
+        Err(TrySpawnError::Alloc(AllocError::TooManyEntities))
+    );
+    assert_eq!(world.len(), 4);
+
+    world.try_insert(b, (true,)).unwrap();
+    assert!(*world.get::<&bool>(b).unwrap());
+    world.despawn(b).unwrap();
+    assert_eq!(
+        world.try_insert(b, (true,)),
+        Err(TryInsertError::NoSuchEntity)
+    );
+}
+
+#[test]
+fn try_spawn_batch_out_of_memory() {
+    struct Huge(#[allow(dead_code)] [u8; 1 << 30]);
+
+    /// Claims to yield many entities without yielding any
+    struct Claim;
+
+    impl Iterator for Claim {
+        type Item = (Huge,);
+
+        fn next(&mut self) -> Option<(Huge,)> {
+            None
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (1 << 20, Some(1 << 20))
+        }
+    }
+
+    let mut world = World::new();
+    match world.try_spawn_batch(Claim) {
+        Err(TrySpawnError::Alloc(AllocError::OutOfMemory(layout))) => {
+            assert_eq!(layout.size(), 1 << 50)
+        }
+        Err(e) => panic!("unexpected error {e}"),
+        Ok(_) => panic!("allocated a petabyte"),
+    }
+    assert_eq!(world.len(), 0);
+    let e = world.spawn((1,));
+    assert!(world.contains(e));
+}
+
+#[test]
+fn try_column_batch_out_of_memory() {
+    struct Huge(#[allow(dead_code)] [u8; 1 << 30]);
+
+    let mut ty = ColumnBatchType::new();
+    ty.add::<u32>().add::<Huge>();
+    let err = ty.try_into_batch(1 << 20).err().unwrap();
+    assert_eq!(err.component_type(), Some(TypeInfo::of::<Huge>()));
+    assert_eq!(err.size(), 1 << 20);
+    match *err.error() {
+        AllocError::OutOfMemory(layout) => assert_eq!(layout.size(), 1 << 50),
+        ref e => panic!("unexpected error {e}"),
+    }
+
+    let mut ty = ColumnBatchType::new();
+    ty.add::<u32>();
+    let mut builder = ColumnBatchBuilder::try_new(ty, 2).unwrap();
+    let mut writer = builder.writer::<u32>().unwrap();
+    writer.push(1).unwrap();
+    writer.push(2).unwrap();
+    let mut world = World::new();
+    world.spawn_column_batch(builder.build().unwrap());
+    assert_eq!(world.query_mut::<&u32>().into_iter().count(), 2);
+}
+
+#[test]
+#[cfg(not(hecs_single_threaded))]
+fn despawn_queue() {
+    let mut world = World::new();
+    let entities = (0..10).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    std::thread::scope(|s| {
+        let world = &world;
+        for chunk in entities.chunks(3).rev() {
+            s.spawn(move || world.despawn_queue().extend(chunk.iter().rev().copied()));
+        }
+    });
+    world.despawn_queue().push(entities[0]);
+    assert_eq!(world.despawn_queue().len(), 11);
+    assert_eq!(world.len(), 10);
+    while !world.maintain(1) {}
+    assert!(world.is_empty());
+    assert!(world.despawn_queue().is_empty());
+
+    // IDs are freed in canonical order, whatever order they were queued in
+    let mut expected = World::new();
+    for i in 0..10 {
+        expected.spawn((i,));
+    }
+    for &e in &entities {
+        expected.despawn(e).unwrap();
+    }
+    for _ in 0..10 {
+        assert_eq!(world.spawn(()), expected.spawn(()));
+    }
+
+    world.despawn_queue().push(entities[0]);
+    assert_eq!(world.despawn_queued(), 0);
+}
+
+#[test]
+fn query_in_archetypes() {
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let b = world.spawn((2, true));
+    let c = world.spawn((3, "abc"));
+    let n = world.archetypes().len();
+    let mut first = world.query::<&mut i32>().in_archetypes(0..n - 1);
+    let mut last = world.query::<&mut i32>().in_archetypes(n - 1..n + 10);
+    {
+        // Mutable borrows of the same component type in disjoint archetypes coexist
+        let first = first.iter();
+        let mut last = last.iter();
+        assert_eq!(first.len(), 2);
+        assert_eq!(last.len(), 1);
+        let (e, x) = last.next().unwrap();
+        assert_eq!(e, c);
+        *x *= 10;
+        first.for_each(|(_, x)| *x += 1);
+    }
+    assert!(first.view().get_mut(c).is_none());
+    assert_eq!(*first.view().get_mut(b).unwrap(), 3);
+    assert_eq!(last.batch_count(1), 1);
+    drop((first, last));
+    let values = world
+        .query::<&i32>()
+        .iter()
+        .map(|(e, &x)| (e, x))
+        .collect::<Vec<_>>();
+    assert_eq!(values, [(a, 2), (b, 3), (c, 30)]);
+    let empty = world
+        .query::<&i32>()
+        .in_archetypes(n + 5..n + 10)
+        .iter()
+        .count();
+    assert_eq!(empty, 0);
+}
+
+#[test]
+#[should_panic(expected = "query violates a unique borrow: `(&i32, &mut i32)`")]
+fn illegal_query_borrow_names_query() {
+    let mut world = World::new();
+    world.query_mut::<(&i32, &mut i32)>();
+}
+
+#[test]
+fn raw_column_pointers() {
+    let mut world = World::new();
+    let a = world.spawn((1u32, true));
+    let b = world.spawn((2u32, false));
+    let tick = world.advance_tick();
+
+    let archetype = world.archetypes().find(|x| x.has::<u32>()).unwrap();
+    assert!(archetype.column_ptr(TypeId::of::<i64>()).is_none());
+    let ids = unsafe {
+        std::slice::from_raw_parts(archetype.ids_ptr().as_ptr(), archetype.len() as usize)
+    };
+    assert_eq!(ids, archetype.ids());
+    let column = archetype.column_ptr_of::<u32>().unwrap();
+    assert_eq!(
+        column.cast::<u8>(),
+        archetype.column_ptr(TypeId::of::<u32>()).unwrap()
+    );
+    for i in 0..archetype.len() as usize {
+        unsafe {
+            *column.as_ptr().add(i) *= 10;
+        }
+    }
+    assert_eq!(
+        world
+            .query::<&u32>()
+            .changed_since::<u32>(tick)
+            .iter()
+            .count(),
+        0
+    );
+    assert!(archetype.mark_column_changed(TypeId::of::<u32>()));
+    assert!(!archetype.mark_column_changed(TypeId::of::<i64>()));
+    assert_eq!(
+        world
+            .query::<&u32>()
+            .changed_since::<u32>(tick)
+            .iter()
+            .count(),
+        2
+    );
+    assert_eq!(*world.get::<&u32>(a).unwrap(), 10);
+    assert_eq!(*world.get::<&u32>(b).unwrap(), 20);
+}
+
+#[test]
+fn pinned_addresses() {
+    let mut world = World::new();
+    let entities = (0..10)
+        .map(|i| world.spawn((Pinned::new(i), i)))
+        .collect::<Vec<_>>();
+    let ptrs = entities
+        .iter()
+        .map(|&e| world.get::<&Pinned<i32>>(e).unwrap().as_ptr())
+        .collect::<Vec<_>>();
+    world.despawn(entities[0]).unwrap();
+    world.insert_one(entities[1], true).unwrap();
+    world.remove_one::<i32>(entities[2]).unwrap();
+    world.spawn_batch((10..1000).map(|i| (Pinned::new(i), i)));
+    for (&e, &ptr) in entities.iter().zip(&ptrs).skip(1) {
+        let mut pinned = world.get::<&mut Pinned<i32>>(e).unwrap();
+        assert_eq!(pinned.as_mut_ptr().cast_const(), ptr);
+        assert_eq!(unsafe { *ptr }, **pinned);
+    }
+}
+
+#[test]
+fn value_index() {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    struct Team(u8);
+
+    let mut world = World::new();
+    world.register_index::<Team>(IndexKind::Multi);
+    let a = world.spawn((Team(1),));
+    let b = world.spawn((Team(1), true));
+    let c = world.spawn((Team(2), "abc"));
+    assert_eq!(world.lookup_all(&Team(1)), [a, b]);
+    assert_eq!(world.lookup(&Team(1)), Some(a));
+    assert_eq!(world.lookup(&Team(3)), None);
+
+    // Mutation within the same tick is observed
+    for (_, team) in world.query_mut::<&mut Team>() {
+        team.0 += 1;
+    }
+    assert_eq!(world.lookup_all(&Team(1)), []);
+    assert_eq!(world.lookup_all(&Team(2)), [a, b]);
+    assert_eq!(world.lookup_all(&Team(3)), [c]);
+
+    world.remove_one::<Team>(a).unwrap();
+    world.despawn(c).unwrap();
+    let d = world.spawn((Team(3),));
+    assert_eq!(d.id(), c.id());
+    world.insert_one(b, 1.5f32).unwrap();
+    assert_eq!(world.lookup_all(&Team(2)), [b]);
+    assert_eq!(world.lookup_all(&Team(3)), [d]);
+
+    // Removing archetypes reorders them
+    world.despawn(d).unwrap();
+    while !world.maintain(100) {}
+    world.insert_one(a, Team(3)).unwrap();
+    assert_eq!(world.lookup_all(&Team(3)), [a]);
+    assert_eq!(world.lookup_all(&Team(2)), [b]);
+
+    world.clear();
+    assert_eq!(world.lookup(&Team(2)), None);
+}
+
+#[test]
+#[should_panic(expected = "entities share a key in the unique index")]
+fn value_index_unique() {
+    let mut world = World::new();
+    world.register_index::<u64>(IndexKind::Unique);
+    world.spawn((1u64,));
+    world.spawn((1u64, true));
+    world.lookup(&1u64);
+}
+
+#[test]
+#[should_panic(expected = "no index of")]
+fn value_index_unregistered() {
+    World::new().lookup(&1u64);
+}
+
+#[test]
+fn singleton() {
+    struct Camera(u32);
+
+    let mut world = World::new();
+    world.register_singleton::<Camera>(SingletonPolicy::Move);
+    assert!(world.singleton::<Camera>().is_none());
+    let a = world.spawn((Camera(1), true));
+    let b = world.spawn(("b",));
+    world.insert_one(b, Camera(2)).unwrap();
+    assert!(world.contains(a));
+    assert!(!world.satisfies::<&Camera>(a).unwrap());
+    assert!(world.satisfies::<&bool>(a).unwrap());
+    {
+        let (holder, camera) = world.singleton::<Camera>().unwrap();
+        assert_eq!((holder, camera.0), (b, 2));
+    }
+    // Re-inserting on the holder replaces the value
+    world.insert_one(b, Camera(3)).unwrap();
+    let (holder, camera) = world.singleton_mut::<Camera>().unwrap();
+    assert_eq!((holder, camera.0), (b, 3));
+    camera.0 = 4;
+    assert_eq!(world.get::<&Camera>(b).unwrap().0, 4);
+
+    world.register_singleton::<u8>(SingletonPolicy::Reject);
+    let c = world.spawn((1u8,));
+    let d = world.spawn(());
+    match world.try_insert(d, (2u8,)) {
+        Err(TryInsertError::SingletonHeld(holder)) => assert_eq!(holder, c),
+        x => panic!("unexpected result {:?}", x),
+    }
+    assert!(!world.satisfies::<&u8>(d).unwrap());
+    world.despawn(c).unwrap();
+    world.insert_one(d, 2u8).unwrap();
+    assert_eq!(world.singleton::<u8>().unwrap().0, d);
+}
+
+#[test]
+#[should_panic(expected = "is already held by")]
+fn singleton_reject() {
+    let mut world = World::new();
+    world.register_singleton::<u8>(SingletonPolicy::Reject);
+    world.spawn((1u8,));
+    world.spawn((2u8,));
+}
+
+#[test]
+fn singleton_try_spawn() {
+    let mut world = World::new();
+    world.register_singleton::<u8>(SingletonPolicy::Reject);
+    let a = world.try_spawn((1u8,)).unwrap();
+    assert_eq!(
+        world.try_spawn((2u8, true)),
+        Err(TrySpawnError::SingletonHeld(a))
+    );
+    assert_eq!(
+        world.try_spawn_batch([(3u8,)]).err(),
+        Some(TrySpawnError::SingletonBatch(TypeId::of::<u8>()))
+    );
+    assert_eq!(world.len(), 1);
+    assert_eq!(*world.singleton::<u8>().unwrap().1, 1);
+}
+
+#[test]
+#[should_panic(expected = "batch contains singleton component")]
+fn singleton_spawn_batch() {
+    struct Camera;
+
+    let mut world = World::new();
+    world.register_singleton::<Camera>(SingletonPolicy::Reject);
+    world.spawn_batch((0..3).map(|_| (Camera,)));
+}
+
+#[test]
+fn singleton_column_batch() {
+    fn batch(values: &[u8]) -> ColumnBatch {
+        let mut ty = ColumnBatchType::new();
+        ty.add::<u8>();
+        let mut batch = ty.into_batch(values.len() as u32);
+        let mut writer = batch.writer::<u8>().unwrap();
+        for &x in values {
+            assert!(writer.push(x).is_ok());
+        }
+        batch.build().unwrap()
+    }
+
+    let mut world = World::new();
+    world.register_singleton::<u8>(SingletonPolicy::Move);
+    let a = world.spawn((1u8, true));
+    let b = world.spawn_column_batch(batch(&[2])).next().unwrap();
+    assert!(!world.satisfies::<&u8>(a).unwrap());
+    assert_eq!(world.singleton::<u8>().unwrap().0, b);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        world.spawn_column_batch(batch(&[3, 4]));
+    }));
+    assert!(result.is_err());
+    assert_eq!(world.query_mut::<&u8>().into_iter().count(), 1);
+}
+
+#[test]
+fn required_components() {
+    #[derive(Default, Debug, PartialEq)]
+    struct Transform(i32);
+    #[derive(Debug, PartialEq)]
+    struct Layer(u8);
+    struct Sprite;
+
+    let mut registry = ComponentRegistry::new();
+    registry
+        .require::<Sprite, Transform>()
+        .require_with::<Transform, Layer>(|| Layer(3));
+    let mut world = World::new();
+    world.set_requirements(&registry);
+
+    // Requirements apply transitively, and don't replace components already present
+    let a = world.spawn((Sprite,));
+    assert_eq!(*world.get::<&Transform>(a).unwrap(), Transform(0));
+    assert_eq!(*world.get::<&Layer>(a).unwrap(), Layer(3));
+    let b = world.spawn((Sprite, Transform(7)));
+    assert_eq!(*world.get::<&Transform>(b).unwrap(), Transform(7));
+
+    let c = world.spawn(());
+    world.insert_one(c, Sprite).unwrap();
+    assert!(world.satisfies::<(&Transform, &Layer)>(c).unwrap());
+
+    let mut cmd = CommandBuffer::new();
+    cmd.spawn((Sprite,));
+    cmd.run_on(&mut world);
+    assert_eq!(
+        world
+            .query_mut::<&Sprite>()
+            .without::<&Layer>()
+            .into_iter()
+            .count(),
+        0
+    );
+
+    let mut ty = ColumnBatchType::new();
+    ty.add::<Sprite>();
+    let mut batch = ty.into_batch(2);
+    let mut sprites = batch.writer::<Sprite>().unwrap();
+    assert!(sprites.push(Sprite).is_ok() && sprites.push(Sprite).is_ok());
+    let batched = world
+        .spawn_column_batch(batch.build().unwrap())
+        .collect::<Vec<_>>();
+    for entity in batched {
+        assert!(world.satisfies::<(&Transform, &Layer)>(entity).unwrap());
+    }
+
+    // Removal is allowed, and batches spawned from iterators are fixed up on demand
+    world.remove_one::<Layer>(a).unwrap();
+    let d = world.spawn_batch([(Sprite,)]).next().unwrap();
+    assert!(!world.satisfies::<&Transform>(d).unwrap());
+    world.fulfil_requirements();
+    assert!(world.satisfies::<(&Transform, &Layer)>(a).unwrap());
+    assert!(world.satisfies::<(&Transform, &Layer)>(d).unwrap());
+}
+
+#[test]
+fn exclusive_components() {
+    struct Alive;
+    struct Dead;
+    struct Idle;
+    struct Walking;
+
+    let mut world = World::new();
+    world.register_exclusive::<(Alive, Dead)>(ExclusionPolicy::Replace);
+    world.register_exclusive::<(Idle, Walking)>(ExclusionPolicy::Reject);
+
+    let e = world.spawn((Alive, Idle, 1));
+    world.insert_one(e, Dead).unwrap();
+    assert!(!world.satisfies::<&Alive>(e).unwrap());
+    assert!(world.satisfies::<(&Dead, &Idle, &i32)>(e).unwrap());
+
+    assert_eq!(
+        world.try_insert(e, (Walking,)),
+        Err(TryInsertError::Excluded(TypeId::of::<Idle>()))
+    );
+    assert!(!world.satisfies::<&Walking>(e).unwrap());
+    // Exchanging out the conflicting component is allowed
+    world.exchange_one::<Idle, _>(e, Walking).unwrap();
+    assert!(world.satisfies::<(&Walking, &Dead)>(e).unwrap());
+}
+
+#[test]
+#[should_panic(expected = "mutually exclusive")]
+fn exclusive_components_reject() {
+    struct Idle;
+    struct Walking;
+
+    let mut world = World::new();
+    world.register_exclusive::<(Idle, Walking)>(ExclusionPolicy::Reject);
+    let e = world.spawn((Idle,));
+    world.insert_one(e, Walking).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "bundle contains several")]
+fn exclusive_components_in_bundle() {
+    struct Alive;
+    struct Dead;
+
+    let mut world = World::new();
+    world.register_exclusive::<(Alive, Dead)>(ExclusionPolicy::Replace);
+    world.spawn((Alive, Dead));
+}
+
+#[test]
+fn exclusive_components_try() {
+    struct Alive;
+    struct Dead;
+    struct Idle;
+    struct Walking;
+
+    let mut world = World::new();
+    world.register_exclusive::<(Alive, Dead)>(ExclusionPolicy::Replace);
+    world.register_exclusive::<(Idle, Walking)>(ExclusionPolicy::Reject);
+    let excluded = |x: Option<TrySpawnError>| match x {
+        Some(TrySpawnError::Excluded(id)) => {
+            assert!(id == TypeId::of::<Alive>() || id == TypeId::of::<Dead>())
+        }
+        x => panic!("unexpected result {:?}", x),
+    };
+    excluded(world.try_spawn((Alive, Dead)).err());
+    excluded(world.try_spawn_batch([(Alive, 1, Dead)]).err());
+    assert_eq!(world.len(), 0);
+
+    let e = world.try_spawn((Idle,)).unwrap();
+    match world.try_insert(e, (Alive, Dead)) {
+        Err(TryInsertError::Excluded(id)) => {
+            assert!(id == TypeId::of::<Alive>() || id == TypeId::of::<Dead>())
+        }
+        x => panic!("unexpected result {:?}", x),
+    }
+    assert_eq!(
+        world.try_insert(e, (Walking,)),
+        Err(TryInsertError::Excluded(TypeId::of::<Idle>()))
+    );
+    assert!(world.satisfies::<&Idle>(e).unwrap());
+    assert!(!world.satisfies::<Or<&Alive, &Walking>>(e).unwrap());
+}
+
+#[test]
+#[should_panic(expected = "bundle contains several")]
+fn exclusive_components_in_batch() {
+    struct Alive;
+    struct Dead;
+
+    let mut world = World::new();
+    world.register_exclusive::<(Alive, Dead)>(ExclusionPolicy::Reject);
+    world.spawn_batch((0..3).map(|_| (Alive, Dead)));
+}
+
+#[test]
+#[should_panic(expected = "bundle contains several")]
+fn exclusive_components_in_column_batch() {
+    struct Alive;
+    struct Dead;
+
+    let mut world = World::new();
+    world.register_exclusive::<(Alive, Dead)>(ExclusionPolicy::Replace);
+    let mut ty = ColumnBatchType::new();
+    ty.add::<Alive>().add::<Dead>();
+    let mut batch = ty.into_batch(1);
+    assert!(batch.writer::<Alive>().unwrap().push(Alive).is_ok());
+    assert!(batch.writer::<Dead>().unwrap().push(Dead).is_ok());
+    world.spawn_column_batch(batch.build().unwrap());
+}
+
+#[test]
+fn validators() {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    struct Sprite;
+    #[derive(Debug, PartialEq)]
+    struct Transform(i32);
+    struct Stale;
+
+    let calls = Arc::new(AtomicU32::new(0));
+    let mut world = World::new();
+    world.add_validator(ValidationMode::Always, |t| {
+        if t.adds::<Sprite>() && !t.has::<Transform>() {
+            t.add(Transform(0));
+        }
+        if t.removed().contains(&TypeId::of::<Transform>()) && t.has::<Sprite>() {
+            return Err(ValidationError::new("sprites need transforms"));
+        }
+        Ok(())
+    });
+    world.add_validator(ValidationMode::Always, |t| {
+        if t.has::<Stale>() {
+            t.remove::<Stale>();
+        }
+        Ok(())
+    });
+    let counter = calls.clone();
+    world.add_validator(ValidationMode::Debug, move |_| {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    });
+
+    let a = world.spawn((Sprite, Stale));
+    assert_eq!(*world.get::<&Transform>(a).unwrap(), Transform(0));
+    assert!(!world.satisfies::<&Stale>(a).unwrap());
+
+    let b = world.spawn((Transform(5),));
+    world.insert_one(b, Sprite).unwrap();
+    assert_eq!(*world.get::<&Transform>(b).unwrap(), Transform(5));
+
+    world.try_insert(b, (1,)).unwrap();
+    let c = world.spawn((Transform(1),));
+    world.remove_one::<Transform>(c).unwrap();
+    let expected = if cfg!(debug_assertions) { 6 } else { 0 };
+    assert_eq!(calls.load(Ordering::Relaxed), expected);
+}
+
+#[test]
+#[should_panic(expected = "validation failed: sprites need transforms")]
+fn validator_rejects() {
+    struct Sprite;
+    struct Transform;
+
+    let mut world = World::new();
+    world.add_validator(ValidationMode::Always, |t| {
+        if t.has::<Sprite>() && !t.has::<Transform>() {
+            return Err(ValidationError::new("sprites need transforms"));
+        }
+        Ok(())
+    });
+    let e = world.spawn((Sprite, Transform));
+    world.remove_one::<Transform>(e).unwrap();
+}
+
+#[test]
+fn plugins() {
+    #[derive(Clone, Default)]
+    struct Counter {
+        spawned: u32,
+        maintained: u32,
+    }
+
+    impl Plugin for Counter {
+        fn build(&mut self, world: &mut World) {
+            world.add_validator(ValidationMode::Always, |t| {
+                if t.entity().is_none() {
+                    t.add(true);
+                }
+                Ok(())
+            });
+        }
+
+        fn maintain(&mut self, world: &mut World) {
+            assert!(world.plugin::<Counter>().is_none());
+            self.maintained += 1;
+            self.spawned = world.query_mut::<&bool>().into_iter().count() as u32;
+        }
+
+        fn snapshot(&self) -> Option<Self> {
+            Some(self.clone())
+        }
+
+        fn restore(&mut self, snapshot: &Self) {
+            *self = snapshot.clone();
+        }
+    }
+
+    struct Stateless;
+
+    impl Plugin for Stateless {
+        fn build(&mut self, _: &mut World) {}
+    }
+
+    let registry = ComponentRegistry::new();
+    let mut world = World::new();
+    world.add_plugin(Counter::default());
+    world.add_plugin(Stateless);
+    let e = world.spawn(());
+    assert!(world.satisfies::<&bool>(e).unwrap());
+    world.maintain(0);
+    let snapshot = world.snapshot(&registry);
+
+    world.spawn(());
+    world.maintain(0);
+    world.plugin_mut::<Counter>().unwrap().spawned = 100;
+    assert_eq!(world.plugin::<Counter>().unwrap().maintained, 2);
+
+    world.restore_exact(&snapshot);
+    let counter = world.plugin::<Counter>().unwrap();
+    assert_eq!((counter.spawned, counter.maintained), (1, 1));
+    assert!(world.plugin::<Stateless>().is_some());
+}
+
+#[test]
+#[should_panic(expected = "already added")]
+fn plugin_added_twice() {
+    struct Noop;
+
+    impl Plugin for Noop {
+        fn build(&mut self, _: &mut World) {}
+    }
+
+    let mut world = World::new();
+    world.add_plugin(Noop);
+    world.add_plugin(Noop);
+}
+
+#[test]
+fn serialize_changes_since() {
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_clone::<i32>("i32")
+        .register_eq::<i32>("i32")
+        .register_clone::<String>("String")
+        .register_eq::<String>("String");
+
+    let mut world = World::new();
+    let a = world.spawn((1, String::from("a")));
+    let b = world.spawn((2,));
+    let c = world.spawn((3, true));
+    let mut mirror = World::new();
+    let patch = world.serialize_changes_since(0, &registry);
+    assert_eq!(patch.len(), 3);
+    mirror.apply_patch(&patch);
+    // Unregistered components aren't replicated
+    assert!(!mirror.satisfies::<&bool>(c).unwrap());
+    mirror.insert_one(c, true).unwrap();
+    assert!(WorldDiff::between(&world, &mirror, &registry).is_empty());
+
+    let since = world.advance_tick();
+    assert!(world.serialize_changes_since(since, &registry).is_empty());
+    *world.get::<&mut String>(a).unwrap() = String::from("A");
+    world.remove_one::<i32>(a).unwrap();
+    world.insert_one(b, String::from("b")).unwrap();
+    world.despawn(c).unwrap();
+    let d = world.spawn((4,));
+    assert_eq!(d.id(), c.id());
+    let e = world.spawn((5,));
+    world.despawn(e).unwrap();
+
+    let patch = world.serialize_changes_since(since, &registry);
+    assert_eq!(patch.since(), since);
+    assert_eq!(patch.tick(), since);
+    assert_eq!(patch.despawned(), [e.id()]);
+    mirror.apply_patch(&patch);
+    assert!(WorldDiff::between(&world, &mirror, &registry).is_empty());
+    assert!(!mirror.contains(c));
+    assert_eq!(*mirror.get::<&i32>(d).unwrap(), 4);
+
+    let since = world.advance_tick();
+    *world.get::<&mut i32>(b).unwrap() = 20;
+    let patch = world.serialize_changes_since(since, &registry);
+    // Only the modified column of the modified archetype is captured
+    assert_eq!(patch.len(), 1);
+    mirror.apply_patch(&patch);
+    assert_eq!(*mirror.get::<&i32>(b).unwrap(), 20);
+    assert!(WorldDiff::between(&world, &mirror, &registry).is_empty());
+}
+
+#[test]
+fn find_entities_from_ids() {
+    let mut world = World::new();
+    let entities = (0..10).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    for &entity in entities.iter().step_by(3) {
+        world.despawn(entity).unwrap();
+    }
+    let reserved = world.reserve_entity();
+    let ids = entities
+        .iter()
+        .map(|x| x.id())
+        .chain([reserved.id(), u32::MAX])
+        .collect::<Vec<_>>();
+    let mut out = vec![Some(entities[0])];
+    world.find_entities_from_ids(&ids, &mut out);
+    assert_eq!(out.len(), 12);
+    for (i, &entity) in entities.iter().enumerate() {
+        assert_eq!(out[i], (i % 3 != 0).then_some(entity));
+    }
+    assert_eq!(out[10..], [None, None]);
+    world.flush();
+    world.find_entities_from_ids(&[reserved.id()], &mut out);
+    assert_eq!(out, [Some(reserved)]);
+}
+
+#[test]
+fn located_iteration() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2,));
+    let c = world.spawn((3,));
+    let located = world
+        .query::<&i32>()
+        .iter_located()
+        .map(|(e, loc, &i)| (e, loc, i))
+        .collect::<Vec<_>>();
+    assert_eq!(located.len(), 3);
+    for &(e, loc, i) in &located {
+        assert_eq!(loc.entity(), e);
+        assert_eq!(world.locate(e).unwrap(), loc);
+        let entity = world.entity_at(loc).unwrap();
+        assert_eq!(entity.entity(), e);
+        assert_eq!(*entity.get::<&i32>().unwrap(), i);
+    }
+    let loc = |e| located.iter().find(|x| x.0 == e).unwrap().1;
+
+    // Despawning `b` moves `c` into its row
+    world.despawn(b).unwrap();
+    assert!(world.entity_at(loc(b)).is_none());
+    assert!(world.entity_at(loc(c)).is_none());
+    assert!(world.entity_at(loc(a)).is_some());
+
+    // A new entity reusing a despawned entity's ID and row doesn't revive its location
+    let x = world.spawn((7u8,));
+    let stale = world.locate(x).unwrap();
+    world.despawn(x).unwrap();
+    let y = world.spawn((8u8,));
+    assert_eq!(y.id(), x.id());
+    assert_eq!(world.locate(y).unwrap().row(), stale.row());
+    assert!(world.entity_at(stale).is_none());
+}
+
+#[test]
+fn ref_try_map() {
+    let mut world = World::new();
+    let e = world.spawn((Some(3),));
+
+    let opt = world.get::<&Option<i32>>(e).unwrap();
+    let value = Ref::try_map(opt, |x| x.as_ref()).ok().unwrap();
+    assert_eq!(*value, 3);
+    drop(value);
+
+    let opt = world.get::<&mut Option<i32>>(e).unwrap();
+    let mut value = RefMut::try_map(opt, |x| x.as_mut()).ok().unwrap();
+    *value = 4;
+    drop(value);
+
+    *world.get::<&mut Option<i32>>(e).unwrap() = None;
+    let opt = world.get::<&mut Option<i32>>(e).unwrap();
+    let opt = RefMut::try_map(opt, |x| x.as_mut()).err().unwrap();
+    assert!(opt.is_none());
+    drop(opt);
+    assert_eq!(*world.get::<&Option<i32>>(e).unwrap(), None);
+}
+
+#[test]
+fn budgets() {
+    struct Marker;
+
+    fn reserved(world: &World) -> usize {
+        world
+            .archetypes()
+            .map(|x| {
+                let row = x
+                    .component_types()
+                    .map(|ty| x.column_layout(ty).map_or(0, |layout| layout.size()));
+                x.capacity() as usize * row.sum::<usize>()
+            })
+            .sum()
+    }
+
+    let mut world = World::new();
+    world.set_budget(Budget {
+        entities: Some(66),
+        component_bytes: Some(600),
+        archetypes: Some(3),
+    });
+    // Capacity for 64 entities is reserved up front
+    let a = world.try_spawn((1u32,)).unwrap();
+    assert_eq!(
+        world.budget_usage(),
+        BudgetUsage {
+            entities: 1,
+            component_bytes: 256,
+            archetypes: 2,
+        }
+    );
+    world.try_spawn_batch((0..63).map(|i| (i as u32,))).unwrap();
+    assert_eq!(world.budget_usage().component_bytes, 256);
+
+    assert_eq!(
+        world.try_spawn((0u64, 0u64)).err(),
+        Some(TrySpawnError::Alloc(AllocError::BudgetExceeded(
+            BudgetKind::ComponentBytes
+        )))
+    );
+    assert_eq!(
+        world.try_spawn_batch((0..3).map(|i| (i as u32,))).err(),
+        Some(TrySpawnError::Alloc(AllocError::BudgetExceeded(
+            BudgetKind::Entities
+        )))
+    );
+
+    // A third archetype is allowed, but not a fourth
+    world.try_spawn((true,)).unwrap();
+    assert_eq!(world.budget_usage().component_bytes, 320);
+    assert_eq!(
+        world.try_spawn((Marker,)).err(),
+        Some(TrySpawnError::Alloc(AllocError::BudgetExceeded(
+            BudgetKind::Archetypes
+        )))
+    );
+    assert_eq!(
+        world.try_insert(a, (Marker,)),
+        Err(TryInsertError::Alloc(AllocError::BudgetExceeded(
+            BudgetKind::Archetypes
+        )))
+    );
+    assert_eq!(world.archetypes().len(), 3);
+
+    // Growing a full archetype reserves its new capacity
+    world.try_spawn((2u32,)).unwrap();
+    assert_eq!(world.budget_usage().component_bytes, 576);
+    assert_eq!(
+        world.try_spawn(()),
+        Err(TrySpawnError::Alloc(AllocError::BudgetExceeded(
+            BudgetKind::Entities
+        )))
+    );
+
+    // Despawning keeps capacity reserved
+    world.despawn(a).unwrap();
+    assert_eq!(world.budget_usage().component_bytes, 576);
+    assert_eq!(world.len(), 65);
+
+    // Infallible methods aren't limited
+    world.spawn((1u8,));
+    assert_eq!(world.len(), 66);
+    assert_eq!(
+        world.budget_usage(),
+        BudgetUsage {
+            entities: 66,
+            component_bytes: 640,
+            archetypes: 4,
+        }
+    );
+    assert_eq!(reserved(&world), 640);
+    world.maintain(u32::MAX);
+    assert_eq!(world.budget_usage().component_bytes, reserved(&world));
+}
+
+#[test]
+fn soft_budgets() {
+    let warnings = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut world = World::new();
+    let sink = warnings.clone();
+    let soft = Budget {
+        component_bytes: Some(300),
+        ..Budget::default()
+    };
+    world.set_soft_budget(soft, move |kind, usage| {
+        sink.lock().unwrap().push((kind, usage.component_bytes));
+    });
+    world.try_spawn((1u32,)).unwrap();
+    assert!(warnings.lock().unwrap().is_empty());
+    let a = world.try_spawn((true,)).unwrap();
+    world.try_spawn((2u32,)).unwrap();
+    assert_eq!(
+        *warnings.lock().unwrap(),
+        [(BudgetKind::ComponentBytes, 320)]
+    );
+    // Dropping back to the threshold rearms the callback
+    world.despawn(a).unwrap();
+    world.maintain(u32::MAX);
+    assert_eq!(world.budget_usage().component_bytes, 8);
+    world.try_spawn((3u32,)).unwrap();
+    world.try_spawn((1u8,)).unwrap();
+    assert_eq!(
+        *warnings.lock().unwrap(),
+        [
+            (BudgetKind::ComponentBytes, 320),
+            (BudgetKind::ComponentBytes, 328)
+        ]
+    );
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn compaction_task() {
+    let mut world = World::new();
+    let entities = world
+        .spawn_batch((0..100).map(|i| (i, true)))
+        .collect::<Vec<_>>();
+    for &e in &entities[1..] {
+        world.despawn(e).unwrap();
+    }
+    let a = world.spawn((1u8,));
+    world.despawn(a).unwrap();
+    assert_eq!(world.archetypes().len(), 3);
+
+    let mut task = CompactionTask::new();
+    assert!(task.is_idle());
+    assert!(!task.run(&mut world, 1));
+    assert!(!task.is_idle());
+    // Doesn't disturb `maintain`'s own pass
+    world.maintain(1);
+    assert!(task.run_for(&mut world, std::time::Duration::from_secs(60)));
+    assert!(task.is_idle());
+    assert_eq!(world.archetypes().len(), 2);
+    assert!(world.archetypes().all(|x| x.capacity() <= 1));
+    assert_eq!(*world.get::<&i32>(entities[0]).unwrap(), 0);
+}
+
+#[test]
+fn remap_entities() {
+    struct Parent(Entity);
+
+    impl VisitEntities for Parent {
+        fn visit_entities(&mut self, f: &mut dyn FnMut(&mut Entity)) {
+            self.0.visit_entities(f);
+        }
+    }
+
+    struct Links(Vec<Entity>, Option<Entity>);
+
+    impl VisitEntities for Links {
+        fn visit_entities(&mut self, f: &mut dyn FnMut(&mut Entity)) {
+            self.0.visit_entities(f);
+            self.1.visit_entities(f);
+        }
+    }
+
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_entities::<Parent>("Parent")
+        .register_entities::<Links>("Links")
+        .register::<Entity>("Entity");
+    assert!(registry.get(TypeId::of::<Parent>()).unwrap().has_entities());
+    assert!(!registry.get(TypeId::of::<Entity>()).unwrap().has_entities());
+
+    let mut world = World::new();
+    let a = world.spawn(());
+    let b = world.spawn(());
+    let c = world.spawn(());
+    let x = world.spawn((Parent(a), Links(vec![a, b, c], Some(b))));
+    let y = world.spawn((Parent(c), a));
+    world.advance_tick();
+
+    let remap = [(a, b), (b, a)].into_iter().collect::<EntityRemap>();
+    assert_eq!(world.remap_entities(&remap, &registry), 4);
+    assert_eq!(world.get::<&Parent>(x).unwrap().0, b);
+    let links = world.get::<&Links>(x).unwrap();
+    assert_eq!(links.0, [b, a, c]);
+    assert_eq!(links.1, Some(a));
+    drop(links);
+    assert_eq!(world.get::<&Parent>(y).unwrap().0, c);
+    // Unregistered types are left alone
+    assert_eq!(*world.get::<&Entity>(y).unwrap(), a);
+
+    // Only columns containing rewritten handles are marked changed
+    let changed = world
+        .query::<&Parent>()
+        .changed_since::<Parent>(1)
+        .iter()
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(changed, [x]);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+fn derived_visit_entities() {
+    #[derive(VisitEntities)]
+    struct Target {
+        #[entities]
+        entity: Entity,
+        #[entities]
+        fallback: Option<Entity>,
+        other: Entity,
+    }
+
+    let mut world = World::new();
+    let a = world.spawn(());
+    let b = world.spawn(());
+    let mut target = Target {
+        entity: a,
+        fallback: Some(a),
+        other: a,
+    };
+    let mut visited = 0;
+    target.visit_entities(&mut |e| {
+        *e = b;
+        visited += 1;
+    });
+    assert_eq!(visited, 2);
+    assert_eq!(
+        (target.entity, target.fallback, target.other),
+        (b, Some(b), a)
+    );
+}
+
+#[test]
+fn frozen_order() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2,));
+    let c = world.spawn((3, true));
+    let d = world.spawn((4,));
+    let f = world.spawn((6, true));
+    let token = world.freeze_order();
+    assert_eq!(token.entities(), [a, c, f, b, d]);
+
+    // Shuffle storage: `f` takes `a`'s row, and `b` changes archetype
+    world.despawn(a).unwrap();
+    world.insert_one(b, true).unwrap();
+    let e = world.spawn((5,));
+    assert_eq!(
+        world
+            .query::<&i32>()
+            .iter()
+            .map(|(e, _)| e)
+            .collect::<Vec<_>>(),
+        [f, c, b, d, e]
+    );
+
+    let mut query = world.query::<&mut i32>();
+    let frozen = query
+        .iter_frozen(&token)
+        .map(|(e, &mut i)| (e, i))
+        .collect::<Vec<_>>();
+    assert_eq!(frozen, [(c, 3), (f, 6), (b, 2), (d, 4), (e, 5)]);
+    drop(query);
+
+    // Entities that no longer match are skipped
+    let frozen = world
+        .query::<&bool>()
+        .iter_frozen(&token)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(frozen, [c, f, b]);
+
+    // Per-query tokens only capture matching entities
+    let token = world.query::<&bool>().freeze_order();
+    assert_eq!(token.entities(), [f, c, b]);
+    assert!(!token.contains(d));
+}
+
+#[test]
+fn snapshot_rollback() {
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_clone::<i32>("i32")
+        .register_clone::<bool>("bool");
+    let mut world = World::new();
+    for i in 0..8 {
+        world.spawn((i, i % 2 == 0));
+    }
+    world.advance_tick();
+    world.advance_tick();
+
+    fn step(world: &mut World, frame: i32) {
+        let victims = world
+            .query::<&i32>()
+            .iter()
+            .filter(|&(_, &i)| i % 3 == frame % 3)
+            .map(|(e, _)| e)
+            .collect::<Vec<_>>();
+        for e in victims {
+            world.despawn(e).unwrap();
+        }
+        world.spawn((frame * 10, true));
+        for (_, x) in world.query_mut::<&mut i32>() {
+            *x += 1;
+        }
+        world.advance_tick();
+    }
+
+    fn state(world: &World) -> (u32, Vec<(Entity, i32)>) {
+        let items = world.query::<&i32>().iter().map(|(e, &i)| (e, i)).collect();
+        (world.tick(), items)
+    }
+
+    let snapshot = world.snapshot(&registry);
+    assert_eq!(snapshot.tick(), 2);
+    let mut history = Vec::new();
+    for frame in 0..4 {
+        step(&mut world, frame);
+        history.push(state(&world));
+    }
+
+    // Roll back and replay the same inputs
+    world.restore_exact(&snapshot);
+    assert_eq!(world.tick(), 2);
+    assert_eq!(
+        world.query::<&i32>().changed_since::<i32>(2).iter().count(),
+        8
+    );
+    for (frame, expected) in history.iter().enumerate() {
+        step(&mut world, frame as i32);
+        assert_eq!(&state(&world), expected);
+    }
+}
+
+#[test]
+fn incremental_snapshots() {
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_clone::<i32>("i32")
+        .register_clone::<bool>("bool")
+        .register_clone::<&'static str>("str");
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2, false));
+    let c = world.spawn((3, "c"));
+
+    let first = world.snapshot(&registry);
+    let second = world.snapshot_incremental(&first, &registry);
+    assert_eq!(second.shared_columns(&first), 4);
+
+    // Only the mutated column is copied
+    *world.get::<&mut i32>(c).unwrap() = 30;
+    let third = world.snapshot_incremental(&second, &registry);
+    assert_eq!(third.shared_columns(&second), 3);
+
+    // Every column of an archetype whose entities changed is copied
+    world.despawn(b).unwrap();
+    let fourth = world.snapshot_incremental(&third, &registry);
+    assert_eq!(fourth.shared_columns(&third), 2);
+
+    // New archetypes prevent sharing
+    world.spawn(("d",));
+    let fifth = world.snapshot_incremental(&fourth, &registry);
+    assert_eq!(fifth.shared_columns(&fourth), 0);
+
+    // Snapshots of other worlds aren't shared
+    let other = World::new().snapshot_incremental(&fifth, &registry);
+    assert_eq!(other.shared_columns(&fifth), 0);
+    assert!(other.is_empty());
+
+    world.restore_exact(&second);
+    assert_eq!(*world.get::<&i32>(b).unwrap(), 2);
+    assert_eq!(*world.get::<&i32>(c).unwrap(), 3);
+    world.restore_exact(&fourth);
+    assert!(!world.contains(b));
+    assert_eq!(*world.get::<&i32>(a).unwrap(), 1);
+    assert_eq!(*world.get::<&i32>(c).unwrap(), 30);
+    assert_eq!(world.len(), 2);
+}
+
+#[test]
+fn allocator_state() {
+    let mut world = World::builder().id_reuse_delay(1).build();
+    let ids = (0..6).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    world.despawn(ids[1]).unwrap();
+    world.despawn(ids[4]).unwrap();
+    world.advance_id_reuse();
+    world.despawn(ids[2]).unwrap();
+    let reserved = world.reserve_entity();
+    let state = world.allocator_state();
+
+    let mut loaded = World::builder().id_reuse_delay(1).build();
+    assert_eq!(
+        loaded.set_allocator_state(&state),
+        Err(AllocatorStateMismatch)
+    );
+    for &i in &[0, 3, 5] {
+        loaded.spawn_at(ids[i], (i,));
+    }
+    loaded.spawn_at(reserved, ());
+    // The reserved entity is marked free
+    assert_eq!(
+        loaded.set_allocator_state(&state),
+        Err(AllocatorStateMismatch)
+    );
+    loaded.despawn(reserved).unwrap();
+    loaded.set_allocator_state(&state).unwrap();
+
+    // The reservation is restored
+    loaded.flush();
+    assert!(loaded.contains(reserved));
+    for _ in 0..2 {
+        let expected = (0..4).map(|_| world.spawn(())).collect::<Vec<_>>();
+        let actual = (0..4).map(|_| loaded.spawn(())).collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+        world.advance_id_reuse();
+        loaded.advance_id_reuse();
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn allocator_state_serde() {
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let b = world.spawn((2,));
+    world.despawn(a).unwrap();
+    let state = world.allocator_state();
+    let bytes = bincode::serialize(&state).unwrap();
+    let loaded_state = bincode::deserialize::<AllocatorState>(&bytes).unwrap();
+    assert_eq!(loaded_state, state);
+
+    let mut loaded = World::new();
+    loaded.spawn_at(b, (2,));
+    loaded.set_allocator_state(&loaded_state).unwrap();
+    assert_eq!(loaded.spawn(()), world.spawn(()));
+    assert_eq!(loaded.spawn(()), world.spawn(()));
+}
+
+#[test]
+fn par_iter_batched() {
+    let mut world = World::new();
+    world.spawn_batch((0..20).map(|i| (i, true)));
+    world.spawn_batch((0..9).map(|i| (i,)));
+
+    let mut query = world.query::<&i32>();
+    let batches = query.par_iter_batched(4);
+    assert_eq!(batches.len(), 5 + 3);
+    let batches = batches
+        .into_iter()
+        .map(|batch| batch.map(|(e, _)| e).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let sizes = batches.iter().map(|x| x.len()).collect::<Vec<_>>();
+    assert_eq!(sizes, [4, 4, 4, 4, 4, 4, 4, 1]);
+    let entities = batches.concat();
+    drop(query);
+    let expected = world
+        .query::<()>()
+        .iter()
+        .map(|(e, ())| e)
+        .collect::<Vec<_>>();
+    assert_eq!(entities, expected);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn par_for_each_ordered() {
+    let mut world = World::new();
+    world.spawn_batch((0..300).map(|i| (i as f32 * 0.37,)));
+    world.spawn_batch((0..200).map(|i| (i as f32 * 1e5, 'x')));
+
+    let run = |world: &World, executor: &dyn Executor| {
+        let mut sum = 0.0f32;
+        let mut order = Vec::new();
+        world.query::<&f32>().par_for_each_ordered(
+            9,
+            executor,
+            |_, &x| x.sin() * 1e3,
+            |e, x| {
+                sum += x;
+                order.push(e);
+            },
+        );
+        (sum.to_bits(), order)
+    };
+    let expected = run(&world, &SerialExecutor);
+    assert_eq!(expected.1.len(), 500);
+    let iterated = world
+        .query::<&f32>()
+        .iter()
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(expected.1, iterated);
+    for threads in 1..6 {
+        assert_eq!(run(&world, &ThreadExecutor::new(threads)), expected);
+    }
+}
+
+#[test]
+fn change_tracking() {
+    fn flagged<Q: for<'q> Query<Item<'q> = bool>>(world: &World) -> Vec<Entity> {
+        let mut flagged = world
+            .query::<Q>()
+            .iter()
+            .filter(|&(_, x)| x)
+            .map(|(e, _)| e)
+            .collect::<Vec<_>>();
+        flagged.sort();
+        flagged
+    }
+
+    let mut world = World::new();
+    let a = world.spawn((1u32,));
+    world.track_changes::<u32>();
+    let b = world.spawn((2u32,));
+    let c = world.spawn((3u32, true));
+    assert_eq!(flagged::<Added<u32>>(&world), [b, c]);
+    assert_eq!(flagged::<Changed<u32>>(&world), [b, c]);
+    assert!(flagged::<Added<bool>>(&world).is_empty());
+
+    world.clear_trackers();
+    assert!(flagged::<Changed<u32>>(&world).is_empty());
+    let mut batch = world
+        .spawn_batch((10..13u32).map(|x| (x, 'x')))
+        .collect::<Vec<_>>();
+    batch.sort();
+    assert_eq!(flagged::<Added<u32>>(&world), batch);
+
+    // Moving between archetypes and swap removal carry ticks with the entity
+    world.clear_trackers();
+    world.insert_one(a, true).unwrap();
+    world.despawn(b).unwrap();
+    world.remove_one::<bool>(c).unwrap();
+    world.despawn(batch[0]).unwrap();
+    assert!(flagged::<Changed<u32>>(&world).is_empty());
+    world.insert_one(a, 5u32).unwrap();
+    *world.get::<&mut u32>(c).unwrap() += 1;
+    for (_, (x, _)) in world.query_mut::<(&mut u32, &char)>() {
+        *x += 1;
+    }
+    let mut expected = vec![a, c, batch[1], batch[2]];
+    expected.sort();
+    assert_eq!(flagged::<Changed<u32>>(&world), expected);
+    assert!(flagged::<Added<u32>>(&world).is_empty());
+
+    // Sorted archetypes permute ticks with their rows
+    world.clear_trackers();
+    world.keep_sorted_by_key::<u32>();
+    let d = world.spawn((0u32, 'y'));
+    assert_eq!(flagged::<Added<u32>>(&world), [d]);
+    world.clear_trackers();
+    *world.get::<&mut u32>(batch[2]).unwrap() = 100;
+    world.insert_one(batch[2], 'z').unwrap();
+    assert_eq!(flagged::<Changed<u32>>(&world), [batch[2]]);
+}
+
+#[test]
+fn removed_components() {
+    let mut world = World::new();
+    world.track_removals::<u32>();
+    let a = world.spawn((1u32, true));
+    let b = world.spawn((2u32,));
+    let c = world.spawn((3u32, 'c'));
+    let d = world.spawn((4u32,));
+    let e = world.spawn((true,));
+
+    world.remove_one::<bool>(a).unwrap();
+    world.remove_one::<bool>(e).unwrap();
+    world.exchange_one::<u32, i64>(c, 3).unwrap();
+    world.exchange_one::<u32, u32>(d, 5).unwrap();
+    world.remove_dynamic(a, TypeId::of::<u32>()).unwrap();
+    drop(world.take(b).unwrap());
+    assert_eq!(world.removed::<u32>().collect::<Vec<_>>(), [c, a, b]);
+    assert_eq!(world.removed::<u32>().count(), 0);
+    assert_eq!(world.removed::<bool>().count(), 0);
+
+    world.spawn_at(d, (6u32,));
+    let f = world.spawn((7u32,));
+    world.despawn(e).unwrap();
+    assert_eq!(world.removed::<u32>().collect::<Vec<_>>(), [d]);
+    world.clear();
+    let mut cleared = world.removed::<u32>().collect::<Vec<_>>();
+    let mut expected = vec![d, f];
+    cleared.sort();
+    expected.sort();
+    assert_eq!(cleared, expected);
+}
+
+#[test]
+fn canonical_archetype_order() {
+    fn types(world: &World) -> Vec<Vec<TypeId>> {
+        world
+            .archetypes()
+            .map(|x| {
+                let mut ids = x.component_types().collect::<Vec<_>>();
+                ids.sort();
+                ids
+            })
+            .collect()
+    }
+    fn values(world: &mut World) -> Vec<u32> {
+        world
+            .query_mut::<&u32>()
+            .into_iter()
+            .map(|(_, &x)| x)
+            .collect()
+    }
+
+    let mut a = World::builder().canonical_archetype_order(true).build();
+    let id = a.register_bundle::<(u32, bool)>();
+    let e = a.spawn((1u32, 'a'));
+    a.spawn((2u32,));
+    a.insert_one(e, 1.5f32).unwrap();
+    a.exchange_one::<char, i64>(e, 3).unwrap();
+    a.spawn_with_id(id, (3u32, true));
+    a.spawn_batch([(4u32, "x")]);
+
+    let mut b = World::builder().canonical_archetype_order(true).build();
+    b.spawn_batch([(4u32, "x")]);
+    b.spawn((3u32, true));
+    b.spawn((2u32,));
+    let f = b.spawn((1u32, 'a', 1.5f32));
+    b.remove_one::<char>(f).unwrap();
+    b.insert_one(f, 3i64).unwrap();
+
+    while !a.maintain(u32::MAX) {}
+    while !b.maintain(u32::MAX) {}
+    assert_eq!(types(&a), types(&b));
+    let mut sorted = types(&a);
+    sorted.sort();
+    assert_eq!(types(&a), sorted);
+    assert_eq!(values(&mut a), values(&mut b));
+    assert_eq!(*a.get::<&i64>(e).unwrap(), 3);
+
+    let mut c = World::new();
+    c.spawn((2u32,));
+    c.spawn((1u32, 1.5f32, 3i64));
+    c.spawn((4u32, "x"));
+    c.spawn((3u32, true));
+    c.sort_archetypes();
+    assert_eq!(types(&c), types(&a));
+    assert_eq!(values(&mut c), values(&mut a));
+
+    // Handles survive renumbering
+    a.spawn_with_id(id, (5u32, false));
+    assert_eq!(a.query_mut::<(&u32, &bool)>().into_iter().count(), 2);
+}
+
+#[test]
+fn merge_worlds() {
+    struct Target(Option<Entity>);
+
+    impl VisitEntities for Target {
+        fn visit_entities(&mut self, f: &mut dyn FnMut(&mut Entity)) {
+            self.0.visit_entities(f);
+        }
+    }
+
+    let mut registry = ComponentRegistry::new();
+    registry.register_entities::<Target>("Target");
+
+    let mut world = World::new();
+    let live = (0..5).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    let watcher = world.spawn((Target(Some(live[0])),));
+
+    // The saved world's handles collide with the live world's
+    let mut saved = World::new();
+    let a = saved.spawn((10,));
+    let b = saved.spawn((Target(Some(a)), 11));
+    let c = saved.spawn((Target(None),));
+    let d = saved.spawn((Target(Some(watcher)),));
+    assert_eq!(a, live[0]);
+
+    let remap = world.merge(saved, &registry);
+    assert_eq!(remap.len(), 4);
+    let new = [a, b, c, d].map(|x| remap.get(x).unwrap());
+    for x in new {
+        assert!(world.contains(x));
+        assert!(!live.contains(&x) && x != watcher);
+    }
+    assert_eq!(*world.get::<&i32>(new[0]).unwrap(), 10);
+    assert_eq!(world.get::<&Target>(new[1]).unwrap().0, Some(new[0]));
+    assert_eq!(world.get::<&Target>(new[2]).unwrap().0, None);
+    // References to handles absent from the merged world are untouched
+    assert_eq!(world.get::<&Target>(new[3]).unwrap().0, Some(watcher));
+    assert_eq!(world.get::<&Target>(watcher).unwrap().0, Some(live[0]));
+    assert_eq!(world.len(), 10);
+}
+
+#[test]
+fn runtime_component_types() {
+    use std::alloc::Layout;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+    unsafe fn count_drop(ptr: *mut u8) {
+        DROPPED.fetch_add(ptr.cast::<u64>().read() as usize, Ordering::Relaxed);
+    }
+
+    let mut world = World::new();
+    let weight = world
+        .register_dynamic(Layout::new::<u64>(), Some(count_drop), "Weight")
+        .unwrap();
+    let tag = world
+        .register_dynamic(Layout::new::<()>(), None, "Tag")
+        .unwrap();
+    assert_ne!(weight, tag);
+    assert_ne!(weight.id(), TypeId::of::<u64>());
+    assert_eq!(world.dynamic_key("Weight"), Some(weight));
+    assert_eq!(world.dynamic_key("Height"), None);
+    // Keys are distinct across worlds
+    assert_ne!(
+        World::new()
+            .register_dynamic(Layout::new::<u64>(), None, "Weight")
+            .unwrap(),
+        weight
+    );
+
+    let mut builder = EntityBuilder::new();
+    let e = unsafe {
+        world.spawn(
+            builder
+                .add(1u64)
+                .add_dynamic(weight, &10u64.to_ne_bytes())
+                .add_dynamic(tag, &[])
+                .build(),
+        )
+    };
+    let f = unsafe { world.spawn(builder.add_dynamic(weight, &100u64.to_ne_bytes()).build()) };
+
+    let query = QueryBuilder::new().read_dynamic(weight.id()).build();
+    assert!(world.satisfies_dynamic(e, &query).unwrap());
+    assert!(world.satisfies_dynamic(f, &query).unwrap());
+    assert_eq!(*world.get::<&u64>(e).unwrap(), 1);
+
+    let ptr = world.entity(e).unwrap().component_ptr(weight.id()).unwrap();
+    unsafe {
+        assert_eq!(ptr.cast::<u64>().as_ptr().read(), 10);
+        ptr.cast::<u64>().as_ptr().write(20);
+    }
+    assert!(world.entity(f).unwrap().component_ptr(tag.id()).is_none());
+
+    assert_eq!(world.remove_dynamic(e, weight.id()), Ok(true));
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 20);
+    assert!(world.entity(e).unwrap().has::<u64>());
+    world.despawn(f).unwrap();
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 120);
+}
+
+#[test]
+fn query_dynamic() {
+    use std::alloc::Layout;
+
+    let mut world = World::new();
+    let a = world
+        .register_dynamic(Layout::new::<u16>(), None, "A")
+        .unwrap();
+    let b = world
+        .register_dynamic(Layout::new::<u32>(), None, "B")
+        .unwrap();
+    let mut builder = EntityBuilder::new();
+    let mut spawn = |world: &mut World, x: Option<u16>, y: Option<u32>| unsafe {
+        if let Some(x) = x {
+            builder.add_dynamic(a, &x.to_ne_bytes());
+        }
+        if let Some(y) = y {
+            builder.add_dynamic(b, &y.to_ne_bytes());
+        }
+        world.spawn(builder.add(true).build())
+    };
+    let e = spawn(&mut world, Some(1), Some(10));
+    let f = spawn(&mut world, Some(2), None);
+    let g = spawn(&mut world, Some(3), Some(30));
+    spawn(&mut world, None, Some(40));
+    // An archetype emptied by despawning is skipped
+    let h = spawn(&mut world, Some(4), Some(50));
+    world.insert_one(h, 'x').unwrap();
+    world.despawn(h).unwrap();
+
+    let mut seen = Vec::new();
+    for batch in world.query_dynamic(&[b, a]) {
+        assert!(!batch.is_empty());
+        let (ys, xs) = (batch.column(0), batch.column(1));
+        assert_eq!(xs.len(), batch.len());
+        assert_eq!(ys.layout(), Layout::new::<u32>());
+        assert!(xs.get(batch.len()).is_none());
+        assert_eq!(
+            unsafe { xs.bytes() }.len(),
+            batch.len() * std::mem::size_of::<u16>()
+        );
+        for (i, entity) in batch.entities().enumerate() {
+            let x = unsafe { xs.get(i).unwrap().cast::<u16>().as_ptr().read() };
+            let y = ys.get(i).unwrap().cast::<u32>();
+            unsafe {
+                seen.push((entity, x, y.as_ptr().read()));
+                y.as_ptr().write(u32::from(x));
+            }
+        }
+        batch.mark_changed(0);
+    }
+    seen.sort_unstable();
+    assert_eq!(seen, [(e, 1, 10), (g, 3, 30)]);
+
+    assert_eq!(world.query_dynamic(&[a]).map(|x| x.len()).sum::<usize>(), 3);
+    // Writes through the columns are visible to later queries
+    let keys = [a, b];
+    let batch = world.query_dynamic(&keys).next().unwrap();
+    let ys = batch.column(1);
+    let written = (0..batch.len())
+        .map(|i| unsafe { ys.get(i).unwrap().cast::<u32>().as_ptr().read() })
+        .collect::<Vec<_>>();
+    assert_eq!(written, [1, 3]);
+    // No keys matches every non-empty archetype
+    assert_eq!(world.query_dynamic(&[]).count(), 3);
+    assert!(world
+        .query_dynamic(&[a])
+        .any(|x| x.entities().eq([f]) && x.column(0).len() == 1));
+}
+
+#[test]
+fn reflect_component_info() {
+    use hecs::reflect::{ComponentInfo, FieldInfo, Reflect};
+    use std::alloc::Layout;
+
+    #[repr(C)]
+    struct Stats {
+        health: u32,
+        speed: f64,
+    }
+
+    impl Reflect for Stats {
+        const FIELDS: &'static [FieldInfo] = &[
+            FieldInfo::new("health", "u32", 0, 4),
+            FieldInfo::new("speed", "f64", 8, 8),
+        ];
+    }
+
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_reflect::<Stats>("Stats")
+        .register::<bool>("bool");
+
+    let mut world = World::new();
+    let e = world.spawn((
+        Stats {
+            health: 1,
+            speed: 2.0,
+        },
+        true,
+        7u16,
+    ));
+    let entity = world.entity(e).unwrap();
+    let infos = entity.component_info(&registry);
+    assert_eq!(infos.len(), 3);
+    let mut infos = infos.collect::<Vec<ComponentInfo>>();
+    infos.sort_by_key(|x| x.name());
+
+    assert_eq!(infos[0].name(), None);
+    assert_eq!(infos[0].id(), TypeId::of::<u16>());
+    assert_eq!((infos[0].size(), infos[0].align()), (2, 2));
+    assert_eq!(infos[0].fields(), None);
+
+    assert_eq!(infos[1].name(), Some("Stats"));
+    assert_eq!(infos[1].layout(), Layout::new::<Stats>());
+    let fields = infos[1].fields().unwrap();
+    assert_eq!(fields.len(), 2);
+    assert_eq!((fields[1].name(), fields[1].type_name()), ("speed", "f64"));
+    assert_eq!((fields[1].offset(), fields[1].size()), (8, 8));
+
+    // Registered, but without field metadata
+    assert_eq!(infos[2].name(), Some("bool"));
+    assert_eq!(infos[2].fields(), None);
+    assert_eq!(
+        registry.get_by_name("bool").unwrap().component_info(),
+        infos[2]
+    );
+}
+
+#[test]
+fn spawn_order_iteration() {
+    let mut world = World::builder()
+        .iteration_order(IterationOrder::SpawnOrder)
+        .build();
+    assert_eq!(world.iteration_order(), IterationOrder::SpawnOrder);
+    assert_eq!(World::new().iteration_order(), IterationOrder::Storage);
+
+    let a = world.spawn((0, true));
+    let b = world.spawn((1,));
+    let c = world.spawn((2, 'x'));
+    let d = world.spawn((3, true));
+    // Moving entities between archetypes doesn't change their order
+    world.remove_one::<bool>(a).unwrap();
+    world.insert_one(d, 'y').unwrap();
+    world.insert_one(b, 1.0f32).unwrap();
+    // A respawned ID goes last
+    world.despawn(c).unwrap();
+    let e = world.spawn((4, 'z'));
+    assert_eq!(e.id(), c.id());
+    let batch = world
+        .spawn_batch((5..7).map(|i| (i, true)))
+        .collect::<Vec<_>>();
+    let reserved = world.reserve_entity();
+    world.insert_one(reserved, 7).unwrap();
+
+    let expected = [a, b, d, e, batch[0], batch[1], reserved];
+    let order = |world: &World| {
+        world
+            .query::<&i32>()
+            .iter()
+            .map(|(e, &i)| (e, i))
+            .collect::<Vec<_>>()
+    };
+    let visited = order(&world);
+    assert_eq!(visited.iter().map(|x| x.0).collect::<Vec<_>>(), expected);
+    assert_eq!(
+        visited.iter().map(|x| x.1).collect::<Vec<_>>(),
+        [0, 1, 3, 4, 5, 6, 7]
+    );
+
+    let mut query = world.query::<&i32>();
+    let iter = query.iter();
+    assert_eq!(iter.len(), 7);
+    assert_eq!(
+        iter.rev().map(|(e, _)| e).collect::<Vec<_>>(),
+        expected.iter().rev().copied().collect::<Vec<_>>()
+    );
+    let (left, right) = query.iter().split_at(3);
+    assert_eq!((left.len(), right.len()), (3, 4));
+    assert_eq!(
+        left.chain(right).map(|(e, _)| e).collect::<Vec<_>>(),
+        expected
+    );
+    assert_eq!(query.iter().nth(2).map(|(e, _)| e), Some(d));
+    assert_eq!(query.iter().nth_back(1).map(|(e, _)| e), Some(batch[1]));
+    let mut mask = EntityMask::new();
+    mask.insert(e);
+    mask.insert(a);
+    assert_eq!(
+        query.iter_masked(&mask).map(|(e, _)| e).collect::<Vec<_>>(),
+        [a, e]
+    );
+    for (entity, location, _) in query.iter_located() {
+        assert_eq!(world.entity_at(location).unwrap().entity(), entity);
+    }
+    drop(query);
+
+    // Filters restrict, but don't reorder, the results
+    assert_eq!(
+        world
+            .query::<&i32>()
+            .with::<&char>()
+            .iter()
+            .map(|(e, _)| e)
+            .collect::<Vec<_>>(),
+        [d, e]
+    );
+    for (_, x) in world.query_mut::<&mut i32>() {
+        *x *= 10;
+    }
+    assert_eq!(
+        world
+            .query_mut::<&i32>()
+            .into_iter()
+            .map(|(_, &x)| x)
+            .collect::<Vec<_>>(),
+        [0, 10, 30, 40, 50, 60, 70]
+    );
+}
+
+#[test]
+fn op_log_replay() {
+    let mut registry = ComponentRegistry::new();
+    registry.register_clone::<i32>("i32");
+    registry.register_clone::<bool>("bool");
+    registry.register_clone::<String>("String");
+
+    let mut world = World::new();
+    let before = world.spawn((0,));
+    world.start_op_log(&registry);
+    world.advance_tick();
+    let a = world.spawn((1, true));
+    let batch = world
+        .spawn_batch((2..5).map(|x| (x, String::from("x"))))
+        .collect::<Vec<_>>();
+    let reserved = world.reserve_entity();
+    world.flush();
+    world.insert(reserved, (5, 'c')).unwrap();
+    world.advance_tick();
+    let clones = world
+        .spawn_clones(a, 2, &registry)
+        .unwrap()
+        .collect::<Vec<_>>();
+    world.remove_one::<String>(batch[1]).unwrap();
+    world.despawn(batch[2]).unwrap();
+    world.exchange_one::<bool, _>(a, String::from("y")).unwrap();
+    let log = world.take_op_log().unwrap();
+    world.spawn((6,));
+    assert_eq!(log.unrecorded(), [TypeId::of::<char>()]);
+    assert!(matches!(log.ops()[0].op(), Op::Spawn { entity, .. } if *entity == a));
+    assert_eq!(log.ops()[0].tick(), 1);
+    assert!(matches!(log.ops()[4].op(), Op::Flush { entities } if **entities == [reserved]));
+
+    let mut replayed = World::new();
+    replayed.spawn((0,));
+    log.replay(&mut replayed).unwrap();
+    assert_eq!(replayed.tick(), 2);
+    assert_eq!(replayed.len(), 7);
+    let contents = |world: &World, entity| {
+        let entity = world.entity(entity).unwrap();
+        (
+            entity.get::<&i32>().map(|x| *x),
+            entity.get::<&bool>().map(|x| *x),
+            entity.get::<&String>().map(|x| (*x).clone()),
+        )
+    };
+    for entity in [before, a, batch[0], batch[1], reserved]
+        .into_iter()
+        .chain(clones)
+    {
+        assert_eq!(contents(&replayed, entity), contents(&world, entity));
+    }
+    assert!(!replayed.contains(batch[2]));
+    assert!(!replayed.satisfies::<&char>(reserved).unwrap());
+}
+
+#[test]
+fn apply_world_diff() {
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_clone::<i32>("i32")
+        .register_eq::<i32>("i32")
+        .register_clone::<String>("String")
+        .register_eq::<String>("String");
+    registry.register_clone::<bool>("bool");
+
+    let mut old = World::new();
+    let a = old.spawn((1, String::from("a")));
+    let b = old.spawn((2, true));
+    let c = old.spawn((3,));
+    let mut new = World::new();
+    new.spawn_at(a, (1, String::from("changed")));
+    new.spawn_at(b, (2, 'x'));
+    new.spawn_at(c, (3,));
+    new.despawn(c).unwrap();
+    let d = new.spawn((4, false));
+    assert_eq!(d.id(), c.id());
+
+    let diff = WorldDiff::between(&old, &new, &registry);
+    assert_eq!(diff.spawned(), [d]);
+    assert_eq!(diff.despawned(), [c]);
+    assert_eq!(diff.patches().map(|(e, _)| e).collect::<Vec<_>>(), [a, d]);
+
+    let mut mirror = World::new();
+    mirror.spawn_at(a, (1, String::from("a")));
+    mirror.spawn_at(b, (2, true));
+    mirror.spawn_at(c, (3,));
+    mirror.apply_diff(&diff);
+    assert!(!mirror.contains(c));
+    assert_eq!(*mirror.get::<&String>(a).unwrap(), "changed");
+    assert_eq!(*mirror.get::<&i32>(d).unwrap(), 4);
+    assert!(!*mirror.get::<&bool>(d).unwrap());
+    assert!(!mirror.satisfies::<&bool>(b).unwrap());
+    // `char` isn't registered for cloning, so its addition can't be applied
+    assert!(!mirror.satisfies::<&char>(b).unwrap());
+    let rest = WorldDiff::between(&mirror, &new, &registry);
+    assert!(rest.despawned().is_empty() && rest.spawned().is_empty());
+    assert_eq!(
+        rest.changes(),
+        [ComponentChange {
+            entity: b,
+            component: TypeId::of::<char>(),
+            kind: ChangeKind::Added,
+        }]
+    );
+}
+
+#[test]
+fn world_stable_hash() {
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_stable_hash::<f32>("f32")
+        .register_stable_hash::<Entity>("Entity")
+        .register_stable_hash::<String>("String");
+
+    // Same state reached through different archetype and storage orders
+    let mut a = World::new();
+    let x = a.spawn((1.0f32, String::from("x")));
+    let y = a.spawn((f32::NAN,));
+    a.insert_one(y, x).unwrap();
+    let mut b = World::new();
+    b.spawn_at(y, (x, f32::from_bits(0xffc0_0001), 'c'));
+    b.spawn_at(x, (String::from("x"), 1.0f32));
+    assert_eq!(a.stable_hash(&registry), b.stable_hash(&registry));
+
+    b.remove_one::<String>(x).unwrap();
+    assert_ne!(a.stable_hash(&registry), b.stable_hash(&registry));
+    b.insert_one(x, String::from("x")).unwrap();
+    assert_eq!(a.stable_hash(&registry), b.stable_hash(&registry));
+    b.despawn(y).unwrap();
+    b.spawn_at(
+        Entity::from_bits(y.to_bits().get() + (1 << 32)).unwrap(),
+        (x, f32::NAN),
+    );
+    assert_ne!(a.stable_hash(&registry), b.stable_hash(&registry));
+}
+
+#[test]
+#[cfg(feature = "macros")]
+fn derived_stable_hash() {
+    #[derive(StableHash)]
+    struct Body<T> {
+        position: [f32; 2],
+        tag: T,
+    }
+
+    #[derive(StableHash)]
+    enum Shape {
+        Circle(f32),
+        Rect { width: f32, height: f32 },
+        Point,
+    }
+
+    fn hash(x: impl StableHash) -> u64 {
+        let mut hasher = StableHasher::new();
+        x.stable_hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let body = |x| Body {
+        position: [x, 0.0],
+        tag: 7u8,
+    };
+    assert_eq!(hash(body(f32::NAN)), hash(body(-f32::NAN)));
+    assert_ne!(hash(body(1.0)), hash(body(2.0)));
+    assert_eq!(hash(body(1.0)), hash(([1.0f32, 0.0], 7u8)));
+    assert_ne!(hash(Shape::Circle(1.0)), hash(Shape::Point));
+    assert_ne!(
+        hash(Shape::Rect {
+            width: 1.0,
+            height: 2.0
+        }),
+        hash(Shape::Rect {
+            width: 2.0,
+            height: 1.0
+        })
+    );
+}
+
+#[test]
+fn try_clone_world() {
+    let mut registry = ComponentRegistry::new();
+    registry
+        .register_clone::<i32>("i32")
+        .register_clone::<String>("String");
+
+    let mut world = World::new();
+    let entities = (0..6)
+        .map(|i| world.spawn((i, String::from("x"))))
+        .collect::<Vec<_>>();
+    world.spawn((10,));
+    world.despawn(entities[1]).unwrap();
+    world.despawn(entities[4]).unwrap();
+    let since = world.advance_tick();
+    *world.get::<&mut i32>(entities[2]).unwrap() = 20;
+    let reserved = world.reserve_entity();
+
+    let mut copy = world.try_clone(&registry).unwrap();
+    assert_eq!(copy.tick(), world.tick());
+    assert_eq!(copy.allocator_state(), world.allocator_state());
+    let contents = |world: &mut World| {
+        world
+            .query_mut::<(&i32, Option<&String>)>()
+            .into_iter()
+            .map(|(e, (&x, s))| (e, x, s.cloned()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(contents(&mut copy), contents(&mut world));
+    let changed = |world: &World| {
+        world
+            .query::<&i32>()
+            .changed_since::<i32>(since)
+            .iter()
+            .map(|(e, _)| e)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(changed(&copy), changed(&world));
+    assert!(changed(&copy).contains(&entities[2]));
+
+    // The copy is independent, and allocates the same handles
+    assert_eq!(copy.spawn((1,)), world.spawn((1,)));
+    copy.despawn(entities[0]).unwrap();
+    assert!(world.contains(entities[0]));
+    copy.flush();
+    world.flush();
+    assert!(copy.contains(reserved));
+
+    world.spawn(('c',));
+    assert_eq!(
+        world.try_clone(&registry).err(),
+        Some(MissingClone(TypeId::of::<char>()))
+    );
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn try_clone_panic_releases_borrow() {
+    struct Fragile(bool);
+
+    impl Clone for Fragile {
+        fn clone(&self) -> Self {
+            assert!(!self.0, "clone failed");
+            Fragile(self.0)
+        }
+    }
+
+    let mut registry = ComponentRegistry::new();
+    registry.register_clone::<Fragile>("Fragile");
+    let mut world = World::new();
+    world.spawn((Fragile(false),));
+    let e = world.spawn((Fragile(true),));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        world.try_clone(&registry).ok();
+    }));
+    assert!(result.is_err());
+    world.get::<&mut Fragile>(e).unwrap().0 = false;
+    assert_eq!(world.try_clone(&registry).unwrap().len(), 2);
+}